@@ -0,0 +1,62 @@
+/* C interface of Ruxnasm, provided by the library built with the `capi` feature. */
+
+#ifndef RUXNASM_H
+#define RUXNASM_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef enum RuxnasmSeverity {
+    RUXNASM_SEVERITY_ERROR = 0,
+    RUXNASM_SEVERITY_WARNING = 1,
+} RuxnasmSeverity;
+
+/* An error or a warning of the assembled program. The strings are owned by the assembly. */
+typedef struct RuxnasmDiagnostic {
+    RuxnasmSeverity severity;
+    /* Code of the diagnostic, e.g. "E0029". */
+    const char *code;
+    /* Name of the warning, e.g. "unused-label", or NULL for errors. */
+    const char *name;
+    /* Message of the diagnostic, e.g. "label `main` is never used". */
+    const char *message;
+    /* Byte offsets of the span of the diagnostic in the source. */
+    size_t start;
+    size_t end;
+} RuxnasmDiagnostic;
+
+/* An assembled program, owning the binary and the diagnostics. */
+typedef struct RuxnasmAssembly RuxnasmAssembly;
+
+/*
+ * Assembles the program of the given length, in bytes. The binary is written to `rom` and its
+ * length to `rom_length`, or NULL and zero if the program contains errors. The binary is owned
+ * by the returned assembly, which must be freed with `ruxnasm_assembly_free`.
+ *
+ * Returns NULL if `source` is NULL. `rom` and `rom_length` can be NULL.
+ */
+RuxnasmAssembly *ruxnasm_assemble(const char *source, size_t length, const uint8_t **rom,
+                                  size_t *rom_length);
+
+/*
+ * Writes the next diagnostic of the assembly to `diagnostic`, the errors first. Returns false
+ * if there are no more diagnostics.
+ */
+bool ruxnasm_next_diagnostic(RuxnasmAssembly *assembly, RuxnasmDiagnostic *diagnostic);
+
+/*
+ * Frees the assembly along with its binary and diagnostics, including the strings of the
+ * diagnostics. Does nothing if it's NULL.
+ */
+void ruxnasm_assembly_free(RuxnasmAssembly *assembly);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
@@ -0,0 +1,88 @@
+use js_sys::{Array, Object, Reflect, Uint8Array};
+use std::ops::Range;
+use wasm_bindgen::prelude::*;
+
+/// Assembles an Uxntal program, returning an object with the `rom`, which is a `Uint8Array` of
+/// the binary, and the `diagnostics`, which list the errors and warnings of the program. The
+/// `rom` is empty if the program contains errors.
+///
+/// Every diagnostic is an object with the `severity`, either `"error"` or `"warning"`, the
+/// `code` which can be looked up with `ruxnasm --explain`, the `name` of a warning, the
+/// `message` describing it, and the `start` and `end` of its span, counted in UTF-16 code units
+/// just like the indices of JavaScript strings.
+///
+/// Included files can't be read, since there is no file system to read them from.
+#[wasm_bindgen]
+pub fn assemble(source: &str) -> Object {
+    let (rom, errors, warnings) = match ruxnasm::assemble(source.as_bytes()) {
+        Ok((binary, warnings)) => (binary, Vec::new(), warnings),
+        Err((errors, warnings)) => (Vec::new(), errors, warnings),
+    };
+
+    let diagnostics: Array = errors
+        .iter()
+        .map(|error| {
+            diagnostic(
+                source,
+                "error",
+                error.code(),
+                None,
+                &error.to_string(),
+                error.span(),
+            )
+        })
+        .chain(warnings.iter().map(|warning| {
+            diagnostic(
+                source,
+                "warning",
+                warning.code(),
+                Some(warning.name()),
+                &warning.to_string(),
+                warning.span(),
+            )
+        }))
+        .collect();
+
+    let result = Object::new();
+    set(&result, "rom", &Uint8Array::from(&rom[..]));
+    set(&result, "diagnostics", &diagnostics);
+    result
+}
+
+fn diagnostic(
+    source: &str,
+    severity: &str,
+    code: &str,
+    name: Option<&str>,
+    message: &str,
+    span: &Range<usize>,
+) -> Object {
+    let diagnostic = Object::new();
+    set(&diagnostic, "severity", &severity.into());
+    set(&diagnostic, "code", &code.into());
+    set(
+        &diagnostic,
+        "name",
+        &name.map_or(JsValue::NULL, JsValue::from),
+    );
+    set(&diagnostic, "message", &message.into());
+    set(
+        &diagnostic,
+        "start",
+        &utf16_offset(source, span.start).into(),
+    );
+    set(&diagnostic, "end", &utf16_offset(source, span.end).into());
+    diagnostic
+}
+
+/// Converts the byte offset into an offset in UTF-16 code units. Offsets past the end of the
+/// source point at its end.
+fn utf16_offset(source: &str, offset: usize) -> u32 {
+    let bytes = &source.as_bytes()[..offset.min(source.len())];
+    String::from_utf8_lossy(bytes).encode_utf16().count() as u32
+}
+
+fn set(object: &Object, key: &str, value: &JsValue) {
+    // Setting a property of a plain object can't fail.
+    let _ = Reflect::set(object, &key.into(), value);
+}
@@ -1,9 +1,26 @@
-use std::ops::Range;
+use alloc::{boxed::Box, string::String, vec::Vec};
+use core::{fmt, ops::Range};
+
+/// Replacement of a part of the source code that fixes the error or the warning it is attached
+/// to, without changing the meaning of the program.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Suggestion {
+    /// Span of the source code to be replaced.
+    pub span: Range<usize>,
+    /// The text to replace the span with.
+    pub replacement: String,
+}
 
 /// Enum representing every warning that can be reported from Ruxnasm.
+///
+/// New warnings can be added in minor versions, so matching on them needs a wildcard arm.
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
 pub enum Warning {
     /// This warnings gets reported when a token is longer than 64 characters and must be cut off.
+    /// The maximum length can be changed with [`Options::token_length`](crate::Options::token_length).
     ///
     /// # Example
     ///
@@ -11,8 +28,16 @@ pub enum Warning {
     /// @a-really-long-label-name-like-seriously-this-is-so-long-why-would-anyone-do-this
     /// ```
     TokenTrimmed {
+        /// The token as it has been taken into account, i.e. its first 64 characters, or as many
+        /// as the maximum length allows.
+        token: String,
+        /// The cut off part of the token.
+        trimmed: String,
         /// Span of the cut off part of the token.
         span: Range<usize>,
+        /// Splitting of the token in two, offered for raw words, whose cut off part can be a
+        /// raw word of its own.
+        suggestions: Vec<Suggestion>,
     },
     /// This warning gets reported when an instruction mode is defined multiple times for a
     /// single instruction, which is valid, but unnecessary.
@@ -58,10 +83,436 @@ pub enum Warning {
         /// Span of the label definition.
         span: Range<usize>,
     },
+    /// This warning gets reported when a sublabel is never used within its scope.
+    ///
+    /// # Example
+    ///
+    /// ```uxntal
+    /// |0000 @label &sublabel $1
+    /// ```
+    SublabelUnused {
+        /// Name of the label in which scope the sublabel is defined.
+        label_name: String,
+        /// Name of the unused sublabel.
+        name: String,
+        /// Address of the sublabel, in case it is located on the zero page.
+        zero_page_address: Option<u8>,
+        /// Span of the sublabel definition.
+        span: Range<usize>,
+    },
+    /// This warning gets reported when a comment looks like a directive, but it is malformed
+    /// or it mentions an unknown warning.
+    ///
+    /// # Example
+    ///
+    /// ```uxntal
+    /// ( ruxnasm: allow unused-potato )
+    /// ```
+    DirectiveInvalid {
+        /// Contents of the comment.
+        directive: String,
+        /// Span of the comment.
+        span: Range<usize>,
+    },
+    /// This warning gets reported when a label is defined at the same address as a previously
+    /// defined label, which is sometimes intentional, but often a sign of a missing pad.
+    ///
+    /// # Example
+    ///
+    /// ```uxntal
+    /// |0000 @x @y $1
+    /// ```
+    LabelAliased {
+        /// Name of the label.
+        name: String,
+        /// Name of the previously defined label with the same address.
+        other_name: String,
+        /// The address of both labels.
+        address: u16,
+        /// Span of the label definition.
+        span: Range<usize>,
+        /// Span of the previously defined label with the same address.
+        other_span: Range<usize>,
+    },
+    /// This warning gets reported when a label that has been defined before any pad is
+    /// referenced, which usually indicates a missing `|0100` pad.
+    ///
+    /// # Example
+    ///
+    /// ```uxntal
+    /// @main
+    /// ;main JMP2
+    /// ```
+    UnpaddedLabelReferenced {
+        /// Name of the label.
+        name: String,
+        /// The address of the label.
+        address: u16,
+        /// Span of the label reference.
+        span: Range<usize>,
+        /// Span of the label definition.
+        other_span: Range<usize>,
+    },
+    /// This warning gets reported when bytes are written to the memory reserved by the
+    /// compatibility profile set in the [`Options::reserved_memory`](crate::Options), by default
+    /// the top page of the memory, occupied by the stacks and the devices in some Uxn runtimes.
+    /// Only the first statement writing to the reserved memory after each absolute pad is
+    /// reported.
+    ///
+    /// # Example
+    ///
+    /// ```uxntal
+    /// |fefe #0102 #0304
+    /// ```
+    BytesInReservedMemory {
+        /// The first reserved address the bytes are written to.
+        address: u16,
+        /// Start of the reserved memory.
+        limit: u16,
+        /// Name of the compatibility profile that reserves the memory.
+        profile: String,
+        /// Span of the statement writing the bytes.
+        span: Range<usize>,
+    },
+    /// This warning gets reported when a value is written with a `DEO` instruction to a device
+    /// port whose declared width, i.e. the relative pad after its label or sublabel in the zero
+    /// page, is different from the size of the value.
+    ///
+    /// # Example
+    ///
+    /// ```uxntal
+    /// |10 @Console &vector $2 &read $1 &pad $5 &write $1
+    /// |0100 #0041 .Console/write DEO2
+    /// ```
+    PortWidthMismatch {
+        /// Name of the label or the full `label/sublabel` path of the sublabel.
+        name: String,
+        /// Declared width of the port in bytes.
+        width: u16,
+        /// Number of bytes written by the instruction.
+        written: u16,
+        /// Span of the port reference.
+        span: Range<usize>,
+        /// Span of the port definition.
+        other_span: Range<usize>,
+    },
+    /// This warning gets reported instead of
+    /// [`Error::BytesOverwritten`](crate::Error::BytesOverwritten) when
+    /// [`Options::backward_padding`](crate::Options::backward_padding) is allowed.
+    ///
+    /// # Example
+    ///
+    /// ```uxntal
+    /// |0100 #01 #02 ADD
+    /// |0101 #03
+    /// ```
+    BytesOverwritten {
+        /// The first address that is overwritten.
+        address: u16,
+        /// Span of the statement that overwrites the bytes.
+        span: Range<usize>,
+        /// Span of the statement that has previously written the bytes.
+        other_span: Range<usize>,
+    },
+    /// This warning gets reported when
+    /// [`Options::stack_warnings`](crate::Options::stack_warnings) are turned on, and an
+    /// instruction of a vector takes more bytes from a stack than there are on it, given that
+    /// vectors start with empty stacks.
+    ///
+    /// # Example
+    ///
+    /// ```uxntal
+    /// |0100 @on-reset #01 ADD BRK
+    /// ```
+    StackUnderflow {
+        /// Name of the label or the full `label/sublabel` path of the sublabel of the vector, or
+        /// `|0100` for the reset vector without a label.
+        name: String,
+        /// The instruction taking the bytes, e.g. `ADD2`.
+        instruction: String,
+        /// Whether it's the return stack that underflows, rather than the working stack.
+        r#return: bool,
+        /// Span of the instruction.
+        span: Range<usize>,
+        /// Span of the label definition.
+        other_span: Range<usize>,
+    },
+    /// This warning gets reported when
+    /// [`Options::stack_warnings`](crate::Options::stack_warnings) are turned on, and a vector
+    /// doesn't leave its stacks empty at `BRK`, or a subroutine doesn't leave its return address
+    /// on top of the return stack at `JMP2r`.
+    ///
+    /// # Example
+    ///
+    /// ```uxntal
+    /// |0100 @on-reset #01 #02 BRK
+    /// ```
+    StackHeightUnexpected {
+        /// Name of the label or the full `label/sublabel` path of the sublabel of the routine, or
+        /// `|0100` for the reset vector without a label.
+        name: String,
+        /// The instruction ending the routine, i.e. `BRK` or `JMP2r`.
+        instruction: String,
+        /// Whether it's the height of the return stack that is unexpected, rather than of the
+        /// working stack.
+        r#return: bool,
+        /// Number of bytes left over on the stack, or the negated number of bytes missing.
+        bytes: i32,
+        /// Span of the instruction.
+        span: Range<usize>,
+        /// Span of the label definition.
+        other_span: Range<usize>,
+    },
+    /// This warning gets reported when bytes are emitted right after `BRK` or an unconditional
+    /// jump, with no label defined in between, so they can never be executed nor referenced.
+    ///
+    /// # Example
+    ///
+    /// ```uxntal
+    /// |0100 @on-reset #01 #02 POP2 BRK #03
+    /// ```
+    CodeUnreachable {
+        /// The instruction the execution never continues past, e.g. `JMP2r`.
+        instruction: String,
+        /// Span of the first unreachable statement.
+        span: Range<usize>,
+        /// Span of the instruction.
+        other_span: Range<usize>,
+    },
+    /// This warning gets reported when a label assigned to the vector of a device can fall
+    /// through to the next label without reaching `BRK` or an unconditional jump, so the
+    /// execution runs away into the code after it.
+    ///
+    /// # Example
+    ///
+    /// ```uxntal
+    /// |20 @Screen &vector $2
+    /// |0100 ;on-frame .Screen/vector DEO2 BRK
+    /// @on-frame #01 .Screen/vector DEI POP2
+    /// @draw JMP2r
+    /// ```
+    VectorUnterminated {
+        /// Name of the label or the full `label/sublabel` path of the sublabel of the vector.
+        name: String,
+        /// Name of the label the execution falls through to.
+        next_name: String,
+        /// Span of the last statement of the vector.
+        span: Range<usize>,
+        /// Span of the label definition.
+        other_span: Range<usize>,
+    },
+    /// This warning gets reported when
+    /// [`Options::relative_address_margin`](crate::Options::relative_address_margin) is given,
+    /// and a literal relative address is within that many bytes of the limit of 126 bytes, past
+    /// which it's reported with an [`Error::AddressTooFar`](crate::Error::AddressTooFar).
+    ///
+    /// # Example
+    ///
+    /// ```uxntal
+    /// |0100 ,label JMP |017c @label
+    /// ```
+    AddressNearLimit {
+        /// Distance to the label in bytes.
+        distance: usize,
+        /// The margin the distance is within.
+        margin: usize,
+        /// Name of the referenced label.
+        identifier: String,
+        /// Span of the address.
+        span: Range<usize>,
+        /// Span of the label definition.
+        other_span: Range<usize>,
+    },
+    /// This warning gets reported when the bytes reserved in the zero page for a variable or a
+    /// device port, with the relative pads right after its label, overlap the bytes reserved
+    /// for another one.
+    ///
+    /// # Example
+    ///
+    /// ```uxntal
+    /// |00 @x $2
+    /// |01 @y $1
+    /// ```
+    VariablesOverlapping {
+        /// Name of the label or the full `label/sublabel` path of the variable defined later.
+        name: String,
+        /// Name of the label or the full `label/sublabel` path of the variable defined earlier.
+        other_name: String,
+        /// The first address reserved for both variables.
+        address: u16,
+        /// Span of the later label definition.
+        span: Range<usize>,
+        /// Span of the earlier label definition.
+        other_span: Range<usize>,
+    },
+    /// This warning gets reported when a label or a sublabel is given the name of an
+    /// instruction, which is valid, but the name assembles as the instruction whenever it's
+    /// written without a rune.
+    ///
+    /// # Example
+    ///
+    /// ```uxntal
+    /// @POP2 &ADD
+    /// ```
+    InstructionShadowed {
+        /// Name of the label or the sublabel, which is also an instruction.
+        name: String,
+        /// Whether it's a sublabel rather than a label.
+        sublabel: bool,
+        /// Span of the label or sublabel definition.
+        span: Range<usize>,
+    },
+    /// This warning gets reported when a macro and a label have the same name, which is valid,
+    /// as the references to the label always have runes, but ambiguous to the readers.
+    ///
+    /// # Example
+    ///
+    /// ```uxntal
+    /// %draw { #01 .Screen/pixel DEO }
+    /// @draw
+    /// ```
+    MacroNamedLikeLabel {
+        /// The name of both the macro and the label.
+        name: String,
+        /// Span of the label definition.
+        span: Range<usize>,
+        /// Span of the macro definition.
+        other_span: Range<usize>,
+    },
+}
+
+impl Warning {
+    /// Names of every warning, as returned by [`Warning::name`], in the order of their codes.
+    pub const NAMES: &'static [&'static str] = &[
+        "token-trimmed",
+        "redundant-instruction-mode",
+        "unused-macro",
+        "unused-label",
+        "unused-sublabel",
+        "invalid-directive",
+        "aliased-label",
+        "unpadded-label",
+        "reserved-page",
+        "port-width",
+        "overwritten-bytes",
+        "stack-underflow",
+        "stack-height",
+        "unreachable-code",
+        "unterminated-vector",
+        "address-near-limit",
+        "overlapping-variables",
+        "shadowed-instruction",
+        "macro-named-like-label",
+    ];
+
+    /// Returns the stable name of the warning, which can be used to refer to the warning e.g.
+    /// when allowing or denying it.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let (_, warnings) = ruxnasm::assemble(b"%macro { #01 }").unwrap();
+    ///
+    /// assert_eq!(warnings[0].name(), "unused-macro");
+    /// ```
+    pub fn name(&self) -> &'static str {
+        // The names are listed in the order of the codes, so every warning is named only once.
+        let number: usize = self.code()[1..].parse().expect("codes are numbered");
+        Self::NAMES[number - 1]
+    }
+
+    /// Returns the code of the warning. Codes are stable, i.e. they never get reassigned to a
+    /// different warning, so they can be used to look up the warning e.g. with
+    /// `ruxnasm --explain`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let (_, warnings) = ruxnasm::assemble(b"%macro { #01 }").unwrap();
+    ///
+    /// assert_eq!(warnings[0].code(), "W0003");
+    /// ```
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::TokenTrimmed { .. } => "W0001",
+            Self::InstructionModeDefinedMoreThanOnce { .. } => "W0002",
+            Self::MacroUnused { .. } => "W0003",
+            Self::LabelUnused { .. } => "W0004",
+            Self::SublabelUnused { .. } => "W0005",
+            Self::DirectiveInvalid { .. } => "W0006",
+            Self::LabelAliased { .. } => "W0007",
+            Self::UnpaddedLabelReferenced { .. } => "W0008",
+            Self::BytesInReservedMemory { .. } => "W0009",
+            Self::PortWidthMismatch { .. } => "W0010",
+            Self::BytesOverwritten { .. } => "W0011",
+            Self::StackUnderflow { .. } => "W0012",
+            Self::StackHeightUnexpected { .. } => "W0013",
+            Self::CodeUnreachable { .. } => "W0014",
+            Self::VectorUnterminated { .. } => "W0015",
+            Self::AddressNearLimit { .. } => "W0016",
+            Self::VariablesOverlapping { .. } => "W0017",
+            Self::InstructionShadowed { .. } => "W0018",
+            Self::MacroNamedLikeLabel { .. } => "W0019",
+        }
+    }
+
+    /// Returns the span of the source code the warning points at.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let (_, warnings) = ruxnasm::assemble(b"%macro { #01 }").unwrap();
+    ///
+    /// assert_eq!(warnings[0].span(), &(0..6));
+    /// ```
+    pub fn span(&self) -> &Range<usize> {
+        match self {
+            Self::TokenTrimmed { span, .. } => span,
+            Self::InstructionModeDefinedMoreThanOnce { span, .. } => span,
+            Self::MacroUnused { span, .. } => span,
+            Self::LabelUnused { span, .. } => span,
+            Self::SublabelUnused { span, .. } => span,
+            Self::DirectiveInvalid { span, .. } => span,
+            Self::LabelAliased { span, .. } => span,
+            Self::UnpaddedLabelReferenced { span, .. } => span,
+            Self::BytesInReservedMemory { span, .. } => span,
+            Self::PortWidthMismatch { span, .. } => span,
+            Self::BytesOverwritten { span, .. } => span,
+            Self::StackUnderflow { span, .. } => span,
+            Self::StackHeightUnexpected { span, .. } => span,
+            Self::CodeUnreachable { span, .. } => span,
+            Self::VectorUnterminated { span, .. } => span,
+            Self::AddressNearLimit { span, .. } => span,
+            Self::VariablesOverlapping { span, .. } => span,
+            Self::InstructionShadowed { span, .. } => span,
+            Self::MacroNamedLikeLabel { span, .. } => span,
+        }
+    }
+
+    /// Returns the replacements of the source code that fix the warning, if there are any.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let source = format!("|0100 \"{}", "a".repeat(70));
+    /// let (_, warnings) = ruxnasm::assemble(source.as_bytes()).unwrap();
+    ///
+    /// assert_eq!(warnings[0].suggestions()[0].replacement, " \"");
+    /// ```
+    pub fn suggestions(&self) -> &[Suggestion] {
+        match self {
+            Self::TokenTrimmed { suggestions, .. } => suggestions,
+            _ => &[],
+        }
+    }
 }
 
 /// Enum representing every error that can be reported from Ruxnasm.
+///
+/// New errors can be added in minor versions, so matching on them needs a wildcard arm.
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
 pub enum Error {
     /// This error gets reported when an opening parenthesis is not closed i.e. it has
     /// no matching closing parenthesis.
@@ -311,6 +762,11 @@ pub enum Error {
         name: String,
         /// Span of the macro invocation.
         span: Range<usize>,
+        /// The instruction with its letter case corrected, in case the name of the macro
+        /// matches an instruction case-insensitively.
+        instruction: Option<String>,
+        /// Name of a previously defined macro with a similar name, if there is one.
+        similar_macro: Option<String>,
     },
     /// This error gets reported when a macro with the same name is defined
     /// multiple times.
@@ -347,7 +803,7 @@ pub enum Error {
         other_span: Range<usize>,
     },
     /// This error gets reported when an opening brace character is not directly
-    /// preceded by a macro definition.
+    /// preceded by a macro definition or a directive taking a body.
     ///
     /// # Example
     ///
@@ -420,7 +876,9 @@ pub enum Error {
         /// Span of the opening bracket with no matching closing bracket.
         span: Range<usize>,
     },
-    /// This error wraps an error that has been reported from a macro definition.
+    /// This error wraps an error that has been reported from a macro definition. Errors from
+    /// macros invoked in other macros are wrapped once for every invocation they have been
+    /// expanded through, with the outermost invocation on the outside.
     ///
     /// # Example
     ///
@@ -429,7 +887,8 @@ pub enum Error {
     /// macro
     /// ```
     MacroError {
-        /// The error that has been reported from a macro definition.
+        /// The error that has been reported from a macro definition, possibly itself wrapped in
+        /// an inner macro invocation.
         original_error: Box<Error>,
         /// Span of the macro invocation.
         span: Range<usize>,
@@ -478,6 +937,10 @@ pub enum Error {
         identifier: String,
         /// Span of the literal zero-page address.
         span: Range<usize>,
+        /// Replacements that turn the literal zero-page address into a literal absolute
+        /// address, along with the instruction that follows it. Empty if there is no such
+        /// replacement that would preserve the meaning of the program.
+        suggestions: Vec<Suggestion>,
     },
     /// This error gets reported during an attempt to reference a label that
     /// is too far to be a relative address after a literal relative address rune.
@@ -497,6 +960,15 @@ pub enum Error {
         span: Range<usize>,
         /// Span of the label definition that is referenced by the literal relative address.
         other_span: Range<usize>,
+        /// Breakdown of the distance into the regions of the labels lying in between the
+        /// literal relative address and the label definition, in the order of their addresses.
+        /// Each region consists of the name of the label, the number of bytes it contributes to
+        /// the distance, and the span of the label definition.
+        regions: Vec<(String, usize, Range<usize>)>,
+        /// Replacements that turn the literal relative address into a literal absolute
+        /// address, along with the instruction that follows it. Empty if there is no such
+        /// replacement that would preserve the meaning of the program.
+        suggestions: Vec<Suggestion>,
     },
     /// This error gets reported when there are bytes in the zeroth page (first
     /// 256 bytes) of the binary.
@@ -542,4 +1014,1089 @@ pub enum Error {
         chain: Vec<(String, Range<usize>)>,
         span: Range<usize>,
     },
+    /// This error gets reported when a file includes itself, either directly or through other
+    /// included files.
+    ///
+    /// # Example
+    ///
+    /// ```uxntal
+    /// ( library.tal )
+    /// ~library.tal
+    /// ```
+    CircularInclude {
+        /// Paths of the files in the include chain, along with the spans of the includes.
+        chain: Vec<(String, Range<usize>)>,
+        /// Span of the include that closes the chain.
+        span: Range<usize>,
+    },
+    /// This error gets reported when a file to be included cannot be read.
+    ///
+    /// # Example
+    ///
+    /// ```uxntal
+    /// ~does-not-exist.tal
+    /// ```
+    IncludeNotReadable {
+        /// Path of the file.
+        path: String,
+        /// Description of the reason why the file cannot be read.
+        message: String,
+        /// Span of the include.
+        span: Range<usize>,
+    },
+    /// This error gets reported when the word after a conditional rune is not one of the
+    /// directives: `ifdef`, `ifndef`, `else`, `end`, `repeat`, `zeropage`, `library`, or
+    /// `checksum`.
+    ///
+    /// # Example
+    ///
+    /// ```uxntal
+    /// ?if DEBUG
+    /// ```
+    ConditionalDirectiveUnknown {
+        /// The whole unknown directive, including the conditional rune.
+        directive: String,
+        /// Span of the directive.
+        span: Range<usize>,
+    },
+    /// This error gets reported when there is no macro name after an `?ifdef` or `?ifndef`
+    /// directive.
+    ///
+    /// # Example
+    ///
+    /// ```uxntal
+    /// ?ifdef #01 ?end
+    /// ```
+    ConditionalNameExpected {
+        /// Span of the directive.
+        span: Range<usize>,
+    },
+    /// This error gets reported when an `?else` or `?end` directive has no matching `?ifdef`
+    /// or `?ifndef` directive.
+    ///
+    /// # Example
+    ///
+    /// ```uxntal
+    /// ?end
+    /// ```
+    NoMatchingConditional {
+        /// The directive with no matching `?ifdef` or `?ifndef`.
+        directive: String,
+        /// Span of the directive.
+        span: Range<usize>,
+    },
+    /// This error gets reported when an `?ifdef`, `?ifndef`, or `?library` directive is not
+    /// closed i.e. it has no matching `?end` directive.
+    ///
+    /// # Example
+    ///
+    /// ```uxntal
+    /// ?ifdef DEBUG
+    /// ```
+    NoMatchingEnd {
+        /// Span of the directive with no matching `?end`.
+        span: Range<usize>,
+    },
+    /// This error gets reported when a conditional has more than one `?else` directive.
+    ///
+    /// # Example
+    ///
+    /// ```uxntal
+    /// ?ifdef DEBUG ?else ?else ?end
+    /// ```
+    ElseDefinedMoreThanOnce {
+        /// Span of the redundant `?else`.
+        span: Range<usize>,
+        /// Span of the first `?else` of the conditional.
+        other_span: Range<usize>,
+    },
+    /// This error gets reported when there is no constant name after a constant definition
+    /// rune.
+    ///
+    /// # Example
+    ///
+    /// ```uxntal
+    /// = 10
+    /// ```
+    ConstantNameExpected {
+        /// Span of the constant definition rune.
+        span: Range<usize>,
+    },
+    /// This error gets reported when a constant definition is followed neither by a raw
+    /// hexadecimal number nor by an expression in brackets.
+    ///
+    /// # Example
+    ///
+    /// ```uxntal
+    /// =width #10
+    /// ```
+    ConstantValueExpected {
+        /// Span of the constant definition.
+        span: Range<usize>,
+    },
+    /// This error gets reported when an expression of a pad or a constant contains a token that
+    /// is neither a hexadecimal number, a name of a label or a constant, a query, nor an operator.
+    ///
+    /// # Example
+    ///
+    /// ```uxntal
+    /// |[ 0100 #10 + ]
+    /// ```
+    ExpressionTokenInvalid {
+        /// Span of the token.
+        span: Range<usize>,
+    },
+    /// This error gets reported when an expression of a pad or a constant references a label or
+    /// a constant that has not been defined before the expression.
+    ///
+    /// # Example
+    ///
+    /// ```uxntal
+    /// |[ buffer 10 + ] @buffer
+    /// ```
+    ExpressionLabelUndefined {
+        /// Name of the label or constant.
+        name: String,
+        /// Span of the reference.
+        span: Range<usize>,
+    },
+    /// This error gets reported when an operator in an expression of a pad or a constant doesn't
+    /// have two operands before it.
+    ///
+    /// # Example
+    ///
+    /// ```uxntal
+    /// |[ 0100 + ]
+    /// ```
+    OperandsExpected {
+        /// Span of the operator.
+        span: Range<usize>,
+    },
+    /// This error gets reported when an expression of a pad or a constant doesn't result in
+    /// exactly one value.
+    ///
+    /// # Example
+    ///
+    /// ```uxntal
+    /// |[ 0100 10 ]
+    /// ```
+    ExpressionMalformed {
+        /// Number of values the expression results in.
+        values: usize,
+        /// Span of the expression, including the brackets.
+        span: Range<usize>,
+    },
+    /// This error gets reported when an operation in an expression of a pad or a constant
+    /// results in a value that is negative or doesn't fit in a short.
+    ///
+    /// # Example
+    ///
+    /// ```uxntal
+    /// |[ 0100 0200 - ]
+    /// ```
+    ExpressionOverflow {
+        /// Span of the operator.
+        span: Range<usize>,
+    },
+    /// This error gets reported during an attempt to divide by zero in an expression of a pad or
+    /// a constant.
+    ///
+    /// # Example
+    ///
+    /// ```uxntal
+    /// |[ 0100 00 / ]
+    /// ```
+    DivisionByZero {
+        /// Span of the operator.
+        span: Range<usize>,
+    },
+    /// This error gets reported when macros are expanded within other macros, or files are
+    /// included within other files, deeper than the [`Limits`](crate::Limits) allow.
+    ///
+    /// # Example
+    ///
+    /// ```uxntal
+    /// %m0 { m1 } %m1 { m2 } ( ... ) %m256 { #01 }
+    /// m0
+    /// ```
+    DepthLimitExceeded {
+        /// What is nested too deep: a `macro expansion` or an `include`.
+        construct: String,
+        /// The exceeded limit.
+        limit: usize,
+        /// Span of the macro invocation or the include that exceeds the limit.
+        span: Range<usize>,
+    },
+    /// This error gets reported when there is no hexadecimal number after a `?repeat`
+    /// directive.
+    ///
+    /// # Example
+    ///
+    /// ```uxntal
+    /// ?repeat { 00 }
+    /// ```
+    RepeatCountExpected {
+        /// Span of the directive.
+        span: Range<usize>,
+    },
+    /// This error gets reported when a constant whose value doesn't fit in a byte is pushed as a
+    /// byte.
+    ///
+    /// # Example
+    ///
+    /// ```uxntal
+    /// =size [ 10 20 * ]
+    /// .size
+    /// ```
+    ConstantTooLarge {
+        /// Name of the constant.
+        name: String,
+        /// Value of the constant.
+        value: u16,
+        /// Span of the reference.
+        span: Range<usize>,
+        /// Span of the constant definition.
+        other_span: Range<usize>,
+    },
+    /// This error gets reported when the variables of `?zeropage` sections don't fit in the
+    /// 256 bytes of the zero page.
+    ///
+    /// # Example
+    ///
+    /// ```uxntal
+    /// ?zeropage { @buffer $80 @other-buffer $81 }
+    /// ```
+    ZeroPageExhausted {
+        /// Number of bytes needed for the variables allocated so far.
+        size: usize,
+        /// Span of the relative pad of the variable that doesn't fit.
+        span: Range<usize>,
+        /// Span of the `?zeropage` directive.
+        other_span: Range<usize>,
+    },
+    /// This error gets reported when a `?zeropage` section contains a token other than a label
+    /// definition, a sublabel definition, or a relative pad.
+    ///
+    /// # Example
+    ///
+    /// ```uxntal
+    /// ?zeropage { @counter $1 #00 }
+    /// ```
+    ZeroPageTokenInvalid {
+        /// Span of the token.
+        span: Range<usize>,
+    },
+    /// This error gets reported when a backslash in a raw word or a string doesn't start one
+    /// of the known escape sequences: `\0`, `\t`, `\n`, `\r`, `\s`, `\\`, or `` \` ``.
+    ///
+    /// # Example
+    ///
+    /// ```uxntal
+    /// "hello\q
+    /// ```
+    EscapeSequenceInvalid {
+        /// The whole escape sequence, including the backslash.
+        sequence: String,
+        /// Span of the escape sequence.
+        span: Range<usize>,
+    },
+    /// This error gets reported when a string opened with a backtick is not closed i.e. it has
+    /// no matching closing backtick.
+    ///
+    /// # Example
+    ///
+    /// ```uxntal
+    /// `hello world
+    /// ```
+    NoMatchingClosingBacktick {
+        /// Span of the opening backtick.
+        span: Range<usize>,
+    },
+    /// This error gets reported when a `?checksum` directive is not followed by the names of
+    /// the labels or sublabels at the start and at the end of the summed range.
+    ///
+    /// # Example
+    ///
+    /// ```uxntal
+    /// @table 01 02 03 @table-end
+    /// ?checksum table
+    /// ```
+    ChecksumLabelExpected {
+        /// Span of the directive.
+        span: Range<usize>,
+    },
+    /// This error gets reported when the range of a `?checksum` directive is reversed, starts
+    /// in the zero page, or ends after the checksum itself, where the bytes are not yet known.
+    ///
+    /// # Example
+    ///
+    /// ```uxntal
+    /// |0100 ?checksum table table-end
+    /// @table 01 02 03 @table-end
+    /// ```
+    ChecksumRangeInvalid {
+        /// Address of the start of the range.
+        from: u16,
+        /// Address of the end of the range.
+        to: u16,
+        /// Address of the checksum.
+        address: u16,
+        /// Span of the directive along with the names of the labels.
+        span: Range<usize>,
+    },
+    /// This error gets reported when a decimal number after a literal decimal prefix `#+`
+    /// contains an invalid digit.
+    ///
+    /// # Example
+    ///
+    /// ```uxntal
+    /// #+4a
+    /// ```
+    DecimalDigitInvalid {
+        /// The invalid digit.
+        digit: char,
+        /// The whole decimal number with the invalid digit.
+        number: String,
+        /// Span of the invalid digit.
+        span: Range<usize>,
+    },
+    /// This error gets reported when a decimal number after a literal decimal prefix `#+`
+    /// doesn't fit in a short.
+    ///
+    /// # Example
+    ///
+    /// ```uxntal
+    /// #+65536
+    /// ```
+    DecimalNumberTooLarge {
+        /// The whole decimal number.
+        number: String,
+        /// Span of the decimal number.
+        span: Range<usize>,
+    },
+    /// This error gets reported when a query, such as `$here`, is written outside of an
+    /// expression.
+    ///
+    /// # Example
+    ///
+    /// ```uxntal
+    /// #00 $here
+    /// ```
+    QueryOutsideExpression {
+        /// Span of the query.
+        span: Range<usize>,
+    },
+    /// This error gets reported when a binary number after a literal binary prefix `#b`
+    /// contains an invalid digit.
+    ///
+    /// # Example
+    ///
+    /// ```uxntal
+    /// #b00120000
+    /// ```
+    BinaryDigitInvalid {
+        /// The invalid digit.
+        digit: char,
+        /// The whole binary number with the invalid digit.
+        number: String,
+        /// Span of the invalid digit.
+        span: Range<usize>,
+    },
+    /// This error gets reported when a binary number after a literal binary prefix `#b` has
+    /// neither 8 nor 16 digits.
+    ///
+    /// # Example
+    ///
+    /// ```uxntal
+    /// #b0011001
+    /// ```
+    BinaryNumberLengthInvalid {
+        /// Number of digits of the binary number.
+        length: usize,
+        /// The whole binary number.
+        number: String,
+        /// Span of the binary number.
+        span: Range<usize>,
+    },
+    /// This error gets reported when a `?repeat` directive expands into more words than
+    /// [`Limits::repeat_length`](crate::Limits::repeat_length) allows.
+    ///
+    /// # Example
+    ///
+    /// ```uxntal
+    /// ?repeat ffff { ?repeat ffff { 00 } }
+    /// ```
+    RepeatTooLong {
+        /// Number of words the repeat would expand into, including the expansions of the repeats
+        /// nested in it.
+        length: usize,
+        /// The exceeded limit.
+        limit: usize,
+        /// Span of the repeat directive.
+        span: Range<usize>,
+    },
+    /// This error gets reported in place of the errors beyond
+    /// [`Options::error_limit`](crate::Options::error_limit), after which the assembly stops.
+    TooManyErrors {
+        /// The exceeded limit.
+        limit: usize,
+        /// Span of the first error that has been left out.
+        span: Range<usize>,
+    },
+    /// This error gets reported when a statement writes bytes at the addresses that have already
+    /// been written by a previous statement.
+    ///
+    /// # Example
+    ///
+    /// ```uxntal
+    /// |0100 #01 #02 ADD
+    /// |0101 #03
+    /// ```
+    BytesOverwritten {
+        /// The first address that is overwritten.
+        address: u16,
+        /// Span of the statement that overwrites the bytes.
+        span: Range<usize>,
+        /// Span of the statement that has previously written the bytes.
+        other_span: Range<usize>,
+    },
+    /// This error gets reported when the binary is longer than
+    /// [`Options::size`](crate::Options::size).
+    ///
+    /// # Example
+    ///
+    /// ```uxntal
+    /// |0100 #01 #02 ADD
+    /// ```
+    BinaryTooLarge {
+        /// The requested size of the binary.
+        size: usize,
+        /// Span of the statement that goes past the size.
+        span: Range<usize>,
+    },
+}
+
+impl Error {
+    /// Returns the code of the error. Codes are stable, i.e. they never get reassigned to a
+    /// different error, so they can be used to look up the error e.g. with `ruxnasm --explain`.
+    /// Errors reported from a macro definition have the code of the original error.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let (errors, _) = ruxnasm::assemble(b"|0100 .label").unwrap_err();
+    ///
+    /// assert_eq!(errors[0].code(), "E0029");
+    /// ```
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::NoMatchingClosingParenthesis { .. } => "E0001",
+            Self::NoMatchingOpeningParenthesis { .. } => "E0002",
+            Self::MacroNameExpected { .. } => "E0003",
+            Self::LabelExpected { .. } => "E0004",
+            Self::SublabelExpected { .. } => "E0005",
+            Self::SlashInLabelOrSublabel { .. } => "E0006",
+            Self::MoreThanOneSlashInIdentifier { .. } => "E0007",
+            Self::AmpersandAtTheStartOfLabel { .. } => "E0008",
+            Self::IdentifierExpected { .. } => "E0009",
+            Self::HexNumberExpected { .. } => "E0010",
+            Self::HexNumberOrCharacterExpected { .. } => "E0011",
+            Self::CharacterExpected { .. } => "E0012",
+            Self::MoreThanOneByteFound { .. } => "E0013",
+            Self::HexDigitInvalid { .. } => "E0014",
+            Self::HexNumberUnevenLength { .. } => "E0015",
+            Self::HexNumberTooLong { .. } => "E0016",
+            Self::MacroCannotBeAHexNumber { .. } => "E0017",
+            Self::MacroCannotBeAnInstruction { .. } => "E0018",
+            Self::MacroUndefined { .. } => "E0019",
+            Self::MacroDefinedMoreThanOnce { .. } => "E0020",
+            Self::LabelDefinedMoreThanOnce { .. } => "E0021",
+            Self::OpeningBraceNotAfterMacroDefinition { .. } => "E0022",
+            Self::NoMatchingOpeningBrace { .. } => "E0023",
+            Self::NoMatchingClosingBrace { .. } => "E0024",
+            Self::SublabelDefinedWithoutScope { .. } => "E0025",
+            Self::NoMatchingOpeningBracket { .. } => "E0026",
+            Self::NoMatchingClosingBracket { .. } => "E0027",
+            Self::MacroError { original_error, .. } => original_error.code(),
+            Self::SublabelReferencedWithoutScope { .. } => "E0028",
+            Self::LabelUndefined { .. } => "E0029",
+            Self::AddressNotZeroPage { .. } => "E0030",
+            Self::AddressTooFar { .. } => "E0031",
+            Self::BytesInZerothPage { .. } => "E0032",
+            Self::PaddedBackwards { .. } => "E0033",
+            Self::ProgramTooLong { .. } => "E0034",
+            Self::RecursiveMacro { .. } => "E0035",
+            Self::CircularInclude { .. } => "E0036",
+            Self::IncludeNotReadable { .. } => "E0037",
+            Self::ConditionalDirectiveUnknown { .. } => "E0038",
+            Self::ConditionalNameExpected { .. } => "E0039",
+            Self::NoMatchingConditional { .. } => "E0040",
+            Self::NoMatchingEnd { .. } => "E0041",
+            Self::ElseDefinedMoreThanOnce { .. } => "E0042",
+            Self::ConstantNameExpected { .. } => "E0043",
+            Self::ConstantValueExpected { .. } => "E0044",
+            Self::ExpressionTokenInvalid { .. } => "E0045",
+            Self::ExpressionLabelUndefined { .. } => "E0046",
+            Self::OperandsExpected { .. } => "E0047",
+            Self::ExpressionMalformed { .. } => "E0048",
+            Self::ExpressionOverflow { .. } => "E0049",
+            Self::DivisionByZero { .. } => "E0050",
+            Self::DepthLimitExceeded { .. } => "E0051",
+            Self::RepeatCountExpected { .. } => "E0052",
+            Self::ConstantTooLarge { .. } => "E0053",
+            Self::ZeroPageExhausted { .. } => "E0054",
+            Self::ZeroPageTokenInvalid { .. } => "E0055",
+            Self::EscapeSequenceInvalid { .. } => "E0056",
+            Self::NoMatchingClosingBacktick { .. } => "E0057",
+            Self::ChecksumLabelExpected { .. } => "E0058",
+            Self::ChecksumRangeInvalid { .. } => "E0059",
+            Self::DecimalDigitInvalid { .. } => "E0060",
+            Self::DecimalNumberTooLarge { .. } => "E0061",
+            Self::QueryOutsideExpression { .. } => "E0062",
+            Self::BinaryDigitInvalid { .. } => "E0063",
+            Self::BinaryNumberLengthInvalid { .. } => "E0064",
+            Self::RepeatTooLong { .. } => "E0065",
+            Self::TooManyErrors { .. } => "E0066",
+            Self::BytesOverwritten { .. } => "E0067",
+            Self::BinaryTooLarge { .. } => "E0068",
+        }
+    }
+
+    /// Returns the span of the source code the error points at.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let (errors, _) = ruxnasm::assemble(b"|0100 .label").unwrap_err();
+    ///
+    /// assert_eq!(errors[0].span(), &(6..12));
+    /// ```
+    pub fn span(&self) -> &Range<usize> {
+        match self {
+            Self::NoMatchingClosingParenthesis { span, .. } => span,
+            Self::NoMatchingOpeningParenthesis { span, .. } => span,
+            Self::MacroNameExpected { span, .. } => span,
+            Self::LabelExpected { span, .. } => span,
+            Self::SublabelExpected { span, .. } => span,
+            Self::SlashInLabelOrSublabel { span, .. } => span,
+            Self::MoreThanOneSlashInIdentifier { span, .. } => span,
+            Self::AmpersandAtTheStartOfLabel { span, .. } => span,
+            Self::IdentifierExpected { span, .. } => span,
+            Self::HexNumberExpected { span, .. } => span,
+            Self::HexNumberOrCharacterExpected { span, .. } => span,
+            Self::CharacterExpected { span, .. } => span,
+            Self::MoreThanOneByteFound { span, .. } => span,
+            Self::HexDigitInvalid { span, .. } => span,
+            Self::HexNumberUnevenLength { span, .. } => span,
+            Self::HexNumberTooLong { span, .. } => span,
+            Self::MacroCannotBeAHexNumber { span, .. } => span,
+            Self::MacroCannotBeAnInstruction { span, .. } => span,
+            Self::MacroUndefined { span, .. } => span,
+            Self::MacroDefinedMoreThanOnce { span, .. } => span,
+            Self::LabelDefinedMoreThanOnce { span, .. } => span,
+            Self::OpeningBraceNotAfterMacroDefinition { span, .. } => span,
+            Self::NoMatchingOpeningBrace { span, .. } => span,
+            Self::NoMatchingClosingBrace { span, .. } => span,
+            Self::SublabelDefinedWithoutScope { span, .. } => span,
+            Self::NoMatchingOpeningBracket { span, .. } => span,
+            Self::NoMatchingClosingBracket { span, .. } => span,
+            Self::MacroError { span, .. } => span,
+            Self::SublabelReferencedWithoutScope { span, .. } => span,
+            Self::LabelUndefined { span, .. } => span,
+            Self::AddressNotZeroPage { span, .. } => span,
+            Self::AddressTooFar { span, .. } => span,
+            Self::BytesInZerothPage { span, .. } => span,
+            Self::PaddedBackwards { span, .. } => span,
+            Self::ProgramTooLong { span, .. } => span,
+            Self::RecursiveMacro { span, .. } => span,
+            Self::CircularInclude { span, .. } => span,
+            Self::IncludeNotReadable { span, .. } => span,
+            Self::ConditionalDirectiveUnknown { span, .. } => span,
+            Self::ConditionalNameExpected { span, .. } => span,
+            Self::NoMatchingConditional { span, .. } => span,
+            Self::NoMatchingEnd { span, .. } => span,
+            Self::ElseDefinedMoreThanOnce { span, .. } => span,
+            Self::ConstantNameExpected { span, .. } => span,
+            Self::ConstantValueExpected { span, .. } => span,
+            Self::ExpressionTokenInvalid { span, .. } => span,
+            Self::ExpressionLabelUndefined { span, .. } => span,
+            Self::OperandsExpected { span, .. } => span,
+            Self::ExpressionMalformed { span, .. } => span,
+            Self::ExpressionOverflow { span, .. } => span,
+            Self::DivisionByZero { span, .. } => span,
+            Self::DepthLimitExceeded { span, .. } => span,
+            Self::RepeatCountExpected { span, .. } => span,
+            Self::ConstantTooLarge { span, .. } => span,
+            Self::ZeroPageExhausted { span, .. } => span,
+            Self::ZeroPageTokenInvalid { span, .. } => span,
+            Self::EscapeSequenceInvalid { span, .. } => span,
+            Self::NoMatchingClosingBacktick { span, .. } => span,
+            Self::ChecksumLabelExpected { span, .. } => span,
+            Self::ChecksumRangeInvalid { span, .. } => span,
+            Self::DecimalDigitInvalid { span, .. } => span,
+            Self::DecimalNumberTooLarge { span, .. } => span,
+            Self::QueryOutsideExpression { span, .. } => span,
+            Self::BinaryDigitInvalid { span, .. } => span,
+            Self::BinaryNumberLengthInvalid { span, .. } => span,
+            Self::RepeatTooLong { span, .. } => span,
+            Self::TooManyErrors { span, .. } => span,
+            Self::BytesOverwritten { span, .. } => span,
+            Self::BinaryTooLarge { span, .. } => span,
+        }
+    }
+
+    /// Returns the replacements of the source code that fix the error, if there are any.
+    pub fn suggestions(&self) -> &[Suggestion] {
+        match self {
+            Self::AddressNotZeroPage { suggestions, .. } => suggestions,
+            Self::AddressTooFar { suggestions, .. } => suggestions,
+            _ => &[],
+        }
+    }
+}
+
+/// Describes the error in a single line, without the source code it points at.
+///
+/// # Example
+///
+/// ```
+/// let (errors, _) = ruxnasm::assemble(b"|0100 ;missing").unwrap_err();
+///
+/// assert_eq!(errors[0].to_string(), "label `missing` is not defined");
+/// ```
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoMatchingClosingParenthesis { .. } => write!(
+                f,
+                "no matching closing parenthesis found for an opening parenthesis"
+            ),
+            Self::NoMatchingOpeningParenthesis { .. } => write!(
+                f,
+                "no matching opening parenthesis found for a closing parenthesis"
+            ),
+            Self::MacroNameExpected { .. } => write!(f, "expected a macro name"),
+            Self::LabelExpected { .. } => write!(f, "expected a label name"),
+            Self::SublabelExpected { .. } => write!(f, "expected a sublabel name"),
+            Self::SlashInLabelOrSublabel { .. } => write!(
+                f,
+                "label and sublabel names can't include the '/' character"
+            ),
+            Self::MoreThanOneSlashInIdentifier { .. } => {
+                write!(f, "identifiers can't have more than one '/' character")
+            }
+            Self::AmpersandAtTheStartOfLabel { .. } => {
+                write!(f, "label names can't have '&' as their first character")
+            }
+            Self::IdentifierExpected { .. } => write!(f, "expected an identifier"),
+            Self::HexNumberExpected { .. } => write!(f, "expected a hexadecimal number"),
+            Self::HexNumberOrCharacterExpected { .. } => {
+                write!(f, "expected a hexadecimal number or a character")
+            }
+            Self::CharacterExpected { .. } => write!(f, "expected a character"),
+            Self::MoreThanOneByteFound { bytes, .. } => write!(
+                f,
+                "found more than one byte after a raw character rune: {:x?}",
+                bytes
+            ),
+            Self::HexDigitInvalid { digit, number, .. } => write!(
+                f,
+                "invalid digit `{}` in a hexadecimal number `{}`",
+                digit, number
+            ),
+            Self::HexNumberUnevenLength { length, number, .. } => write!(
+                f,
+                "hexadecimal number `{}` has an uneven length of {}",
+                number, length
+            ),
+            Self::HexNumberTooLong { length, number, .. } => write!(
+                f,
+                "hexadecimal number `{}` of length {} is too long",
+                number, length
+            ),
+            Self::MacroCannotBeAHexNumber { number, .. } => write!(
+                f,
+                "`{}` cannot be used as a macro name, as it is a valid hexadecimal number",
+                number
+            ),
+            Self::MacroCannotBeAnInstruction { instruction, .. } => write!(
+                f,
+                "`{}` cannot be used as a macro name, as it is a valid instruction",
+                instruction
+            ),
+            Self::MacroUndefined { name, .. } => write!(f, "macro `{}` is not defined", name),
+            Self::MacroDefinedMoreThanOnce { name, .. } => {
+                write!(f, "macro `{}` is defined multiple times", name)
+            }
+            Self::LabelDefinedMoreThanOnce { name, .. } => {
+                write!(f, "label `{}` is defined multiple times", name)
+            }
+            Self::OpeningBraceNotAfterMacroDefinition { .. } => write!(
+                f,
+                "found an opening brace that is not a part of a macro definition or a directive"
+            ),
+            Self::NoMatchingOpeningBrace { .. } => {
+                write!(f, "no matching opening brace found for a closing brace")
+            }
+            Self::NoMatchingClosingBrace { .. } => {
+                write!(f, "no matching closing brace found for an opening brace")
+            }
+            Self::SublabelDefinedWithoutScope { name, .. } => write!(
+                f,
+                "sublabel `{}` was defined without a previously defined label",
+                name
+            ),
+            Self::NoMatchingOpeningBracket { .. } => {
+                write!(f, "no matching opening bracket found for a closing bracket")
+            }
+            Self::NoMatchingClosingBracket { .. } => {
+                write!(
+                    f,
+                    "no matching closing bracket found for an opening bracket"
+                )
+            }
+            Self::MacroError { original_error, .. } => match original_error.as_ref() {
+                // Errors from macros invoked in other macros are only described once.
+                Self::MacroError { .. } => write!(f, "{}", original_error),
+                _ => write!(f, "{} in a macro invocation", original_error),
+            },
+            Self::SublabelReferencedWithoutScope { name, .. } => write!(
+                f,
+                "sublabel `{}` was referenced without a previously defined label",
+                name
+            ),
+            Self::LabelUndefined { name, .. } => write!(f, "label `{}` is not defined", name),
+            Self::AddressNotZeroPage {
+                address,
+                identifier,
+                ..
+            } => write!(
+                f,
+                "address {:#06x} of label `{}` is not zero-page",
+                address, identifier
+            ),
+            Self::AddressTooFar {
+                distance,
+                identifier,
+                ..
+            } => write!(
+                f,
+                "address of label `{}` is too far to be a relative address (distance {})",
+                identifier, distance
+            ),
+            Self::BytesInZerothPage { .. } => write!(f, "found bytes on the zeroth page"),
+            Self::PaddedBackwards {
+                previous_pointer,
+                desired_pointer,
+                ..
+            } => write!(
+                f,
+                "the binary can only be padded forwards, not from address {} to address {}",
+                previous_pointer, desired_pointer
+            ),
+            Self::ProgramTooLong { .. } => write!(f, "program size exceeded 65536 bytes"),
+            Self::RecursiveMacro { chain, .. } => match chain.as_slice() {
+                [(name, _)] => write!(f, "macro `{}` invokes itself", name),
+                chain => {
+                    write!(f, "found a recursive macro chain ")?;
+                    write_chain(f, chain)
+                }
+            },
+            Self::CircularInclude { chain, .. } => match chain.as_slice() {
+                [(path, _)] => write!(f, "file `{}` includes itself", path),
+                chain => {
+                    write!(f, "found a circular include ")?;
+                    write_chain(f, chain)
+                }
+            },
+            Self::IncludeNotReadable { path, message, .. } => {
+                write!(f, "could not read included file `{}`: {}", path, message)
+            }
+            Self::ConditionalDirectiveUnknown { directive, .. } => {
+                write!(f, "unknown conditional directive `{}`", directive)
+            }
+            Self::ConditionalNameExpected { .. } => {
+                write!(f, "expected a macro name after a conditional directive")
+            }
+            Self::NoMatchingConditional { directive, .. } => write!(
+                f,
+                "no matching `?ifdef` or `?ifndef` found for `{}`",
+                directive
+            ),
+            Self::NoMatchingEnd { .. } => {
+                write!(f, "no matching `?end` found for a conditional directive")
+            }
+            Self::ElseDefinedMoreThanOnce { .. } => {
+                write!(f, "conditional has more than one `?else`")
+            }
+            Self::ConstantNameExpected { .. } => write!(f, "expected a constant name"),
+            Self::ConstantValueExpected { .. } => write!(
+                f,
+                "expected a hexadecimal number after a constant definition"
+            ),
+            Self::ExpressionTokenInvalid { .. } => write!(f, "unexpected token in an expression"),
+            Self::ExpressionLabelUndefined { name, .. } => {
+                write!(f, "label `{}` is not defined before the expression", name)
+            }
+            Self::OperandsExpected { .. } => {
+                write!(f, "expected two operands before an operator")
+            }
+            Self::ExpressionMalformed { values, .. } => {
+                write!(f, "expression results in {} values instead of one", values)
+            }
+            Self::ExpressionOverflow { .. } => {
+                write!(f, "result of the operation doesn't fit in a short")
+            }
+            Self::DivisionByZero { .. } => write!(f, "attempt to divide by zero"),
+            Self::DepthLimitExceeded {
+                construct, limit, ..
+            } => write!(
+                f,
+                "{} is nested too deep, as the depth is limited to {}",
+                construct, limit
+            ),
+            Self::RepeatCountExpected { .. } => {
+                write!(f, "expected a hexadecimal number after a repeat directive")
+            }
+            Self::ConstantTooLarge { name, value, .. } => write!(
+                f,
+                "value {:#06x} of constant `{}` doesn't fit in a byte",
+                value, name
+            ),
+            Self::ZeroPageExhausted { size, .. } => {
+                write!(f, "zero page exhausted, as {} bytes are needed", size)
+            }
+            Self::ZeroPageTokenInvalid { .. } => {
+                write!(f, "unexpected token in a zero-page section")
+            }
+            Self::EscapeSequenceInvalid { sequence, .. } => {
+                write!(f, "unknown escape sequence `{}`", sequence)
+            }
+            Self::NoMatchingClosingBacktick { .. } => {
+                write!(f, "no matching closing backtick found for a string")
+            }
+            Self::ChecksumLabelExpected { .. } => write!(
+                f,
+                "expected the names of two labels after a checksum directive"
+            ),
+            Self::ChecksumRangeInvalid { from, to, .. } => write!(
+                f,
+                "invalid checksum range from {:#06x} to {:#06x}",
+                from, to
+            ),
+            Self::DecimalDigitInvalid { digit, number, .. } => write!(
+                f,
+                "invalid digit `{}` in a decimal number `{}`",
+                digit, number
+            ),
+            Self::DecimalNumberTooLarge { number, .. } => {
+                write!(f, "decimal number `{}` doesn't fit in a short", number)
+            }
+            Self::QueryOutsideExpression { .. } => write!(f, "query outside of an expression"),
+            Self::BinaryDigitInvalid { digit, number, .. } => write!(
+                f,
+                "invalid digit `{}` in a binary number `{}`",
+                digit, number
+            ),
+            Self::BinaryNumberLengthInvalid { length, number, .. } => {
+                write!(f, "binary number `{}` has {} digits", number, length)
+            }
+            Self::RepeatTooLong { length, limit, .. } => write!(
+                f,
+                "repeat expands into {} words, while the expansion is limited to {}",
+                length, limit
+            ),
+            Self::TooManyErrors { limit, .. } => {
+                write!(f, "aborting after {} errors", limit)
+            }
+            Self::BytesOverwritten { address, .. } => {
+                write!(f, "bytes at address {:#06x} are overwritten", address)
+            }
+            Self::BinaryTooLarge { size, .. } => {
+                write!(f, "binary exceeds the size of {} bytes", size)
+            }
+        }
+    }
+}
+
+/// Writes the names of the macros or files of a cycle, ending with the first one again.
+fn write_chain(f: &mut fmt::Formatter<'_>, chain: &[(String, Range<usize>)]) -> fmt::Result {
+    for (name, _) in chain {
+        write!(f, "`{}` -> ", name)?;
+    }
+    match chain.first() {
+        Some((name, _)) => write!(f, "`{}`", name),
+        None => Ok(()),
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Error {}
+
+/// Describes the warning in a single line, without the source code it points at.
+///
+/// # Example
+///
+/// ```
+/// let (_, warnings) = ruxnasm::assemble(b"|0100 @main BRK").unwrap();
+///
+/// assert_eq!(warnings[0].to_string(), "label `main` is never used");
+/// ```
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TokenTrimmed { token, trimmed, .. } => write!(
+                f,
+                "token has been cut off to `{}`, as `{}` is ignored",
+                token, trimmed
+            ),
+            Self::InstructionModeDefinedMoreThanOnce {
+                instruction_mode,
+                instruction,
+                ..
+            } => write!(
+                f,
+                "instruction mode `{}` is defined multiple times for instruction `{}`",
+                instruction_mode, instruction
+            ),
+            Self::MacroUnused { name, .. } => write!(f, "macro `{}` is never used", name),
+            Self::LabelUnused { name, .. } => write!(f, "label `{}` is never used", name),
+            Self::SublabelUnused {
+                label_name, name, ..
+            } => write!(
+                f,
+                "sublabel `{}` is never used within the scope of label `{}`",
+                name, label_name
+            ),
+            Self::DirectiveInvalid { directive, .. } => {
+                write!(f, "invalid directive `{}`", directive)
+            }
+            Self::LabelAliased {
+                name,
+                other_name,
+                address,
+                ..
+            } => write!(
+                f,
+                "label `{}` has the same address {:#06x} as label `{}`",
+                name, address, other_name
+            ),
+            Self::UnpaddedLabelReferenced { name, address, .. } => write!(
+                f,
+                "label `{}` is defined before any pad and resolves to address {:#06x}",
+                name, address
+            ),
+            Self::BytesInReservedMemory { address, limit, .. } => write!(
+                f,
+                "bytes are written to address {:#06x}, in the memory reserved from {:#06x}",
+                address, limit
+            ),
+            Self::PortWidthMismatch {
+                name,
+                width,
+                written,
+                ..
+            } => write!(
+                f,
+                "writing {} to port `{}`, which is {} wide",
+                if *written == 1 { "a byte" } else { "a short" },
+                name,
+                if *width == 1 { "1 byte" } else { "2 bytes" }
+            ),
+            Self::BytesOverwritten { address, .. } => {
+                write!(f, "bytes at address {:#06x} are overwritten", address)
+            }
+            Self::StackUnderflow {
+                name,
+                instruction,
+                r#return,
+                ..
+            } => write!(
+                f,
+                "`{}` underflows the {} stack in `{}`",
+                instruction,
+                stack_name(*r#return),
+                name
+            ),
+            Self::StackHeightUnexpected {
+                name,
+                instruction,
+                r#return,
+                bytes,
+                ..
+            } => write_stack_height(f, name, instruction, *r#return, *bytes),
+            Self::CodeUnreachable { instruction, .. } => {
+                write!(f, "unreachable code after `{}`", instruction)
+            }
+            Self::VectorUnterminated {
+                name, next_name, ..
+            } => write!(
+                f,
+                "vector `{}` falls through to `{}` without `BRK`",
+                name, next_name
+            ),
+            Self::AddressNearLimit {
+                distance,
+                identifier,
+                ..
+            } => write!(
+                f,
+                "relative address of label `{}` is {} bytes away, close to the limit of 126",
+                identifier, distance
+            ),
+            Self::VariablesOverlapping {
+                name,
+                other_name,
+                address,
+                ..
+            } => write!(
+                f,
+                "variable `{}` overlaps `{}` at address {:#04x}",
+                name, other_name, address
+            ),
+            Self::InstructionShadowed { name, sublabel, .. } => write!(
+                f,
+                "{} `{}` has the name of an instruction",
+                if *sublabel { "sublabel" } else { "label" },
+                name
+            ),
+            Self::MacroNamedLikeLabel { name, .. } => {
+                write!(f, "macro `{}` has the name of a label", name)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Warning {}
+
+fn stack_name(r#return: bool) -> &'static str {
+    if r#return {
+        "return"
+    } else {
+        "working"
+    }
+}
+
+/// Describes the unexpected height of a stack at the end of a routine, e.g. `` `on-frame` leaves
+/// 2 bytes on the working stack at `BRK` ``.
+fn write_stack_height(
+    f: &mut fmt::Formatter<'_>,
+    name: &str,
+    instruction: &str,
+    r#return: bool,
+    bytes: i32,
+) -> fmt::Result {
+    let count = bytes.abs();
+    write!(
+        f,
+        "`{}` {} {} byte{} {} the {} stack at `{}`",
+        name,
+        if bytes > 0 { "leaves" } else { "takes" },
+        count,
+        if count == 1 { "" } else { "s" },
+        if bytes > 0 { "on" } else { "too many from" },
+        stack_name(r#return),
+        instruction
+    )
 }
@@ -0,0 +1,145 @@
+use crate::{Chunk, Symbol, Warning};
+#[cfg(feature = "std")]
+use crate::{InstructionSet, LayoutHint, PostProcessor, Reference, Source, TraceStep};
+use alloc::{string::String, vec, vec::Vec};
+#[cfg(feature = "std")]
+use std::collections::HashMap;
+
+/// An assembled program, along with everything known about it after the assembly.
+///
+/// Created by [`assemble_with_options`](crate::assemble_with_options).
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Assembly {
+    /// The binary, which is loaded at address 0x0100. Empty if the binary has been written to
+    /// the [`Records::writer`](crate::Records::writer) instead.
+    pub binary: Vec<u8>,
+    /// Every label and sublabel defined in the program, in the order of their addresses.
+    pub symbols: Vec<Symbol>,
+    /// Names of the macros defined in the program, in alphabetical order.
+    pub macros: Vec<String>,
+    /// The warnings reported during the assembly.
+    pub warnings: Vec<Warning>,
+    /// Address of the first byte emitted by the program, or nothing if the binary is empty.
+    /// Bytes preceding it in the binary are the zeroes of the padding.
+    pub first_address: Option<u16>,
+    /// Address of the last byte of the binary, or nothing if the binary is empty.
+    pub last_address: Option<u16>,
+    /// Number of bytes emitted by the program, i.e. the bytes of the binary other than the
+    /// zeroes of the padding.
+    pub byte_count: usize,
+    /// Length of the binary, which is kept even if the binary itself is not.
+    pub(crate) length: usize,
+}
+
+impl Assembly {
+    pub(crate) fn new(
+        binary: Vec<u8>,
+        symbols: Vec<Symbol>,
+        macros: Vec<String>,
+        warnings: Vec<Warning>,
+        chunks: &[Chunk],
+    ) -> Self {
+        let first_address = chunks.iter().map(|chunk| chunk.address).min();
+        let last_address = chunks
+            .iter()
+            .map(|chunk| chunk.address + (chunk.bytes.len() - 1) as u16)
+            .max();
+        // Bytes written again after padding backwards are counted only once.
+        let mut written = vec![false; 0x10000];
+        for chunk in chunks {
+            let start = chunk.address as usize;
+            for is_written in &mut written[start..start + chunk.bytes.len()] {
+                *is_written = true;
+            }
+        }
+        let byte_count = written[0x0100..]
+            .iter()
+            .filter(|is_written| **is_written)
+            .count();
+
+        Self {
+            length: binary.len(),
+            binary,
+            symbols,
+            macros,
+            warnings,
+            first_address,
+            last_address,
+            byte_count,
+        }
+    }
+
+    /// Length of the binary, in bytes, including the binary written to the
+    /// [`Records::writer`](crate::Records::writer).
+    pub fn length(&self) -> usize {
+        self.length
+    }
+}
+
+/// Everything recorded by [`assemble_with_records`](crate::assemble_with_records) besides the
+/// [`Assembly`] itself, so that the tools needing more than the binary can get it from a single
+/// assembly, along with the hooks into the assembly. The records that take longer to collect are
+/// only collected if they are set to `Some` beforehand.
+///
+/// New records and hooks can be added in minor versions, so the records are set starting from
+/// the defaults.
+///
+/// # Example
+///
+/// ```rust
+/// let mut rom = Vec::new();
+/// let mut records = ruxnasm::Records::default();
+/// records.writer = Some(&mut rom);
+/// let assembly = ruxnasm::assemble_with_records(
+///     b"|0100 #02 #03 ADD",
+///     &Default::default(),
+///     &ruxnasm::FileSystem,
+///     &mut records,
+/// )
+/// .unwrap()
+/// .unwrap();
+///
+/// assert!(assembly.binary.is_empty());
+/// assert_eq!(assembly.length(), 5);
+/// assert_eq!(rom, [0x80, 0x02, 0x80, 0x03, 0x18]);
+/// ```
+#[cfg(feature = "std")]
+#[derive(Default)]
+#[non_exhaustive]
+pub struct Records<'a> {
+    /// Every file included in the program, listed even if the program contains errors.
+    pub sources: Vec<Source>,
+    /// The [`Chunk`]s of bytes emitted from each statement, along with their addresses and the
+    /// spans of the statements. Pads don't emit any chunks.
+    pub chunks: Vec<Chunk>,
+    /// Every step of the emission of the binary. No steps are recorded if the program contains
+    /// errors that prevent the binary from being emitted at all.
+    pub trace: Option<Vec<TraceStep>>,
+    /// Every reference to a label or a sublabel, in the order in which they are written.
+    pub references: Option<Vec<Reference>>,
+    /// The profile of the execution of the binary, mapping the addresses to the number of times
+    /// they have been executed, e.g. in a run of an emulator, along with the routines suggested
+    /// to be moved closer to the jumps to them, so that the jumps could use relative addresses.
+    /// Only the routines entered at least once according to the profile are suggested, the most
+    /// often entered ones first. No hints are given if the program contains errors.
+    pub layout_hints: Option<(&'a HashMap<u16, u64>, Vec<LayoutHint>)>,
+    /// Instruction set whose instructions are recognized and encoded instead of the ones of the
+    /// [`Options::opcode_table`](crate::Options::opcode_table), e.g. for a variant of Uxn with
+    /// extra or remapped instructions.
+    pub instruction_set: Option<&'a dyn InstructionSet>,
+    /// Writer the binary is written to as it's emitted, e.g. straight to a file. With
+    /// [`Options::backward_padding`](crate::Options::backward_padding), the binary is written
+    /// only at the end, since any of its bytes can be overwritten until then.
+    ///
+    /// Nothing is written if the errors are found before the emission, but the binary may be
+    /// partially written if they are found during it. The assembly stops writing at the first
+    /// error of the writer.
+    pub writer: Option<&'a mut dyn std::io::Write>,
+    /// Post-processor the binary is passed through once it's assembled, before it's returned or
+    /// written to the [`Records::writer`], in which case the binary is written only at the end.
+    /// The binaries of libraries are not post-processed. The failure of the post-processor is
+    /// returned as an [`std::io::Error`] of the [`Other`](std::io::ErrorKind::Other) kind, along
+    /// with the reason of the failure.
+    pub post_processor: Option<&'a mut dyn PostProcessor>,
+}
@@ -0,0 +1,86 @@
+// Thread locals can only be initialized with `const` by compilers newer than the pinned one.
+#![allow(clippy::missing_const_for_thread_local)]
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+thread_local! {
+    /// Bytes allocated by the thread so far.
+    static ALLOCATED: Cell<usize> = Cell::new(0);
+    /// Bytes allocated by the thread and not deallocated yet. Memory allocated by one thread and
+    /// deallocated by another makes the number negative.
+    static LIVE: Cell<isize> = Cell::new(0);
+    /// Highest number of the live bytes since the start of the measurement.
+    static PEAK: Cell<isize> = Cell::new(0);
+}
+
+/// System allocator counting the bytes allocated by every thread, so that the memory used by
+/// the requests of the language server, each handled on its own thread, can be measured.
+pub struct Counting;
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let pointer = System.alloc(layout);
+        if !pointer.is_null() {
+            count(layout.size() as isize);
+        }
+        pointer
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let pointer = System.alloc_zeroed(layout);
+        if !pointer.is_null() {
+            count(layout.size() as isize);
+        }
+        pointer
+    }
+
+    unsafe fn dealloc(&self, pointer: *mut u8, layout: Layout) {
+        System.dealloc(pointer, layout);
+        count(-(layout.size() as isize));
+    }
+
+    unsafe fn realloc(&self, pointer: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_pointer = System.realloc(pointer, layout, new_size);
+        if !new_pointer.is_null() {
+            count(new_size as isize - layout.size() as isize);
+        }
+        new_pointer
+    }
+}
+
+fn count(bytes: isize) {
+    // The counters are gone while the thread is being torn down, and the memory freed then is not
+    // counted anymore.
+    let _ = LIVE.try_with(|live| {
+        live.set(live.get() + bytes);
+        let _ = PEAK.try_with(|peak| peak.set(peak.get().max(live.get())));
+    });
+    if bytes > 0 {
+        let _ = ALLOCATED.try_with(|allocated| allocated.set(allocated.get() + bytes as usize));
+    }
+}
+
+/// Memory used by the current thread while running a function.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Usage {
+    /// Bytes allocated in total, including the ones deallocated since.
+    pub allocated: usize,
+    /// Highest number of bytes allocated at the same time.
+    pub peak: usize,
+}
+
+/// Runs the function, measuring the memory it allocates on the current thread.
+pub fn measure<T>(function: impl FnOnce() -> T) -> (T, Usage) {
+    let allocated = ALLOCATED.with(Cell::get);
+    let live = LIVE.with(Cell::get);
+    PEAK.with(|peak| peak.set(live));
+
+    let result = function();
+
+    let usage = Usage {
+        allocated: ALLOCATED.with(Cell::get) - allocated,
+        peak: (PEAK.with(Cell::get) - live) as usize,
+    };
+    (result, usage)
+}
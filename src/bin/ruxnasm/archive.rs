@@ -0,0 +1,62 @@
+use flate2::{write::GzEncoder, Compression};
+use std::io::Write;
+
+const BLOCK_SIZE: usize = 512;
+const NAME_LENGTH: usize = 100;
+
+/// Packs the files into a gzip-compressed tar archive. Modification times and owners are left
+/// out, so the same files always result in the same archive.
+pub fn archive(files: &[(String, Vec<u8>)]) -> Vec<u8> {
+    let mut tar = Vec::new();
+    for (path, contents) in files {
+        if path.len() > NAME_LENGTH {
+            // Longer paths are stored in a GNU long name entry preceding the file.
+            let mut long_name = path.as_bytes().to_vec();
+            long_name.push(0);
+            append(&mut tar, "././@LongLink", b'L', &long_name);
+        }
+        append(&mut tar, path, b'0', contents);
+    }
+    // The end of the archive is marked with two empty blocks.
+    tar.resize(tar.len() + 2 * BLOCK_SIZE, 0);
+
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::best());
+    encoder
+        .write_all(&tar)
+        .expect("writing to a vector never fails");
+    encoder.finish().expect("writing to a vector never fails")
+}
+
+/// Appends an entry of the given type to the tar archive, padding it to the size of a block.
+fn append(tar: &mut Vec<u8>, path: &str, entry_type: u8, contents: &[u8]) {
+    tar.extend_from_slice(&header(path, entry_type, contents.len()));
+    tar.extend_from_slice(contents);
+    tar.resize(tar.len() + padding(contents.len()), 0);
+}
+
+/// Builds the header of a tar entry. Paths longer than 100 bytes are cut off.
+fn header(path: &str, entry_type: u8, size: usize) -> [u8; BLOCK_SIZE] {
+    let name = &path.as_bytes()[..path.len().min(NAME_LENGTH)];
+
+    let mut header = [0; BLOCK_SIZE];
+    header[..name.len()].copy_from_slice(name);
+    header[100..108].copy_from_slice(b"0000644\0");
+    header[108..116].copy_from_slice(b"0000000\0");
+    header[116..124].copy_from_slice(b"0000000\0");
+    header[124..136].copy_from_slice(format!("{:011o}\0", size).as_bytes());
+    header[136..148].copy_from_slice(b"00000000000\0");
+    header[156] = entry_type;
+    header[257..265].copy_from_slice(b"ustar  \0");
+
+    // The checksum is computed as if its own field was filled with spaces.
+    header[148..156].copy_from_slice(b"        ");
+    let checksum: u32 = header.iter().map(|byte| *byte as u32).sum();
+    header[148..156].copy_from_slice(format!("{:06o}\0 ", checksum).as_bytes());
+
+    header
+}
+
+/// Returns the number of bytes needed to fill up the last block of a file of the given size.
+fn padding(size: usize) -> usize {
+    (BLOCK_SIZE - size % BLOCK_SIZE) % BLOCK_SIZE
+}
@@ -1,61 +1,829 @@
+use crate::{config::Config, explanations, standard};
 use std::{
     env,
     path::{Path, PathBuf},
     process::exit,
 };
 
-const HELP_MESSAGE: &'static str = r#"Usage: ruxnasm [OPTIONS] INPUT OUTPUT
+const HELP_MESSAGE: &'static str = r#"Usage: ruxnasm [OPTIONS] INPUT... OUTPUT
+       ruxnasm --out-dir DIR [OPTIONS] INPUT...
+       ruxnasm --library [OPTIONS] INPUT...
+       ruxnasm --explain CODE
+       ruxnasm dasm BINARY
+       ruxnasm fmt INPUT...
+       ruxnasm lsp
+       ruxnasm verify INPUT...
+
+Multiple INPUT files are assembled as if they were concatenated in order.
+The dasm subcommand prints the instructions of BINARY along with their
+addresses. The fmt subcommand rewrites the INPUT files in the canonical
+format, changing only the whitespace between tokens and comments. The lsp
+subcommand runs a language server over the standard input and output, which
+answers ruxnasm/status requests with the documents it holds, the requests it is
+running, and the timings and the memory of the messages it has handled. The
+verify subcommand assembles the INPUT files, disassembles the binary, and checks
+that assembling the disassembled instructions gives the same binary.
+INPUT and OUTPUT can be omitted if they are set in a ruxnasm.toml file found in
+the current directory or any of its parents.
 
 Options:
     -h, --help          Display this message
     -V, --version       Print version info and exit
+    -D NAME[=HEX]       Define an empty macro NAME, or a macro NAME expanding to
+                        the literal number #HEX
+        --std NAME      Prepend the standard library NAME, e.g. lib for the latest
+                        version of the library of common macros, or lib@1 for
+                        its first version
+        --allow NAME    Don't report the warning NAME
+        --deny NAME     Report the warning NAME as an error
+        --fix           Apply the suggested fixes to the input file
+        --include DIR   Look for the included files not found relative to the
+                        working directory in DIR, which can be given many times
+        --library       Check INPUT as a library meant to be included in other
+                        programs, printing its labels instead of writing a binary
+        --trace-emit    Print every step of the emission of the binary
+        --out-dir DIR   Write the binary to DIR as NAME-HASH.rom, where NAME is the
+                        name of the last input file, and link DIR/latest to it
+        --post-cmd CMD  Pass the binary through the shell command CMD, from its
+                        standard input to its standard output, before writing it
+        --symbols FILE  Write the addresses of all labels to FILE
+        --memory-map FILE
+                        Write an SVG map of the memory to FILE, with the written
+                        bytes colored by the labels owning them
+        --map FILE      Write a linker-style map of the memory to FILE, with the
+                        ranges owned by the labels, the padding between them, and
+                        the layout of the zero page
+        --source-map FILE
+                        Write a JSON map from the addresses of the binary to the
+                        files, spans, and macro invocations they come from to FILE
+        --debug-info FILE
+                        Write the scopes of the labels, the ranges of the macro
+                        expansions, and the zero-page variables as JSON to FILE
+        --profile FILE  Read the hit counts of the addresses from an emulator run
+                        from FILE, and print which executed routines could be
+                        moved closer to their jumps to use relative addresses
+        --emit KIND     Write a file of KIND next to the binary: graph, a Graphviz
+                        graph of the references to the labels and the invocations
+                        of the macros, as BINARY.dot. Can be given more than once
+        --verify-against ROM
+                        Compare the binary with ROM, e.g. one assembled by another
+                        assembler, and list the addresses at which they differ
+                        along with the closest labels
+        --compat NAME   Follow the command line conventions of the assembler NAME:
+                        uxnasm, to take exactly INPUT and OUTPUT, always write
+                        the symbols to OUTPUT.sym, and print a summary of the
+                        assembly like uxnasm does
+        --embed-source  Write a compressed archive of the sources next to the
+                        binary, as BINARY.src.tar.gz, to be able to assemble it
+                        again later
+        --stats         Print the size of the binary, the highest address written,
+                        the free space left, and the numbers of the labels and the
+                        macros after a successful assembly
+        --report KIND   Print a report of KIND after a successful assembly: sizes,
+                        the number of bytes from every label to the next one,
+                        the largest first, or references, the files, lines, and
+                        runes of the references to every label. Can be given
+                        more than once
+        --crash-dump    In case the assembler crashes, write an archive with the
+                        input, the options, the backtrace, and the tokens to the
+                        temporary directory, to be attached to the bug report
+        --message-format FORMAT
+                        Print diagnostics as FORMAT: human (default) or sarif
+        --diagnostic-style STYLE
+                        Print human diagnostics in STYLE: rich (default), with
+                        the source code they point at, or short, with a single
+                        line per diagnostic
+        --error-limit N Stop after N errors, 20 by default, or never if N is 0
+        --pad-to SIZE   Pad the binary with zeros up to SIZE bytes, at most 65280
+        --format FORMAT Write the binary as FORMAT: rom (default), c-header, a C
+                        array along with its length, rust, a Rust static, or
+                        hexdump, a dump of the bytes along with their labels
+        --opcode-table TABLE
+                        Assemble the instructions with the opcodes of TABLE:
+                        modern (default), or legacy, the numbering from before
+                        the opcodes were reordered, for older sources
+        --reserved-memory PROFILE
+                        Warn about the bytes written to the memory reserved by
+                        PROFILE: uxn-legacy (default), the top page, none, or
+                        the hexadecimal address the reserved memory starts at
+        --opcodes FILE  Add the extra or remapped instructions listed in FILE to
+                        the opcode table, one MNEMONIC,HEX pair per line, e.g.
+                        for a variant of Uxn
+        --no-trim       Keep the pads at the end of the binary, up to the highest
+                        address padded to
+        --check-stacks  Warn about the routines that underflow their stacks, or
+                        leave unexpected heights of their stacks at BRK or JMP2r
+        --relative-margin N
+                        Warn about the relative addresses within N bytes of their
+                        limit of 126 bytes, e.g. 8, which break with the next few
+                        bytes added between them and their labels
+        --allow-backward-padding
+                        Allow absolute pads back to the bytes written before, e.g.
+                        to patch a table, and warn about the overwritten bytes
+        --diagnostics-output FILE
+                        Write the diagnostics to FILE instead of the standard error
+        --tab-width N   Render tabs in the source code shown by diagnostics as N
+                        spaces, 2 by default
+        --context-lines N
+                        Show at least N lines of the source code after the start
+                        and before the end of multi-line labels, instead of 3 and 1
+        --charset NAME  Draw the diagnostics with NAME characters: unicode (default)
+                        or ascii
+        --color WHEN    Color the diagnostics: auto (default), always, or never. With
+                        auto, diagnostics are colored only when printed to a
+                        terminal and the NO_COLOR environment variable isn't set
+        --explain CODE  Print a detailed explanation of an error or warning code
+    -Werror             Report all warnings as errors
 "#;
 const VERSION_MESSAGE: &'static str = concat!("ruxnasm ", env!("CARGO_PKG_VERSION"));
 
+/// Size of the largest binary, which fills the memory after the zero page.
+const MAX_BINARY_SIZE: usize = 0x10000 - 0x0100;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum WarningLevel {
+    Allow,
+    Warn,
+    Deny,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum MessageFormat {
+    Human,
+    Sarif,
+}
+
+/// How the diagnostics in the human message format are rendered.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum DiagnosticStyle {
+    Rich,
+    Short,
+}
+
+/// Characters with which the source code shown by diagnostics is framed and underlined.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Charset {
+    Unicode,
+    Ascii,
+}
+
+/// How the source code is laid out in the rich diagnostics.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Layout {
+    pub tab_width: usize,
+    /// Lines shown after the start and before the end of multi-line labels, or the defaults of
+    /// `codespan-reporting` if not set.
+    pub context_lines: Option<usize>,
+    pub charset: Charset,
+}
+
+/// Whether the diagnostics are colored.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ColorChoice {
+    Auto,
+    Always,
+    Never,
+}
+
+/// Report about the assembled program printed after a successful assembly.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Report {
+    /// Sizes of the labels, i.e. the numbers of bytes up to the next labels.
+    Sizes,
+    /// References to the labels, along with the places they are made in.
+    References,
+}
+
+/// Additional file written next to the binary.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Emit {
+    /// Graph of the references to the labels and the invocations of the macros, in the DOT
+    /// format.
+    Graph,
+}
+
+/// Assembler whose command line conventions are followed instead of the usual ones.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Compat {
+    /// The reference assembler: exactly INPUT and OUTPUT, with the symbols always written to
+    /// OUTPUT.sym and a summary of the assembly printed to the standard error.
+    Uxnasm,
+}
+
+/// How the binary is written to the output.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum OutputFormat {
+    Rom,
+    CHeader,
+    Rust,
+    Hexdump,
+}
+
+/// Where the binary is written to.
+#[derive(Debug)]
+pub enum Output {
+    File(PathBuf),
+    Directory(PathBuf),
+}
+
 #[derive(Debug)]
 pub struct Arguments {
-    input_file_path: PathBuf,
-    output_file_path: PathBuf,
+    input_file_paths: Vec<PathBuf>,
+    output: Option<Output>,
+    defines: Vec<(String, Option<String>)>,
+    std_libraries: Vec<String>,
+    include_directories: Vec<PathBuf>,
+    post_commands: Vec<String>,
+    symbols_file_path: Option<PathBuf>,
+    memory_map_file_path: Option<PathBuf>,
+    map_file_path: Option<PathBuf>,
+    source_map_file_path: Option<PathBuf>,
+    debug_info_file_path: Option<PathBuf>,
+    profile_file_path: Option<PathBuf>,
+    embed_source: bool,
+    stats: bool,
+    reports: Vec<Report>,
+    emits: Vec<Emit>,
+    reference_file_path: Option<PathBuf>,
+    compat: Option<Compat>,
+    crash_dump: bool,
+    warning_levels: Vec<(String, WarningLevel)>,
+    warnings_as_errors: bool,
+    fix: bool,
+    trace_emit: bool,
+    library: bool,
+    message_format: MessageFormat,
+    diagnostic_style: DiagnosticStyle,
+    color_choice: ColorChoice,
+    layout: Layout,
+    diagnostics_output_path: Option<PathBuf>,
+    error_limit: Option<usize>,
+    backward_padding: bool,
+    size: Option<usize>,
+    trim: bool,
+    output_format: OutputFormat,
+    opcode_table: ruxnasm::OpcodeTable,
+    reserved_memory: Option<ruxnasm::ReservedMemory>,
+    opcodes_file_path: Option<PathBuf>,
+    check_stacks: bool,
+    relative_address_margin: Option<usize>,
 }
 
 impl Arguments {
-    pub fn input_file_path(&self) -> &Path {
-        &self.input_file_path
+    /// Returns the paths of the input files, in the order in which they are assembled. There is
+    /// always at least one input file.
+    pub fn input_file_paths(&self) -> &[PathBuf] {
+        &self.input_file_paths
+    }
+
+    /// Returns where the binary is written to, which is always present unless a library is
+    /// being checked.
+    pub fn output(&self) -> Option<&Output> {
+        self.output.as_ref()
+    }
+
+    /// Returns the names of the macros defined on the command line, along with the hexadecimal
+    /// numbers they expand to.
+    pub fn defines(&self) -> &[(String, Option<String>)] {
+        &self.defines
+    }
+
+    /// Returns the names of the standard libraries to prepend to the input files, each with its
+    /// version and mentioned only once.
+    pub fn std_libraries(&self) -> &[String] {
+        &self.std_libraries
+    }
+
+    /// Returns the directories searched for the included files, in order.
+    pub fn include_directories(&self) -> &[PathBuf] {
+        &self.include_directories
+    }
+
+    /// Returns the commands which the binary is passed through before it gets written, in
+    /// order.
+    pub fn post_commands(&self) -> &[String] {
+        &self.post_commands
+    }
+
+    pub fn symbols_file_path(&self) -> Option<&Path> {
+        self.symbols_file_path.as_deref()
+    }
+
+    pub fn memory_map_file_path(&self) -> Option<&Path> {
+        self.memory_map_file_path.as_deref()
+    }
+
+    pub fn map_file_path(&self) -> Option<&Path> {
+        self.map_file_path.as_deref()
+    }
+
+    pub fn source_map_file_path(&self) -> Option<&Path> {
+        self.source_map_file_path.as_deref()
+    }
+
+    pub fn debug_info_file_path(&self) -> Option<&Path> {
+        self.debug_info_file_path.as_deref()
+    }
+
+    pub fn profile_file_path(&self) -> Option<&Path> {
+        self.profile_file_path.as_deref()
+    }
+
+    pub fn embed_source(&self) -> bool {
+        self.embed_source
+    }
+
+    pub fn stats(&self) -> bool {
+        self.stats
+    }
+
+    pub fn reports(&self) -> &[Report] {
+        &self.reports
+    }
+
+    pub fn emits(&self) -> &[Emit] {
+        &self.emits
+    }
+
+    pub fn compat(&self) -> Option<Compat> {
+        self.compat
+    }
+
+    pub fn reference_file_path(&self) -> Option<&Path> {
+        self.reference_file_path.as_deref()
+    }
+
+    pub fn crash_dump(&self) -> bool {
+        self.crash_dump
+    }
+
+    pub fn fix(&self) -> bool {
+        self.fix
+    }
+
+    pub fn trace_emit(&self) -> bool {
+        self.trace_emit
+    }
+
+    pub fn library(&self) -> bool {
+        self.library
+    }
+
+    pub fn message_format(&self) -> MessageFormat {
+        self.message_format
+    }
+
+    pub fn diagnostic_style(&self) -> DiagnosticStyle {
+        self.diagnostic_style
+    }
+
+    pub fn color_choice(&self) -> ColorChoice {
+        self.color_choice
+    }
+
+    pub fn layout(&self) -> Layout {
+        self.layout
+    }
+
+    pub fn diagnostics_output_path(&self) -> Option<&Path> {
+        self.diagnostics_output_path.as_deref()
+    }
+
+    /// Returns the maximum number of errors reported, if there is one.
+    pub fn error_limit(&self) -> Option<usize> {
+        self.error_limit
     }
 
-    pub fn output_file_path(&self) -> &Path {
-        &self.output_file_path
+    pub fn backward_padding(&self) -> bool {
+        self.backward_padding
+    }
+
+    /// Returns the size the binary is padded to, if there is one.
+    pub fn size(&self) -> Option<usize> {
+        self.size
+    }
+
+    pub fn trim(&self) -> bool {
+        self.trim
+    }
+
+    pub fn check_stacks(&self) -> bool {
+        self.check_stacks
+    }
+
+    pub fn relative_address_margin(&self) -> Option<usize> {
+        self.relative_address_margin
+    }
+
+    pub fn output_format(&self) -> OutputFormat {
+        self.output_format
+    }
+
+    pub fn opcode_table(&self) -> ruxnasm::OpcodeTable {
+        self.opcode_table
+    }
+
+    pub fn reserved_memory(&self) -> Option<ruxnasm::ReservedMemory> {
+        self.reserved_memory
+    }
+
+    pub fn opcodes_file_path(&self) -> Option<&Path> {
+        self.opcodes_file_path.as_deref()
+    }
+
+    /// Returns the level of the warning with the given name. The last `--allow` or `--deny`
+    /// option mentioning the warning takes precedence over `-Werror`.
+    pub fn warning_level(&self, name: &str) -> WarningLevel {
+        match self
+            .warning_levels
+            .iter()
+            .rev()
+            .find(|(warning_name, _)| warning_name == name)
+        {
+            Some((_, level)) => *level,
+            None if self.warnings_as_errors => WarningLevel::Deny,
+            None => WarningLevel::Warn,
+        }
     }
 }
 
 pub enum Error {
     NoInputProvided,
     NoOutputProvided,
-    UnexpectedArgument { argument: String },
-    UnrecognizedOption { option: String },
+    UnrecognizedOption {
+        option: String,
+    },
+    MissingOptionValue {
+        option: String,
+    },
+    UnknownWarning {
+        name: String,
+    },
+    UnknownCode {
+        code: String,
+    },
+    UnknownMessageFormat {
+        format: String,
+    },
+    UnknownOutputFormat {
+        format: String,
+    },
+    UnknownOpcodeTable {
+        name: String,
+    },
+    UnknownReservedMemory {
+        profile: String,
+    },
+    UnknownReport {
+        kind: String,
+    },
+    UnknownEmit {
+        kind: String,
+    },
+    UnknownCompat {
+        name: String,
+    },
+    UnknownDiagnosticStyle {
+        style: String,
+    },
+    UnknownColorChoice {
+        when: String,
+    },
+    UnknownCharset {
+        name: String,
+    },
+    InvalidNumber {
+        option: String,
+        value: String,
+    },
+    NumberTooLarge {
+        option: String,
+        value: usize,
+        limit: usize,
+    },
+    UnknownStdLibrary {
+        name: String,
+    },
+    UnexpectedArgument {
+        argument: String,
+    },
 }
 
-pub fn parse_arguments() -> Result<Arguments, Error> {
-    if env::args().len() == 1 {
+/// A tool run instead of the assembler.
+pub enum Subcommand {
+    /// `ruxnasm dasm BINARY`
+    Disassemble(PathBuf),
+    /// `ruxnasm fmt INPUT...`
+    Format(Vec<PathBuf>),
+    /// `ruxnasm lsp`
+    LanguageServer,
+    /// `ruxnasm verify INPUT...`
+    Verify(Vec<PathBuf>),
+}
+
+/// Parses the arguments of the subcommand if the assembler has been run with one, and returns
+/// nothing otherwise.
+pub fn parse_subcommand() -> Option<Result<Subcommand, Error>> {
+    let mut args = env::args().skip(1);
+    let subcommand = args.next()?;
+    let file_paths: Vec<PathBuf> = args.map(PathBuf::from).collect();
+
+    match subcommand.as_str() {
+        "dasm" => Some(match file_paths.as_slice() {
+            [binary_file_path] => Ok(Subcommand::Disassemble(binary_file_path.clone())),
+            [] => Err(Error::NoInputProvided),
+            [_, argument, ..] => Err(Error::UnexpectedArgument {
+                argument: argument.to_string_lossy().into_owned(),
+            }),
+        }),
+        "fmt" if file_paths.is_empty() => Some(Err(Error::NoInputProvided)),
+        "fmt" => Some(Ok(Subcommand::Format(file_paths))),
+        "verify" if file_paths.is_empty() => Some(Err(Error::NoInputProvided)),
+        "verify" => Some(Ok(Subcommand::Verify(file_paths))),
+        // Editors commonly tell the language servers to communicate over the standard streams
+        // explicitly, which is the only way supported anyway.
+        "lsp" => Some(
+            match file_paths
+                .iter()
+                .find(|argument| argument.as_os_str() != "--stdio")
+            {
+                Some(argument) => Err(Error::UnexpectedArgument {
+                    argument: argument.to_string_lossy().into_owned(),
+                }),
+                None => Ok(Subcommand::LanguageServer),
+            },
+        ),
+        _ => None,
+    }
+}
+
+/// Parses the command-line arguments, which take precedence over the values set in the
+/// configuration file.
+pub fn parse_arguments(config: Option<Config>) -> Result<Arguments, Error> {
+    if env::args().len() == 1 && config.is_none() {
         exit_with_help_message();
     }
+    let config = config.unwrap_or_default();
 
     let mut args = env::args();
     args.next();
-    let mut input_file_path: Option<PathBuf> = None;
-    let mut output_file_path: Option<PathBuf> = None;
+    let mut file_paths: Vec<PathBuf> = Vec::new();
+    let mut out_dir: Option<PathBuf> = None;
+    let mut defines: Vec<(String, Option<String>)> = Vec::new();
+    let mut std_libraries: Vec<String> = Vec::new();
+    let mut include_directories: Vec<PathBuf> = config.include_directories;
+    let mut post_commands: Vec<String> = Vec::new();
+    let mut symbols_file_path: Option<PathBuf> = config.symbols_file_path;
+    let mut memory_map_file_path: Option<PathBuf> = None;
+    let mut map_file_path: Option<PathBuf> = None;
+    let mut source_map_file_path: Option<PathBuf> = None;
+    let mut debug_info_file_path: Option<PathBuf> = None;
+    let mut profile_file_path: Option<PathBuf> = None;
+    let mut diagnostics_output_path: Option<PathBuf> = None;
+    let mut error_limit: Option<usize> = Some(20);
+    let mut warning_levels: Vec<(String, WarningLevel)> = config.warning_levels;
+    let mut warnings_as_errors = config.warnings_as_errors;
+    let mut embed_source = false;
+    let mut stats = false;
+    let mut reports: Vec<Report> = Vec::new();
+    let mut emits: Vec<Emit> = Vec::new();
+    let mut reference_file_path: Option<PathBuf> = None;
+    let mut compat: Option<Compat> = None;
+    let mut crash_dump = false;
+    let mut fix = false;
+    let mut trace_emit = false;
+    let mut library = false;
+    let mut backward_padding = false;
+    let mut size: Option<usize> = None;
+    let mut trim = true;
+    let mut check_stacks = false;
+    let mut relative_address_margin: Option<usize> = None;
+    let mut output_format = config.output_format.unwrap_or(OutputFormat::Rom);
+    let mut opcode_table = ruxnasm::OpcodeTable::Modern;
+    let mut reserved_memory = Some(ruxnasm::ReservedMemory::UXN_LEGACY);
+    let mut opcodes_file_path: Option<PathBuf> = None;
+    let mut message_format = config.message_format.unwrap_or(MessageFormat::Human);
+    let mut diagnostic_style = config.diagnostic_style.unwrap_or(DiagnosticStyle::Rich);
+    let mut color_choice = ColorChoice::Auto;
+    let mut layout = Layout {
+        tab_width: config.tab_width.unwrap_or(2),
+        context_lines: config.context_lines,
+        charset: config.charset.unwrap_or(Charset::Unicode),
+    };
 
-    for arg in args {
+    while let Some(arg) = args.next() {
         if arg.starts_with("--") {
-            match &arg[2..] {
+            let (option, value) = match arg.find('=') {
+                Some(position) => (&arg[2..position], Some(arg[position + 1..].to_owned())),
+                None => (&arg[2..], None),
+            };
+            match option {
                 "help" => exit_with_help_message(),
                 "version" => exit_with_version_message(),
+                "fix" => fix = true,
+                "embed-source" => embed_source = true,
+                "stats" => stats = true,
+                "report" => {
+                    let kind = option_value(option, value, &mut args)?;
+                    reports.push(match kind.as_str() {
+                        "sizes" => Report::Sizes,
+                        "references" => Report::References,
+                        _ => return Err(Error::UnknownReport { kind }),
+                    });
+                }
+                "verify-against" => {
+                    reference_file_path = Some(option_value(option, value, &mut args)?.into());
+                }
+                "emit" => {
+                    let kind = option_value(option, value, &mut args)?;
+                    emits.push(match kind.as_str() {
+                        "graph" => Emit::Graph,
+                        _ => return Err(Error::UnknownEmit { kind }),
+                    });
+                }
+                "compat" => {
+                    let name = option_value(option, value, &mut args)?;
+                    compat = Some(match name.as_str() {
+                        "uxnasm" => Compat::Uxnasm,
+                        _ => return Err(Error::UnknownCompat { name }),
+                    });
+                }
+                "crash-dump" => crash_dump = true,
+                "trace-emit" => trace_emit = true,
+                "library" => library = true,
+                "allow-backward-padding" => backward_padding = true,
+                "no-trim" => trim = false,
+                "check-stacks" => check_stacks = true,
+                "out-dir" => {
+                    out_dir = Some(option_value(option, value, &mut args)?.into());
+                }
+                "include" => {
+                    include_directories.push(option_value(option, value, &mut args)?.into());
+                }
+                "post-cmd" => post_commands.push(option_value(option, value, &mut args)?),
+                "symbols" => {
+                    symbols_file_path = Some(option_value(option, value, &mut args)?.into());
+                }
+                "memory-map" => {
+                    memory_map_file_path = Some(option_value(option, value, &mut args)?.into());
+                }
+                "map" => {
+                    map_file_path = Some(option_value(option, value, &mut args)?.into());
+                }
+                "source-map" => {
+                    source_map_file_path = Some(option_value(option, value, &mut args)?.into());
+                }
+                "debug-info" => {
+                    debug_info_file_path = Some(option_value(option, value, &mut args)?.into());
+                }
+                "profile" => {
+                    profile_file_path = Some(option_value(option, value, &mut args)?.into());
+                }
+                "diagnostics-output" => {
+                    diagnostics_output_path = Some(option_value(option, value, &mut args)?.into());
+                }
+                "message-format" => {
+                    let format = option_value(option, value, &mut args)?;
+                    message_format = match format.as_str() {
+                        "human" => MessageFormat::Human,
+                        "sarif" => MessageFormat::Sarif,
+                        _ => return Err(Error::UnknownMessageFormat { format }),
+                    };
+                }
+                "format" => {
+                    let format = option_value(option, value, &mut args)?;
+                    output_format = match format.as_str() {
+                        "rom" => OutputFormat::Rom,
+                        "c-header" => OutputFormat::CHeader,
+                        "rust" => OutputFormat::Rust,
+                        "hexdump" => OutputFormat::Hexdump,
+                        _ => return Err(Error::UnknownOutputFormat { format }),
+                    };
+                }
+                "opcode-table" => {
+                    let name = option_value(option, value, &mut args)?;
+                    opcode_table = match name.as_str() {
+                        "modern" => ruxnasm::OpcodeTable::Modern,
+                        "legacy" => ruxnasm::OpcodeTable::Legacy,
+                        _ => return Err(Error::UnknownOpcodeTable { name }),
+                    };
+                }
+                "reserved-memory" => {
+                    let profile = option_value(option, value, &mut args)?;
+                    reserved_memory = match profile.as_str() {
+                        "uxn-legacy" => Some(ruxnasm::ReservedMemory::UXN_LEGACY),
+                        "none" => None,
+                        _ => match u16::from_str_radix(&profile, 16) {
+                            Ok(start) => Some(ruxnasm::ReservedMemory {
+                                profile: "custom",
+                                start,
+                            }),
+                            Err(_) => return Err(Error::UnknownReservedMemory { profile }),
+                        },
+                    };
+                }
+                "opcodes" => {
+                    opcodes_file_path = Some(option_value(option, value, &mut args)?.into());
+                }
+                "diagnostic-style" => {
+                    let style = option_value(option, value, &mut args)?;
+                    diagnostic_style = match style.as_str() {
+                        "rich" => DiagnosticStyle::Rich,
+                        "short" => DiagnosticStyle::Short,
+                        _ => return Err(Error::UnknownDiagnosticStyle { style }),
+                    };
+                }
+                "color" => {
+                    let when = option_value(option, value, &mut args)?;
+                    color_choice = match when.as_str() {
+                        "auto" => ColorChoice::Auto,
+                        "always" => ColorChoice::Always,
+                        "never" => ColorChoice::Never,
+                        _ => return Err(Error::UnknownColorChoice { when }),
+                    };
+                }
+                "error-limit" => {
+                    error_limit = match number_value(option, value, &mut args)? {
+                        0 => None,
+                        limit => Some(limit),
+                    };
+                }
+                "pad-to" => {
+                    let value = number_value(option, value, &mut args)?;
+                    if value > MAX_BINARY_SIZE {
+                        return Err(Error::NumberTooLarge {
+                            option: option.to_owned(),
+                            value,
+                            limit: MAX_BINARY_SIZE,
+                        });
+                    }
+                    size = Some(value);
+                }
+                "relative-margin" => {
+                    relative_address_margin = Some(number_value(option, value, &mut args)?);
+                }
+                "tab-width" => {
+                    layout.tab_width = number_value(option, value, &mut args)?;
+                }
+                "context-lines" => {
+                    layout.context_lines = Some(number_value(option, value, &mut args)?);
+                }
+                "charset" => {
+                    let name = option_value(option, value, &mut args)?;
+                    layout.charset = match name.as_str() {
+                        "unicode" => Charset::Unicode,
+                        "ascii" => Charset::Ascii,
+                        _ => return Err(Error::UnknownCharset { name }),
+                    };
+                }
+                "explain" => {
+                    let code = option_value(option, value, &mut args)?;
+                    match explanations::explain(&code) {
+                        Some(explanation) => exit_with_explanation(explanation),
+                        None => return Err(Error::UnknownCode { code }),
+                    }
+                }
+                "std" => {
+                    let name = option_value(option, value, &mut args)?;
+                    match standard::find(&name) {
+                        Some((name, _)) if std_libraries.contains(&name) => (),
+                        Some((name, _)) => std_libraries.push(name),
+                        None => return Err(Error::UnknownStdLibrary { name }),
+                    }
+                }
+                "allow" | "deny" => {
+                    let name = option_value(option, value, &mut args)?;
+                    if !ruxnasm::Warning::NAMES.contains(&name.as_str()) {
+                        return Err(Error::UnknownWarning { name });
+                    }
+                    let level = if option == "allow" {
+                        WarningLevel::Allow
+                    } else {
+                        WarningLevel::Deny
+                    };
+                    warning_levels.push((name, level));
+                }
                 option => {
                     return Err(Error::UnrecognizedOption {
                         option: option.to_owned(),
                     })
                 }
             }
+        } else if arg == "-Werror" {
+            warnings_as_errors = true;
+        } else if let Some(define) = arg.strip_prefix("-D") {
+            let define = if !define.is_empty() {
+                define.to_owned()
+            } else {
+                option_value("D", None, &mut args)?
+            };
+            defines.push(match define.find('=') {
+                Some(position) => (
+                    define[..position].to_owned(),
+                    Some(define[position + 1..].to_owned()),
+                ),
+                None => (define, None),
+            });
         } else if arg.starts_with("-") {
             for ch in arg[1..].chars() {
                 match ch {
@@ -69,26 +837,122 @@ pub fn parse_arguments() -> Result<Arguments, Error> {
                 }
             }
         } else {
-            if input_file_path.is_none() {
-                input_file_path = Some(arg.into());
-            } else if output_file_path.is_none() {
-                output_file_path = Some(arg.into());
-            } else {
+            file_paths.push(arg.into());
+        }
+    }
+
+    if file_paths.is_empty() && config.input_file_path.is_none() {
+        return Err(Error::NoInputProvided);
+    }
+
+    // uxnasm takes exactly one input and one output, and always writes the symbols next to the
+    // output.
+    if compat == Some(Compat::Uxnasm) {
+        match file_paths.as_slice() {
+            [] => return Err(Error::NoInputProvided),
+            [_] => return Err(Error::NoOutputProvided),
+            [_, output_file_path] => {
+                if symbols_file_path.is_none() {
+                    let mut symbols_file_name = output_file_path.clone().into_os_string();
+                    symbols_file_name.push(".sym");
+                    symbols_file_path = Some(symbols_file_name.into());
+                }
+            }
+            [_, _, argument, ..] => {
                 return Err(Error::UnexpectedArgument {
-                    argument: arg.to_owned(),
-                });
+                    argument: argument.to_string_lossy().into_owned(),
+                })
             }
         }
     }
 
-    match (input_file_path, output_file_path) {
-        (Some(input_file_path), Some(output_file_path)) => Ok(Arguments {
-            input_file_path,
-            output_file_path,
-        }),
-        (None, _) => Err(Error::NoInputProvided),
-        (_, None) => Err(Error::NoOutputProvided),
-    }
+    // Libraries are only checked, so they don't have an output. Otherwise, the last file is the
+    // output, unless it is the only one or an output directory is given.
+    let output = if library {
+        None
+    } else if let Some(out_dir) = out_dir {
+        Some(Output::Directory(out_dir))
+    } else if file_paths.len() >= 2 {
+        file_paths.pop().map(Output::File)
+    } else if let Some(output_file_path) = config.output_file_path {
+        Some(Output::File(output_file_path))
+    } else if let Some(out_dir) = config.out_dir {
+        Some(Output::Directory(out_dir))
+    } else {
+        return Err(Error::NoOutputProvided);
+    };
+    let input_file_paths = if file_paths.is_empty() {
+        config.input_file_path.into_iter().collect()
+    } else {
+        file_paths
+    };
+
+    Ok(Arguments {
+        input_file_paths,
+        output,
+        defines,
+        std_libraries,
+        include_directories,
+        post_commands,
+        symbols_file_path,
+        memory_map_file_path,
+        map_file_path,
+        source_map_file_path,
+        debug_info_file_path,
+        profile_file_path,
+        embed_source,
+        stats,
+        reports,
+        emits,
+        reference_file_path,
+        compat,
+        crash_dump,
+        warning_levels,
+        warnings_as_errors,
+        fix,
+        trace_emit,
+        library,
+        message_format,
+        diagnostic_style,
+        color_choice,
+        layout,
+        diagnostics_output_path,
+        error_limit,
+        backward_padding,
+        size,
+        trim,
+        output_format,
+        opcode_table,
+        reserved_memory,
+        opcodes_file_path,
+        check_stacks,
+        relative_address_margin,
+    })
+}
+
+/// Returns the value of an option, given either as `--option=value` or as `--option value`.
+fn option_value(
+    option: &str,
+    value: Option<String>,
+    args: &mut impl Iterator<Item = String>,
+) -> Result<String, Error> {
+    value
+        .or_else(|| args.next())
+        .ok_or_else(|| Error::MissingOptionValue {
+            option: option.to_owned(),
+        })
+}
+
+fn number_value(
+    option: &str,
+    value: Option<String>,
+    args: &mut impl Iterator<Item = String>,
+) -> Result<usize, Error> {
+    let value = option_value(option, value, args)?;
+    value.parse().map_err(|_| Error::InvalidNumber {
+        option: option.to_owned(),
+        value,
+    })
 }
 
 fn exit_with_help_message() {
@@ -96,6 +960,11 @@ fn exit_with_help_message() {
     exit(0);
 }
 
+fn exit_with_explanation(explanation: &str) {
+    print!("{}", explanation);
+    exit(0);
+}
+
 fn exit_with_version_message() {
     println!("{}", VERSION_MESSAGE);
     exit(0);
@@ -0,0 +1,98 @@
+use ruxnasm::Symbol;
+use std::path::PathBuf;
+
+/// Address at which the binaries are loaded.
+const START: usize = 0x0100;
+/// Maximum number of bytes of each of the binaries shown for a single difference.
+const SHOWN_BYTES: usize = 8;
+
+pub enum Error {
+    BinariesDiffer {
+        reference_path: PathBuf,
+        byte_count: usize,
+        range_count: usize,
+    },
+}
+
+/// A range of the addresses at which the binaries differ. Bytes missing from the shorter binary
+/// are nothing.
+pub struct Difference {
+    pub start: usize,
+    pub end: usize,
+    pub bytes: Vec<Option<u8>>,
+    pub reference_bytes: Vec<Option<u8>>,
+}
+
+/// Finds the ranges of the addresses at which the binary differs from the reference binary.
+pub fn compare(binary: &[u8], reference: &[u8]) -> Vec<Difference> {
+    let mut differences: Vec<Difference> = Vec::new();
+    for offset in 0..binary.len().max(reference.len()) {
+        let byte = binary.get(offset).copied();
+        let reference_byte = reference.get(offset).copied();
+        if byte == reference_byte {
+            continue;
+        }
+        let address = START + offset;
+        match differences.last_mut() {
+            Some(difference) if difference.end == address => {
+                difference.end += 1;
+                difference.bytes.push(byte);
+                difference.reference_bytes.push(reference_byte);
+            }
+            _ => differences.push(Difference {
+                start: address,
+                end: address + 1,
+                bytes: vec![byte],
+                reference_bytes: vec![reference_byte],
+            }),
+        }
+    }
+    differences
+}
+
+/// Prints the differences, one per line, along with the closest labels defined at or before
+/// them, and the bytes of both binaries.
+pub fn print(differences: &[Difference], symbols: &[Symbol]) {
+    let labels: Vec<&Symbol> = symbols
+        .iter()
+        .filter(|symbol| !symbol.relative && symbol.address as usize >= START)
+        .collect();
+
+    for difference in differences {
+        // The symbols are sorted by their addresses.
+        let label = match labels.partition_point(|label| label.address as usize <= difference.start)
+        {
+            0 => "(no label)".to_owned(),
+            position => {
+                let label = labels[position - 1];
+                match difference.start - label.address as usize {
+                    0 => label.name.clone(),
+                    offset => format!("{}+{}", label.name, offset),
+                }
+            }
+        };
+        println!(
+            "{:04x}..{:04x}  {:<24}  ours: {:<24}  reference: {}",
+            difference.start,
+            difference.end,
+            label,
+            bytes(&difference.bytes),
+            bytes(&difference.reference_bytes)
+        );
+    }
+}
+
+fn bytes(bytes: &[Option<u8>]) -> String {
+    let mut shown: Vec<String> = bytes
+        .iter()
+        .take(SHOWN_BYTES)
+        .map(|byte| match byte {
+            Some(byte) => format!("{:02x}", byte),
+            None => "--".to_owned(),
+        })
+        .collect();
+    if bytes.len() > SHOWN_BYTES {
+        shown.push("..".to_owned());
+    }
+    shown.join(" ")
+}
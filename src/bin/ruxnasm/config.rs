@@ -0,0 +1,279 @@
+use crate::argument_parser::{Charset, DiagnosticStyle, MessageFormat, OutputFormat, WarningLevel};
+use std::{
+    env, fs, io,
+    path::{Path, PathBuf},
+};
+
+const CONFIG_FILE_NAME: &str = "ruxnasm.toml";
+
+/// Project configuration read from a `ruxnasm.toml` file. Paths are relative to the directory
+/// containing the file.
+#[derive(Debug, Default)]
+pub struct Config {
+    /// Path of the configuration file itself.
+    pub file_path: Option<PathBuf>,
+    pub input_file_path: Option<PathBuf>,
+    pub output_file_path: Option<PathBuf>,
+    pub out_dir: Option<PathBuf>,
+    pub symbols_file_path: Option<PathBuf>,
+    /// Directories searched for the included files not found relative to the working directory.
+    pub include_directories: Vec<PathBuf>,
+    pub output_format: Option<OutputFormat>,
+    pub message_format: Option<MessageFormat>,
+    pub diagnostic_style: Option<DiagnosticStyle>,
+    pub tab_width: Option<usize>,
+    pub context_lines: Option<usize>,
+    pub charset: Option<Charset>,
+    pub warning_levels: Vec<(String, WarningLevel)>,
+    pub warnings_as_errors: bool,
+}
+
+pub enum Error {
+    CouldNotReadFile {
+        file_path: PathBuf,
+        io_error: io::Error,
+    },
+    Syntax {
+        file_path: PathBuf,
+        line: usize,
+        message: String,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Value {
+    String(String),
+    Boolean(bool),
+    Integer(usize),
+}
+
+/// Looks for a `ruxnasm.toml` file in the current working directory and its ancestors, and
+/// reads the first one found.
+pub fn discover() -> Result<Option<Config>, Error> {
+    let current_dir = match env::current_dir() {
+        Ok(current_dir) => current_dir,
+        Err(_) => return Ok(None),
+    };
+
+    for directory in current_dir.ancestors() {
+        let file_path = directory.join(CONFIG_FILE_NAME);
+        if file_path.is_file() {
+            return read(&file_path).map(Some);
+        }
+    }
+
+    Ok(None)
+}
+
+fn read(file_path: &Path) -> Result<Config, Error> {
+    let contents = fs::read_to_string(file_path).map_err(|io_error| Error::CouldNotReadFile {
+        file_path: file_path.to_path_buf(),
+        io_error,
+    })?;
+    let directory = file_path.parent().unwrap_or_else(|| Path::new(""));
+    let syntax_error = |line: usize, message: String| Error::Syntax {
+        file_path: file_path.to_path_buf(),
+        line,
+        message,
+    };
+
+    let mut config = Config {
+        file_path: Some(file_path.to_path_buf()),
+        ..Config::default()
+    };
+    let mut table = String::new();
+
+    for (index, line) in contents.lines().enumerate() {
+        let line_number = index + 1;
+        let line = strip_comment(line).trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        if line.starts_with('[') {
+            if !line.ends_with(']') {
+                return Err(syntax_error(line_number, "expected `]`".to_owned()));
+            }
+            table = line[1..line.len() - 1].trim().to_owned();
+            if table != "warnings" {
+                return Err(syntax_error(
+                    line_number,
+                    format!("unknown table `{}`", table),
+                ));
+            }
+            continue;
+        }
+
+        let (key, value) = match line.find('=') {
+            Some(position) => (line[..position].trim(), line[position + 1..].trim()),
+            None => return Err(syntax_error(line_number, "expected `=`".to_owned())),
+        };
+        let key = key.trim_matches('"');
+        let value = parse_value(value).map_err(|message| syntax_error(line_number, message))?;
+
+        match (table.as_str(), key, value) {
+            ("", "input", Value::String(path)) => {
+                config.input_file_path = Some(directory.join(path));
+            }
+            ("", "output", Value::String(path)) => {
+                config.output_file_path = Some(directory.join(path));
+            }
+            ("", "out-dir", Value::String(path)) => {
+                config.out_dir = Some(directory.join(path));
+            }
+            ("", "symbols", Value::String(path)) => {
+                config.symbols_file_path = Some(directory.join(path));
+            }
+            ("", "include", Value::String(path)) => {
+                config.include_directories.push(directory.join(path));
+            }
+            ("", "format", Value::String(format)) => {
+                config.output_format = Some(match format.as_str() {
+                    "rom" => OutputFormat::Rom,
+                    "c-header" => OutputFormat::CHeader,
+                    "rust" => OutputFormat::Rust,
+                    "hexdump" => OutputFormat::Hexdump,
+                    _ => {
+                        return Err(syntax_error(
+                            line_number,
+                            format!("unknown output format `{}`", format),
+                        ))
+                    }
+                });
+            }
+            ("", "message-format", Value::String(format)) => {
+                config.message_format = Some(match format.as_str() {
+                    "human" => MessageFormat::Human,
+                    "sarif" => MessageFormat::Sarif,
+                    _ => {
+                        return Err(syntax_error(
+                            line_number,
+                            format!("unknown message format `{}`", format),
+                        ))
+                    }
+                });
+            }
+            ("", "diagnostic-style", Value::String(style)) => {
+                config.diagnostic_style = Some(match style.as_str() {
+                    "rich" => DiagnosticStyle::Rich,
+                    "short" => DiagnosticStyle::Short,
+                    _ => {
+                        return Err(syntax_error(
+                            line_number,
+                            format!("unknown diagnostic style `{}`", style),
+                        ))
+                    }
+                });
+            }
+            ("", "tab-width", Value::Integer(tab_width)) => {
+                config.tab_width = Some(tab_width);
+            }
+            ("", "context-lines", Value::Integer(context_lines)) => {
+                config.context_lines = Some(context_lines);
+            }
+            ("", "charset", Value::String(name)) => {
+                config.charset = Some(match name.as_str() {
+                    "unicode" => Charset::Unicode,
+                    "ascii" => Charset::Ascii,
+                    _ => {
+                        return Err(syntax_error(
+                            line_number,
+                            format!("unknown charset `{}`", name),
+                        ))
+                    }
+                });
+            }
+            ("", "werror", Value::Boolean(warnings_as_errors)) => {
+                config.warnings_as_errors = warnings_as_errors;
+            }
+            ("warnings", name, Value::String(level)) => {
+                if !ruxnasm::Warning::NAMES.contains(&name) {
+                    return Err(syntax_error(
+                        line_number,
+                        format!("unknown warning `{}`", name),
+                    ));
+                }
+                let level = match level.as_str() {
+                    "allow" => WarningLevel::Allow,
+                    "warn" => WarningLevel::Warn,
+                    "deny" => WarningLevel::Deny,
+                    _ => {
+                        return Err(syntax_error(
+                            line_number,
+                            format!("unknown warning level `{}`", level),
+                        ))
+                    }
+                };
+                config.warning_levels.push((name.to_owned(), level));
+            }
+            (_, key, _) => {
+                return Err(syntax_error(
+                    line_number,
+                    format!("unknown key `{}` or invalid value", key),
+                ))
+            }
+        }
+    }
+
+    Ok(config)
+}
+
+/// Removes a comment from the end of the line, unless the `#` character is inside of a string.
+fn strip_comment(line: &str) -> &str {
+    let mut in_string = false;
+    let mut escaped = false;
+
+    for (position, ch) in line.char_indices() {
+        match ch {
+            '\\' if in_string => escaped = !escaped,
+            '"' if !escaped => in_string = !in_string,
+            '#' if !in_string => return &line[..position],
+            _ => escaped = false,
+        }
+    }
+
+    line
+}
+
+/// Parses a basic string, a boolean, or a non-negative integer, the only kinds of values used in
+/// the configuration.
+fn parse_value(value: &str) -> Result<Value, String> {
+    match value {
+        "true" => return Ok(Value::Boolean(true)),
+        "false" => return Ok(Value::Boolean(false)),
+        _ => (),
+    }
+
+    if !value.is_empty() && value.bytes().all(|byte| byte.is_ascii_digit()) {
+        return value
+            .parse()
+            .map(Value::Integer)
+            .map_err(|_| format!("integer `{}` is too large", value));
+    }
+
+    if value.len() < 2 || !value.starts_with('"') || !value.ends_with('"') {
+        return Err(format!(
+            "expected a string, a boolean, or an integer, found `{}`",
+            value
+        ));
+    }
+
+    let mut string = String::new();
+    let mut chars = value[1..value.len() - 1].chars();
+    while let Some(ch) = chars.next() {
+        match ch {
+            '\\' => match chars.next() {
+                Some('"') => string.push('"'),
+                Some('\\') => string.push('\\'),
+                Some('n') => string.push('\n'),
+                Some('t') => string.push('\t'),
+                Some(ch) => return Err(format!("invalid escape sequence `\\{}`", ch)),
+                None => return Err("unterminated escape sequence".to_owned()),
+            },
+            '"' => return Err(format!("unexpected `\"` in `{}`", value)),
+            ch => string.push(ch),
+        }
+    }
+
+    Ok(Value::String(string))
+}
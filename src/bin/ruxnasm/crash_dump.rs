@@ -0,0 +1,56 @@
+use crate::archive;
+use std::path::PathBuf;
+use std::{env, fs, io, process};
+
+/// Everything needed to reproduce a crash of the assembler, except for the backtrace.
+#[derive(Clone)]
+pub struct Bundle {
+    arguments: Vec<String>,
+    input: Vec<u8>,
+    /// Tokens of the input, or nothing if they haven't been scanned yet.
+    tokens: Option<String>,
+}
+
+impl Bundle {
+    /// Gathers the command-line arguments and the input.
+    pub fn new(input_file_contents: &[u8]) -> Self {
+        Self {
+            arguments: env::args().collect(),
+            input: input_file_contents.to_vec(),
+            tokens: None,
+        }
+    }
+
+    /// Scans the input, which is the first pass of the assembler, so it may crash as well.
+    pub fn with_tokens(self) -> Self {
+        let tokens = match ruxnasm::unstable::scan(&self.input) {
+            Ok((words, _)) => format!("{:#?}\n", words),
+            Err((errors, _)) => format!("{:#?}\n", errors),
+        };
+        Self {
+            tokens: Some(tokens),
+            ..self
+        }
+    }
+
+    /// Writes the bundle along with the panic message and the backtrace to an archive in the
+    /// temporary directory, and returns its path.
+    pub fn write(&self, panic_message: &str) -> Result<PathBuf, io::Error> {
+        let arguments: Vec<String> = self.arguments.iter().cloned().map(crate::quote).collect();
+        let panic = format!("{}\n\n{:?}\n", panic_message, backtrace::Backtrace::new());
+        let mut files = vec![
+            (
+                "arguments.txt".to_owned(),
+                format!("{}\n", arguments.join(" ")).into_bytes(),
+            ),
+            ("input.tal".to_owned(), self.input.clone()),
+            ("panic.txt".to_owned(), panic.into_bytes()),
+        ];
+        if let Some(tokens) = &self.tokens {
+            files.push(("tokens.txt".to_owned(), tokens.clone().into_bytes()));
+        }
+
+        let path = env::temp_dir().join(format!("ruxnasm-crash-{}.tar.gz", process::id()));
+        fs::write(&path, archive::archive(&files)).map(|()| path)
+    }
+}
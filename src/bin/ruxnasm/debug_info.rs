@@ -0,0 +1,162 @@
+use crate::json::Value;
+use crate::source_map::{index_files, location, text};
+use ruxnasm::{Chunk, Source, Symbol};
+use std::ops::Range;
+
+/// Version of the format of the debug information, raised with every incompatible change.
+const VERSION: f64 = 1.0;
+
+/// A run of consecutive bytes expanded through the same chain of macro invocations.
+struct Expansion {
+    /// Spans of the invocations, starting with the outermost one.
+    chain: Vec<Range<usize>>,
+    start: usize,
+    end: usize,
+}
+
+/// Renders the debug information of the program as JSON, for the debuggers that need more than
+/// the flat list of symbols:
+///
+/// - the scopes of the labels, i.e. the addresses from each label up to the next one, along with
+///   the sublabels defined in them,
+/// - the ranges of the addresses expanded from each macro invocation, nested invocations
+///   included,
+/// - the variables of the zero page along with their sizes, if they have been given with
+///   relative pads.
+///
+/// Locations are given just like in the source maps, i.e. as indices of the files, spans relative
+/// to the files, and lines and columns starting at 1. Relative symbols of libraries are left out.
+pub fn render(chunks: &[Chunk], symbols: &[Symbol], files: &[&Source]) -> Vec<u8> {
+    let (files, file_names) = index_files(files);
+    let symbols: Vec<&Symbol> = symbols.iter().filter(|symbol| !symbol.relative).collect();
+    let binary_end = chunks
+        .iter()
+        .map(|chunk| chunk.address as usize + chunk.bytes.len())
+        .max()
+        .unwrap_or(0);
+
+    let labels: Vec<&Symbol> = symbols
+        .iter()
+        .copied()
+        .filter(|symbol| symbol.address >= 0x0100 && !symbol.name.contains('/'))
+        .collect();
+    let scopes = labels
+        .iter()
+        .map(|label| {
+            let start = label.address as usize;
+            let end = labels
+                .iter()
+                .map(|other| other.address as usize)
+                .find(|address| *address > start)
+                .unwrap_or_else(|| binary_end.max(start));
+            let prefix = format!("{}/", label.name);
+            let sublabels = symbols
+                .iter()
+                .filter(|symbol| symbol.name.starts_with(&prefix))
+                .map(|sublabel| {
+                    let mut members = vec![
+                        (
+                            "name".to_owned(),
+                            Value::String(sublabel.name[prefix.len()..].to_owned()),
+                        ),
+                        ("address".to_owned(), Value::Number(sublabel.address as f64)),
+                    ];
+                    members.extend(location(&files, &sublabel.span));
+                    Value::Object(members)
+                })
+                .collect();
+
+            let mut members = vec![
+                ("name".to_owned(), Value::String(label.name.clone())),
+                ("address".to_owned(), Value::Number(start as f64)),
+                ("end".to_owned(), Value::Number(end as f64)),
+            ];
+            members.extend(location(&files, &label.span));
+            members.push(("sublabels".to_owned(), Value::Array(sublabels)));
+            Value::Object(members)
+        })
+        .collect();
+
+    let expansions = expansions(chunks)
+        .into_iter()
+        .map(|expansion| {
+            let span = expansion.chain.last().cloned().unwrap_or_default();
+            let mut members = vec![
+                ("macro".to_owned(), Value::String(text(&files, &span))),
+                ("address".to_owned(), Value::Number(expansion.start as f64)),
+                ("end".to_owned(), Value::Number(expansion.end as f64)),
+                (
+                    "depth".to_owned(),
+                    Value::Number((expansion.chain.len() - 1) as f64),
+                ),
+            ];
+            members.extend(location(&files, &span));
+            Value::Object(members)
+        })
+        .collect();
+
+    let variables = symbols
+        .iter()
+        .filter(|symbol| symbol.address < 0x0100)
+        .map(|variable| {
+            let mut members = vec![
+                ("name".to_owned(), Value::String(variable.name.clone())),
+                ("address".to_owned(), Value::Number(variable.address as f64)),
+                (
+                    "size".to_owned(),
+                    match variable.size {
+                        Some(size) => Value::Number(size as f64),
+                        None => Value::Null,
+                    },
+                ),
+            ];
+            members.extend(location(&files, &variable.span));
+            Value::Object(members)
+        })
+        .collect();
+
+    let debug_info = Value::Object(vec![
+        ("version".to_owned(), Value::Number(VERSION)),
+        ("files".to_owned(), Value::Array(file_names)),
+        ("scopes".to_owned(), Value::Array(scopes)),
+        ("expansions".to_owned(), Value::Array(expansions)),
+        ("variables".to_owned(), Value::Array(variables)),
+    ]);
+
+    format!("{}\n", debug_info).into_bytes()
+}
+
+/// Groups the consecutive chunks expanded through the same invocations into the expansions of
+/// each of the invocations, in the order in which they start.
+fn expansions(chunks: &[Chunk]) -> Vec<Expansion> {
+    let mut expansions: Vec<Expansion> = Vec::new();
+    // Indices of the expansions the previous chunk belongs to, one for every invocation.
+    let mut open: Vec<usize> = Vec::new();
+
+    for chunk in chunks {
+        let start = chunk.address as usize;
+        let end = start + chunk.bytes.len();
+        for depth in 0..chunk.invocations.len() {
+            let chain = &chunk.invocations[..=depth];
+            match open.get(depth) {
+                Some(&index)
+                    if expansions[index].chain == chain && expansions[index].end == start =>
+                {
+                    expansions[index].end = end;
+                }
+                _ => {
+                    open.truncate(depth);
+                    open.push(expansions.len());
+                    expansions.push(Expansion {
+                        chain: chain.to_vec(),
+                        start,
+                        end,
+                    });
+                }
+            }
+        }
+        open.truncate(chunk.invocations.len());
+    }
+
+    expansions
+}
@@ -0,0 +1,103 @@
+use crate::argument_parser::OutputFormat;
+use ruxnasm::Symbol;
+use std::fmt::Write;
+
+/// Number of bytes in a single line of the arrays of bytes.
+const BYTES_PER_LINE: usize = 12;
+/// Number of bytes in a single line of the hexdump.
+const DUMP_BYTES_PER_LINE: usize = 16;
+/// Address of the first byte of the binary.
+const START_ADDRESS: usize = 0x0100;
+
+/// Encodes the binary in the output format, e.g. as a C array to be compiled into the firmware
+/// of a host. The symbols are only used to annotate the hexdump.
+pub fn encode(binary: Vec<u8>, format: OutputFormat, symbols: &[Symbol]) -> Vec<u8> {
+    match format {
+        OutputFormat::Rom => binary,
+        OutputFormat::CHeader => c_header(&binary).into_bytes(),
+        OutputFormat::Rust => rust(&binary).into_bytes(),
+        OutputFormat::Hexdump => hexdump(&binary, symbols).into_bytes(),
+    }
+}
+
+/// Returns the extension of the files written in the output format.
+pub fn extension(format: OutputFormat) -> &'static str {
+    match format {
+        OutputFormat::Rom => "rom",
+        OutputFormat::CHeader => "h",
+        OutputFormat::Rust => "rs",
+        OutputFormat::Hexdump => "txt",
+    }
+}
+
+/// Writes the binary as the `rom` array and its length as `rom_len`, just like `xxd -i` does.
+fn c_header(binary: &[u8]) -> String {
+    let mut header = String::from("unsigned char rom[] = {\n");
+    header.push_str(&byte_lines(binary, "  ", ""));
+    let _ = write!(header, "}};\nunsigned int rom_len = {};\n", binary.len());
+    header
+}
+
+/// Writes the binary as the `ROM` static, to be included in a Rust module.
+fn rust(binary: &[u8]) -> String {
+    let mut module = String::new();
+    let _ = writeln!(module, "pub static ROM: [u8; {}] = [", binary.len());
+    module.push_str(&byte_lines(binary, "    ", ","));
+    module.push_str("];\n");
+    module
+}
+
+/// Dumps the binary just like `xxd` does, but with the addresses of the bytes in the memory, and
+/// with every line followed by the last label or sublabel defined up to the end of the line.
+fn hexdump(binary: &[u8], symbols: &[Symbol]) -> String {
+    // The symbols are sorted by their addresses, after the relative ones.
+    let symbols: Vec<&Symbol> = symbols.iter().filter(|symbol| !symbol.relative).collect();
+    let mut dump = String::new();
+
+    for (index, line) in binary.chunks(DUMP_BYTES_PER_LINE).enumerate() {
+        let address = START_ADDRESS + index * DUMP_BYTES_PER_LINE;
+        let _ = write!(dump, "{:04x}:", address);
+        for (offset, byte) in line.iter().enumerate() {
+            if offset % 2 == 0 {
+                dump.push(' ');
+            }
+            let _ = write!(dump, "{:02x}", byte);
+        }
+        // The characters are aligned even if the line is shorter.
+        let missing = DUMP_BYTES_PER_LINE - line.len();
+        dump.push_str(&" ".repeat(missing * 2 + missing / 2 + 2));
+        for byte in line {
+            dump.push(match byte {
+                0x20..=0x7e => *byte as char,
+                _ => '.',
+            });
+        }
+
+        let end = address + line.len();
+        let position = symbols.partition_point(|symbol| (symbol.address as usize) < end);
+        if position > 0 {
+            dump.push_str(&" ".repeat(missing + 2));
+            dump.push_str(&symbols[position - 1].name);
+        }
+        dump.push('\n');
+    }
+
+    dump
+}
+
+/// Lists the bytes in hexadecimal, separated with commas, in lines starting with the indent. The
+/// last byte is followed by the terminator.
+fn byte_lines(binary: &[u8], indent: &str, terminator: &str) -> String {
+    let lines: Vec<String> = binary
+        .chunks(BYTES_PER_LINE)
+        .map(|line| {
+            let bytes: Vec<String> = line.iter().map(|byte| format!("0x{:02x}", byte)).collect();
+            format!("{}{}", indent, bytes.join(", "))
+        })
+        .collect();
+    if lines.is_empty() {
+        String::new()
+    } else {
+        format!("{}{}\n", lines.join(",\n"), terminator)
+    }
+}
@@ -0,0 +1,1345 @@
+/// Extended explanations of every error and warning code, as returned by
+/// `ruxnasm::Error::code` and `ruxnasm::Warning::code`.
+const EXPLANATIONS: &[(&str, &str)] = &[
+    (
+        "E0001",
+        r#"An opening parenthesis has no matching closing parenthesis.
+
+Erroneous code example:
+
+    ( this comment is never closed
+
+Every comment must be closed. Comments can be nested, so each opening
+parenthesis inside of a comment needs its own closing parenthesis:
+
+    ( this comment is ( properly ) closed )
+"#,
+    ),
+    (
+        "E0002",
+        r#"A closing parenthesis has no matching opening parenthesis.
+
+Erroneous code example:
+
+    #01 #02 ADD )
+
+Remove the closing parenthesis, or add an opening parenthesis to start
+a comment:
+
+    #01 #02 ADD ( comment )
+"#,
+    ),
+    (
+        "E0003",
+        r#"A macro definition rune is not followed by a macro name.
+
+Erroneous code example:
+
+    % { #01 ADD }
+
+Give the macro a name:
+
+    %INC { #01 ADD }
+"#,
+    ),
+    (
+        "E0004",
+        r#"A label definition rune is not followed by a label name.
+
+Erroneous code example:
+
+    @
+
+Give the label a name:
+
+    @label
+"#,
+    ),
+    (
+        "E0005",
+        r#"A sublabel definition rune is not followed by a sublabel name.
+
+Erroneous code example:
+
+    @label &
+
+Give the sublabel a name:
+
+    @label &sublabel
+"#,
+    ),
+    (
+        "E0006",
+        r#"A label or a sublabel name contains a slash character.
+
+Erroneous code example:
+
+    @label/name
+
+Slashes separate the label name from the sublabel name when referencing
+a sublabel, so they cannot appear in names. Define a sublabel instead:
+
+    @label &name
+"#,
+    ),
+    (
+        "E0007",
+        r#"An identifier contains more than one slash character.
+
+Erroneous code example:
+
+    .label-name/sublabel/name
+
+An identifier can refer to a label, or to a sublabel of a label, so it
+can contain at most one slash:
+
+    .label-name/sublabel
+"#,
+    ),
+    (
+        "E0008",
+        r#"A label name starts with an ampersand.
+
+Erroneous code example:
+
+    @&label-name
+
+Names starting with an ampersand clash with the `.&sublabel` syntax,
+which refers to a sublabel of the most recently defined label. Rename
+the label:
+
+    @label-name
+"#,
+    ),
+    (
+        "E0009",
+        r#"An address rune is not followed by an identifier.
+
+Erroneous code example:
+
+    .
+
+The literal zero-page address rune (`.`), the literal relative address
+rune (`,`), the literal absolute address rune (`;`), and the raw
+address rune (`:`) must be followed by the name of a label:
+
+    .label
+"#,
+    ),
+    (
+        "E0010",
+        r#"A pad rune is not followed by a hexadecimal number.
+
+Erroneous code example:
+
+    |
+
+Both the absolute pad rune (`|`) and the relative pad rune (`$`) must be
+followed by the address or the number of bytes to pad by:
+
+    |0100
+"#,
+    ),
+    (
+        "E0011",
+        r#"A literal hex rune is not followed by a hexadecimal number or a
+character.
+
+Erroneous code example:
+
+    #
+
+Follow the rune with a byte, a short, or a single character:
+
+    #01 #0001 #a
+"#,
+    ),
+    (
+        "E0012",
+        r#"A raw character rune is not followed by a character.
+
+Erroneous code example:
+
+    '
+
+Follow the rune with a single character:
+
+    'a
+"#,
+    ),
+    (
+        "E0013",
+        r#"A raw character rune is followed by more than one byte.
+
+Erroneous code example:
+
+    'characters
+
+A raw character rune produces exactly one byte. Use a raw word to
+produce multiple bytes:
+
+    "characters
+
+A character encoded in UTF-8 with multiple bytes, e.g. `'é`, is allowed
+after a `( ruxnasm: utf8 )` comment, which turns on the UTF-8 mode for
+the rest of the file. The rune then produces all bytes of the character.
+"#,
+    ),
+    (
+        "E0014",
+        r#"A hexadecimal number contains an invalid digit.
+
+Erroneous code example:
+
+    #00g0
+
+Hexadecimal numbers can only contain the digits 0-9 and a-f:
+
+    #00f0
+"#,
+    ),
+    (
+        "E0015",
+        r#"A hexadecimal number after a literal hex rune is made out of exactly
+3 digits.
+
+Erroneous code example:
+
+    #000
+
+Literal numbers are either bytes (2 digits) or shorts (4 digits), so
+it's unclear which one is meant. Add or remove a digit:
+
+    #0000
+"#,
+    ),
+    (
+        "E0016",
+        r#"A hexadecimal number is longer than 4 digits.
+
+Erroneous code example:
+
+    #fffff
+
+Numbers can be at most a short (4 digits) long:
+
+    #ffff
+"#,
+    ),
+    (
+        "E0017",
+        r#"A macro name is a valid hexadecimal number.
+
+Erroneous code example:
+
+    %01 { #01 }
+
+Macro names made out of exactly 2 or 4 hexadecimal digits would be
+indistinguishable from raw numbers. Rename the macro:
+
+    %ONE { #01 }
+"#,
+    ),
+    (
+        "E0018",
+        r#"A macro name is a valid instruction.
+
+Erroneous code example:
+
+    %ADD { #01 ADD }
+
+Macro names that are instructions would be indistinguishable from the
+instructions. Rename the macro:
+
+    %INC { #01 ADD }
+"#,
+    ),
+    (
+        "E0019",
+        r#"A macro is invoked, but it has not been previously defined.
+
+Erroneous code example:
+
+    macro
+
+Macros must be defined before they are invoked. Note that instructions
+are case-sensitive, so `add` is treated as a macro invocation and not as
+the `ADD` instruction:
+
+    %macro { #01 ADD }
+    macro
+"#,
+    ),
+    (
+        "E0020",
+        r#"A macro with the same name is defined multiple times.
+
+Erroneous code example:
+
+    %macro { ADD }
+    %macro { ADD }
+
+Remove one of the definitions, or rename one of the macros.
+"#,
+    ),
+    (
+        "E0021",
+        r#"A label with the same name is defined multiple times.
+
+Erroneous code example:
+
+    @label
+    @label
+
+Remove one of the definitions, or rename one of the labels.
+"#,
+    ),
+    (
+        "E0022",
+        r#"An opening brace is not directly preceded by a macro definition or a
+directive taking a body.
+
+Erroneous code example:
+
+    { ADD }
+
+Braces can only be used to delimit the body of a macro, a repeat, or a
+zero-page section:
+
+    %macro { ADD }
+    ?repeat 02 { ADD }
+"#,
+    ),
+    (
+        "E0023",
+        r#"A closing brace has no matching opening brace.
+
+Erroneous code example:
+
+    }
+
+Remove the closing brace, or add the missing macro definition:
+
+    %macro { }
+"#,
+    ),
+    (
+        "E0024",
+        r#"An opening brace has no matching closing brace.
+
+Erroneous code example:
+
+    %macro {
+
+Close the body of the macro:
+
+    %macro { }
+"#,
+    ),
+    (
+        "E0025",
+        r#"A sublabel is defined without a previously defined label.
+
+Erroneous code example:
+
+    &sublabel
+
+Every sublabel belongs to the most recently defined label, so a label
+must be defined first:
+
+    @label &sublabel
+"#,
+    ),
+    (
+        "E0026",
+        r#"A closing bracket has no matching opening bracket.
+
+Erroneous code example:
+
+    ]
+
+Remove the closing bracket, or add the missing opening bracket:
+
+    [ ]
+"#,
+    ),
+    (
+        "E0027",
+        r#"An opening bracket has no matching closing bracket.
+
+Erroneous code example:
+
+    [
+
+Add the missing closing bracket:
+
+    [ ]
+"#,
+    ),
+    (
+        "E0028",
+        r#"A sublabel is referenced with the `.&sublabel` syntax without a
+previously defined label.
+
+Erroneous code example:
+
+    .&sublabel
+
+The `.&sublabel` syntax refers to a sublabel of the most recently
+defined label, so a label must be defined first:
+
+    @label &sublabel .&sublabel
+"#,
+    ),
+    (
+        "E0029",
+        r#"A label that has not been defined is referenced.
+
+Erroneous code example:
+
+    .label
+
+Define the label, or fix the typo in its name:
+
+    @label .label
+"#,
+    ),
+    (
+        "E0030",
+        r#"A label outside of the zero page is referenced with a literal
+zero-page address.
+
+Erroneous code example:
+
+    |0100 @label
+    .label LDZ
+
+A literal zero-page address is a single byte, so it can only refer to
+the first 256 addresses. Use a literal absolute address along with an
+absolute-addressing instruction instead:
+
+    |0100 @label
+    ;label LDA
+
+If the literal zero-page address is directly followed by `LDZ` or `STZ`,
+`ruxnasm --fix` applies this change automatically.
+"#,
+    ),
+    (
+        "E0031",
+        r#"A label is too far to be referenced with a literal relative address.
+
+Erroneous code example:
+
+    @label
+    |0100 ,label JMP
+
+A literal relative address is a signed byte, so the label must be
+within 126 bytes of the reference. Move the label closer, or use a
+literal absolute address along with an absolute-addressing instruction
+instead:
+
+    @label
+    |0100 ;label JMP2
+
+If the literal relative address is directly followed by `JMP`, `JCN`,
+`JSR`, `LDR`, or `STR`, `ruxnasm --fix` applies this change
+automatically.
+"#,
+    ),
+    (
+        "E0032",
+        r#"There are bytes in the zeroth page (first 256 bytes) of the binary.
+
+Erroneous code example:
+
+    #01 #02 ADD
+
+Uxn programs start at address 0x0100, and the zeroth page is reserved
+for variables. Pad to the start of the program first:
+
+    |0100 #01 #02 ADD
+"#,
+    ),
+    (
+        "E0033",
+        r#"An absolute pad goes to an address before the current address.
+
+Erroneous code example:
+
+    |0100 #01 #02 ADD
+    |0000 #02 #03 ADD
+
+Padding backwards would overwrite the bytes that have already been
+assembled. Reorder the code so that the pads are increasing.
+
+Code that deliberately patches the bytes written before, e.g. a table,
+can be assembled with `--allow-backward-padding`, in which case the
+overwritten bytes are reported with a warning instead.
+"#,
+    ),
+    (
+        "E0034",
+        r#"The program size exceeds 65536 bytes.
+
+Erroneous code example:
+
+    |ffff #01 #02 ADD
+
+The Uxn address space is 65536 bytes long. Shrink the program or move
+it to a lower address.
+"#,
+    ),
+    (
+        "E0035",
+        r#"A macro invokes itself, either directly or through other macros.
+
+Erroneous code example:
+
+    %macro { #01 macro }
+    macro
+
+Expanding such a macro would never end. Remove the recursive invocation.
+"#,
+    ),
+    (
+        "E0036",
+        r#"A file includes itself, either directly or through other included files.
+
+Erroneous code example:
+
+    ( library.tal )
+    ~library.tal
+
+Including such a file would never end. Remove the include that closes
+the chain, and include every file only from the files that need it.
+"#,
+    ),
+    (
+        "E0037",
+        r#"A file to be included cannot be read.
+
+Erroneous code example:
+
+    ~does-not-exist.tal
+
+Included files are looked up relative to the current working directory,
+not relative to the file containing the include. Fix the path, or run
+the assembler from a different directory.
+"#,
+    ),
+    (
+        "E0038",
+        r#"The word after a conditional rune is not a known directive.
+
+Erroneous code example:
+
+    ?if DEBUG
+
+The conditional rune `?` starts one of the conditional assembly
+directives: `?ifdef`, `?ifndef`, `?else`, and `?end`, or one of the
+`?repeat`, `?zeropage`, `?library`, and `?checksum` directives:
+
+    ?ifdef DEBUG #01 #18 DEO ?end
+"#,
+    ),
+    (
+        "E0039",
+        r#"There is no macro name after an `?ifdef` or `?ifndef` directive.
+
+Erroneous code example:
+
+    ?ifdef #01 ?end
+
+The directives check whether a macro has been defined so far, either in
+the program or on the command line with `-D NAME`:
+
+    ?ifdef DEBUG #01 ?end
+"#,
+    ),
+    (
+        "E0040",
+        r#"An `?else` or `?end` directive has no matching `?ifdef` or `?ifndef`.
+
+Erroneous code example:
+
+    #01 ?end
+
+Open the conditional region first, or remove the directive:
+
+    ?ifdef DEBUG #01 ?end
+"#,
+    ),
+    (
+        "E0041",
+        r#"An `?ifdef`, `?ifndef`, or `?library` directive has no matching `?end`.
+
+Erroneous code example:
+
+    ?ifdef DEBUG #01
+
+Close the conditional region:
+
+    ?ifdef DEBUG #01 ?end
+
+Library regions are closed the same way:
+
+    ?library @print-hex #04 SFT JMP2r ?end
+"#,
+    ),
+    (
+        "E0042",
+        r#"A conditional region has more than one `?else` directive.
+
+Erroneous code example:
+
+    ?ifdef DEBUG #01 ?else #02 ?else #03 ?end
+
+Every `?ifdef` or `?ifndef` can have at most one `?else`. Nest the
+conditionals instead:
+
+    ?ifdef DEBUG #01 ?else ?ifdef TRACE #02 ?else #03 ?end ?end
+"#,
+    ),
+    (
+        "E0043",
+        r#"There is no constant name after a constant definition rune.
+
+Erroneous code example:
+
+    = 10
+
+Name the constant right after the rune:
+
+    =width 10
+"#,
+    ),
+    (
+        "E0044",
+        r#"A constant definition is not followed by a hexadecimal number or an
+expression.
+
+Erroneous code example:
+
+    =width #10
+
+The value of a constant is a raw hexadecimal number of 2 or 4 digits,
+written without any rune, or an expression in brackets:
+
+    =width 10
+    =area [ width 08 * ]
+
+The constant can then be referenced like a label, e.g. `.width` pushes
+the byte 0x10 and `;width` pushes the short 0x0010.
+"#,
+    ),
+    (
+        "E0045",
+        r#"An expression of a pad or a constant contains an unexpected token.
+
+Erroneous code example:
+
+    |[ 0100 #10 + ]
+
+Expressions are written in brackets right after the `|` or `$` rune, or
+after the name of a constant, with the operators after their operands. They consist only of raw hexadecimal
+numbers of 2 or 4 digits, names of labels and constants, the queries
+`$here` and `$defined:NAME`, and the `+`, `-`, `*`, and `/` operators:
+
+    |[ 0100 10 + ]
+"#,
+    ),
+    (
+        "E0046",
+        r#"An expression of a pad or a constant references a label or a constant
+that has not been defined before the expression.
+
+Erroneous code example:
+
+    |[ buffer 10 + ] @buffer
+
+The value of a pad determines the addresses of everything after it, so
+it must be known at the point of the pad. The same goes for the values
+of constants, which are computed where they are defined. Define the
+label or constant first:
+
+    @buffer |[ buffer 10 + ]
+"#,
+    ),
+    (
+        "E0047",
+        r#"An operator in an expression of a pad or a constant doesn't have two
+operands before it.
+
+Erroneous code example:
+
+    |[ 0100 + ]
+
+Operators come after both of their operands:
+
+    |[ 0100 10 + ]
+"#,
+    ),
+    (
+        "E0048",
+        r#"An expression of a pad or a constant doesn't result in exactly one
+value.
+
+Erroneous code example:
+
+    |[ 0100 10 ]
+
+Every value but one must be consumed by an operator:
+
+    |[ 0100 10 + ]
+"#,
+    ),
+    (
+        "E0049",
+        r#"An operation in an expression of a pad or a constant results in a value
+that is negative or doesn't fit in a short.
+
+Erroneous code example:
+
+    |[ 0100 0200 - ]
+
+Values of expressions are addresses or sizes, so they must stay between
+0000 and ffff at every step of the computation.
+"#,
+    ),
+    (
+        "E0050",
+        r#"An expression of a pad or a constant divides by zero.
+
+Erroneous code example:
+
+    |[ 0100 00 / ]
+"#,
+    ),
+    (
+        "E0051",
+        r#"Macros are expanded within other macros, or files are included within
+other files, too deep.
+
+Erroneous code example:
+
+    %m0 { m1 } %m1 { m2 } ( ... ) %m256 { #01 }
+    m0
+
+The depth of the expansions is limited to 256 macros, and the depth of
+the includes is limited to 64 files, which guards the assembler against
+pathological programs. Flatten the macros or the includes.
+"#,
+    ),
+    (
+        "E0052",
+        r#"There is no hexadecimal number after a `?repeat` directive.
+
+Erroneous code example:
+
+    @buffer ?repeat { 00 }
+
+The number says how many times the words in the braces after it are
+assembled:
+
+    @buffer ?repeat 40 { 00 }
+"#,
+    ),
+    (
+        "E0053",
+        r#"A constant whose value doesn't fit in a byte is pushed as a byte.
+
+Erroneous code example:
+
+    =size [ 10 20 * ]
+    .size
+
+The literal zero-page address rune `.` pushes a single byte, so it can
+only be used with constants between 00 and ff. Push the constant as a
+short instead:
+
+    ;size
+"#,
+    ),
+    (
+        "E0054",
+        r#"The variables of the zero-page sections don't fit in the zero page.
+
+Erroneous code example:
+
+    ?zeropage { @buffer $80 @other-buffer $81 }
+
+The variables of all `?zeropage` sections in the program are allocated
+one after another from the address 0000, and the zero page ends at the
+address 00ff. Make some of the variables smaller, or move them out of
+the zero page:
+
+    ?zeropage { @buffer $80 }
+    |8000 @other-buffer $81
+"#,
+    ),
+    (
+        "E0055",
+        r#"A zero-page section contains a token other than a label definition, a
+sublabel definition, or a relative pad.
+
+Erroneous code example:
+
+    ?zeropage { @counter $1 #00 }
+
+Zero-page sections only allocate the variables, each of them being a
+label or a sublabel followed by a relative pad with its size. The bytes
+of the zero page can't be set in the binary:
+
+    ?zeropage { @counter $1 }
+"#,
+    ),
+    (
+        "E0056",
+        r#"A backslash in a raw word or a string doesn't start a known escape
+sequence.
+
+Erroneous code example:
+
+    "hello\q
+
+The known escape sequences are `\0` for the null byte, `\t` for a tab,
+`\n` for a newline, `\r` for a carriage return, `\s` for a space, `\\`
+for a backslash, and `\`` for a backtick. Write a double backslash to
+get a backslash:
+
+    "hello\\q
+"#,
+    ),
+    (
+        "E0057",
+        r#"A string opened with a backtick has no matching closing backtick.
+
+Erroneous code example:
+
+    `hello world
+
+Close the string with a backtick. Backticks inside of the string are
+written as `\``:
+
+    `hello world`
+"#,
+    ),
+    (
+        "E0058",
+        r#"A `?checksum` directive is not followed by the names of two labels.
+
+Erroneous code example:
+
+    @table 01 02 03 @table-end
+    ?checksum table
+
+The directive sums up the bytes from the first label up to the second
+one. Name both labels:
+
+    @table 01 02 03 @table-end
+    ?checksum table table-end
+"#,
+    ),
+    (
+        "E0059",
+        r#"The range of a `?checksum` directive is invalid.
+
+Erroneous code example:
+
+    |0100 ?checksum table table-end
+    @table 01 02 03 @table-end
+
+The range must not be reversed, it must start after the zero page, which
+is not a part of the binary, and it must end at or before the checksum,
+since the bytes after it are not known yet when it is emitted. Place the
+checksum after the range:
+
+    |0100 @table 01 02 03 @table-end
+    ?checksum table table-end
+"#,
+    ),
+    (
+        "E0060",
+        r#"A decimal number after a literal decimal prefix `#+` contains an
+invalid digit.
+
+Erroneous code example:
+
+    #+4a
+
+Decimal numbers can only contain the digits 0-9. Use a literal hex rune
+for hexadecimal numbers:
+
+    #4a
+"#,
+    ),
+    (
+        "E0061",
+        r#"A decimal number after a literal decimal prefix `#+` doesn't fit in a
+short.
+
+Erroneous code example:
+
+    #+65536
+
+Literal decimal numbers are bytes or shorts, so they can be at most
+65535:
+
+    #+65535
+"#,
+    ),
+    (
+        "E0062",
+        r#"A query is written outside of an expression.
+
+Erroneous code example:
+
+    #00 $here
+
+Queries are values known only during the assembly: `$here` is the
+address pointer, and `$defined:NAME` is 1 if the macro `NAME` has been
+defined so far, e.g. with `-D NAME`, and 0 otherwise. They can only be
+used in expressions of pads and constants:
+
+    =remaining [ 0200 $here - ]
+"#,
+    ),
+    (
+        "E0063",
+        r#"A binary number after a literal binary prefix `#b` contains an invalid
+digit.
+
+Erroneous code example:
+
+    #b00120000
+
+Binary numbers can only contain the digits 0 and 1:
+
+    #b00110000
+"#,
+    ),
+    (
+        "E0064",
+        r#"A binary number after a literal binary prefix `#b` has neither 8 nor 16
+digits.
+
+Erroneous code example:
+
+    #b0011001
+
+Literal binary numbers are bytes of 8 digits or shorts of 16 digits, so
+write out the leading zeros:
+
+    #b00011001
+"#,
+    ),
+    (
+        "E0065",
+        r#"A `?repeat` directive expands into too many words.
+
+Erroneous code example:
+
+    ?repeat ffff { ?repeat ffff { 00 } }
+
+The body of the repeat is written out once for every repetition, so the
+words of a repeat can't exceed the size of the memory. Repeat smaller
+bodies, or fewer times.
+"#,
+    ),
+    (
+        "E0066",
+        r#"The program contains more errors than the limit allows.
+
+The errors found after the limit is reached are left out, and the
+assembly stops as soon as possible. Errors often cause other errors
+later in the program, so it's usually best to fix the first few errors
+and assemble the program again.
+
+The command line limits the errors to 20 by default. The limit can be
+changed with `--error-limit N`, where 0 removes it.
+"#,
+    ),
+    (
+        "E0067",
+        r#"A statement writes bytes at addresses that have already been written.
+
+Erroneous code example:
+
+    |0100 #01 #02 ADD
+    |0101 #03
+
+The later statement would silently replace the bytes of the earlier one.
+Pad to an address after the bytes that have already been written, or
+move the statements so that they don't overlap.
+"#,
+    ),
+    (
+        "E0068",
+        r#"The binary is longer than the size it's padded to.
+
+Erroneous code example, assembled with `--pad-to 4`:
+
+    |0100 #01 #02 ADD
+
+The binary is padded with zeros up to the size given with `--pad-to`,
+but it can't be cut to fit in it. Shrink the program or raise the size.
+"#,
+    ),
+    (
+        "W0001",
+        r#"A token is longer than 64 characters and is cut off.
+
+Example:
+
+    @a-really-long-label-name-like-seriously-this-is-so-long-why-would-anyone-do-this
+
+Only the first 64 characters of the token are taken into account. Use a
+shorter name.
+
+A raw word that is too long can be split into multiple raw words, which
+is what `--fix` does:
+
+    "a-really-long-sentence-like-seriously-this-is-so-long-why-would "-anyone-write-this
+
+This warning can be allowed with `--allow token-trimmed`.
+"#,
+    ),
+    (
+        "W0002",
+        r#"An instruction mode is defined multiple times for a single
+instruction.
+
+Example:
+
+    ADD2k2
+
+This is valid, but unnecessary. Remove the repeated mode:
+
+    ADD2k
+
+This warning can be allowed with `--allow redundant-instruction-mode`.
+"#,
+    ),
+    (
+        "W0003",
+        r#"A macro is never used.
+
+Example:
+
+    %macro { #0001 }
+
+Remove the macro, or invoke it somewhere.
+
+This warning can be allowed with `--allow unused-macro`.
+"#,
+    ),
+    (
+        "W0004",
+        r#"A label is never used.
+
+Example:
+
+    @label
+
+Remove the label, or reference it somewhere. Labels starting with a
+capital letter are never reported, since they are often meant to be
+used from the outside, e.g. as device ports.
+
+This warning can be allowed with `--allow unused-label`.
+"#,
+    ),
+    (
+        "W0005",
+        r#"A sublabel is never used within its scope.
+
+Example:
+
+    |0000 @label &sublabel $1
+
+Remove the sublabel, or reference it somewhere. Sublabels starting with
+a capital letter, or belonging to a label starting with a capital
+letter, are never reported.
+
+This warning can be allowed with `--allow unused-sublabel`.
+"#,
+    ),
+    (
+        "W0006",
+        r#"A comment looks like a directive, but it is malformed or it mentions
+an unknown warning.
+
+Example:
+
+    ( ruxnasm: allow unused-potato )
+
+Directives have the form `( ruxnasm: allow NAME... )`, where each NAME
+is the name of a warning, such as `unused-label`:
+
+    ( ruxnasm: allow unused-label ) @label
+
+The only other directive is `( ruxnasm: utf8 )`, which turns on the
+UTF-8 mode for the rest of the file.
+
+This warning can be allowed with `--allow invalid-directive`.
+"#,
+    ),
+    (
+        "W0007",
+        r#"A label is defined at the same address as a previously defined label.
+
+Example:
+
+    |0000 @x @y $1
+
+This is sometimes intentional, e.g. when giving a second name to the
+same memory, but it is often a sign of a missing pad after the first
+label:
+
+    |0000 @x $1 @y $1
+
+This warning can be allowed with `--allow aliased-label`.
+"#,
+    ),
+    (
+        "W0008",
+        r#"A label that has been defined before any pad is referenced.
+
+Example:
+
+    @main
+    ;main JMP2
+
+Before the first pad, the address pointer is 0x0000, so the label
+resolves to the very beginning of the zero page. Programs start at
+address 0x0100, so this usually means that a `|0100` pad is missing:
+
+    |0100 @main
+    ;main JMP2
+
+This warning can be allowed with `--allow unpadded-label`.
+"#,
+    ),
+    (
+        "W0009",
+        r#"Bytes are written to the memory reserved by a compatibility profile.
+
+Example:
+
+    |fefe #0102 #0304
+
+In the `uxn-legacy` compatibility profile, which describes the early Uxn
+runtimes and is used by default, the memory starting at address 0xff00
+is occupied by the stacks and the devices. Code or data placed there
+would be overwritten at runtime. Move it to a lower address. Pads into
+the reserved memory, e.g. for labels of the devices, are fine as long
+as nothing is written there.
+
+The profile can be changed with `--reserved-memory`, e.g. to `none` or to
+the hexadecimal address the reserved memory starts at. This warning can
+be allowed with `--allow reserved-page`.
+"#,
+    ),
+    (
+        "W0010",
+        r#"A value written to a device port with `DEO` or `DEO2` has a different
+size than the port.
+
+Example:
+
+    |10 @Console &vector $2 &read $1 &pad $5 &write $1
+    |0100 #0041 .Console/write DEO2
+
+The width of a port is declared with the relative pad right after its
+label or sublabel in the zero page. Writing a short to a port that is
+one byte wide writes its low byte to the next port, and writing a byte
+to a port that is two bytes wide sets only its high byte. Write a value
+of the declared size:
+
+    |0100 #41 .Console/write DEO
+
+Only ports that are one or two bytes wide are checked.
+
+This warning can be allowed with `--allow port-width`.
+"#,
+    ),
+    (
+        "W0011",
+        r#"A statement writes bytes at addresses that have already been written.
+
+Example:
+
+    |0100 #01 #02 ADD
+    |0101 #03
+
+This is only allowed with `--allow-backward-padding`, and is otherwise
+reported as the error E0067. The later statement replaces the bytes of
+the earlier one, which is rarely intended unless the bytes are patched
+on purpose.
+
+This warning can be allowed with `--allow overwritten-bytes`.
+"#,
+    ),
+    (
+        "W0012",
+        r#"A routine takes more bytes from a stack than there are on it.
+
+Example:
+
+    |0100 @on-reset
+        #01 ADD
+    BRK
+
+This is only checked with `--check-stacks`. Vectors, i.e. the reset
+vector and the routines assigned to the vectors of the devices, are
+entered with empty stacks. Subroutines are checked against the bytes
+taken by their callers from their own stacks, once the effects of the
+subroutines are known.
+
+This warning can be allowed with `--allow stack-underflow`.
+"#,
+    ),
+    (
+        "W0013",
+        r#"A routine leaves an unexpected number of bytes on a stack when it ends.
+
+Example:
+
+    |0100 @on-reset
+        #01 #02
+    BRK
+
+This is only checked with `--check-stacks`. Vectors are expected to
+leave their stacks empty at `BRK`, while subroutines are expected to
+leave only their return address on the return stack at `JMP2r`. The
+bytes left behind pile up every time the routine runs.
+
+This warning can be allowed with `--allow stack-height`.
+"#,
+    ),
+    (
+        "W0014",
+        r#"Bytes are emitted right after `BRK` or an unconditional jump, with no
+label defined in between.
+
+Example:
+
+    |0100 @on-reset
+        #01 #02 POP2
+    BRK
+        #03
+
+The execution never continues past `BRK` and the jumps, and the bytes
+can't be jumped to nor referenced without a label, so they never get
+executed. This is often a leftover of an edit, or data that is missing
+its label.
+
+This warning can be allowed with `--allow unreachable-code`.
+"#,
+    ),
+    (
+        "W0015",
+        r#"A vector of a device can fall through to the next label without
+reaching `BRK`.
+
+Example:
+
+    |20 @Screen &vector $2
+    |0100 ;on-frame .Screen/vector DEO2 BRK
+    @on-frame
+        #01 .Screen/vector DEI POP2
+    @draw
+        JMP2r
+
+Vectors are the labels assigned to the vectors of the devices with
+`;label .Device/vector DEO2`. The device runs the vector on every event,
+and the vector is expected to end with `BRK`, which hands the control
+back to the emulator. A vector missing its `BRK` runs away into the code
+after it, here the `JMP2r` of `draw`, which pops a return address that
+was never pushed.
+
+This warning can be allowed with `--allow unterminated-vector`.
+"#,
+    ),
+    (
+        "W0016",
+        r#"A literal relative address is close to the limit of 126 bytes.
+
+Example:
+
+    |0100 ,label JMP
+    |017c @label
+
+This is only checked with `--relative-margin N`, for the addresses within
+N bytes of the limit. Past the limit, the address is reported as the
+error E0031, so a jump like this one breaks with the next few bytes
+added between it and its label. Consider using an absolute address,
+e.g. `;label JMP2`, for the jumps that are expected to grow.
+
+This warning can be allowed with `--allow address-near-limit`.
+"#,
+    ),
+    (
+        "W0017",
+        r#"Two variables or device ports of the zero page share some of their bytes.
+
+Example:
+
+    |00 @x $2
+    |01 @y $1
+
+The sizes of the variables are given with the relative pads right after
+their labels, here 2 bytes for `x`, at 0x00 and 0x01, and 1 byte for
+`y`, at 0x01. Writing to either of them silently changes the other one,
+which is rarely intended. Absolute pads into the zero page are the usual
+cause, e.g. when a variable gets added before an explicitly placed one.
+
+This warning can be allowed with `--allow overlapping-variables`.
+"#,
+    ),
+    (
+        "W0018",
+        r#"A label or a sublabel has the name of an instruction.
+
+Example:
+
+    @POP2
+        &ADD
+
+The definitions are valid, and references with runes, e.g. `;POP2` or
+`,&ADD`, point at the label and the sublabel. A bare `POP2` or `ADD`,
+however, always assembles as the instruction, so the name reads as two
+different things depending on the rune in front of it. Names of
+instructions are three uppercase letters followed by the modes, so
+lowercase names never collide with them.
+
+This warning can be allowed with `--allow shadowed-instruction`.
+"#,
+    ),
+    (
+        "W0019",
+        r#"A macro has the same name as a label.
+
+Example:
+
+    %draw { #01 .Screen/pixel DEO }
+    @draw
+
+The definitions are valid, as a bare `draw` always invokes the macro,
+while the references to the label always have runes, e.g. `;draw`.
+The readers, however, have to keep the runes in mind to tell which of
+the two is meant, and the label may be mistaken for the macro or the
+other way around.
+
+This warning can be allowed with `--allow macro-named-like-label`.
+"#,
+    ),
+];
+
+/// Returns the extended explanation of the error or warning with the given code.
+pub fn explain(code: &str) -> Option<&'static str> {
+    EXPLANATIONS
+        .iter()
+        .find(|(explained_code, _)| explained_code.eq_ignore_ascii_case(code))
+        .map(|(_, explanation)| *explanation)
+}
@@ -0,0 +1,23 @@
+use ruxnasm::Suggestion;
+
+/// Applies the suggestions to the source code. A suggestion that overlaps with a previously
+/// applied one is skipped.
+pub fn apply(source: &[u8], suggestions: &[Suggestion]) -> Vec<u8> {
+    let mut suggestions: Vec<&Suggestion> = suggestions.iter().collect();
+    suggestions.sort_by_key(|suggestion| (suggestion.span.start, suggestion.span.end));
+
+    let mut fixed: Vec<u8> = Vec::with_capacity(source.len());
+    let mut position = 0;
+
+    for suggestion in suggestions {
+        if suggestion.span.start < position || suggestion.span.end > source.len() {
+            continue;
+        }
+        fixed.extend_from_slice(&source[position..suggestion.span.start]);
+        fixed.extend_from_slice(suggestion.replacement.as_bytes());
+        position = suggestion.span.end;
+    }
+    fixed.extend_from_slice(&source[position..]);
+
+    fixed
+}
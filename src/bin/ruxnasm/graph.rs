@@ -0,0 +1,94 @@
+use crate::source_map::{index_files, text};
+use ruxnasm::{Chunk, Reference, Source, Symbol};
+use std::collections::BTreeSet;
+use std::fmt::Write;
+
+/// A node of the graph, i.e. a label or a macro.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+enum Node {
+    Label(String),
+    Macro(String),
+}
+
+impl Node {
+    /// Identifier of the node in the DOT file. Macros are prefixed with their rune, so that they
+    /// don't clash with the labels of the same names.
+    fn id(&self) -> String {
+        let name = match self {
+            Self::Label(name) => name.clone(),
+            Self::Macro(name) => format!("%{}", name),
+        };
+        format!("\"{}\"", name.replace('\\', "\\\\").replace('"', "\\\""))
+    }
+}
+
+/// Renders a graph of the program in the DOT format of Graphviz, whose nodes are the labels and
+/// the macros, and whose edges are the references to the labels and the invocations of the
+/// macros. References and invocations belong to the labels owning the addresses they are written
+/// at, i.e. the closest labels defined at or before them, and the invocations nested in other
+/// macros belong to those macros. References to the sublabels are drawn as references to their
+/// labels.
+///
+/// Labels and macros without incoming edges are never used from the rest of the program, e.g.
+/// the entry point, or dead code.
+pub fn render(
+    chunks: &[Chunk],
+    symbols: &[Symbol],
+    macros: &[String],
+    references: &[Reference],
+    files: &[&Source],
+) -> Vec<u8> {
+    let (files, _) = index_files(files);
+    let labels: Vec<&Symbol> = symbols
+        .iter()
+        .filter(|symbol| !symbol.relative && !symbol.name.contains('/'))
+        .collect();
+    // The symbols are sorted by their addresses, and the labels of the zero page never own any
+    // of the bytes of the binary.
+    let first_owner = labels.partition_point(|label| label.address < 0x0100);
+    let owners = &labels[first_owner..];
+    let owner = |address: u16| match owners.partition_point(|label| label.address <= address) {
+        0 => None,
+        position => Some(Node::Label(owners[position - 1].name.clone())),
+    };
+
+    let mut edges: BTreeSet<(Node, Node, bool)> = BTreeSet::new();
+    for reference in references {
+        let label = reference.name.split('/').next().unwrap_or_default();
+        if let Some(from) = owner(reference.address) {
+            let to = Node::Label(label.to_owned());
+            if from != to {
+                edges.insert((from, to, false));
+            }
+        }
+    }
+    for chunk in chunks {
+        let mut from = owner(chunk.address);
+        for span in &chunk.invocations {
+            let to = Node::Macro(text(&files, span));
+            if let Some(from) = from {
+                edges.insert((from, to.clone(), true));
+            }
+            from = Some(to);
+        }
+    }
+
+    let mut graph = String::new();
+    let _ = writeln!(graph, "digraph program {{");
+    let _ = writeln!(graph, "    node [fontname=\"monospace\"];");
+    for label in &labels {
+        let node = Node::Label(label.name.clone());
+        let _ = writeln!(graph, "    {} [shape=box];", node.id());
+    }
+    for name in macros {
+        let node = Node::Macro(name.clone());
+        let _ = writeln!(graph, "    {} [shape=ellipse];", node.id());
+    }
+    for (from, to, invocation) in edges {
+        let style = if invocation { " [style=dashed]" } else { "" };
+        let _ = writeln!(graph, "    {} -> {}{};", from.id(), to.id(), style);
+    }
+    let _ = writeln!(graph, "}}");
+
+    graph.into_bytes()
+}
@@ -0,0 +1,259 @@
+use std::fmt;
+
+/// A JSON value, as used in the messages of the language server and in the source maps.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Null,
+    Boolean(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Value>),
+    /// Members of the object, in the order in which they have been written.
+    Object(Vec<(String, Value)>),
+}
+
+impl Value {
+    /// Returns the member of the object with the given key, or nothing if the value is not an
+    /// object or doesn't have such member.
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        match self {
+            Self::Object(members) => members
+                .iter()
+                .find(|(member_key, _)| member_key == key)
+                .map(|(_, value)| value),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Self::String(string) => Some(string),
+            _ => None,
+        }
+    }
+
+    pub fn as_usize(&self) -> Option<usize> {
+        match self {
+            Self::Number(number) if *number >= 0.0 && number.fract() == 0.0 => {
+                Some(*number as usize)
+            }
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Null => write!(f, "null"),
+            Self::Boolean(boolean) => write!(f, "{}", boolean),
+            Self::Number(number) => write!(f, "{}", number),
+            Self::String(value) => write!(f, "{}", string(value)),
+            Self::Array(elements) => {
+                let elements: Vec<String> = elements.iter().map(Value::to_string).collect();
+                write!(f, "[{}]", elements.join(","))
+            }
+            Self::Object(members) => {
+                let members: Vec<String> = members
+                    .iter()
+                    .map(|(key, value)| format!("{}:{}", string(key), value))
+                    .collect();
+                write!(f, "{{{}}}", members.join(","))
+            }
+        }
+    }
+}
+
+/// Encodes the string as a JSON string literal.
+pub fn string(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len() + 2);
+    encoded.push('"');
+    for ch in value.chars() {
+        match ch {
+            '"' => encoded.push_str("\\\""),
+            '\\' => encoded.push_str("\\\\"),
+            '\n' => encoded.push_str("\\n"),
+            '\r' => encoded.push_str("\\r"),
+            '\t' => encoded.push_str("\\t"),
+            ch if (ch as u32) < 0x20 => encoded.push_str(&format!("\\u{:04x}", ch as u32)),
+            ch => encoded.push(ch),
+        }
+    }
+    encoded.push('"');
+    encoded
+}
+
+/// Parses a JSON document.
+pub fn parse(source: &str) -> Result<Value, String> {
+    let mut parser = Parser {
+        chars: source.chars().collect(),
+        position: 0,
+    };
+    let value = parser.value()?;
+    parser.skip_whitespace();
+    match parser.peek() {
+        None => Ok(value),
+        Some(ch) => Err(format!("unexpected `{}` after the value", ch)),
+    }
+}
+
+struct Parser {
+    chars: Vec<char>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.position).copied()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let ch = self.peek()?;
+        self.position += 1;
+        Some(ch)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(' ') | Some('\t') | Some('\n') | Some('\r') = self.peek() {
+            self.position += 1;
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        match self.next() {
+            Some(ch) if ch == expected => Ok(()),
+            Some(ch) => Err(format!("expected `{}`, found `{}`", expected, ch)),
+            None => Err(format!(
+                "expected `{}`, found the end of the document",
+                expected
+            )),
+        }
+    }
+
+    fn keyword(&mut self, keyword: &str, value: Value) -> Result<Value, String> {
+        for expected in keyword.chars() {
+            self.expect(expected)?;
+        }
+        Ok(value)
+    }
+
+    fn value(&mut self) -> Result<Value, String> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some('n') => self.keyword("null", Value::Null),
+            Some('t') => self.keyword("true", Value::Boolean(true)),
+            Some('f') => self.keyword("false", Value::Boolean(false)),
+            Some('"') => self.string().map(Value::String),
+            Some('[') => self.array(),
+            Some('{') => self.object(),
+            Some(ch) if ch == '-' || ch.is_ascii_digit() => self.number(),
+            Some(ch) => Err(format!("unexpected `{}`", ch)),
+            None => Err("expected a value, found the end of the document".to_owned()),
+        }
+    }
+
+    fn number(&mut self) -> Result<Value, String> {
+        let start = self.position;
+        while let Some(ch) = self.peek() {
+            if ch.is_ascii_digit() || "+-.eE".contains(ch) {
+                self.position += 1;
+            } else {
+                break;
+            }
+        }
+        let number: String = self.chars[start..self.position].iter().collect();
+        number
+            .parse()
+            .map(Value::Number)
+            .map_err(|_| format!("invalid number `{}`", number))
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        self.expect('"')?;
+        let mut string = String::new();
+        loop {
+            match self.next() {
+                Some('"') => return Ok(string),
+                Some('\\') => match self.next() {
+                    Some('"') => string.push('"'),
+                    Some('\\') => string.push('\\'),
+                    Some('/') => string.push('/'),
+                    Some('b') => string.push('\u{8}'),
+                    Some('f') => string.push('\u{c}'),
+                    Some('n') => string.push('\n'),
+                    Some('r') => string.push('\r'),
+                    Some('t') => string.push('\t'),
+                    Some('u') => {
+                        let high = self.code_unit()?;
+                        // Characters outside of the Basic Multilingual Plane are written as
+                        // surrogate pairs.
+                        let code_units = if (0xd800..0xdc00).contains(&high) {
+                            self.expect('\\')?;
+                            self.expect('u')?;
+                            vec![high, self.code_unit()?]
+                        } else {
+                            vec![high]
+                        };
+                        string.push_str(
+                            &String::from_utf16(&code_units)
+                                .map_err(|_| "invalid surrogate pair".to_owned())?,
+                        );
+                    }
+                    Some(ch) => return Err(format!("invalid escape sequence `\\{}`", ch)),
+                    None => return Err("unterminated escape sequence".to_owned()),
+                },
+                Some(ch) => string.push(ch),
+                None => return Err("unterminated string".to_owned()),
+            }
+        }
+    }
+
+    /// Parses the four hexadecimal digits of a `\u` escape sequence.
+    fn code_unit(&mut self) -> Result<u16, String> {
+        let digits: String = (0..4).filter_map(|_| self.next()).collect();
+        u16::from_str_radix(&digits, 16)
+            .map_err(|_| format!("invalid escape sequence `\\u{}`", digits))
+    }
+
+    fn array(&mut self) -> Result<Value, String> {
+        self.expect('[')?;
+        let mut elements = Vec::new();
+        self.skip_whitespace();
+        if self.peek() == Some(']') {
+            self.position += 1;
+            return Ok(Value::Array(elements));
+        }
+        loop {
+            elements.push(self.value()?);
+            self.skip_whitespace();
+            match self.next() {
+                Some(',') => (),
+                Some(']') => return Ok(Value::Array(elements)),
+                _ => return Err("expected `,` or `]` in the array".to_owned()),
+            }
+        }
+    }
+
+    fn object(&mut self) -> Result<Value, String> {
+        self.expect('{')?;
+        let mut members = Vec::new();
+        self.skip_whitespace();
+        if self.peek() == Some('}') {
+            self.position += 1;
+            return Ok(Value::Object(members));
+        }
+        loop {
+            self.skip_whitespace();
+            let key = self.string()?;
+            self.skip_whitespace();
+            self.expect(':')?;
+            members.push((key, self.value()?));
+            self.skip_whitespace();
+            match self.next() {
+                Some(',') => (),
+                Some('}') => return Ok(Value::Object(members)),
+                _ => return Err("expected `,` or `}` in the object".to_owned()),
+            }
+        }
+    }
+}
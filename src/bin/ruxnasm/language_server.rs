@@ -0,0 +1,637 @@
+use crate::allocator::{self, Usage};
+use crate::json::Value;
+use crate::reporter::{self, lsp, FileDiagnostic};
+use crate::scheduler::{
+    self, Cancellation, Job, Key, Outcome, Scheduler, Workers, REQUEST_CANCELLED,
+};
+use ruxnasm::unstable::{Identifier, Query, Token, Word};
+use ruxnasm::TokenClass;
+use std::collections::HashMap;
+use std::io::{self, BufRead, Write};
+use std::ops::Range;
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Error code of JSON-RPC for messages which are not valid JSON.
+const PARSE_ERROR: f64 = -32700.0;
+/// Error code of JSON-RPC for requests of unknown methods.
+const METHOD_NOT_FOUND: f64 = -32601.0;
+/// Full synchronization of the documents, i.e. every change sends the whole text.
+const TEXT_DOCUMENT_SYNC_FULL: f64 = 1.0;
+/// Types of the semantic tokens, to which the classes of the tokens are mapped.
+const TOKEN_TYPES: [&str; 6] = [
+    "comment", "keyword", "function", "macro", "number", "operator",
+];
+/// Modifiers of the semantic tokens, as bit flags.
+const TOKEN_MODIFIERS: [&str; 1] = ["declaration"];
+const DECLARATION: usize = 1;
+
+/// Runs the language server over the standard input and output until the client tells it to
+/// exit, and returns whether the client has shut the server down before that. The input ending
+/// without an exit is reported as an error.
+///
+/// The messages are read on another thread and handled in order. The requests reading the
+/// documents and the assemblies of the documents for their diagnostics run on their own threads,
+/// so that a long one doesn't hold up the others, and can be cancelled while they're running.
+pub fn run() -> Result<bool, io::Error> {
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        let stdin = io::stdin();
+        let mut input = stdin.lock();
+        loop {
+            let message = read_message(&mut input);
+            let ended = !matches!(message, Ok(Some(_)));
+            if sender.send(message).is_err() || ended {
+                break;
+            }
+        }
+    });
+    let mut scheduler = Scheduler::new(receiver);
+    let mut server = Server {
+        documents: HashMap::new(),
+        workers: Workers::new(io::stdout()),
+        shut_down: false,
+    };
+
+    loop {
+        match scheduler.next_job()? {
+            Some(Job::Message {
+                message,
+                superseded,
+            }) => {
+                if scheduler::method(&message) == Some("exit") {
+                    return Ok(server.shut_down);
+                }
+                server.handle(&message, superseded)?;
+            }
+            Some(Job::Invalid(error)) => {
+                server
+                    .workers
+                    .write(&error_response(Value::Null, PARSE_ERROR, error))?
+            }
+            Some(Job::Cancelled { id, method }) => {
+                server.workers.record(
+                    &method,
+                    Outcome::Cancelled,
+                    Duration::default(),
+                    Usage::default(),
+                );
+                server.workers.write(&cancelled(id, &method))?;
+            }
+            // Requests which have already been answered can't be cancelled anymore.
+            Some(Job::Cancel { id }) => {
+                server.workers.cancel(&Key::request(&id));
+            }
+            None => return Err(io::ErrorKind::UnexpectedEof.into()),
+        }
+    }
+}
+
+struct Server {
+    /// Texts of the documents opened in the editor, by their URIs, shared with the jobs reading
+    /// them.
+    documents: HashMap<String, Arc<String>>,
+    workers: Workers,
+    shut_down: bool,
+}
+
+impl Server {
+    /// Handles a request or a notification, writing the messages to be sent back, or starting
+    /// the jobs which write them. Superseded changes of the documents are stored without
+    /// assembling the documents.
+    fn handle(&mut self, message: &Value, superseded: bool) -> Result<(), io::Error> {
+        let method = message.get("method").and_then(Value::as_str);
+        let params = message.get("params").unwrap_or(&Value::Null);
+
+        match (message.get("id"), method) {
+            (Some(id), Some(method)) => self.request(id, method, params),
+            (None, Some(method)) => self.notify(method, params, superseded),
+            // Responses of the client are not expected.
+            (_, None) => Ok(()),
+        }
+    }
+
+    /// Answers the request right away, or starts a job answering it if it reads a document. The
+    /// job gets the text of the document at the time of the request.
+    fn request(&mut self, id: &Value, method: &str, params: &Value) -> Result<(), io::Error> {
+        match method {
+            "textDocument/definition" => {
+                let located = self.locate(params);
+                self.spawn(id, method, move |_| {
+                    Some(
+                        located
+                            .and_then(|(uri, text, offset)| definition(&uri, &text, offset))
+                            .unwrap_or(Value::Null),
+                    )
+                });
+                return Ok(());
+            }
+            "textDocument/hover" => {
+                let located = self.locate(params);
+                self.spawn(id, method, move |_| {
+                    Some(
+                        located
+                            .and_then(|(_, text, offset)| hover(&text, offset))
+                            .unwrap_or(Value::Null),
+                    )
+                });
+                return Ok(());
+            }
+            "textDocument/semanticTokens/full" => {
+                let text = params
+                    .get("textDocument")
+                    .and_then(|text_document| text_document.get("uri"))
+                    .and_then(Value::as_str)
+                    .and_then(|uri| self.documents.get(uri))
+                    .cloned();
+                self.spawn(id, method, move |cancellation| match text {
+                    Some(text) => semantic_tokens(&text, cancellation),
+                    None => Some(Value::Null),
+                });
+                return Ok(());
+            }
+            _ => (),
+        }
+
+        let start = Instant::now();
+        let (result, usage) = allocator::measure(|| self.answer(method));
+        let message = match result {
+            Some(result) => response(id.clone(), result),
+            None => error_response(
+                id.clone(),
+                METHOD_NOT_FOUND,
+                format!("unknown method `{}`", method),
+            ),
+        };
+        self.workers
+            .record(method, Outcome::Handled, start.elapsed(), usage);
+        self.workers.write(&message)
+    }
+
+    /// Starts a job answering the request with the result it returns. The job returns nothing
+    /// if it has been cancelled.
+    fn spawn<F>(&self, id: &Value, method: &str, request: F)
+    where
+        F: FnOnce(&Cancellation) -> Option<Value> + Send + 'static,
+    {
+        let response_id = id.clone();
+        self.workers.spawn(
+            Key::request(id),
+            method,
+            Some(cancelled(id.clone(), method)),
+            move |cancellation| request(cancellation).map(|result| response(response_id, result)),
+        );
+    }
+
+    /// Returns the result of the request not reading any document, or nothing if the method is
+    /// unknown.
+    fn answer(&mut self, method: &str) -> Option<Value> {
+        match method {
+            "initialize" => Some(Value::Object(vec![
+                (
+                    "capabilities".to_owned(),
+                    Value::Object(vec![
+                        (
+                            "textDocumentSync".to_owned(),
+                            Value::Number(TEXT_DOCUMENT_SYNC_FULL),
+                        ),
+                        ("definitionProvider".to_owned(), Value::Boolean(true)),
+                        ("hoverProvider".to_owned(), Value::Boolean(true)),
+                        (
+                            "semanticTokensProvider".to_owned(),
+                            Value::Object(vec![
+                                (
+                                    "legend".to_owned(),
+                                    Value::Object(vec![
+                                        ("tokenTypes".to_owned(), strings(&TOKEN_TYPES)),
+                                        ("tokenModifiers".to_owned(), strings(&TOKEN_MODIFIERS)),
+                                    ]),
+                                ),
+                                ("full".to_owned(), Value::Boolean(true)),
+                            ]),
+                        ),
+                    ]),
+                ),
+                (
+                    "serverInfo".to_owned(),
+                    Value::Object(vec![
+                        ("name".to_owned(), Value::String("ruxnasm".to_owned())),
+                        (
+                            "version".to_owned(),
+                            Value::String(env!("CARGO_PKG_VERSION").to_owned()),
+                        ),
+                    ]),
+                ),
+            ])),
+            "shutdown" => {
+                self.shut_down = true;
+                Some(Value::Null)
+            }
+            "ruxnasm/status" => Some(self.status()),
+            _ => None,
+        }
+    }
+
+    /// Updates the documents, starting the jobs publishing their new diagnostics, unless the
+    /// change has been superseded by a later one. A job still assembling an older text of the
+    /// document is cancelled.
+    fn notify(&mut self, method: &str, params: &Value, superseded: bool) -> Result<(), io::Error> {
+        let uri = match params
+            .get("textDocument")
+            .and_then(|text_document| text_document.get("uri"))
+            .and_then(Value::as_str)
+        {
+            Some(uri) => uri.to_owned(),
+            None => return Ok(()),
+        };
+
+        let text = match method {
+            "textDocument/didOpen" => params
+                .get("textDocument")
+                .and_then(|text_document| text_document.get("text")),
+            // The whole text is sent with every change, so only the last one matters.
+            "textDocument/didChange" => match params.get("contentChanges") {
+                Some(Value::Array(changes)) => changes.last().and_then(|change| change.get("text")),
+                _ => None,
+            },
+            "textDocument/didClose" => {
+                self.documents.remove(&uri);
+                self.workers.cancel(&Key::Diagnostics(uri.clone()));
+                return self.workers.write(&publish_diagnostics(&uri, Vec::new()));
+            }
+            _ => None,
+        };
+        let text = match text.and_then(Value::as_str) {
+            Some(text) => Arc::new(text.to_owned()),
+            None => return Ok(()),
+        };
+
+        self.documents.insert(uri.clone(), Arc::clone(&text));
+        if superseded {
+            self.workers.record(
+                method,
+                Outcome::Superseded,
+                Duration::default(),
+                Usage::default(),
+            );
+            return Ok(());
+        }
+        self.workers.spawn(
+            Key::Diagnostics(uri.clone()),
+            method,
+            None,
+            move |cancellation| {
+                diagnostics(&uri, &text, cancellation)
+                    .map(|diagnostics| publish_diagnostics(&uri, diagnostics))
+            },
+        );
+        Ok(())
+    }
+
+    /// Describes the state of the server, for diagnosing slowdowns in long editing sessions: the
+    /// documents held in memory, the jobs running at the moment, and the timings and the memory
+    /// of the handled messages by their methods.
+    fn status(&self) -> Value {
+        let document_bytes: usize = self.documents.values().map(|text| text.len()).sum();
+        let (running, methods) = self.workers.status();
+
+        Value::Object(vec![
+            (
+                "documents".to_owned(),
+                Value::Number(self.documents.len() as f64),
+            ),
+            (
+                "documentBytes".to_owned(),
+                Value::Number(document_bytes as f64),
+            ),
+            ("running".to_owned(), running),
+            ("methods".to_owned(), methods),
+        ])
+    }
+
+    /// Finds the document and the offset in it pointed to by the parameters of a request.
+    fn locate(&self, params: &Value) -> Option<(String, Arc<String>, usize)> {
+        let uri = params.get("textDocument")?.get("uri")?.as_str()?;
+        let text = self.documents.get(uri)?;
+        let position = params.get("position")?;
+        let line = position.get("line")?.as_usize()?;
+        let character = position.get("character")?.as_usize()?;
+        let offset = lsp::offset(text, line, character);
+
+        Some((uri.to_owned(), Arc::clone(text), offset))
+    }
+}
+
+/// Assembles the document and converts the errors and warnings into LSP diagnostics. Files
+/// included in the document are read relative to the working directory of the server, just like
+/// in the command line. Returns nothing if the job has been cancelled.
+fn diagnostics(uri: &str, text: &str, cancellation: &Cancellation) -> Option<Vec<Value>> {
+    let (errors, warnings) = match ruxnasm::assemble(text.as_bytes()) {
+        Ok((_, warnings)) => (Vec::new(), warnings),
+        Err((errors, warnings)) => (errors, warnings),
+    };
+    if cancellation.is_cancelled() {
+        return None;
+    }
+
+    Some(
+        reporter::error_diagnostics(errors)
+            .into_iter()
+            .chain(warnings.into_iter().map(FileDiagnostic::from))
+            .map(|diagnostic| lsp::diagnostic(&diagnostic, uri, text))
+            .collect(),
+    )
+}
+
+/// Name of a label, a sublabel, or a macro, as it appears in the program.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Name {
+    /// Name of the label, or the full `label/sublabel` path of the sublabel.
+    Label(String),
+    Macro(String),
+}
+
+/// Every name defined or referenced in the document, along with the spans of the tokens.
+struct Index {
+    definitions: Vec<(Name, Range<usize>)>,
+    occurrences: Vec<(Name, Range<usize>)>,
+}
+
+impl Index {
+    /// Scans the document, resolving the sublabels in the scope of the closest label defined
+    /// before them. Names defined in the included files are not listed.
+    fn new(text: &str) -> Self {
+        let words = match ruxnasm::unstable::scan(text.as_bytes()) {
+            Ok((words, _)) => words,
+            Err(_) => Vec::new(),
+        };
+        let mut index = Self {
+            definitions: Vec::new(),
+            occurrences: Vec::new(),
+        };
+        let mut scope = String::new();
+
+        for word in &words {
+            let token = match word {
+                Word::Fine { token, .. } => token,
+                Word::Faulty { .. } => continue,
+            };
+            let span: Range<usize> = token.span.from.offset..token.span.to.offset;
+            if span.end > text.len() {
+                continue;
+            }
+            let (name, defined) = match &token.node {
+                Token::LabelDefine(name) => {
+                    scope = lossy(name);
+                    (Name::Label(scope.clone()), true)
+                }
+                Token::ConstantDefine(name) => (Name::Label(lossy(name)), true),
+                Token::SublabelDefine(name) => (sublabel(&scope, name), true),
+                Token::MacroDefine(name) => (Name::Macro(lossy(name)), true),
+                Token::MacroInvoke(name) | Token::Query(Query::Defined(name)) => {
+                    (Name::Macro(lossy(name)), false)
+                }
+                Token::LiteralZeroPageAddress(identifier)
+                | Token::LiteralRelativeAddress(identifier)
+                | Token::LiteralAbsoluteAddress(identifier)
+                | Token::RawAddress(identifier) => (
+                    match identifier {
+                        Identifier::Label(name) => Name::Label(lossy(name)),
+                        Identifier::Sublabel(name) => sublabel(&scope, name),
+                        Identifier::Path(name, sublabel_name) => {
+                            Name::Label(format!("{}/{}", lossy(name), lossy(sublabel_name)))
+                        }
+                    },
+                    false,
+                ),
+                _ => continue,
+            };
+            if defined {
+                index.definitions.push((name.clone(), span.clone()));
+            }
+            index.occurrences.push((name, span));
+        }
+
+        index
+    }
+
+    /// Returns the name at the offset, along with the span of its token.
+    fn name_at(&self, offset: usize) -> Option<&(Name, Range<usize>)> {
+        self.occurrences
+            .iter()
+            .find(|(_, span)| span.start <= offset && offset < span.end)
+    }
+
+    fn definition(&self, name: &Name) -> Option<&Range<usize>> {
+        self.definitions
+            .iter()
+            .find(|(defined_name, _)| defined_name == name)
+            .map(|(_, span)| span)
+    }
+}
+
+/// Finds the definition of the label or macro at the offset.
+fn definition(uri: &str, text: &str, offset: usize) -> Option<Value> {
+    let index = Index::new(text);
+    let (name, _) = index.name_at(offset)?;
+    let span = index.definition(name)?;
+
+    Some(lsp::location(uri, text, span))
+}
+
+/// Describes the label or macro at the offset: labels along with their resolved addresses, and
+/// macros along with their bodies.
+fn hover(text: &str, offset: usize) -> Option<Value> {
+    let index = Index::new(text);
+    let (name, span) = index.name_at(offset)?;
+
+    let contents = match name {
+        Name::Label(name) => {
+            let assembly =
+                ruxnasm::assemble_with_options(text.as_bytes(), &ruxnasm::Options::default())
+                    .ok()?;
+            let symbol = assembly
+                .symbols
+                .iter()
+                .find(|symbol| symbol.name == *name)?;
+            format!("`{}`: `{:04x}`", symbol.name, symbol.address)
+        }
+        Name::Macro(name) => {
+            let start = index.definition(&Name::Macro(name.clone()))?.start;
+            let end = text[start..]
+                .find('}')
+                .map_or(text.len(), |position| start + position + 1);
+            format!("```uxntal\n{}\n```", &text[start..end])
+        }
+    };
+
+    Some(Value::Object(vec![
+        (
+            "contents".to_owned(),
+            Value::Object(vec![
+                ("kind".to_owned(), Value::String("markdown".to_owned())),
+                ("value".to_owned(), Value::String(contents)),
+            ]),
+        ),
+        ("range".to_owned(), lsp::range(text, span)),
+    ]))
+}
+
+/// Classifies the tokens of the document, encoding them relative to each other as required by
+/// the protocol. Tokens spanning multiple lines are split into one token per line. The tokens
+/// are listed in order, so their positions are found in a single pass over the document. Returns
+/// nothing if the job has been cancelled.
+fn semantic_tokens(text: &str, cancellation: &Cancellation) -> Option<Value> {
+    let mut data = Vec::new();
+    let mut cursor = lsp::Cursor::new(text);
+    let (mut previous_line, mut previous_character) = (0, 0);
+
+    for token in ruxnasm::classify(text.as_bytes()) {
+        let (token_type, modifiers) = match token.class {
+            TokenClass::Comment => (0, 0),
+            TokenClass::Instruction | TokenClass::Directive => (1, 0),
+            TokenClass::LabelDefinition => (2, DECLARATION),
+            TokenClass::LabelReference => (2, 0),
+            TokenClass::MacroDefinition => (3, DECLARATION),
+            TokenClass::MacroInvocation => (3, 0),
+            TokenClass::Literal => (4, 0),
+            TokenClass::Pad | TokenClass::Delimiter => (5, 0),
+            TokenClass::Invalid => continue,
+        };
+        if cancellation.is_cancelled() {
+            return None;
+        }
+
+        let mut start = token.span.start;
+        for segment in text.as_bytes()[token.span.clone()].split(|byte| *byte == b'\n') {
+            let (line, character) = cursor.line_character(start);
+            let length = String::from_utf8_lossy(segment).encode_utf16().count();
+            start += segment.len() + 1;
+            if length == 0 {
+                continue;
+            }
+            let delta_character = if line == previous_line {
+                character - previous_character
+            } else {
+                character
+            };
+            for number in &[
+                line - previous_line,
+                delta_character,
+                length,
+                token_type,
+                modifiers,
+            ] {
+                data.push(Value::Number(*number as f64));
+            }
+            previous_line = line;
+            previous_character = character;
+        }
+    }
+
+    Some(Value::Object(vec![("data".to_owned(), Value::Array(data))]))
+}
+
+fn strings(strings: &[&str]) -> Value {
+    Value::Array(
+        strings
+            .iter()
+            .map(|string| Value::String((*string).to_owned()))
+            .collect(),
+    )
+}
+
+fn sublabel(scope: &str, name: &[u8]) -> Name {
+    Name::Label(format!("{}/{}", scope, lossy(name)))
+}
+
+fn lossy(name: &[u8]) -> String {
+    String::from_utf8_lossy(name).into_owned()
+}
+
+fn response(id: Value, result: Value) -> Value {
+    Value::Object(vec![
+        ("jsonrpc".to_owned(), Value::String("2.0".to_owned())),
+        ("id".to_owned(), id),
+        ("result".to_owned(), result),
+    ])
+}
+
+fn cancelled(id: Value, method: &str) -> Value {
+    error_response(
+        id,
+        REQUEST_CANCELLED,
+        format!("request `{}` has been cancelled", method),
+    )
+}
+
+fn error_response(id: Value, code: f64, message: String) -> Value {
+    Value::Object(vec![
+        ("jsonrpc".to_owned(), Value::String("2.0".to_owned())),
+        ("id".to_owned(), id),
+        (
+            "error".to_owned(),
+            Value::Object(vec![
+                ("code".to_owned(), Value::Number(code)),
+                ("message".to_owned(), Value::String(message)),
+            ]),
+        ),
+    ])
+}
+
+fn publish_diagnostics(uri: &str, diagnostics: Vec<Value>) -> Value {
+    Value::Object(vec![
+        ("jsonrpc".to_owned(), Value::String("2.0".to_owned())),
+        (
+            "method".to_owned(),
+            Value::String("textDocument/publishDiagnostics".to_owned()),
+        ),
+        (
+            "params".to_owned(),
+            Value::Object(vec![
+                ("uri".to_owned(), Value::String(uri.to_owned())),
+                ("diagnostics".to_owned(), Value::Array(diagnostics)),
+            ]),
+        ),
+    ])
+}
+
+/// Reads the content of a message, which is preceded by the headers. Returns nothing if the
+/// input has ended.
+fn read_message(input: &mut impl BufRead) -> Result<Option<String>, io::Error> {
+    let mut content_length = None;
+    loop {
+        let mut line = String::new();
+        if input.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some(value) = line.strip_prefix("Content-Length:") {
+            content_length = value.trim().parse().ok();
+        }
+    }
+
+    let content_length = content_length.ok_or_else(|| {
+        io::Error::new(io::ErrorKind::InvalidData, "missing Content-Length header")
+    })?;
+    let mut content = vec![0; content_length];
+    input.read_exact(&mut content)?;
+    String::from_utf8(content)
+        .map(Some)
+        .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))
+}
+
+pub fn write_message(output: &mut impl Write, message: &Value) -> Result<(), io::Error> {
+    let content = message.to_string();
+    write!(
+        output,
+        "Content-Length: {}\r\n\r\n{}",
+        content.len(),
+        content
+    )?;
+    output.flush()
+}
@@ -1,52 +1,495 @@
+use argument_parser::{Compat, Emit, Output, OutputFormat, Report, Subcommand, WarningLevel};
+use reporter::{FileDiagnostic, Severity};
 use std::panic::set_hook;
+use std::path::{Component, Path, PathBuf};
 use std::process::exit;
 
+pub mod allocator;
+pub mod archive;
 pub mod argument_parser;
+pub mod comparison;
+pub mod config;
+pub mod crash_dump;
+pub mod debug_info;
+pub mod encoder;
+pub mod explanations;
+pub mod fix;
+pub mod graph;
+pub mod json;
+pub mod language_server;
+pub mod map_file;
+pub mod memory_map;
+pub mod opcodes;
+pub mod post_process;
+pub mod profile;
 pub mod reader;
+pub mod report;
 pub mod reporter;
+pub mod scheduler;
+pub mod search_path;
+pub mod source_map;
+pub mod standard;
+pub mod verify;
 pub mod writer;
 
+/// Counts the memory allocated by every thread, for the metrics of the language server.
+#[global_allocator]
+static ALLOCATOR: allocator::Counting = allocator::Counting;
+
+/// Name under which the diagnostics refer to the macros defined on the command line.
+const DEFINES_FILE_NAME: &str = "<command line>";
+
 struct InternalAssemblerError {
     message: String,
+    /// Path of the crash dump, if it has been requested.
+    crash_dump: Option<Result<PathBuf, std::io::Error>>,
 }
 
 fn try_main() -> Result<(), ()> {
-    match argument_parser::parse_arguments() {
-        Ok(arguments) => match reader::read(arguments.input_file_path()) {
-            Ok(input_file_contents) => {
-                let reporter = reporter::VoidReporter::new()
-                    .promote(arguments.input_file_path(), &input_file_contents);
-                match ruxnasm::assemble(&input_file_contents) {
-                    Ok((binary, warnings)) => {
-                        for warning in warnings {
-                            reporter.emit(warning.into());
-                        }
-                        match writer::write(arguments.output_file_path(), &binary) {
-                            Ok(()) => Ok(()),
-                            Err(error) => {
-                                let reporter = reporter.demote();
-                                reporter.emit(error.into());
-                                Err(())
-                            }
+    match argument_parser::parse_subcommand() {
+        Some(Ok(Subcommand::Disassemble(binary_file_path))) => {
+            return disassemble(&binary_file_path)
+        }
+        Some(Ok(Subcommand::Format(input_file_paths))) => return format(&input_file_paths),
+        Some(Ok(Subcommand::Verify(input_file_paths))) => return verify(&input_file_paths),
+        Some(Ok(Subcommand::LanguageServer)) => {
+            // The exit code tells the client whether the server has been shut down properly.
+            return match language_server::run() {
+                Ok(true) => Ok(()),
+                Ok(false) | Err(_) => Err(()),
+            };
+        }
+        Some(Err(error)) => {
+            let reporter = reporter::VoidReporter::new();
+            reporter.emit(error.into());
+            return Err(());
+        }
+        None => (),
+    }
+
+    let config = match config::discover() {
+        Ok(config) => config,
+        Err(error) => {
+            let reporter = reporter::VoidReporter::new();
+            reporter.emit(error.into());
+            return Err(());
+        }
+    };
+
+    let config_file_path = config.as_ref().and_then(|config| config.file_path.clone());
+
+    match argument_parser::parse_arguments(config) {
+        Ok(arguments) => {
+            let reporter = diagnostic_reporter(&arguments)?;
+            match reader::read_input_files(arguments.input_file_paths()) {
+                Ok(input_files) => {
+                    let input_files = if arguments.fix() {
+                        fix_input_files(input_files, &arguments, &reporter)?
+                    } else {
+                        input_files
+                    };
+                    let profile = match arguments.profile_file_path().map(profile::read) {
+                        Some(Ok(profile)) => Some(profile),
+                        Some(Err(error)) => {
+                            reporter.emit(error.into());
+                            return Err(());
                         }
+                        None => None,
+                    };
+                    let opcodes = match arguments
+                        .opcodes_file_path()
+                        .map(|file_path| opcodes::read(file_path, arguments.opcode_table()))
+                    {
+                        Some(Ok(opcodes)) => Some(opcodes),
+                        Some(Err(error)) => {
+                            reporter.emit(error.into());
+                            return Err(());
+                        }
+                        None => None,
+                    };
+                    let input_files = with_std_libraries(input_files, arguments.std_libraries());
+                    let input_files = with_defines(input_files, arguments.defines());
+                    let input_file_contents = reader::concatenate(&input_files);
+                    if arguments.crash_dump() {
+                        // The tokens can't be scanned lazily inside of the hook, since a panic
+                        // in a panic hook aborts the process. They're scanned up front instead,
+                        // only with `--crash-dump`, under a hook bundling the input alone, so
+                        // that a crash of the scanner itself still gets its dump.
+                        let bundle = crash_dump::Bundle::new(&input_file_contents);
+                        set_panic_hook(Some(bundle.clone()));
+                        set_panic_hook(Some(bundle.with_tokens()));
                     }
-                    Err((errors, warnings)) => {
-                        for error in errors {
+                    // Binaries going straight to a file are written next to it while they're
+                    // assembled, and replace it only if the program turns out to be valid.
+                    let streamed_path = match arguments.output() {
+                        Some(Output::File(output_file_path))
+                            if writer::is_streamable(output_file_path)
+                                && !arguments.library()
+                                && arguments.reference_file_path().is_none()
+                                && arguments.output_format() == OutputFormat::Rom =>
+                        {
+                            Some(output_file_path.as_path())
+                        }
+                        _ => None,
+                    };
+                    let mut streamed = match streamed_path.map(writer::Streamed::create) {
+                        Some(Ok(streamed)) => Some(streamed),
+                        Some(Err(error)) => {
                             reporter.emit(error.into());
+                            return Err(());
                         }
-                        for warning in warnings {
-                            reporter.emit(warning.into());
+                        None => None,
+                    };
+                    let mut options = ruxnasm::Options::default();
+                    options.backward_padding = arguments.backward_padding();
+                    options.size = arguments.size();
+                    options.trim = arguments.trim();
+                    options.opcode_table = arguments.opcode_table();
+                    options.reserved_memory = arguments.reserved_memory();
+                    options.stack_warnings = arguments.check_stacks();
+                    options.relative_address_margin = arguments.relative_address_margin();
+                    options.library = arguments.library();
+                    options.error_limit = arguments.error_limit();
+                    // Everything printed and written about the program comes from the same
+                    // assembly as the binary, so it follows the same options.
+                    let mut records = ruxnasm::Records::default();
+                    if arguments.trace_emit() {
+                        records.trace = Some(Vec::new());
+                    }
+                    if arguments.emits().contains(&Emit::Graph)
+                        || arguments.reports().contains(&Report::References)
+                    {
+                        records.references = Some(Vec::new());
+                    }
+                    records.layout_hints = profile.as_ref().map(|profile| (profile, Vec::new()));
+                    records.instruction_set = opcodes
+                        .as_ref()
+                        .map(|opcodes| opcodes as &dyn ruxnasm::InstructionSet);
+                    records.writer = streamed.as_mut().map(writer::Streamed::file);
+                    let mut post_processor = post_process::Commands::new(arguments.post_commands());
+                    if !arguments.post_commands().is_empty() {
+                        records.post_processor = Some(&mut post_processor);
+                    }
+                    let result = match ruxnasm::assemble_with_records(
+                        &input_file_contents,
+                        &options,
+                        &search_path::SearchPath::new(arguments.include_directories()),
+                        &mut records,
+                    ) {
+                        Ok(result) => result,
+                        Err(io_error) => {
+                            match post_processor.failure(&io_error) {
+                                Some(error) => reporter.emit(error.into()),
+                                None => {
+                                    let file_path = streamed_path.expect(
+                                        "nothing is written during the assembly without a writer",
+                                    );
+                                    reporter.emit(
+                                        writer::Error::CouldNotWriteFile {
+                                            file_path: file_path.to_path_buf(),
+                                            io_error,
+                                        }
+                                        .into(),
+                                    );
+                                }
+                            }
+                            return Err(());
+                        }
+                    };
+                    // The records are taken apart, so that the streamed file is not borrowed
+                    // anymore once it's kept.
+                    let ruxnasm::Records {
+                        sources,
+                        chunks,
+                        trace,
+                        references,
+                        layout_hints,
+                        ..
+                    } = records;
+                    let layout_hints = layout_hints.map(|(_, hints)| hints);
+                    if let Some(trace) = &trace {
+                        print_trace(&input_file_contents, &sources, trace);
+                    }
+                    let reporter = reporter
+                        .promote(&input_files[0].path, &input_files[0].contents)
+                        .with_sources(&input_files[1..])
+                        .with_sources(&sources)
+                        .with_message_format(arguments.message_format());
+                    let assembly = match result {
+                        Ok(mut assembly) => {
+                            let warnings = std::mem::take(&mut assembly.warnings);
+                            if report_warnings(&reporter, warnings, &arguments) {
+                                Err(())
+                            } else {
+                                Ok(assembly)
+                            }
+                        }
+                        Err((errors, warnings)) => {
+                            for diagnostic in reporter::error_diagnostics(errors) {
+                                reporter.emit(diagnostic);
+                            }
+                            report_warnings(&reporter, warnings, &arguments);
+                            Err(())
+                        }
+                    };
+                    reporter.finish();
+                    let mut assembly = assembly?;
+                    // Streamed binaries have been written during the assembly, and they are never
+                    // compared or encoded.
+                    let binary = if streamed.is_some() {
+                        Vec::new()
+                    } else {
+                        let binary = std::mem::take(&mut assembly.binary);
+                        if let Some(reference_file_path) = arguments.reference_file_path() {
+                            let differences = reader::read(reference_file_path)
+                                .map(|reference| comparison::compare(&binary, &reference));
+                            match differences {
+                                Ok(differences) if differences.is_empty() => (),
+                                Ok(differences) => {
+                                    comparison::print(&differences, &assembly.symbols);
+                                    let reporter = reporter.demote();
+                                    reporter.emit(
+                                        comparison::Error::BinariesDiffer {
+                                            reference_path: reference_file_path.to_path_buf(),
+                                            byte_count: differences
+                                                .iter()
+                                                .map(|difference| difference.end - difference.start)
+                                                .sum(),
+                                            range_count: differences.len(),
+                                        }
+                                        .into(),
+                                    );
+                                    return Err(());
+                                }
+                                Err(error) => {
+                                    let reporter = reporter.demote();
+                                    reporter.emit(error.into());
+                                    return Err(());
+                                }
+                            }
+                        }
+                        encoder::encode(binary, arguments.output_format(), &assembly.symbols)
+                    };
+                    let written = match (arguments.output(), streamed) {
+                        (Some(Output::File(output_file_path)), Some(streamed)) => {
+                            streamed.keep().map(|()| output_file_path.to_path_buf())
+                        }
+                        (Some(Output::File(output_file_path)), None) => {
+                            writer::write(output_file_path, &binary)
+                                .map(|()| output_file_path.to_path_buf())
+                        }
+                        (Some(Output::Directory(out_dir)), _) => writer::write_to_directory(
+                            out_dir,
+                            &rom_name(&input_files),
+                            encoder::extension(arguments.output_format()),
+                            &binary,
+                        ),
+                        (None, _) => {
+                            print_library_symbols(&assembly.symbols);
+                            return Ok(());
+                        }
+                    };
+                    let written = written.and_then(|binary_path| {
+                        if arguments.embed_source() {
+                            write_source_archive(
+                                &binary_path,
+                                &input_files,
+                                &sources,
+                                config_file_path.as_deref(),
+                            )?;
+                        }
+                        for emit in arguments.emits() {
+                            match emit {
+                                Emit::Graph => {
+                                    let files: Vec<&ruxnasm::Source> =
+                                        input_files.iter().chain(&sources).collect();
+                                    write_graph(
+                                        &binary_path,
+                                        &assembly,
+                                        &chunks,
+                                        references.as_deref().unwrap_or_default(),
+                                        &files,
+                                    )?;
+                                }
+                            }
+                        }
+                        if let Some(memory_map_file_path) = arguments.memory_map_file_path() {
+                            write_memory_map(memory_map_file_path, &chunks, &assembly.symbols)?;
+                        }
+                        if let Some(map_file_path) = arguments.map_file_path() {
+                            write_map_file(map_file_path, &chunks, &assembly.symbols)?;
+                        }
+                        if let Some(source_map_file_path) = arguments.source_map_file_path() {
+                            let files: Vec<&ruxnasm::Source> =
+                                input_files.iter().chain(&sources).collect();
+                            write_source_map(source_map_file_path, &chunks, &files)?;
+                        }
+                        if let Some(debug_info_file_path) = arguments.debug_info_file_path() {
+                            let files: Vec<&ruxnasm::Source> =
+                                input_files.iter().chain(&sources).collect();
+                            write_debug_info(
+                                debug_info_file_path,
+                                &chunks,
+                                &assembly.symbols,
+                                &files,
+                            )?;
+                        }
+                        if let Some(symbols_file_path) = arguments.symbols_file_path() {
+                            write_symbols(symbols_file_path, &assembly.symbols)?;
+                        }
+                        if arguments.compat() == Some(Compat::Uxnasm) {
+                            print_uxnasm_summary(&binary_path, &assembly);
+                        }
+                        Ok(())
+                    });
+                    match written {
+                        Ok(()) => {
+                            if let Some(hints) = &layout_hints {
+                                print_layout_hints(hints);
+                            }
+                            if arguments.stats() {
+                                print_stats(&assembly);
+                            }
+                            let files: Vec<&ruxnasm::Source> =
+                                input_files.iter().chain(&sources).collect();
+                            for report in arguments.reports() {
+                                report::print(
+                                    *report,
+                                    &assembly,
+                                    references.as_deref().unwrap_or_default(),
+                                    &files,
+                                );
+                            }
+                            Ok(())
+                        }
+                        Err(error) => {
+                            let reporter = reporter.demote();
+                            reporter.emit(error.into());
+                            Err(())
                         }
-                        Err(())
                     }
                 }
+                Err(error) => {
+                    reporter.emit(error.into());
+                    Err(())
+                }
+            }
+        }
+        Err(error) => {
+            let reporter = reporter::VoidReporter::new();
+            reporter.emit(error.into());
+            Err(())
+        }
+    }
+}
+
+/// Prints the instructions of the binary, one per line, along with their addresses and bytes.
+fn disassemble(binary_file_path: &Path) -> Result<(), ()> {
+    let binary = match reader::read(binary_file_path) {
+        Ok(binary) => binary,
+        Err(error) => {
+            let reporter = reporter::VoidReporter::new();
+            reporter.emit(error.into());
+            return Err(());
+        }
+    };
+
+    for instruction in ruxnasm::disassemble(&binary) {
+        let bytes: Vec<String> = instruction
+            .bytes
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect();
+        println!(
+            "{:04x}  {:<8}  {}",
+            instruction.address,
+            bytes.join(" "),
+            instruction.text
+        );
+    }
+
+    Ok(())
+}
+
+/// Rewrites the files in the canonical format. Files which can't be split into tokens are
+/// reported and left untouched.
+fn format(input_file_paths: &[PathBuf]) -> Result<(), ()> {
+    let mut result = Ok(());
+
+    for input_file_path in input_file_paths {
+        let contents = match reader::read(input_file_path) {
+            Ok(contents) => contents,
+            Err(error) => {
+                let reporter = reporter::VoidReporter::new();
+                reporter.emit(error.into());
+                result = Err(());
+                continue;
             }
+        };
+        let formatted = match ruxnasm::format(&contents) {
+            Ok(formatted) => formatted,
             Err(error) => {
+                let reporter = reporter::VoidReporter::new().promote(input_file_path, &contents);
+                reporter.emit(error.into());
+                result = Err(());
+                continue;
+            }
+        };
+        if formatted != contents {
+            if let Err(error) = writer::write(input_file_path, &formatted) {
                 let reporter = reporter::VoidReporter::new();
                 reporter.emit(error.into());
-                Err(())
+                result = Err(());
+            }
+        }
+    }
+
+    result
+}
+
+/// Assembles the files, and checks that the binary survives being disassembled and assembled once
+/// again, reporting the first address at which it doesn't.
+fn verify(input_file_paths: &[PathBuf]) -> Result<(), ()> {
+    let input_files = match reader::read_input_files(input_file_paths) {
+        Ok(input_files) => input_files,
+        Err(error) => {
+            let reporter = reporter::VoidReporter::new();
+            reporter.emit(error.into());
+            return Err(());
+        }
+    };
+    let input_file_contents = reader::concatenate(&input_files);
+    let mut records = ruxnasm::Records::default();
+    let result = ruxnasm::assemble_with_records(
+        &input_file_contents,
+        &ruxnasm::Options::default(),
+        &ruxnasm::FileSystem,
+        &mut records,
+    )
+    .expect("nothing is written during the assembly without a writer");
+    let binary = match result {
+        Ok(assembly) => assembly.binary,
+        Err((errors, _)) => {
+            let reporter = reporter::VoidReporter::new()
+                .promote(&input_files[0].path, &input_files[0].contents)
+                .with_sources(&input_files[1..])
+                .with_sources(&records.sources);
+            for diagnostic in reporter::error_diagnostics(errors) {
+                reporter.emit(diagnostic);
             }
-        },
+            reporter.finish();
+            return Err(());
+        }
+    };
+
+    match verify::round_trip(&binary) {
+        Ok(()) => {
+            println!(
+                "the binary of {} bytes is the same after disassembling and assembling it again",
+                binary.len()
+            );
+            Ok(())
+        }
         Err(error) => {
             let reporter = reporter::VoidReporter::new();
             reporter.emit(error.into());
@@ -55,18 +498,446 @@ fn try_main() -> Result<(), ()> {
     }
 }
 
-fn main() {
-    set_hook(Box::new(|panic_info| {
+/// Creates the reporter of the diagnostics as set up by the arguments. Reports the file to which
+/// the diagnostics should be written if it can't be created.
+fn diagnostic_reporter(
+    arguments: &argument_parser::Arguments,
+) -> Result<reporter::VoidReporter, ()> {
+    let reporter = reporter::VoidReporter::new()
+        .with_color_choice(arguments.color_choice())
+        .with_diagnostic_style(arguments.diagnostic_style())
+        .with_layout(arguments.layout());
+
+    match arguments.diagnostics_output_path().map(writer::create) {
+        Some(Ok(file)) => Ok(reporter.with_output_file(file, arguments.color_choice())),
+        Some(Err(error)) => {
+            reporter.emit(error.into());
+            Err(())
+        }
+        None => Ok(reporter),
+    }
+}
+
+/// Applies the fixes suggested by the errors and the reported warnings to the input files and
+/// returns their new contents.
+fn fix_input_files(
+    input_files: Vec<ruxnasm::Source>,
+    arguments: &argument_parser::Arguments,
+    reporter: &reporter::VoidReporter,
+) -> Result<Vec<ruxnasm::Source>, ()> {
+    let (errors, warnings) = match ruxnasm::assemble(&reader::concatenate(&input_files)) {
+        Ok((_, warnings)) => (Vec::new(), warnings),
+        Err((errors, warnings)) => (errors, warnings),
+    };
+    let suggestions: Vec<ruxnasm::Suggestion> = errors
+        .iter()
+        .flat_map(|error| error.suggestions().iter().cloned())
+        .chain(
+            warnings
+                .iter()
+                .filter(|warning| arguments.warning_level(warning.name()) != WarningLevel::Allow)
+                .flat_map(|warning| warning.suggestions().iter().cloned()),
+        )
+        .collect();
+    if suggestions.is_empty() {
+        return Ok(input_files);
+    }
+
+    let mut fixed_files = Vec::new();
+    for input_file in input_files {
+        let range = input_file.range();
+        let file_suggestions: Vec<ruxnasm::Suggestion> = suggestions
+            .iter()
+            .filter(|suggestion| {
+                range.start <= suggestion.span.start && suggestion.span.end <= range.end
+            })
+            .map(|suggestion| ruxnasm::Suggestion {
+                span: suggestion.span.start - range.start..suggestion.span.end - range.start,
+                replacement: suggestion.replacement.clone(),
+            })
+            .collect();
+        if file_suggestions.is_empty() {
+            fixed_files.push((input_file.path, input_file.contents));
+            continue;
+        }
+
+        let fixed_contents = fix::apply(&input_file.contents, &file_suggestions);
+        if let Err(error) = writer::write(&input_file.path, &fixed_contents) {
+            reporter.emit(error.into());
+            return Err(());
+        }
+        fixed_files.push((input_file.path, fixed_contents));
+    }
+
+    Ok(reader::lay_out(fixed_files))
+}
+
+/// Prepends the standard libraries to the input files, each as a file of its own.
+fn with_std_libraries(input_files: Vec<ruxnasm::Source>, names: &[String]) -> Vec<ruxnasm::Source> {
+    if names.is_empty() {
+        return input_files;
+    }
+
+    let mut files: Vec<(PathBuf, Vec<u8>)> = names
+        .iter()
+        .filter_map(|name| standard::find(name))
+        .map(|(name, contents)| (PathBuf::from(format!("<std {}>", name)), contents.to_vec()))
+        .collect();
+    files.extend(
+        input_files
+            .into_iter()
+            .map(|input_file| (input_file.path, input_file.contents)),
+    );
+    reader::lay_out(files)
+}
+
+/// Prepends the macros defined on the command line to the input files, as a file of its own.
+fn with_defines(
+    input_files: Vec<ruxnasm::Source>,
+    defines: &[(String, Option<String>)],
+) -> Vec<ruxnasm::Source> {
+    if defines.is_empty() {
+        return input_files;
+    }
+
+    let mut contents = String::new();
+    for (name, value) in defines {
+        // The macros are not meant to be used by every program.
+        contents.push_str("( ruxnasm: allow unused-macro ) ");
+        match value {
+            Some(value) => contents.push_str(&format!("%{} {{ #{} }}\n", name, value)),
+            None => contents.push_str(&format!("%{} {{ }}\n", name)),
+        }
+    }
+
+    let mut files = vec![(PathBuf::from(DEFINES_FILE_NAME), contents.into_bytes())];
+    files.extend(
+        input_files
+            .into_iter()
+            .map(|input_file| (input_file.path, input_file.contents)),
+    );
+    reader::lay_out(files)
+}
+
+/// Writes the symbol file, in which every label is stored as its address (two bytes, big-endian)
+/// followed by its null-terminated name.
+fn write_symbols(file_path: &Path, symbols: &[ruxnasm::Symbol]) -> Result<(), writer::Error> {
+    let mut contents: Vec<u8> = Vec::new();
+    for symbol in symbols {
+        contents.extend_from_slice(&symbol.address.to_be_bytes());
+        contents.extend_from_slice(symbol.name.as_bytes());
+        contents.push(0);
+    }
+
+    writer::write(file_path, &contents)
+}
+
+/// Writes an SVG map of the memory, showing which labels own the written bytes.
+fn write_memory_map(
+    file_path: &Path,
+    chunks: &[ruxnasm::Chunk],
+    symbols: &[ruxnasm::Symbol],
+) -> Result<(), writer::Error> {
+    writer::write(file_path, &memory_map::render(chunks, symbols))
+}
+
+/// Writes a linker-style map of the memory, showing which labels own which addresses.
+fn write_map_file(
+    file_path: &Path,
+    chunks: &[ruxnasm::Chunk],
+    symbols: &[ruxnasm::Symbol],
+) -> Result<(), writer::Error> {
+    writer::write(file_path, &map_file::render(chunks, symbols))
+}
+
+/// Writes a JSON map from the addresses of the binary to the sources the bytes come from.
+fn write_source_map(
+    file_path: &Path,
+    chunks: &[ruxnasm::Chunk],
+    files: &[&ruxnasm::Source],
+) -> Result<(), writer::Error> {
+    writer::write(file_path, &source_map::render(chunks, files))
+}
+
+/// Writes the debug information of the program, i.e. the scopes of the labels, the expansions of
+/// the macros, and the variables of the zero page.
+fn write_debug_info(
+    file_path: &Path,
+    chunks: &[ruxnasm::Chunk],
+    symbols: &[ruxnasm::Symbol],
+    files: &[&ruxnasm::Source],
+) -> Result<(), writer::Error> {
+    writer::write(file_path, &debug_info::render(chunks, symbols, files))
+}
+
+/// Writes a graph of the references to the labels and the invocations of the macros next to the
+/// binary, as BINARY.dot.
+fn write_graph(
+    binary_path: &Path,
+    assembly: &ruxnasm::Assembly,
+    chunks: &[ruxnasm::Chunk],
+    references: &[ruxnasm::Reference],
+    files: &[&ruxnasm::Source],
+) -> Result<(), writer::Error> {
+    let mut graph_path = binary_path.as_os_str().to_owned();
+    graph_path.push(".dot");
+    writer::write(
+        Path::new(&graph_path),
+        &graph::render(
+            chunks,
+            &assembly.symbols,
+            &assembly.macros,
+            references,
+            files,
+        ),
+    )
+}
+
+/// Writes a compressed archive of the input files, the included files, and the configuration
+/// file next to the binary, along with a manifest describing how the binary has been assembled.
+fn write_source_archive(
+    binary_path: &Path,
+    input_files: &[ruxnasm::Source],
+    sources: &[ruxnasm::Source],
+    config_file_path: Option<&Path>,
+) -> Result<(), writer::Error> {
+    let mut files: Vec<(String, Vec<u8>)> = Vec::new();
+    let mut paths: Vec<&Path> = Vec::new();
+    for source in input_files.iter().chain(sources) {
+        // The macros defined on the command line and the standard libraries are already a part
+        // of the arguments.
+        if source.path.to_string_lossy().starts_with('<') || paths.contains(&source.path.as_path())
+        {
+            continue;
+        }
+        paths.push(&source.path);
+        files.push((archived_path(&source.path), source.contents.clone()));
+    }
+    if let Some(config_file_path) = config_file_path {
+        if let Ok(contents) = std::fs::read(config_file_path) {
+            // The configuration file is found in one of the ancestors of the current directory,
+            // so it is stored at the root of the archive.
+            let file_name = config_file_path.file_name().unwrap_or_default();
+            paths.push(config_file_path);
+            files.push((file_name.to_string_lossy().into_owned(), contents));
+        }
+    }
+
+    let arguments: Vec<String> = std::env::args().skip(1).map(quote).collect();
+    let mut manifest = format!(
+        "Assembled with ruxnasm {} by running:\n\n    ruxnasm {}\n\nfrom the following files:\n\n",
+        env!("CARGO_PKG_VERSION"),
+        arguments.join(" ")
+    );
+    for path in paths {
+        manifest.push_str(&format!("    {}\n", path.to_string_lossy()));
+    }
+    files.insert(0, ("MANIFEST".to_owned(), manifest.into_bytes()));
+
+    let mut archive_path = binary_path.as_os_str().to_owned();
+    archive_path.push(".src.tar.gz");
+    writer::write(Path::new(&archive_path), &archive::archive(&files))
+}
+
+/// Returns the path under which the file is stored in the source archive. Leading slashes and
+/// parent directories are left out, so the archive never extracts outside of its directory.
+fn archived_path(path: &Path) -> String {
+    let components: Vec<String> = path
+        .components()
+        .filter_map(|component| match component {
+            Component::Normal(name) => Some(name.to_string_lossy().into_owned()),
+            _ => None,
+        })
+        .collect();
+    components.join("/")
+}
+
+/// Quotes the command-line argument for the shell, if it needs quoting.
+fn quote(argument: String) -> String {
+    if !argument.is_empty()
+        && argument
+            .chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || "-_=./:,+@%".contains(ch))
+    {
+        argument
+    } else {
+        format!("'{}'", argument.replace('\'', "'\\''"))
+    }
+}
+
+/// Returns the name of the binary written to the output directory, which is the name of the last
+/// input file without the extension.
+fn rom_name(input_files: &[ruxnasm::Source]) -> String {
+    input_files
+        .last()
+        .and_then(|input_file| input_file.path.file_stem())
+        .map(|file_stem| file_stem.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "out".to_owned())
+}
+
+/// Prints the labels of a library, one per line. Addresses relative to the start of the library
+/// are prefixed with a plus sign.
+fn print_library_symbols(symbols: &[ruxnasm::Symbol]) {
+    for symbol in symbols {
+        let sign = if symbol.relative { "+" } else { " " };
+        println!("{}{:04x}  {}", sign, symbol.address, symbol.name);
+    }
+}
+
+/// Prints how much of the memory the binary takes up, along with the numbers of the labels and
+/// the macros of the program.
+fn print_stats(assembly: &ruxnasm::Assembly) {
+    let symbols: Vec<&ruxnasm::Symbol> = assembly
+        .symbols
+        .iter()
+        .filter(|symbol| !symbol.relative)
+        .collect();
+    let label_count = symbols
+        .iter()
+        .filter(|symbol| !symbol.name.contains('/'))
+        .count();
+    let zero_page_count = symbols
+        .iter()
+        .filter(|symbol| symbol.address < 0x0100)
+        .count();
+
+    println!("size             {} bytes", assembly.length());
+    println!("written          {} bytes", assembly.byte_count);
+    match assembly.last_address {
+        Some(last_address) => println!("highest address  {:04x}", last_address),
+        None => println!("highest address  none"),
+    }
+    println!("free             {} bytes", 0xff00 - assembly.length());
+    println!("labels           {}", label_count);
+    println!("sublabels        {}", symbols.len() - label_count);
+    println!("zero page        {} labels and sublabels", zero_page_count);
+    println!("macros           {}", assembly.macros.len());
+}
+
+/// Prints the summary of the assembly to the standard error in the format of uxnasm, where the
+/// labels include the sublabels, and the used space is relative to the 65280 bytes after the zero
+/// page.
+fn print_uxnasm_summary(binary_path: &Path, assembly: &ruxnasm::Assembly) {
+    eprintln!(
+        "Assembled {} in {} bytes({:.2}% used), {} labels, {} macros.",
+        binary_path.display(),
+        assembly.length(),
+        assembly.length() as f64 / 652.80,
+        assembly.symbols.len(),
+        assembly.macros.len()
+    );
+}
+
+/// Prints the routines which could be moved closer to the jumps to them, the most often entered
+/// ones first, along with the number of bytes that would be saved.
+fn print_layout_hints(hints: &[ruxnasm::LayoutHint]) {
+    if hints.is_empty() {
+        println!("no executed routines are jumped to from too far to use relative addresses");
+        return;
+    }
+
+    for hint in hints {
+        let references: Vec<String> = hint
+            .references
+            .iter()
+            .map(|reference| format!("{:04x}", reference))
+            .collect();
+        println!(
+            "{:04x}  {:<24} {:>10} hits  move within 126 bytes of {} to save {} byte{}",
+            hint.address,
+            hint.name,
+            hint.hits,
+            references.join(", "),
+            hint.savings(),
+            if hint.savings() == 1 { "" } else { "s" }
+        );
+    }
+    let savings: usize = hints.iter().map(|hint| hint.savings()).sum();
+    println!(
+        "moving all of the routines would save up to {} byte{}",
+        savings,
+        if savings == 1 { "" } else { "s" }
+    );
+}
+
+/// Prints the steps of the emission of the binary, one per line, along with the source code of
+/// the statements.
+fn print_trace(
+    input_file_contents: &[u8],
+    sources: &[ruxnasm::Source],
+    trace: &[ruxnasm::TraceStep],
+) {
+    for step in trace {
+        let bytes: Vec<String> = step
+            .bytes
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect();
+        let (contents, offset) = sources
+            .iter()
+            .rev()
+            .find(|source| source.offset <= step.span.start)
+            .map(|source| (&source.contents[..], source.offset))
+            .unwrap_or((input_file_contents, 0));
+        let statement =
+            String::from_utf8_lossy(&contents[step.span.start - offset..step.span.end - offset]);
+        println!(
+            "{:04x}  {:<12} {:<16} {}",
+            step.address,
+            bytes.join(" "),
+            statement,
+            step.explanation
+        );
+    }
+}
+
+/// Reports the warnings according to their levels. Returns `true` if any of the warnings has
+/// been denied.
+fn report_warnings(
+    reporter: &reporter::FileReporter,
+    warnings: Vec<ruxnasm::Warning>,
+    arguments: &argument_parser::Arguments,
+) -> bool {
+    let mut denied = false;
+
+    for warning in warnings {
+        let name = warning.name();
+        match arguments.warning_level(name) {
+            WarningLevel::Allow => (),
+            WarningLevel::Warn => reporter.emit(warning.into()),
+            WarningLevel::Deny => {
+                denied = true;
+                reporter.emit(
+                    FileDiagnostic::from(warning)
+                        .with_severity(Severity::Error)
+                        .with_note(format!("warning `{}` is denied", name)),
+                );
+            }
+        }
+    }
+
+    denied
+}
+
+/// Reports panics as internal assembler errors, writing the crash dump bundle if there is one.
+fn set_panic_hook(crash_dump: Option<crash_dump::Bundle>) {
+    set_hook(Box::new(move |panic_info| {
         let reporter = reporter::VoidReporter::new();
 
+        let message = panic_info.to_string();
         let error = InternalAssemblerError {
-            message: panic_info.to_string(),
+            crash_dump: crash_dump.as_ref().map(|bundle| bundle.write(&message)),
+            message,
         };
 
         reporter.emit(error.into());
 
         exit(1);
     }));
+}
+
+fn main() {
+    set_panic_hook(None);
 
     let exit_code = try_main();
 
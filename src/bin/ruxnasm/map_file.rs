@@ -0,0 +1,102 @@
+use ruxnasm::{Chunk, Symbol};
+use std::fmt::Write;
+
+/// Name of the owner of the bytes written before the first label.
+const UNLABELED: &str = "(no label)";
+/// Name of the unwritten bytes between the written ones, which are zeros in the binary.
+const PADDING: &str = "(padding)";
+
+/// Renders a map of the memory in the style of the map files of linkers. The binary is split into
+/// ranges of the addresses owned by the same labels, i.e. the closest labels defined at or before
+/// them, with the gaps between the written bytes listed as padding. The zero page is listed
+/// separately, as it's never a part of the binary, with the sizes of the variables and the ports
+/// given with relative pads.
+pub fn render(chunks: &[Chunk], symbols: &[Symbol]) -> Vec<u8> {
+    let symbols: Vec<&Symbol> = symbols.iter().filter(|symbol| !symbol.relative).collect();
+    let labels: Vec<&Symbol> = symbols
+        .iter()
+        .copied()
+        .filter(|symbol| !symbol.name.contains('/') && symbol.address >= 0x0100)
+        .collect();
+
+    let mut written = vec![false; 0x10000];
+    for chunk in chunks {
+        let start = chunk.address as usize;
+        for is_written in written.iter_mut().skip(start).take(chunk.bytes.len()) {
+            *is_written = true;
+        }
+    }
+    let end = match written.iter().rposition(|is_written| *is_written) {
+        Some(last_address) => (last_address + 1).max(0x0100),
+        None => 0x0100,
+    };
+    // The symbols are sorted by their addresses.
+    let owner = |address: usize| {
+        if !written[address] {
+            return PADDING;
+        }
+        match labels.partition_point(|label| label.address as usize <= address) {
+            0 => UNLABELED,
+            position => &labels[position - 1].name,
+        }
+    };
+
+    let mut map = String::new();
+    let _ = writeln!(map, "Memory");
+    let mut address = 0x0100;
+    while address < end {
+        let start = address;
+        let name = owner(start);
+        while address < end && owner(address) == name {
+            address += 1;
+        }
+        let _ = writeln!(
+            map,
+            "  {:04x}..{:04x}  {:<32} {}",
+            start,
+            address,
+            name,
+            bytes(address - start)
+        );
+    }
+    let _ = writeln!(
+        map,
+        "  {} in total, {} written",
+        bytes(end - 0x0100),
+        bytes(
+            written[0x0100..end]
+                .iter()
+                .filter(|is_written| **is_written)
+                .count()
+        )
+    );
+
+    let _ = writeln!(map);
+    let _ = writeln!(map, "Zero page");
+    for symbol in symbols.iter().filter(|symbol| symbol.address < 0x0100) {
+        match symbol.size {
+            Some(size) => {
+                let _ = writeln!(
+                    map,
+                    "  {:04x}..{:04x}  {:<32} {}",
+                    symbol.address,
+                    symbol.address as usize + size as usize,
+                    symbol.name,
+                    bytes(size as usize)
+                );
+            }
+            None => {
+                let _ = writeln!(map, "  {:04x}        {}", symbol.address, symbol.name);
+            }
+        }
+    }
+
+    map.into_bytes()
+}
+
+fn bytes(count: usize) -> String {
+    match count {
+        1 => "1 byte".to_owned(),
+        _ => format!("{} bytes", count),
+    }
+}
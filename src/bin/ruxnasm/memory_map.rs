@@ -0,0 +1,114 @@
+use ruxnasm::{Chunk, Symbol};
+use std::fmt::Write;
+
+/// Size of the square standing for a single byte, in pixels.
+const CELL_SIZE: usize = 4;
+/// Width of the column with the addresses of the pages, in pixels.
+const MARGIN: usize = 48;
+const UNWRITTEN_COLOR: &str = "#eeeeee";
+/// Name of the owner of the bytes written before the first label.
+const UNLABELED: &str = "(no label)";
+const UNLABELED_COLOR: &str = "#999999";
+
+/// Renders an SVG map of the memory, in which every row is a page of 256 bytes. The bytes written
+/// by the program are colored by the labels owning them, i.e. the closest labels defined at or
+/// before them, and each region can be hovered to see the name of its label.
+pub fn render(chunks: &[Chunk], symbols: &[Symbol]) -> Vec<u8> {
+    let labels: Vec<&Symbol> = symbols
+        .iter()
+        .filter(|symbol| !symbol.name.contains('/'))
+        .collect();
+    let mut owners: Vec<Option<&str>> = vec![None; 0x10000];
+    for chunk in chunks {
+        for offset in 0..chunk.bytes.len() {
+            let address = chunk.address as usize + offset;
+            if address < owners.len() {
+                // The symbols are sorted by their addresses.
+                let position = labels.partition_point(|label| label.address as usize <= address);
+                owners[address] = Some(match position {
+                    0 => UNLABELED,
+                    position => &labels[position - 1].name,
+                });
+            }
+        }
+    }
+
+    let width = MARGIN + 256 * CELL_SIZE;
+    let height = 256 * CELL_SIZE;
+    let mut svg = String::new();
+    let _ = writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" font-family="monospace" font-size="{}">"#,
+        width,
+        height,
+        CELL_SIZE * 4
+    );
+    let _ = writeln!(
+        svg,
+        r#"<rect x="{}" y="0" width="{}" height="{}" fill="{}"/>"#,
+        MARGIN,
+        width - MARGIN,
+        height,
+        UNWRITTEN_COLOR
+    );
+
+    for page in 0..256 {
+        let y = page * CELL_SIZE;
+        if page % 16 == 0 {
+            let _ = writeln!(
+                svg,
+                r#"<text x="0" y="{}" dominant-baseline="hanging">{:04x}</text>"#,
+                y,
+                page << 8
+            );
+        }
+
+        // Consecutive bytes of the same owner in a page are drawn as a single rectangle.
+        let mut column = 0;
+        while column < 256 {
+            let owner = owners[page << 8 | column];
+            let start = column;
+            while column < 256 && owners[page << 8 | column] == owner {
+                column += 1;
+            }
+            let name = match owner {
+                Some(name) => name,
+                None => continue,
+            };
+            let _ = writeln!(
+                svg,
+                r#"<rect x="{}" y="{}" width="{}" height="{}" fill="{}"><title>{} {:04x}..{:04x}</title></rect>"#,
+                MARGIN + start * CELL_SIZE,
+                y,
+                (column - start) * CELL_SIZE,
+                CELL_SIZE,
+                if name == UNLABELED {
+                    UNLABELED_COLOR.to_owned()
+                } else {
+                    color(name)
+                },
+                escape(name),
+                page << 8 | start,
+                page << 8 | column
+            );
+        }
+    }
+
+    svg.push_str("</svg>\n");
+    svg.into_bytes()
+}
+
+/// Picks a color for the label, derived from its name so that it stays the same between runs.
+fn color(name: &str) -> String {
+    // FNV-1a
+    let hash = name.bytes().fold(0x811c9dc5u32, |hash, byte| {
+        (hash ^ byte as u32).wrapping_mul(0x01000193)
+    });
+    format!("hsl({}, 60%, 55%)", hash % 360)
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
@@ -0,0 +1,87 @@
+use ruxnasm::{InstructionSet, OpcodeTable};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::{fs, io};
+
+pub enum Error {
+    CouldNotReadFile {
+        file_path: PathBuf,
+        io_error: io::Error,
+    },
+    Syntax {
+        file_path: PathBuf,
+        line: usize,
+        message: String,
+    },
+}
+
+/// Opcode table extended with extra or remapped instructions.
+pub struct Opcodes {
+    table: OpcodeTable,
+    opcodes: HashMap<Vec<u8>, u8>,
+}
+
+impl InstructionSet for Opcodes {
+    fn opcode(&self, mnemonic: &[u8]) -> Option<u8> {
+        match self.opcodes.get(mnemonic) {
+            Some(opcode) => Some(*opcode),
+            None => self.table.opcode(mnemonic),
+        }
+    }
+
+    fn literal_opcodes(&self) -> (u8, u8) {
+        self.table.literal_opcodes()
+    }
+}
+
+/// Reads the extra or remapped instructions added to the opcode table, in which every line
+/// consists of a mnemonic of 3 uppercase letters and an opcode in hexadecimal separated with a
+/// comma, e.g. `HLT,01`. Empty lines and lines starting with `#` are skipped.
+pub fn read(file_path: &Path, table: OpcodeTable) -> Result<Opcodes, Error> {
+    let contents = fs::read_to_string(file_path).map_err(|io_error| Error::CouldNotReadFile {
+        file_path: file_path.to_path_buf(),
+        io_error,
+    })?;
+    let syntax_error = |line: usize, message: String| Error::Syntax {
+        file_path: file_path.to_path_buf(),
+        line,
+        message,
+    };
+
+    let mut opcodes: HashMap<Vec<u8>, u8> = HashMap::new();
+    for (index, line) in contents.lines().enumerate() {
+        let line_number = index + 1;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+        let (mnemonic, opcode) = match fields.as_slice() {
+            [mnemonic, opcode] => (mnemonic, opcode),
+            _ => {
+                return Err(syntax_error(
+                    line_number,
+                    "expected a mnemonic and an opcode".to_owned(),
+                ))
+            }
+        };
+        if mnemonic.len() != 3 || !mnemonic.bytes().all(|byte| byte.is_ascii_uppercase()) {
+            return Err(syntax_error(
+                line_number,
+                format!(
+                    "expected a mnemonic of 3 uppercase letters, found `{}`",
+                    mnemonic
+                ),
+            ));
+        }
+        let opcode = u8::from_str_radix(opcode, 16).map_err(|_| {
+            syntax_error(
+                line_number,
+                format!("expected a hexadecimal opcode, found `{}`", opcode),
+            )
+        })?;
+        opcodes.insert(mnemonic.as_bytes().to_vec(), opcode);
+    }
+
+    Ok(Opcodes { table, opcodes })
+}
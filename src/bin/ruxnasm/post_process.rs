@@ -0,0 +1,96 @@
+use ruxnasm::PostProcessor;
+use std::{
+    io::{self, Write},
+    process::{Command, Stdio},
+    thread,
+};
+
+pub enum Error {
+    PostProcessingFailed { command: String, message: String },
+}
+
+/// Post-processor running a shell command, which receives the binary on its standard input and
+/// prints the processed binary to its standard output.
+struct ShellCommand<'a> {
+    command: &'a str,
+}
+
+impl<'a> PostProcessor for ShellCommand<'a> {
+    fn process(&mut self, binary: Vec<u8>) -> Result<Vec<u8>, String> {
+        let mut child = shell(self.command)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .map_err(|io_error| io_error.to_string())?;
+
+        // The binary is written from another thread, so that the command doesn't get stuck on a
+        // full standard output while its standard input is still being written.
+        let mut stdin = child.stdin.take().unwrap();
+        let writer = thread::spawn(move || {
+            let _ = stdin.write_all(&binary);
+        });
+        let output = child
+            .wait_with_output()
+            .map_err(|io_error| io_error.to_string())?;
+        let _ = writer.join();
+
+        if output.status.success() {
+            Ok(output.stdout)
+        } else {
+            Err(format!("the command exited with {}", output.status))
+        }
+    }
+}
+
+#[cfg(unix)]
+fn shell(command: &str) -> Command {
+    let mut shell = Command::new("sh");
+    shell.arg("-c").arg(command);
+    shell
+}
+
+#[cfg(not(unix))]
+fn shell(command: &str) -> Command {
+    let mut shell = Command::new("cmd");
+    shell.arg("/C").arg(command);
+    shell
+}
+
+/// Post-processor passing the binary through every command in order, returning the binary
+/// output by the last one.
+pub struct Commands<'a> {
+    commands: &'a [String],
+    /// The command that has failed, if any.
+    failed: Option<&'a str>,
+}
+
+impl<'a> Commands<'a> {
+    pub fn new(commands: &'a [String]) -> Self {
+        Self {
+            commands,
+            failed: None,
+        }
+    }
+
+    /// Returns the command that has failed, if any, along with the reason of the failure
+    /// returned by the assembly.
+    pub fn failure(&self, io_error: &io::Error) -> Option<Error> {
+        self.failed.map(|command| Error::PostProcessingFailed {
+            command: command.to_owned(),
+            message: io_error.to_string(),
+        })
+    }
+}
+
+impl<'a> PostProcessor for Commands<'a> {
+    fn process(&mut self, binary: Vec<u8>) -> Result<Vec<u8>, String> {
+        let failed = &mut self.failed;
+        self.commands.iter().try_fold(binary, |binary, command| {
+            let processed = ShellCommand { command }.process(binary);
+            if processed.is_err() {
+                *failed = Some(command);
+            }
+            processed
+        })
+    }
+}
@@ -0,0 +1,61 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::{fs, io};
+
+pub enum Error {
+    CouldNotReadFile {
+        file_path: PathBuf,
+        io_error: io::Error,
+    },
+    Syntax {
+        file_path: PathBuf,
+        line: usize,
+        message: String,
+    },
+}
+
+/// Reads a profile of an emulator run, in which every line consists of an address in
+/// hexadecimal and the number of times it has been executed in decimal, e.g. `01a0 1024`. Hit
+/// counts of repeated addresses are summed up.
+pub fn read(file_path: &Path) -> Result<HashMap<u16, u64>, Error> {
+    let contents = fs::read_to_string(file_path).map_err(|io_error| Error::CouldNotReadFile {
+        file_path: file_path.to_path_buf(),
+        io_error,
+    })?;
+    let syntax_error = |line: usize, message: String| Error::Syntax {
+        file_path: file_path.to_path_buf(),
+        line,
+        message,
+    };
+
+    let mut profile: HashMap<u16, u64> = HashMap::new();
+    for (index, line) in contents.lines().enumerate() {
+        let line_number = index + 1;
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let (address, hits) = match fields.as_slice() {
+            [] => continue,
+            [address, hits] => (address, hits),
+            _ => {
+                return Err(syntax_error(
+                    line_number,
+                    "expected an address and a hit count".to_owned(),
+                ))
+            }
+        };
+        let address = u16::from_str_radix(address, 16).map_err(|_| {
+            syntax_error(
+                line_number,
+                format!("expected a hexadecimal address, found `{}`", address),
+            )
+        })?;
+        let hits: u64 = hits.parse().map_err(|_| {
+            syntax_error(
+                line_number,
+                format!("expected a decimal hit count, found `{}`", hits),
+            )
+        })?;
+        *profile.entry(address).or_default() += hits;
+    }
+
+    Ok(profile)
+}
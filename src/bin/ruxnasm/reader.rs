@@ -14,3 +14,36 @@ pub fn read(path: &Path) -> Result<Vec<u8>, Error> {
         io_error,
     })
 }
+
+/// Reads the input files, which are assembled as if they were concatenated.
+pub fn read_input_files(paths: &[PathBuf]) -> Result<Vec<ruxnasm::Source>, Error> {
+    let files = paths
+        .iter()
+        .map(|path| read(path).map(|contents| (path.clone(), contents)))
+        .collect::<Result<Vec<_>, Error>>()?;
+    Ok(lay_out(files))
+}
+
+/// Places the files one after another, separated by a newline, so that the spans of the
+/// concatenated files can be traced back to the files they come from.
+pub fn lay_out(files: Vec<(PathBuf, Vec<u8>)>) -> Vec<ruxnasm::Source> {
+    let mut offset = 0;
+    files
+        .into_iter()
+        .map(|(path, contents)| {
+            let source = ruxnasm::Source {
+                path,
+                offset,
+                contents,
+            };
+            offset += source.contents.len() + 1;
+            source
+        })
+        .collect()
+}
+
+/// Concatenates the files laid out by [`lay_out`].
+pub fn concatenate(files: &[ruxnasm::Source]) -> Vec<u8> {
+    let contents: Vec<&[u8]> = files.iter().map(|file| &file.contents[..]).collect();
+    contents.join(&b'\n')
+}
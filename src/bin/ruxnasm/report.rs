@@ -0,0 +1,91 @@
+use crate::argument_parser::Report;
+use crate::source_map::{index_files, locate};
+
+/// Prints the report about the assembled program. The references are only needed by the report
+/// of the references.
+pub fn print(
+    report: Report,
+    assembly: &ruxnasm::Assembly,
+    references: &[ruxnasm::Reference],
+    files: &[&ruxnasm::Source],
+) {
+    match report {
+        Report::Sizes => print_sizes(assembly),
+        Report::References => print_references(assembly, references, files),
+    }
+}
+
+/// Prints every label outside of the zero page along with the number of bytes from it up to the
+/// next label, or up to the last byte of the binary, the largest labels first.
+fn print_sizes(assembly: &ruxnasm::Assembly) {
+    let labels: Vec<&ruxnasm::Symbol> = assembly
+        .symbols
+        .iter()
+        .filter(|symbol| !symbol.relative && !symbol.name.contains('/') && symbol.address >= 0x0100)
+        .collect();
+    // The zeros padding the binary up to a size don't belong to any label.
+    let end = match assembly.last_address {
+        Some(last_address) => last_address as usize + 1,
+        None => 0x0100,
+    };
+
+    // The symbols are sorted by their addresses, so labels sharing an address are all but the
+    // last one empty.
+    let mut sizes: Vec<(usize, &ruxnasm::Symbol)> = labels
+        .iter()
+        .enumerate()
+        .map(|(index, label)| {
+            let next_address = match labels.get(index + 1) {
+                Some(next_label) => next_label.address as usize,
+                None => end.max(label.address as usize),
+            };
+            (next_address - label.address as usize, *label)
+        })
+        .collect();
+    sizes.sort_by_key(|(size, label)| (std::cmp::Reverse(*size), label.address));
+
+    for (size, label) in sizes {
+        println!("{:>6}  {:04x}  {}", size, label.address, label.name);
+    }
+}
+
+/// Prints every label and sublabel along with the places it's referenced from, i.e. the files,
+/// the lines, and the columns of the references, and the runes they are made with.
+fn print_references(
+    assembly: &ruxnasm::Assembly,
+    references: &[ruxnasm::Reference],
+    files: &[&ruxnasm::Source],
+) {
+    let (files, _) = index_files(files);
+
+    for symbol in &assembly.symbols {
+        let sign = if symbol.relative { "+" } else { " " };
+        let symbol_references: Vec<&ruxnasm::Reference> = references
+            .iter()
+            .filter(|reference| reference.name == symbol.name)
+            .collect();
+        println!(
+            "{}{:04x}  {}, {} reference{}",
+            sign,
+            symbol.address,
+            symbol.name,
+            symbol_references.len(),
+            if symbol_references.len() == 1 {
+                ""
+            } else {
+                "s"
+            }
+        );
+        for reference in symbol_references {
+            let location = locate(&files, &reference.span);
+            let path = files
+                .get(location.file)
+                .map(|file| file.path.to_string_lossy())
+                .unwrap_or_default();
+            println!(
+                "         {}  {}:{}:{}",
+                reference.rune, path, location.line, location.column
+            );
+        }
+    }
+}
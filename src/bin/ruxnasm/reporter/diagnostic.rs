@@ -80,6 +80,7 @@ impl FileDiagnosticBuilderStage2 {
     pub fn with_label(self, label: Label) -> FileDiagnostic {
         FileDiagnostic {
             severity: self.severity,
+            code: None,
             message: self.message,
             label,
             additional_labels: Vec::new(),
@@ -90,17 +91,28 @@ impl FileDiagnosticBuilderStage2 {
 }
 
 pub struct FileDiagnostic {
-    severity: Severity,
-    message: String,
-    label: Label,
-    additional_labels: Vec<Label>,
-    notes: Vec<String>,
-    helps: Vec<String>,
+    pub(super) severity: Severity,
+    pub(super) code: Option<String>,
+    pub(super) message: String,
+    pub(super) label: Label,
+    pub(super) additional_labels: Vec<Label>,
+    pub(super) notes: Vec<String>,
+    pub(super) helps: Vec<String>,
 }
 
 impl<'a> FileDiagnostic {
     impl_severities!(FileDiagnosticBuilderStage1);
 
+    pub fn with_severity(mut self, severity: Severity) -> Self {
+        self.severity = severity;
+        self
+    }
+
+    pub fn with_code(mut self, code: impl Into<String>) -> Self {
+        self.code = Some(code.into());
+        self
+    }
+
     pub fn with_label(mut self, label: Label) -> FileDiagnostic {
         self.additional_labels.push(label);
         self
@@ -209,7 +221,7 @@ impl From<FileDiagnostic> for Vec<codespan_reporting::diagnostic::Diagnostic<()>
         );
         let mut codespan_diagnostics = vec![codespan_reporting::diagnostic::Diagnostic {
             severity: diagnostic.severity.into(),
-            code: None,
+            code: diagnostic.code,
             message: diagnostic.message,
             labels,
             notes: Vec::new(),
@@ -1,15 +1,27 @@
 use super::diagnostic::{Label, LabelStyle};
 use super::{FileDiagnostic, VoidDiagnostic};
-use crate::{argument_parser, reader, writer};
+use crate::{
+    argument_parser, comparison, config, opcodes, post_process, profile, reader, verify, writer,
+};
 
 impl From<crate::InternalAssemblerError> for VoidDiagnostic {
     fn from(error: crate::InternalAssemblerError) -> Self {
-        VoidDiagnostic::bug()
+        let diagnostic = VoidDiagnostic::bug()
             .with_message(format!("internal assembler error: {}", error.message))
             .with_note("The assembler unexpectedly panicked. This is a bug.")
             .with_note(
                 "We would appreciate a bug report: https://github.com/karolbelina/ruxnasm/issues",
-            )
+            );
+        match error.crash_dump {
+            Some(Ok(path)) => diagnostic.with_note(format!(
+                "A crash dump has been written to {}. Please attach it to the bug report.",
+                path.to_string_lossy()
+            )),
+            Some(Err(io_error)) => {
+                diagnostic.with_note(format!("The crash dump couldn't be written: {}", io_error))
+            }
+            None => diagnostic,
+        }
     }
 }
 
@@ -22,12 +34,147 @@ impl From<argument_parser::Error> for VoidDiagnostic {
             argument_parser::Error::NoOutputProvided => {
                 VoidDiagnostic::error().with_message("no output filename given")
             }
-            argument_parser::Error::UnexpectedArgument { argument } => {
-                VoidDiagnostic::error().with_message(format!("unexpected argument: '{}'", argument))
-            }
             argument_parser::Error::UnrecognizedOption { option } => {
                 VoidDiagnostic::error().with_message(format!("unrecognized option: '{}'", option))
             }
+            argument_parser::Error::MissingOptionValue { option } => VoidDiagnostic::error()
+                .with_message(format!("missing value for option: '{}'", option)),
+            argument_parser::Error::UnknownWarning { name } => VoidDiagnostic::error()
+                .with_message(format!("unknown warning: '{}'", name))
+                .with_note(format!(
+                    "known warnings are: {}",
+                    ruxnasm::Warning::NAMES.join(", ")
+                )),
+            argument_parser::Error::UnknownCode { code } => VoidDiagnostic::error()
+                .with_message(format!("unknown error or warning code: '{}'", code))
+                .with_note("codes have the form E0001 for errors and W0001 for warnings"),
+            argument_parser::Error::UnknownMessageFormat { format } => VoidDiagnostic::error()
+                .with_message(format!("unknown message format: '{}'", format))
+                .with_note("known message formats are: human, sarif"),
+            argument_parser::Error::UnknownOutputFormat { format } => VoidDiagnostic::error()
+                .with_message(format!("unknown output format: '{}'", format))
+                .with_note("known output formats are: rom, c-header, rust, hexdump"),
+            argument_parser::Error::UnknownOpcodeTable { name } => VoidDiagnostic::error()
+                .with_message(format!("unknown opcode table: '{}'", name))
+                .with_note("known opcode tables are: modern, legacy"),
+            argument_parser::Error::UnknownReservedMemory { profile } => VoidDiagnostic::error()
+                .with_message(format!("unknown reserved memory: '{}'", profile))
+                .with_note("known profiles are: uxn-legacy, none, or a hexadecimal address"),
+            argument_parser::Error::UnknownReport { kind } => VoidDiagnostic::error()
+                .with_message(format!("unknown report: '{}'", kind))
+                .with_note("known reports are: sizes, references"),
+            argument_parser::Error::UnknownEmit { kind } => VoidDiagnostic::error()
+                .with_message(format!("unknown kind of file to emit: '{}'", kind))
+                .with_note("known kinds are: graph"),
+            argument_parser::Error::UnknownCompat { name } => VoidDiagnostic::error()
+                .with_message(format!("unknown compatibility mode: '{}'", name))
+                .with_note("known compatibility modes are: uxnasm"),
+            argument_parser::Error::UnknownDiagnosticStyle { style } => VoidDiagnostic::error()
+                .with_message(format!("unknown diagnostic style: '{}'", style))
+                .with_note("known diagnostic styles are: rich, short"),
+            argument_parser::Error::UnknownColorChoice { when } => VoidDiagnostic::error()
+                .with_message(format!("unknown color choice: '{}'", when))
+                .with_note("known color choices are: auto, always, never"),
+            argument_parser::Error::UnknownCharset { name } => VoidDiagnostic::error()
+                .with_message(format!("unknown charset: '{}'", name))
+                .with_note("known charsets are: unicode, ascii"),
+            argument_parser::Error::InvalidNumber { option, value } => VoidDiagnostic::error()
+                .with_message(format!(
+                    "invalid value for option '{}': '{}' is not a number",
+                    option, value
+                )),
+            argument_parser::Error::NumberTooLarge {
+                option,
+                value,
+                limit,
+            } => VoidDiagnostic::error().with_message(format!(
+                "invalid value for option '{}': {} is larger than {}",
+                option, value, limit
+            )),
+            argument_parser::Error::UnknownStdLibrary { name } => VoidDiagnostic::error()
+                .with_message(format!("unknown standard library: '{}'", name))
+                .with_note(format!(
+                    "known standard libraries are: {}",
+                    crate::standard::names().join(", ")
+                )),
+            argument_parser::Error::UnexpectedArgument { argument } => {
+                VoidDiagnostic::error().with_message(format!("unexpected argument: '{}'", argument))
+            }
+        }
+    }
+}
+
+impl From<config::Error> for VoidDiagnostic {
+    fn from(error: config::Error) -> Self {
+        match error {
+            config::Error::CouldNotReadFile {
+                file_path,
+                io_error,
+            } => VoidDiagnostic::error().with_message(format!(
+                "couldn't read {}: {}",
+                file_path.to_string_lossy(),
+                io_error
+            )),
+            config::Error::Syntax {
+                file_path,
+                line,
+                message,
+            } => VoidDiagnostic::error().with_message(format!(
+                "invalid configuration in {}:{}: {}",
+                file_path.to_string_lossy(),
+                line,
+                message
+            )),
+        }
+    }
+}
+
+impl From<opcodes::Error> for VoidDiagnostic {
+    fn from(error: opcodes::Error) -> Self {
+        match error {
+            opcodes::Error::CouldNotReadFile {
+                file_path,
+                io_error,
+            } => VoidDiagnostic::error().with_message(format!(
+                "couldn't read {}: {}",
+                file_path.to_string_lossy(),
+                io_error
+            )),
+            opcodes::Error::Syntax {
+                file_path,
+                line,
+                message,
+            } => VoidDiagnostic::error().with_message(format!(
+                "invalid opcodes in {}:{}: {}",
+                file_path.to_string_lossy(),
+                line,
+                message
+            )),
+        }
+    }
+}
+
+impl From<profile::Error> for VoidDiagnostic {
+    fn from(error: profile::Error) -> Self {
+        match error {
+            profile::Error::CouldNotReadFile {
+                file_path,
+                io_error,
+            } => VoidDiagnostic::error().with_message(format!(
+                "couldn't read {}: {}",
+                file_path.to_string_lossy(),
+                io_error
+            )),
+            profile::Error::Syntax {
+                file_path,
+                line,
+                message,
+            } => VoidDiagnostic::error().with_message(format!(
+                "invalid profile in {}:{}: {}",
+                file_path.to_string_lossy(),
+                line,
+                message
+            )),
         }
     }
 }
@@ -62,9 +209,86 @@ impl From<writer::Error> for VoidDiagnostic {
     }
 }
 
+impl From<post_process::Error> for VoidDiagnostic {
+    fn from(error: post_process::Error) -> Self {
+        match error {
+            post_process::Error::PostProcessingFailed { command, message } => {
+                VoidDiagnostic::error()
+                    .with_message(format!(
+                        "couldn't post-process the binary with `{}`",
+                        command
+                    ))
+                    .with_note(message)
+            }
+        }
+    }
+}
+
+impl From<comparison::Error> for VoidDiagnostic {
+    fn from(error: comparison::Error) -> Self {
+        match error {
+            comparison::Error::BinariesDiffer {
+                reference_path,
+                byte_count,
+                range_count,
+            } => VoidDiagnostic::error()
+                .with_message(format!(
+                    "the binary differs from {} in {} byte{}",
+                    reference_path.to_string_lossy(),
+                    byte_count,
+                    if byte_count == 1 { "" } else { "s" }
+                ))
+                .with_note(format!(
+                    "the differences in {} range{} of addresses are listed above",
+                    range_count,
+                    if range_count == 1 { "" } else { "s" }
+                )),
+        }
+    }
+}
+
+impl From<verify::Error> for VoidDiagnostic {
+    fn from(error: verify::Error) -> Self {
+        match error {
+            verify::Error::ReassemblyFailed { message } => VoidDiagnostic::bug()
+                .with_message("couldn't assemble the disassembled binary")
+                .with_note(message),
+            verify::Error::BinariesDiffer {
+                address,
+                original,
+                reassembled,
+                instruction,
+            } => {
+                let byte = |byte: Option<u8>| match byte {
+                    Some(byte) => format!("{:02x}", byte),
+                    None => "past the end".to_owned(),
+                };
+                let diagnostic = VoidDiagnostic::bug()
+                    .with_message(format!(
+                        "the reassembled binary differs from the original at address {:#06x}",
+                        address
+                    ))
+                    .with_note(format!(
+                        "the original byte is {}, and the reassembled one is {}",
+                        byte(original),
+                        byte(reassembled)
+                    ));
+                match instruction {
+                    Some(instruction) => diagnostic.with_note(format!(
+                        "the byte has been disassembled as a part of `{}`",
+                        instruction
+                    )),
+                    None => diagnostic,
+                }
+            }
+        }
+    }
+}
+
 impl From<ruxnasm::Error> for FileDiagnostic {
     fn from(error: ruxnasm::Error) -> Self {
-        match error {
+        let code = error.code();
+        let diagnostic = match error {
             ruxnasm::Error::NoMatchingClosingParenthesis { span } => FileDiagnostic::error()
                 .with_message("no matching closing parenthesis found for an opening parenthesis")
                 .with_label(Label {
@@ -115,13 +339,25 @@ impl From<ruxnasm::Error> for FileDiagnostic {
                     span,
                     message: String::new(),
                 }),
-            ruxnasm::Error::MoreThanOneByteFound { bytes, span } => FileDiagnostic::error()
-                .with_message("found more than one byte after a raw character rune")
-                .with_label(Label {
-                    style: LabelStyle::Primary,
-                    span,
-                    message: format!("found bytes: {:x?}", bytes),
-                }),
+            ruxnasm::Error::MoreThanOneByteFound { bytes, span } => {
+                let is_character = std::str::from_utf8(&bytes)
+                    .map(|string| string.chars().count() == 1)
+                    .unwrap_or(false);
+                let diagnostic = FileDiagnostic::error()
+                    .with_message("found more than one byte after a raw character rune")
+                    .with_label(Label {
+                        style: LabelStyle::Primary,
+                        span,
+                        message: format!("found bytes: {:x?}", bytes),
+                    });
+                if is_character {
+                    diagnostic.with_help(
+                        "add a `( ruxnasm: utf8 )` comment before to emit the UTF-8 bytes of the character",
+                    )
+                } else {
+                    diagnostic
+                }
+            }
             ruxnasm::Error::AmpersandAtTheStartOfLabel { span } => FileDiagnostic::error()
                 .with_message("label names can't have '&' as their first character")
                 .with_label(Label {
@@ -222,13 +458,32 @@ impl From<ruxnasm::Error> for FileDiagnostic {
                         message: String::new(),
                     })
             }
-            ruxnasm::Error::MacroUndefined { name, span } => FileDiagnostic::error()
-                .with_message(format!("macro `{}` is not defined", name))
-                .with_label(Label {
-                    style: LabelStyle::Primary,
-                    span,
-                    message: String::new(),
-                }),
+            ruxnasm::Error::MacroUndefined {
+                name,
+                span,
+                instruction,
+                similar_macro,
+            } => {
+                let mut diagnostic = FileDiagnostic::error()
+                    .with_message(format!("macro `{}` is not defined", name))
+                    .with_label(Label {
+                        style: LabelStyle::Primary,
+                        span,
+                        message: String::new(),
+                    });
+                if let Some(instruction) = instruction {
+                    diagnostic = diagnostic
+                        .with_note("instructions are case-sensitive")
+                        .with_help(format!("did you mean the instruction `{}`?", instruction));
+                }
+                if let Some(similar_macro) = similar_macro {
+                    diagnostic = diagnostic.with_help(format!(
+                        "a macro with a similar name exists: `{}`",
+                        similar_macro
+                    ));
+                }
+                diagnostic
+            }
             ruxnasm::Error::MacroDefinedMoreThanOnce {
                 name,
                 span,
@@ -262,7 +517,7 @@ impl From<ruxnasm::Error> for FileDiagnostic {
                     message: format!("previous definition of label `{}` here", name),
                 }),
             ruxnasm::Error::OpeningBraceNotAfterMacroDefinition { span } => FileDiagnostic::error()
-                .with_message("found an opening brace that is not a part of a macro definition")
+                .with_message("found an opening brace that is not a part of a macro definition or a directive")
                 .with_label(Label {
                     style: LabelStyle::Primary,
                     span,
@@ -337,6 +592,7 @@ impl From<ruxnasm::Error> for FileDiagnostic {
                 address,
                 identifier,
                 span,
+                suggestions,
             } => FileDiagnostic::error()
                 .with_message(format!(
                     "address {:#06x} of label `{}` is not zero-page",
@@ -346,27 +602,56 @@ impl From<ruxnasm::Error> for FileDiagnostic {
                     style: LabelStyle::Primary,
                     span,
                     message: String::new(),
-                }),
+                })
+                .with_help(absolute_address_help(&identifier, &suggestions))
+                .with_help("alternatively, move the label definition to the zero page"),
             ruxnasm::Error::AddressTooFar {
                 distance,
                 identifier,
                 span,
                 other_span,
-            } => FileDiagnostic::error()
-                .with_message(format!(
-                    "address of label `{}` is too far to be a relative address (distance {})",
-                    identifier, distance
-                ))
-                .with_label(Label {
-                    style: LabelStyle::Primary,
-                    span,
-                    message: String::new(),
-                })
-                .with_label(Label {
-                    style: LabelStyle::Secondary,
-                    span: other_span,
-                    message: "label definition".to_owned(),
-                }),
+                regions,
+                suggestions,
+            } => {
+                let mut diagnostic = FileDiagnostic::error()
+                    .with_message(format!(
+                        "address of label `{}` is too far to be a relative address (distance {})",
+                        identifier, distance
+                    ))
+                    .with_label(Label {
+                        style: LabelStyle::Primary,
+                        span,
+                        message: String::new(),
+                    })
+                    .with_label(Label {
+                        style: LabelStyle::Secondary,
+                        span: other_span,
+                        message: "label definition".to_owned(),
+                    });
+                for (name, bytes, span) in &regions {
+                    diagnostic = diagnostic.with_label(Label {
+                        style: LabelStyle::Secondary,
+                        span: span.clone(),
+                        message: format!("`{}` contributes {} bytes", name, bytes),
+                    });
+                }
+                if !regions.is_empty() {
+                    let breakdown: Vec<String> = regions
+                        .iter()
+                        .map(|(name, bytes, _)| format!("{} bytes in `{}`", bytes, name))
+                        .collect();
+                    diagnostic = diagnostic.with_note(format!(
+                        "the distance is made up of {}",
+                        breakdown.join(", ")
+                    ));
+                }
+                diagnostic
+                    .with_help(format!(
+                        "move the label definition within 126 bytes of the reference or shrink the regions in between by {} bytes",
+                        distance - 126
+                    ))
+                    .with_help(absolute_address_help(&identifier, &suggestions))
+            }
             ruxnasm::Error::BytesInZerothPage { span } => FileDiagnostic::error()
                 .with_message(format!("found bytes on the zeroth page",))
                 .with_label(Label {
@@ -387,7 +672,8 @@ impl From<ruxnasm::Error> for FileDiagnostic {
                         "tried to pad from address {} to address {}",
                         previous_pointer, desired_pointer
                     ),
-                }),
+                })
+                .with_help("to revisit the earlier addresses anyway, use `--allow-backward-padding`"),
             ruxnasm::Error::ProgramTooLong { span } => FileDiagnostic::error()
                 .with_message("program size exceeded 65536 bytes")
                 .with_label(Label {
@@ -438,57 +724,870 @@ impl From<ruxnasm::Error> for FileDiagnostic {
                         .with_note(format!("cannot invoke macro `{}`, because it would have infinite size if it were to be expanded", first_name))
                 }
             }
-        }
-    }
-}
-
-impl From<ruxnasm::Warning> for FileDiagnostic {
-    fn from(warning: ruxnasm::Warning) -> Self {
-        match warning {
-            ruxnasm::Warning::TokenTrimmed { span } => FileDiagnostic::warning()
-                .with_message(format!(
-                    "token has been cut off, as it's longer than 64 characters"
-                ))
+            ruxnasm::Error::CircularInclude { chain, span } => {
+                let (first_path, first_span) = chain.first().unwrap();
+                let (last_path, _) = chain.last().unwrap();
+                let mut diagnostic = FileDiagnostic::error()
+                    .with_message("found a circular include")
+                    .with_label(Label {
+                        style: LabelStyle::Primary,
+                        span,
+                        message: format!("`{}` includes `{}` here", last_path, first_path),
+                    });
+                for ((current_path, _), (next_path, next_span)) in
+                    chain.iter().zip(chain.iter().skip(1))
+                {
+                    diagnostic = diagnostic.with_label(Label {
+                        style: LabelStyle::Primary,
+                        span: next_span.clone(),
+                        message: format!("`{}` includes `{}` here", current_path, next_path),
+                    })
+                }
+                diagnostic
+                    .with_label(Label {
+                        style: LabelStyle::Secondary,
+                        span: first_span.clone(),
+                        message: format!("initial include of `{}` here", first_path),
+                    })
+                    .with_note(format!(
+                        "cannot include `{}`, because it would never stop including itself",
+                        first_path
+                    ))
+            }
+            ruxnasm::Error::IncludeNotReadable {
+                path,
+                message,
+                span,
+            } => FileDiagnostic::error()
+                .with_message(format!("could not read included file `{}`", path))
+                .with_label(Label {
+                    style: LabelStyle::Primary,
+                    span,
+                    message: String::new(),
+                })
+                .with_note(message)
+                .with_note(
+                    "included files are looked up relative to the current working directory",
+                ),
+            ruxnasm::Error::ConditionalDirectiveUnknown { directive, span } => {
+                FileDiagnostic::error()
+                    .with_message(format!("unknown conditional directive `{}`", directive))
+                    .with_label(Label {
+                        style: LabelStyle::Primary,
+                        span,
+                        message: String::new(),
+                    })
+                    .with_help(
+                        "the known directives are `?ifdef`, `?ifndef`, `?else`, `?end`, `?repeat`, \
+                         `?zeropage`, `?library`, and `?checksum`",
+                    )
+            }
+            ruxnasm::Error::ConditionalNameExpected { span } => FileDiagnostic::error()
+                .with_message("expected a macro name after a conditional directive")
                 .with_label(Label {
                     style: LabelStyle::Primary,
                     span,
                     message: String::new(),
                 }),
-            ruxnasm::Warning::InstructionModeDefinedMoreThanOnce {
-                instruction_mode,
-                instruction,
-                span,
-                other_span,
-            } => FileDiagnostic::warning()
+            ruxnasm::Error::NoMatchingConditional { directive, span } => FileDiagnostic::error()
                 .with_message(format!(
-                    "instruction mode `{}` is defined multiple times for instruction `{}`",
-                    instruction_mode, instruction
+                    "no matching `?ifdef` or `?ifndef` found for `{}`",
+                    directive
                 ))
                 .with_label(Label {
                     style: LabelStyle::Primary,
                     span,
-                    message: format!("mode `{}` redefined here", instruction_mode),
+                    message: String::new(),
+                }),
+            ruxnasm::Error::NoMatchingEnd { span } => FileDiagnostic::error()
+                .with_message("no matching `?end` found for a conditional directive")
+                .with_label(Label {
+                    style: LabelStyle::Primary,
+                    span,
+                    message: String::new(),
+                }),
+            ruxnasm::Error::ElseDefinedMoreThanOnce { span, other_span } => FileDiagnostic::error()
+                .with_message("conditional has more than one `?else`")
+                .with_label(Label {
+                    style: LabelStyle::Primary,
+                    span,
+                    message: String::new(),
                 })
                 .with_label(Label {
                     style: LabelStyle::Secondary,
                     span: other_span,
-                    message: format!("previous definition of mode `{}` here", instruction_mode),
+                    message: "first `?else` here".to_owned(),
                 }),
-            ruxnasm::Warning::MacroUnused { name, span } => FileDiagnostic::warning()
-                .with_message(format!("macro `{}` is never used", name))
+            ruxnasm::Error::ConstantNameExpected { span } => FileDiagnostic::error()
+                .with_message("expected a constant name")
                 .with_label(Label {
                     style: LabelStyle::Primary,
                     span,
                     message: String::new(),
                 }),
-            ruxnasm::Warning::LabelUnused { name, span } => FileDiagnostic::warning()
-                .with_message(format!("label `{}` is never used", name))
+            ruxnasm::Error::ConstantValueExpected { span } => FileDiagnostic::error()
+                .with_message("expected a hexadecimal number after a constant definition")
                 .with_label(Label {
                     style: LabelStyle::Primary,
                     span,
                     message: String::new(),
                 })
-                .with_help("if this is intentional, prefix it with a capital letter"),
-        }
+                .with_help(
+                    "write the value without a rune, e.g. `=width 10`, or as an expression in \
+                     brackets, e.g. `=area [ width 08 * ]`",
+                ),
+            ruxnasm::Error::ExpressionTokenInvalid { span } => FileDiagnostic::error()
+                .with_message("unexpected token in an expression")
+                .with_label(Label {
+                    style: LabelStyle::Primary,
+                    span,
+                    message: String::new(),
+                })
+                .with_note(
+                    "expressions consist of hexadecimal numbers, names of labels and constants, \
+                     and the `+`, `-`, `*`, and `/` operators",
+                ),
+            ruxnasm::Error::ExpressionLabelUndefined { name, span } => FileDiagnostic::error()
+                .with_message(format!(
+                    "label `{}` is not defined before the expression",
+                    name
+                ))
+                .with_label(Label {
+                    style: LabelStyle::Primary,
+                    span,
+                    message: String::new(),
+                })
+                .with_note("the values of pads and constants must be known where they are written"),
+            ruxnasm::Error::OperandsExpected { span } => FileDiagnostic::error()
+                .with_message("expected two operands before an operator")
+                .with_label(Label {
+                    style: LabelStyle::Primary,
+                    span,
+                    message: String::new(),
+                })
+                .with_note("operators come after their operands, e.g. `[ buffer 10 + ]`"),
+            ruxnasm::Error::ExpressionMalformed { values, span } => FileDiagnostic::error()
+                .with_message(format!(
+                    "expression results in {} values instead of one",
+                    values
+                ))
+                .with_label(Label {
+                    style: LabelStyle::Primary,
+                    span,
+                    message: String::new(),
+                }),
+            ruxnasm::Error::ExpressionOverflow { span } => FileDiagnostic::error()
+                .with_message("result of the operation doesn't fit in a short")
+                .with_label(Label {
+                    style: LabelStyle::Primary,
+                    span,
+                    message: String::new(),
+                }),
+            ruxnasm::Error::DivisionByZero { span } => FileDiagnostic::error()
+                .with_message("attempt to divide by zero")
+                .with_label(Label {
+                    style: LabelStyle::Primary,
+                    span,
+                    message: String::new(),
+                }),
+            ruxnasm::Error::DepthLimitExceeded {
+                construct,
+                limit,
+                span,
+            } => FileDiagnostic::error()
+                .with_message(format!("{} is nested too deep", construct))
+                .with_label(Label {
+                    style: LabelStyle::Primary,
+                    span,
+                    message: String::new(),
+                })
+                .with_note(format!("the depth is limited to {}", limit)),
+            ruxnasm::Error::RepeatCountExpected { span } => FileDiagnostic::error()
+                .with_message("expected a hexadecimal number after a repeat directive")
+                .with_label(Label {
+                    style: LabelStyle::Primary,
+                    span,
+                    message: String::new(),
+                }),
+            ruxnasm::Error::ConstantTooLarge {
+                name,
+                value,
+                span,
+                other_span,
+            } => FileDiagnostic::error()
+                .with_message(format!(
+                    "value {:#06x} of constant `{}` doesn't fit in a byte",
+                    value, name
+                ))
+                .with_label(Label {
+                    style: LabelStyle::Primary,
+                    span,
+                    message: String::new(),
+                })
+                .with_label(Label {
+                    style: LabelStyle::Secondary,
+                    span: other_span,
+                    message: "constant defined here".to_owned(),
+                })
+                .with_help(format!("push the constant as a short with `;{}`", name)),
+            ruxnasm::Error::ZeroPageExhausted {
+                size,
+                span,
+                other_span,
+            } => FileDiagnostic::error()
+                .with_message("zero page exhausted")
+                .with_label(Label {
+                    style: LabelStyle::Primary,
+                    span,
+                    message: format!("{} bytes needed so far", size),
+                })
+                .with_label(Label {
+                    style: LabelStyle::Secondary,
+                    span: other_span,
+                    message: "in this section".to_owned(),
+                })
+                .with_note("the variables of all `?zeropage` sections share 256 bytes"),
+            ruxnasm::Error::ZeroPageTokenInvalid { span } => FileDiagnostic::error()
+                .with_message("unexpected token in a zero-page section")
+                .with_label(Label {
+                    style: LabelStyle::Primary,
+                    span,
+                    message: String::new(),
+                })
+                .with_note(
+                    "zero-page sections consist of label and sublabel definitions, each followed \
+                     by a relative pad with the size of the variable, e.g. `@counter $2`",
+                ),
+            ruxnasm::Error::EscapeSequenceInvalid { sequence, span } => FileDiagnostic::error()
+                .with_message(format!("unknown escape sequence `{}`", sequence))
+                .with_label(Label {
+                    style: LabelStyle::Primary,
+                    span,
+                    message: String::new(),
+                })
+                .with_help(
+                    "the known escape sequences are `\\0`, `\\t`, `\\n`, `\\r`, `\\s`, `\\\\`, and `\\``",
+                ),
+            ruxnasm::Error::NoMatchingClosingBacktick { span } => FileDiagnostic::error()
+                .with_message("no matching closing backtick found for a string")
+                .with_label(Label {
+                    style: LabelStyle::Primary,
+                    span,
+                    message: String::new(),
+                }),
+            ruxnasm::Error::ChecksumLabelExpected { span } => FileDiagnostic::error()
+                .with_message("expected the names of two labels after a checksum directive")
+                .with_label(Label {
+                    style: LabelStyle::Primary,
+                    span,
+                    message: String::new(),
+                })
+                .with_help("name the labels at the start and at the end of the range, e.g. `?checksum table table-end`"),
+            ruxnasm::Error::ChecksumRangeInvalid {
+                from,
+                to,
+                address,
+                span,
+            } => FileDiagnostic::error()
+                .with_message(format!(
+                    "invalid checksum range from {:#06x} to {:#06x}",
+                    from, to
+                ))
+                .with_label(Label {
+                    style: LabelStyle::Primary,
+                    span,
+                    message: format!("checksum at {:#06x}", address),
+                })
+                .with_note(
+                    "the range must start after the zero page and end at or before the checksum, \
+                     whose bytes are only known up to that point",
+                ),
+            ruxnasm::Error::DecimalDigitInvalid {
+                digit,
+                number,
+                span,
+            } => FileDiagnostic::error()
+                .with_message(format!(
+                    "invalid digit `{}` in a decimal number `{}`",
+                    digit, number
+                ))
+                .with_label(Label {
+                    style: LabelStyle::Primary,
+                    span,
+                    message: String::new(),
+                }),
+            ruxnasm::Error::DecimalNumberTooLarge { number, span } => FileDiagnostic::error()
+                .with_message(format!("decimal number `{}` doesn't fit in a short", number))
+                .with_label(Label {
+                    style: LabelStyle::Primary,
+                    span,
+                    message: String::new(),
+                })
+                .with_note("the largest short is 65535"),
+            ruxnasm::Error::QueryOutsideExpression { span } => FileDiagnostic::error()
+                .with_message("query outside of an expression")
+                .with_label(Label {
+                    style: LabelStyle::Primary,
+                    span,
+                    message: String::new(),
+                })
+                .with_help("queries can only be used in expressions in brackets, e.g. `$[ 0200 $here - ]`"),
+            ruxnasm::Error::BinaryDigitInvalid {
+                digit,
+                number,
+                span,
+            } => FileDiagnostic::error()
+                .with_message(format!(
+                    "invalid digit `{}` in a binary number `{}`",
+                    digit, number
+                ))
+                .with_label(Label {
+                    style: LabelStyle::Primary,
+                    span,
+                    message: String::new(),
+                }),
+            ruxnasm::Error::BinaryNumberLengthInvalid {
+                length,
+                number,
+                span,
+            } => FileDiagnostic::error()
+                .with_message(format!(
+                    "binary number `{}` has {} digits",
+                    number, length
+                ))
+                .with_label(Label {
+                    style: LabelStyle::Primary,
+                    span,
+                    message: String::new(),
+                })
+                .with_note("binary numbers must have 8 digits for a byte or 16 digits for a short"),
+            ruxnasm::Error::RepeatTooLong {
+                length,
+                limit,
+                span,
+            } => FileDiagnostic::error()
+                .with_message(format!("repeat expands into {} words", length))
+                .with_label(Label {
+                    style: LabelStyle::Primary,
+                    span,
+                    message: String::new(),
+                })
+                .with_note(format!("the expansion is limited to {} words", limit)),
+            ruxnasm::Error::TooManyErrors { limit, span } => FileDiagnostic::error()
+                .with_message(format!("aborting after {} errors", limit))
+                .with_label(Label {
+                    style: LabelStyle::Primary,
+                    span,
+                    message: "more errors are found from here on".to_owned(),
+                })
+                .with_help("raise the limit with `--error-limit`, or set it to 0 to remove it"),
+            ruxnasm::Error::BytesOverwritten {
+                address,
+                span,
+                other_span,
+            } => FileDiagnostic::error()
+                .with_message(format!("bytes at address {:#06x} are overwritten", address))
+                .with_label(Label {
+                    style: LabelStyle::Primary,
+                    span,
+                    message: "overwritten here".to_owned(),
+                })
+                .with_label(Label {
+                    style: LabelStyle::Secondary,
+                    span: other_span,
+                    message: "previously written here".to_owned(),
+                }),
+            ruxnasm::Error::BinaryTooLarge { size, span } => FileDiagnostic::error()
+                .with_message(format!("binary exceeds the size of {} bytes", size))
+                .with_label(Label {
+                    style: LabelStyle::Primary,
+                    span,
+                    message: "the binary goes past the size here".to_owned(),
+                })
+                .with_help("raise the size given with `--pad-to`"),
+            // Errors without a detailed description are described by their messages alone.
+            error => FileDiagnostic::error()
+                .with_message(error.to_string())
+                .with_label(Label {
+                    style: LabelStyle::Primary,
+                    span: error.span().clone(),
+                    message: String::new(),
+                }),
+        };
+        diagnostic.with_code(code)
+    }
+}
+
+/// Number of the other macro invocations pointed at by an error reported from many of them.
+const LISTED_INVOCATIONS: usize = 3;
+
+/// Converts the errors into diagnostics, collapsing the errors reported from the same macro
+/// definition in every invocation of the macro into a single diagnostic, which lists the first
+/// few of the other invocations.
+pub fn error_diagnostics(errors: Vec<ruxnasm::Error>) -> Vec<FileDiagnostic> {
+    let mut groups: Vec<(ruxnasm::Error, Vec<ruxnasm::Error>)> = Vec::new();
+    for error in errors {
+        let position = match error {
+            ruxnasm::Error::MacroError { .. } => groups.iter().position(|(first, _)| {
+                matches!(first, ruxnasm::Error::MacroError { .. })
+                    && original_error(first) == original_error(&error)
+            }),
+            _ => None,
+        };
+        match position {
+            Some(position) => groups[position].1.push(error),
+            None => groups.push((error, Vec::new())),
+        }
+    }
+
+    groups
+        .into_iter()
+        .map(|(first, duplicates)| {
+            if duplicates.is_empty() {
+                return FileDiagnostic::from(first);
+            }
+            let count = duplicates.len() + 1;
+            let mut diagnostic = FileDiagnostic::from(first);
+            for duplicate in duplicates.iter().take(LISTED_INVOCATIONS) {
+                diagnostic = diagnostic.with_label(Label {
+                    style: LabelStyle::Secondary,
+                    span: duplicate.span().clone(),
+                    message: "also in this macro invocation".to_owned(),
+                });
+            }
+            if duplicates.len() > LISTED_INVOCATIONS {
+                diagnostic.with_note(format!(
+                    "this error occurs in {} macro invocations, {} of which are not shown",
+                    count,
+                    duplicates.len() - LISTED_INVOCATIONS
+                ))
+            } else {
+                diagnostic.with_note(format!("this error occurs in {} macro invocations", count))
+            }
+        })
+        .collect()
+}
+
+/// Returns the error reported from the definition of a macro, or the error itself if it hasn't
+/// been reported from a macro.
+fn original_error(error: &ruxnasm::Error) -> &ruxnasm::Error {
+    match error {
+        ruxnasm::Error::MacroError {
+            original_error: error,
+            ..
+        } => original_error(error),
+        error => error,
+    }
+}
+
+/// Proposes a literal absolute address in place of a literal zero-page or relative address,
+/// mentioning the replacement instruction if there is one.
+fn absolute_address_help(identifier: &str, suggestions: &[ruxnasm::Suggestion]) -> String {
+    match suggestions.last() {
+        Some(suggestion) => format!(
+            "use the literal absolute address `;{}` followed by `{}` instead (apply automatically with `--fix`)",
+            identifier, suggestion.replacement
+        ),
+        None => format!(
+            "use the literal absolute address `;{}` instead, adjusting the instructions that consume it",
+            identifier
+        ),
+    }
+}
+
+impl From<ruxnasm::Warning> for FileDiagnostic {
+    fn from(warning: ruxnasm::Warning) -> Self {
+        let code = warning.code();
+        let message = warning.to_string();
+        let diagnostic = match warning {
+            ruxnasm::Warning::TokenTrimmed {
+                token,
+                trimmed,
+                span,
+                suggestions,
+            } => {
+                let diagnostic = FileDiagnostic::warning()
+                    .with_message(format!(
+                        "token has been cut off, as it's longer than 64 characters"
+                    ))
+                    .with_label(Label {
+                        style: LabelStyle::Primary,
+                        span,
+                        message: format!("`{}` is ignored", trimmed),
+                    })
+                    .with_note(format!("the token has become `{}`", token));
+                if suggestions.is_empty() {
+                    diagnostic
+                } else {
+                    diagnostic.with_help(format!(
+                        "split the raw word into `{}` and `\"{}`",
+                        token, trimmed
+                    ))
+                }
+            }
+            ruxnasm::Warning::InstructionModeDefinedMoreThanOnce {
+                instruction_mode,
+                instruction,
+                span,
+                other_span,
+            } => FileDiagnostic::warning()
+                .with_message(format!(
+                    "instruction mode `{}` is defined multiple times for instruction `{}`",
+                    instruction_mode, instruction
+                ))
+                .with_label(Label {
+                    style: LabelStyle::Primary,
+                    span,
+                    message: format!("mode `{}` redefined here", instruction_mode),
+                })
+                .with_label(Label {
+                    style: LabelStyle::Secondary,
+                    span: other_span,
+                    message: format!("previous definition of mode `{}` here", instruction_mode),
+                }),
+            ruxnasm::Warning::MacroUnused { name, span } => FileDiagnostic::warning()
+                .with_message(format!("macro `{}` is never used", name))
+                .with_label(Label {
+                    style: LabelStyle::Primary,
+                    span,
+                    message: String::new(),
+                }),
+            ruxnasm::Warning::LabelUnused { name, span } => FileDiagnostic::warning()
+                .with_message(format!("label `{}` is never used", name))
+                .with_label(Label {
+                    style: LabelStyle::Primary,
+                    span,
+                    message: String::new(),
+                })
+                .with_help("if this is intentional, prefix it with a capital letter"),
+            ruxnasm::Warning::SublabelUnused {
+                label_name,
+                name,
+                zero_page_address,
+                span,
+            } => {
+                let mut diagnostic = FileDiagnostic::warning()
+                    .with_message(format!(
+                        "sublabel `{}` is never used within the scope of label `{}`",
+                        name, label_name
+                    ))
+                    .with_label(Label {
+                        style: LabelStyle::Primary,
+                        span,
+                        message: String::new(),
+                    });
+                if let Some(address) = zero_page_address {
+                    diagnostic = diagnostic.with_note(format!(
+                        "`{}/{}` needlessly occupies address {:#04x} of the zero page",
+                        label_name, name, address
+                    ));
+                }
+                diagnostic.with_help(
+                    "if this is intentional, prefix the sublabel or its label with a capital letter",
+                )
+            }
+            ruxnasm::Warning::DirectiveInvalid { directive, span } => FileDiagnostic::warning()
+                .with_message(format!("invalid directive `{}`", directive))
+                .with_label(Label {
+                    style: LabelStyle::Primary,
+                    span,
+                    message: String::new(),
+                })
+                .with_note("directives have the form `( ruxnasm: allow NAME... )`")
+                .with_note(format!(
+                    "known warnings are: {}",
+                    ruxnasm::Warning::NAMES.join(", ")
+                )),
+            ruxnasm::Warning::LabelAliased {
+                name,
+                other_name,
+                address,
+                span,
+                other_span,
+            } => FileDiagnostic::warning()
+                .with_message(format!(
+                    "label `{}` has the same address {:#06x} as label `{}`",
+                    name, address, other_name
+                ))
+                .with_label(Label {
+                    style: LabelStyle::Primary,
+                    span,
+                    message: String::new(),
+                })
+                .with_label(Label {
+                    style: LabelStyle::Secondary,
+                    span: other_span,
+                    message: format!("label `{}` defined here", other_name),
+                })
+                .with_help(format!(
+                    "if `{}` is meant to occupy memory, add a pad after its definition",
+                    other_name
+                )),
+            ruxnasm::Warning::UnpaddedLabelReferenced {
+                name,
+                address,
+                span,
+                other_span,
+            } => FileDiagnostic::warning()
+                .with_message(format!(
+                    "label `{}` is defined before any pad and resolves to address {:#06x}",
+                    name, address
+                ))
+                .with_label(Label {
+                    style: LabelStyle::Primary,
+                    span,
+                    message: String::new(),
+                })
+                .with_label(Label {
+                    style: LabelStyle::Secondary,
+                    span: other_span,
+                    message: format!("label `{}` defined here", name),
+                })
+                .with_note("programs start at address 0x0100, so this usually means a `|0100` pad is missing before the label definition"),
+            ruxnasm::Warning::BytesInReservedMemory {
+                address,
+                limit,
+                profile,
+                span,
+            } => FileDiagnostic::warning()
+                .with_message(format!(
+                    "bytes are written to address {:#06x}, in the memory reserved from {:#06x}",
+                    address, limit
+                ))
+                .with_label(Label {
+                    style: LabelStyle::Primary,
+                    span,
+                    message: String::new(),
+                })
+                .with_note(format!(
+                    "the memory starting at {:#06x} is reserved in the `{}` compatibility profile",
+                    limit, profile
+                )),
+            ruxnasm::Warning::PortWidthMismatch {
+                name,
+                width,
+                written,
+                span,
+                other_span,
+            } => FileDiagnostic::warning()
+                .with_message(format!(
+                    "writing {} to port `{}`, which is {} wide",
+                    if written == 1 { "a byte" } else { "a short" },
+                    name,
+                    if width == 1 { "1 byte" } else { "2 bytes" }
+                ))
+                .with_label(Label {
+                    style: LabelStyle::Primary,
+                    span,
+                    message: String::new(),
+                })
+                .with_label(Label {
+                    style: LabelStyle::Secondary,
+                    span: other_span,
+                    message: format!("port `{}` declared here", name),
+                })
+                .with_help(if width == 1 {
+                    "use `DEO` to write a byte"
+                } else {
+                    "use `DEO2` to write a short"
+                }),
+            ruxnasm::Warning::BytesOverwritten {
+                address,
+                span,
+                other_span,
+            } => FileDiagnostic::warning()
+                .with_message(format!("bytes at address {:#06x} are overwritten", address))
+                .with_label(Label {
+                    style: LabelStyle::Primary,
+                    span,
+                    message: "overwritten here".to_owned(),
+                })
+                .with_label(Label {
+                    style: LabelStyle::Secondary,
+                    span: other_span,
+                    message: "previously written here".to_owned(),
+                }),
+            ruxnasm::Warning::StackUnderflow {
+                name,
+                span,
+                other_span,
+                ..
+            } => FileDiagnostic::warning()
+                .with_message(message)
+                .with_label(Label {
+                    style: LabelStyle::Primary,
+                    span,
+                    message: String::new(),
+                })
+                .with_label(Label {
+                    style: LabelStyle::Secondary,
+                    span: other_span,
+                    message: format!("`{}` is entered with empty stacks", name),
+                }),
+            ruxnasm::Warning::StackHeightUnexpected {
+                name,
+                instruction,
+                span,
+                other_span,
+                ..
+            } => FileDiagnostic::warning()
+                .with_message(message)
+                .with_label(Label {
+                    style: LabelStyle::Primary,
+                    span,
+                    message: String::new(),
+                })
+                .with_label(Label {
+                    style: LabelStyle::Secondary,
+                    span: other_span,
+                    message: format!("`{}` defined here", name),
+                })
+                .with_note(if instruction == "BRK" {
+                    "vectors are expected to leave their stacks empty"
+                } else {
+                    "subroutines are expected to leave only their return address on the return stack"
+                }),
+            ruxnasm::Warning::CodeUnreachable {
+                instruction,
+                span,
+                other_span,
+            } => FileDiagnostic::warning()
+                .with_message(message)
+                .with_label(Label {
+                    style: LabelStyle::Primary,
+                    span,
+                    message: "never executed".to_owned(),
+                })
+                .with_label(Label {
+                    style: LabelStyle::Secondary,
+                    span: other_span,
+                    message: format!("the execution never continues past `{}`", instruction),
+                })
+                .with_help("if these bytes are data, define a label before them"),
+            ruxnasm::Warning::VectorUnterminated {
+                name,
+                next_name,
+                span,
+                other_span,
+            } => FileDiagnostic::warning()
+                .with_message(message)
+                .with_label(Label {
+                    style: LabelStyle::Primary,
+                    span,
+                    message: format!("the execution continues into `{}` after this", next_name),
+                })
+                .with_label(Label {
+                    style: LabelStyle::Secondary,
+                    span: other_span,
+                    message: format!("vector `{}` defined here", name),
+                })
+                .with_help("end the vector with `BRK`"),
+            ruxnasm::Warning::AddressNearLimit {
+                distance,
+                margin,
+                identifier,
+                span,
+                other_span,
+            } => FileDiagnostic::warning()
+                .with_message(message)
+                .with_label(Label {
+                    style: LabelStyle::Primary,
+                    span,
+                    message: format!("{} bytes away", distance),
+                })
+                .with_label(Label {
+                    style: LabelStyle::Secondary,
+                    span: other_span,
+                    message: format!("label `{}` defined here", identifier),
+                })
+                .with_note(format!(
+                    "adding {} more byte{} between them makes the address too far",
+                    127 - distance,
+                    if 127 - distance == 1 { "" } else { "s" }
+                ))
+                .with_help(format!(
+                    "the warning is given within {} bytes of the limit, as set with `--relative-margin`",
+                    margin
+                )),
+            ruxnasm::Warning::VariablesOverlapping {
+                other_name,
+                span,
+                other_span,
+                ..
+            } => FileDiagnostic::warning()
+                .with_message(message)
+                .with_label(Label {
+                    style: LabelStyle::Primary,
+                    span,
+                    message: String::new(),
+                })
+                .with_label(Label {
+                    style: LabelStyle::Secondary,
+                    span: other_span,
+                    message: format!("`{}` reserved here", other_name),
+                })
+                .with_note("writing to either of the variables changes the other one")
+                .with_help("move the variable past the end of the other one"),
+            ruxnasm::Warning::InstructionShadowed {
+                name,
+                sublabel,
+                span,
+            } => {
+                let reference = if sublabel {
+                    format!("&{}", name)
+                } else {
+                    name.clone()
+                };
+                FileDiagnostic::warning()
+                    .with_message(message)
+                    .with_label(Label {
+                        style: LabelStyle::Primary,
+                        span,
+                        message: String::new(),
+                    })
+                    .with_note(format!(
+                        "references with runes, e.g. `;{}` or `,{}`, point at the {}, but a bare `{}` assembles as the instruction",
+                        reference,
+                        reference,
+                        if sublabel { "sublabel" } else { "label" },
+                        name
+                    ))
+                    .with_help(format!(
+                        "rename the {}, e.g. to `{}`",
+                        if sublabel { "sublabel" } else { "label" },
+                        name.to_lowercase()
+                    ))
+            }
+            ruxnasm::Warning::MacroNamedLikeLabel {
+                name,
+                span,
+                other_span,
+            } => FileDiagnostic::warning()
+                .with_message(message)
+                .with_label(Label {
+                    style: LabelStyle::Primary,
+                    span,
+                    message: format!("label `{}` defined here", name),
+                })
+                .with_label(Label {
+                    style: LabelStyle::Secondary,
+                    span: other_span,
+                    message: format!("macro `{}` defined here", name),
+                })
+                .with_note(format!(
+                    "a bare `{}` invokes the macro, while references with runes, e.g. `;{}`, point at the label",
+                    name, name
+                ))
+                .with_help("rename either of them"),
+            // Warnings without a detailed description are described by their messages alone.
+            warning => FileDiagnostic::warning()
+                .with_message(message)
+                .with_label(Label {
+                    style: LabelStyle::Primary,
+                    span: warning.span().clone(),
+                    message: String::new(),
+                }),
+        };
+        diagnostic.with_code(code)
     }
 }
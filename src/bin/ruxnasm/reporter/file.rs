@@ -15,7 +15,8 @@ pub struct File<'a> {
 
 impl<'a> File<'a> {
     pub fn new(name: &'a Path, source: &'a [u8]) -> Self {
-        let name = name.to_string_lossy().into_owned();
+        // The separators are unified so that the reports are the same on every platform.
+        let name = name.to_string_lossy().replace('\\', "/");
         let source = String::from_utf8_lossy(source);
         let line_starts = files::line_starts(&source).collect();
 
@@ -71,6 +72,69 @@ impl<'a> files::Files<'a> for File<'a> {
     }
 }
 
+/// The input file along with the files included in it. The spans of every file start at its
+/// offset, just like in `ruxnasm::Source`.
+pub struct Sources<'a> {
+    files: Vec<(usize, File<'a>)>,
+}
+
+impl<'a> Sources<'a> {
+    pub fn new(name: &'a Path, source: &'a [u8]) -> Self {
+        Self {
+            files: vec![(0, File::new(name, source))],
+        }
+    }
+
+    pub fn add(&mut self, name: &'a Path, offset: usize, source: &'a [u8]) {
+        self.files.push((offset, File::new(name, source)));
+    }
+
+    /// Finds the file containing the span, returning its ID and the span relative to the
+    /// start of the file.
+    pub fn locate(&self, span: &Range<usize>) -> (usize, Range<usize>) {
+        let file_id = self
+            .files
+            .iter()
+            .rposition(|(offset, _)| *offset <= span.start)
+            .unwrap_or(0);
+        let offset = self.files[file_id].0;
+        (file_id, span.start - offset..span.end - offset)
+    }
+
+    fn file(&self, file_id: usize) -> Result<&File<'a>, files::Error> {
+        self.files
+            .get(file_id)
+            .map(|(_, file)| file)
+            .ok_or(files::Error::FileMissing)
+    }
+}
+
+impl<'a> files::Files<'a> for Sources<'a> {
+    type FileId = usize;
+    type Name = &'a str;
+    type Source = &'a str;
+
+    fn name(&'a self, file_id: usize) -> Result<&'a str, files::Error> {
+        self.file(file_id)?.name(())
+    }
+
+    fn source(&'a self, file_id: usize) -> Result<&'a str, files::Error> {
+        self.file(file_id)?.source(())
+    }
+
+    fn line_index(&'a self, file_id: usize, byte_index: usize) -> Result<usize, files::Error> {
+        self.file(file_id)?.line_index((), byte_index)
+    }
+
+    fn line_range(
+        &'a self,
+        file_id: usize,
+        line_index: usize,
+    ) -> Result<Range<usize>, files::Error> {
+        self.file(file_id)?.line_range((), line_index)
+    }
+}
+
 pub struct Void;
 
 impl<'a> files::Files<'a> for Void {
@@ -0,0 +1,157 @@
+use super::diagnostic::{LabelStyle, Severity};
+use super::FileDiagnostic;
+use crate::json::Value;
+use std::ops::Range;
+
+/// Converts a diagnostic into an LSP diagnostic of the document with the given URI and text.
+/// Diagnostics pointing into the files included in the document are placed at its very
+/// beginning, since the editor only shows the document itself.
+pub fn diagnostic(diagnostic: &FileDiagnostic, uri: &str, text: &str) -> Value {
+    let severity = match diagnostic.severity {
+        Severity::Bug | Severity::Error => 1.0,
+        Severity::Warning => 2.0,
+    };
+
+    let mut message = diagnostic.message.clone();
+    let span = if diagnostic.label.span.end <= text.len() {
+        diagnostic.label.span.clone()
+    } else {
+        message.push_str("\nnote: in a file included in the document");
+        0..0
+    };
+    for note in &diagnostic.notes {
+        message.push_str(&format!("\nnote: {}", note));
+    }
+    for help in &diagnostic.helps {
+        message.push_str(&format!("\nhelp: {}", help));
+    }
+
+    let mut members = vec![
+        ("range".to_owned(), range(text, &span)),
+        ("severity".to_owned(), Value::Number(severity)),
+        ("source".to_owned(), Value::String("ruxnasm".to_owned())),
+        ("message".to_owned(), Value::String(message)),
+    ];
+    if let Some(code) = &diagnostic.code {
+        members.push(("code".to_owned(), Value::String(code.clone())));
+    }
+    let related_information: Vec<Value> = diagnostic
+        .additional_labels
+        .iter()
+        .filter(|label| matches!(label.style, LabelStyle::Secondary))
+        .filter(|label| label.span.end <= text.len())
+        .map(|label| {
+            Value::Object(vec![
+                ("location".to_owned(), location(uri, text, &label.span)),
+                ("message".to_owned(), Value::String(label.message.clone())),
+            ])
+        })
+        .collect();
+    if !related_information.is_empty() {
+        members.push((
+            "relatedInformation".to_owned(),
+            Value::Array(related_information),
+        ));
+    }
+
+    Value::Object(members)
+}
+
+/// Converts the span into an LSP location in the document.
+pub fn location(uri: &str, text: &str, span: &Range<usize>) -> Value {
+    Value::Object(vec![
+        ("uri".to_owned(), Value::String(uri.to_owned())),
+        ("range".to_owned(), range(text, span)),
+    ])
+}
+
+/// Converts the span into an LSP range in the document.
+pub fn range(text: &str, span: &Range<usize>) -> Value {
+    Value::Object(vec![
+        ("start".to_owned(), position(text, span.start)),
+        ("end".to_owned(), position(text, span.end)),
+    ])
+}
+
+fn position(text: &str, offset: usize) -> Value {
+    let (line, character) = line_character(text, offset);
+
+    Value::Object(vec![
+        ("line".to_owned(), Value::Number(line as f64)),
+        ("character".to_owned(), Value::Number(character as f64)),
+    ])
+}
+
+/// Converts the byte offset into the line and the character of an LSP position, which is
+/// counted in UTF-16 code units from the start of the line.
+pub fn line_character(text: &str, offset: usize) -> (usize, usize) {
+    Cursor::new(text).line_character(offset)
+}
+
+/// Position in a document converting increasing byte offsets into LSP positions, scanning only
+/// the text between the consecutive offsets instead of the whole text before each of them.
+pub struct Cursor<'a> {
+    text: &'a str,
+    offset: usize,
+    line: usize,
+    character: usize,
+}
+
+impl<'a> Cursor<'a> {
+    /// Creates a cursor at the beginning of the document.
+    pub fn new(text: &'a str) -> Self {
+        Self {
+            text,
+            offset: 0,
+            line: 0,
+            character: 0,
+        }
+    }
+
+    /// Moves the cursor to the byte offset and returns its line and character. Moving back
+    /// rescans the document from its beginning.
+    pub fn line_character(&mut self, offset: usize) -> (usize, usize) {
+        let offset = offset.min(self.text.len());
+        if offset < self.offset {
+            *self = Self::new(self.text);
+        }
+
+        let bytes = &self.text.as_bytes()[self.offset..offset];
+        match bytes.iter().rposition(|byte| *byte == b'\n') {
+            Some(line_start) => {
+                self.line += bytes.iter().filter(|byte| **byte == b'\n').count();
+                self.character = code_units(&bytes[line_start + 1..]);
+            }
+            None => self.character += code_units(bytes),
+        }
+        self.offset = offset;
+
+        (self.line, self.character)
+    }
+}
+
+fn code_units(bytes: &[u8]) -> usize {
+    String::from_utf8_lossy(bytes).encode_utf16().count()
+}
+
+/// Converts the LSP position back into a byte offset in the document. Positions past the end
+/// of a line point at its end.
+pub fn offset(text: &str, line: usize, character: usize) -> usize {
+    let line_start = match line {
+        0 => 0,
+        line => match text.match_indices('\n').nth(line - 1) {
+            Some((position, _)) => position + 1,
+            None => return text.len(),
+        },
+    };
+
+    let mut code_units = 0;
+    for (position, ch) in text[line_start..].char_indices() {
+        if code_units >= character || ch == '\n' {
+            return line_start + position;
+        }
+        code_units += ch.len_utf16();
+    }
+
+    text.len()
+}
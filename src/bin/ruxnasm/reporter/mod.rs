@@ -1,22 +1,28 @@
-use diagnostic::{FileDiagnostic, VoidDiagnostic};
-use file::{File, Void};
-use std::{path::Path, sync::RwLock};
+use crate::argument_parser::{Charset, ColorChoice, DiagnosticStyle, Layout, MessageFormat};
+use codespan_reporting::term::termcolor::WriteColor;
+use diagnostic::VoidDiagnostic;
+pub use diagnostic::{FileDiagnostic, Severity};
+pub use display::error_diagnostics;
+use file::{Sources, Void};
+use std::{fs::File, io::Write, path::Path, sync::RwLock};
 
 mod diagnostic;
 mod display;
 mod file;
+pub mod lsp;
+mod sarif;
 
 pub struct VoidReporter {
-    pub writer: RwLock<codespan_reporting::term::termcolor::StandardStream>,
+    pub writer: RwLock<Box<dyn WriteColor>>,
     pub config: codespan_reporting::term::Config,
+    /// Whether the diagnostics are written to a file instead of the standard error.
+    pub redirected: bool,
 }
 
 impl VoidReporter {
     pub fn new() -> Self {
         Self {
-            writer: RwLock::new(codespan_reporting::term::termcolor::StandardStream::stderr(
-                codespan_reporting::term::termcolor::ColorChoice::Always,
-            )),
+            writer: RwLock::new(Box::new(standard_error(ColorChoice::Auto))),
             config: codespan_reporting::term::Config {
                 display_style: codespan_reporting::term::DisplayStyle::Rich,
                 tab_width: 2,
@@ -28,14 +34,59 @@ impl VoidReporter {
                 start_context_lines: 3,
                 end_context_lines: 1,
             },
+            redirected: false,
         }
     }
 
+    pub fn with_color_choice(mut self, color_choice: ColorChoice) -> Self {
+        self.writer = RwLock::new(Box::new(standard_error(color_choice)));
+        self
+    }
+
+    /// Writes the diagnostics to the file instead of the standard error. The file is a terminal
+    /// only in rare cases, so the diagnostics are colored only with `always`.
+    pub fn with_output_file(mut self, file: File, color_choice: ColorChoice) -> Self {
+        self.writer = RwLock::new(match color_choice {
+            ColorChoice::Always => Box::new(codespan_reporting::term::termcolor::Ansi::new(file)),
+            ColorChoice::Auto | ColorChoice::Never => {
+                Box::new(codespan_reporting::term::termcolor::NoColor::new(file))
+            }
+        });
+        self.redirected = true;
+        self
+    }
+
+    pub fn with_diagnostic_style(mut self, diagnostic_style: DiagnosticStyle) -> Self {
+        self.config.display_style = match diagnostic_style {
+            DiagnosticStyle::Rich => codespan_reporting::term::DisplayStyle::Rich,
+            DiagnosticStyle::Short => codespan_reporting::term::DisplayStyle::Short,
+        };
+        self
+    }
+
+    pub fn with_layout(mut self, layout: Layout) -> Self {
+        self.config.tab_width = layout.tab_width;
+        if let Some(context_lines) = layout.context_lines {
+            self.config.start_context_lines = context_lines;
+            self.config.end_context_lines = context_lines;
+        }
+        self.config.chars = match layout.charset {
+            Charset::Unicode => codespan_reporting::term::Chars::box_drawing(),
+            Charset::Ascii => codespan_reporting::term::Chars::ascii(),
+        };
+        self
+    }
+
     pub fn promote<'a>(self, file_path: &'a Path, file_contents: &'a [u8]) -> FileReporter<'a> {
         FileReporter {
-            file: File::new(file_path, file_contents),
+            sources: Sources::new(file_path, file_contents),
             writer: self.writer,
             config: self.config,
+            redirected: self.redirected,
+            message_format: MessageFormat::Human,
+            sarif_results: RwLock::new(Vec::new()),
+            error_count: RwLock::new(0),
+            warning_count: RwLock::new(0),
         }
     }
 
@@ -44,7 +95,7 @@ impl VoidReporter {
             diagnostic.into();
         for codespan_diagnostic in codespan_diagnostics {
             let _ = codespan_reporting::term::emit(
-                &mut self.writer.write().unwrap().lock(),
+                &mut **self.writer.write().unwrap(),
                 &self.config,
                 &Void,
                 &codespan_diagnostic,
@@ -54,9 +105,14 @@ impl VoidReporter {
 }
 
 pub struct FileReporter<'a> {
-    pub file: File<'a>,
-    pub writer: RwLock<codespan_reporting::term::termcolor::StandardStream>,
+    pub sources: Sources<'a>,
+    pub writer: RwLock<Box<dyn WriteColor>>,
     pub config: codespan_reporting::term::Config,
+    pub redirected: bool,
+    pub message_format: MessageFormat,
+    sarif_results: RwLock<Vec<String>>,
+    error_count: RwLock<usize>,
+    warning_count: RwLock<usize>,
 }
 
 impl<'a> FileReporter<'a> {
@@ -64,21 +120,142 @@ impl<'a> FileReporter<'a> {
         VoidReporter {
             writer: self.writer,
             config: self.config,
+            redirected: self.redirected,
         }
     }
 
+    pub fn with_message_format(mut self, message_format: MessageFormat) -> Self {
+        self.message_format = message_format;
+        self
+    }
+
+    /// Adds the files included in the input file, so that the diagnostics can point into them.
+    pub fn with_sources(mut self, sources: &'a [ruxnasm::Source]) -> Self {
+        for source in sources {
+            self.sources
+                .add(&source.path, source.offset, &source.contents);
+        }
+        self
+    }
+
     pub fn emit(&self, diagnostic: FileDiagnostic) {
+        match diagnostic.severity {
+            Severity::Bug | Severity::Error => *self.error_count.write().unwrap() += 1,
+            Severity::Warning => *self.warning_count.write().unwrap() += 1,
+        }
+
+        if let MessageFormat::Sarif = self.message_format {
+            let result = sarif::result(&diagnostic, &self.sources);
+            self.sarif_results.write().unwrap().push(result);
+            return;
+        }
+
         let codespan_diagnostics: Vec<codespan_reporting::diagnostic::Diagnostic<()>> =
             diagnostic.into();
         for codespan_diagnostic in codespan_diagnostics {
             let _ = codespan_reporting::term::emit(
-                &mut self.writer.write().unwrap().lock(),
+                &mut **self.writer.write().unwrap(),
                 &self.config,
-                &self.file,
-                &codespan_diagnostic,
+                &self.sources,
+                &self.locate(codespan_diagnostic),
             );
         }
     }
+
+    /// Points the labels of the diagnostic into the files containing their spans.
+    fn locate(
+        &self,
+        diagnostic: codespan_reporting::diagnostic::Diagnostic<()>,
+    ) -> codespan_reporting::diagnostic::Diagnostic<usize> {
+        let labels = diagnostic
+            .labels
+            .into_iter()
+            .map(|label| {
+                let (file_id, range) = self.sources.locate(&label.range);
+                codespan_reporting::diagnostic::Label {
+                    style: label.style,
+                    file_id,
+                    range,
+                    message: label.message,
+                }
+            })
+            .collect();
+        codespan_reporting::diagnostic::Diagnostic {
+            severity: diagnostic.severity,
+            code: diagnostic.code,
+            message: diagnostic.message,
+            labels,
+            notes: diagnostic.notes,
+        }
+    }
+
+    /// Prints all of the collected diagnostics, in case they're not printed as soon as they're
+    /// emitted, or a summary of how many of them have been printed. The SARIF log goes to the
+    /// standard output, unless the diagnostics are redirected to a file.
+    pub fn finish(&self) {
+        match self.message_format {
+            MessageFormat::Human => {
+                let summary = summary(
+                    *self.error_count.read().unwrap(),
+                    *self.warning_count.read().unwrap(),
+                );
+                if let Some(summary) = summary {
+                    let codespan_diagnostics: Vec<codespan_reporting::diagnostic::Diagnostic<()>> =
+                        summary.into();
+                    for codespan_diagnostic in codespan_diagnostics {
+                        let _ = codespan_reporting::term::emit(
+                            &mut **self.writer.write().unwrap(),
+                            &self.config,
+                            &Void,
+                            &codespan_diagnostic,
+                        );
+                    }
+                }
+            }
+            MessageFormat::Sarif => {
+                let log = sarif::log(&self.sarif_results.read().unwrap());
+                if self.redirected {
+                    let _ = writeln!(self.writer.write().unwrap(), "{}", log);
+                } else {
+                    println!("{}", log);
+                }
+            }
+        }
+    }
+}
+
+/// Summarizes the numbers of the emitted errors and warnings, in the style of `rustc`, e.g.
+/// "aborting due to 3 previous errors; 2 warnings emitted".
+fn summary(error_count: usize, warning_count: usize) -> Option<VoidDiagnostic> {
+    let warnings = match warning_count {
+        1 => "1 warning emitted".to_owned(),
+        _ => format!("{} warnings emitted", warning_count),
+    };
+    let errors = match error_count {
+        1 => "aborting due to previous error".to_owned(),
+        _ => format!("aborting due to {} previous errors", error_count),
+    };
+
+    match (error_count, warning_count) {
+        (0, 0) => None,
+        (0, _) => Some(VoidDiagnostic::warning().with_message(warnings)),
+        (_, 0) => Some(VoidDiagnostic::error().with_message(errors)),
+        (_, _) => Some(VoidDiagnostic::error().with_message(format!("{}; {}", errors, warnings))),
+    }
+}
+
+/// Opens the standard error for the diagnostics. With `auto`, colors are used only when it's a
+/// terminal, and `termcolor` additionally turns them off if `NO_COLOR` is set or `TERM=dumb`.
+fn standard_error(
+    color_choice: ColorChoice,
+) -> codespan_reporting::term::termcolor::StandardStream {
+    use codespan_reporting::term::termcolor;
+
+    termcolor::StandardStream::stderr(match color_choice {
+        ColorChoice::Auto if atty::is(atty::Stream::Stderr) => termcolor::ColorChoice::Auto,
+        ColorChoice::Auto | ColorChoice::Never => termcolor::ColorChoice::Never,
+        ColorChoice::Always => termcolor::ColorChoice::Always,
+    })
 }
 
 fn with_blue(blue: codespan_reporting::term::termcolor::Color) -> codespan_reporting::term::Styles {
@@ -0,0 +1,91 @@
+use super::diagnostic::{Label, LabelStyle, Severity};
+use super::file::Sources;
+use super::FileDiagnostic;
+use crate::json::string;
+use codespan_reporting::files::Files;
+
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+const INFORMATION_URI: &str = "https://github.com/karolbelina/ruxnasm";
+
+/// Converts a diagnostic into a SARIF result object.
+pub fn result(diagnostic: &FileDiagnostic, sources: &Sources) -> String {
+    let level = match diagnostic.severity {
+        Severity::Bug | Severity::Error => "error",
+        Severity::Warning => "warning",
+    };
+
+    let mut text = diagnostic.message.clone();
+    for note in &diagnostic.notes {
+        text.push_str(&format!("\nnote: {}", note));
+    }
+    for help in &diagnostic.helps {
+        text.push_str(&format!("\nhelp: {}", help));
+    }
+
+    let mut fields = Vec::new();
+    if let Some(code) = &diagnostic.code {
+        fields.push(format!("\"ruleId\":{}", string(code)));
+    }
+    fields.push(format!("\"level\":{}", string(level)));
+    fields.push(format!("\"message\":{{\"text\":{}}}", string(&text)));
+    fields.push(format!(
+        "\"locations\":[{}]",
+        location(&diagnostic.label, sources)
+    ));
+    let related_locations: Vec<String> = diagnostic
+        .additional_labels
+        .iter()
+        .filter(|label| matches!(label.style, LabelStyle::Secondary))
+        .map(|label| location(label, sources))
+        .collect();
+    if !related_locations.is_empty() {
+        fields.push(format!(
+            "\"relatedLocations\":[{}]",
+            related_locations.join(",")
+        ));
+    }
+
+    format!("{{{}}}", fields.join(","))
+}
+
+/// Wraps the results into a SARIF log with a single run of Ruxnasm.
+pub fn log(results: &[String]) -> String {
+    format!(
+        "{{\"$schema\":{},\"version\":\"2.1.0\",\"runs\":[{{\"tool\":{{\"driver\":{{\"name\":\"ruxnasm\",\"version\":{},\"informationUri\":{}}}}},\"results\":[{}]}}]}}",
+        string(SARIF_SCHEMA),
+        string(env!("CARGO_PKG_VERSION")),
+        string(INFORMATION_URI),
+        results.join(",")
+    )
+}
+
+fn location(label: &Label, sources: &Sources) -> String {
+    let (file_id, span) = sources.locate(&label.span);
+    let mut region = vec![
+        format!("\"byteOffset\":{}", span.start),
+        format!("\"byteLength\":{}", span.end - span.start),
+    ];
+    if let (Ok(start), Ok(end)) = (
+        sources.location(file_id, span.start),
+        sources.location(file_id, span.end),
+    ) {
+        region.push(format!("\"startLine\":{}", start.line_number));
+        region.push(format!("\"startColumn\":{}", start.column_number));
+        region.push(format!("\"endLine\":{}", end.line_number));
+        region.push(format!("\"endColumn\":{}", end.column_number));
+    }
+
+    let mut fields = vec![format!(
+        "\"physicalLocation\":{{\"artifactLocation\":{{\"uri\":{}}},\"region\":{{{}}}}}",
+        string(&sources.name(file_id).unwrap_or_default().replace('\\', "/")),
+        region.join(",")
+    )];
+    if !label.message.is_empty() {
+        fields.push(format!(
+            "\"message\":{{\"text\":{}}}",
+            string(&label.message)
+        ));
+    }
+
+    format!("{{{}}}", fields.join(","))
+}
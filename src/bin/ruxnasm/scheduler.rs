@@ -0,0 +1,424 @@
+use crate::allocator::{self, Usage};
+use crate::json::{self, Value};
+use crate::language_server::write_message;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::io::{self, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{Receiver, TryRecvError};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Error code of LSP for requests cancelled by the client.
+pub const REQUEST_CANCELLED: f64 = -32800.0;
+
+/// A message of the client scheduled to be handled by the language server.
+pub enum Job {
+    /// A valid message, and whether it's a change of a document changed again by a later message,
+    /// in which case the document doesn't need to be assembled.
+    Message { message: Value, superseded: bool },
+    /// A message which is not valid JSON, along with the error.
+    Invalid(String),
+    /// A request cancelled by the client before it's been handled.
+    Cancelled { id: Value, method: String },
+    /// Cancellation of a request which is not queued anymore, and which may still be running.
+    Cancel { id: Value },
+}
+
+/// Queue of the messages received from the client, read on another thread so that the messages
+/// sent while the server is busy are known before it handles the next one. This way the
+/// cancelled requests are skipped, and the changes of a document overridden by later changes
+/// are not assembled.
+pub struct Scheduler {
+    receiver: Receiver<io::Result<Option<String>>>,
+    queue: VecDeque<Job>,
+    /// Whether the input has ended.
+    ended: bool,
+}
+
+impl Scheduler {
+    /// Creates a scheduler of the messages sent by the reading thread, which sends nothing once
+    /// the input has ended.
+    pub fn new(receiver: Receiver<io::Result<Option<String>>>) -> Self {
+        Self {
+            receiver,
+            queue: VecDeque::new(),
+            ended: false,
+        }
+    }
+
+    /// Returns the next job, waiting for the next message if there are none, or nothing if the
+    /// input has ended.
+    pub fn next_job(&mut self) -> io::Result<Option<Job>> {
+        loop {
+            while !self.ended {
+                match self.receiver.try_recv() {
+                    Ok(received) => self.enqueue(received)?,
+                    Err(TryRecvError::Empty) => break,
+                    Err(TryRecvError::Disconnected) => self.ended = true,
+                }
+            }
+            if let Some(job) = self.queue.pop_front() {
+                return Ok(Some(self.supersede(job)));
+            }
+            if self.ended {
+                return Ok(None);
+            }
+            let received = self.receiver.recv().unwrap_or(Ok(None));
+            self.enqueue(received)?;
+        }
+    }
+
+    fn enqueue(&mut self, received: io::Result<Option<String>>) -> io::Result<()> {
+        let message = match received? {
+            Some(message) => message,
+            None => {
+                self.ended = true;
+                return Ok(());
+            }
+        };
+        let message = match json::parse(&message) {
+            Ok(message) => message,
+            Err(error) => {
+                self.queue.push_back(Job::Invalid(error));
+                return Ok(());
+            }
+        };
+
+        if method(&message) == Some("$/cancelRequest") {
+            let id = message.get("params").and_then(|params| params.get("id"));
+            match self
+                .queue
+                .iter_mut()
+                .find(|job| matches!(job, Job::Message { message, .. } if message.get("id") == id))
+            {
+                Some(job) => {
+                    if let Job::Message { message, .. } = job {
+                        *job = Job::Cancelled {
+                            id: message.get("id").cloned().unwrap_or(Value::Null),
+                            method: method(message).unwrap_or_default().to_owned(),
+                        };
+                    }
+                }
+                None => self.queue.push_back(Job::Cancel {
+                    id: id.cloned().unwrap_or(Value::Null),
+                }),
+            }
+            return Ok(());
+        }
+        self.queue.push_back(Job::Message {
+            message,
+            superseded: false,
+        });
+        Ok(())
+    }
+
+    /// Marks the change of the document as superseded if the document is changed or closed by
+    /// any of the queued messages.
+    fn supersede(&self, job: Job) -> Job {
+        let message = match job {
+            Job::Message { message, .. } if method(&message) == Some("textDocument/didChange") => {
+                message
+            }
+            job => return job,
+        };
+        let superseded = self.queue.iter().any(|queued| match queued {
+            Job::Message {
+                message: queued_message,
+                ..
+            } => {
+                matches!(
+                    method(queued_message),
+                    Some("textDocument/didChange") | Some("textDocument/didClose")
+                ) && uri(queued_message) == uri(&message)
+            }
+            _ => false,
+        });
+
+        Job::Message {
+            message,
+            superseded,
+        }
+    }
+}
+
+/// Job running on its own thread: a request, or the assembly of a document for its diagnostics.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Key {
+    /// Request with the ID, encoded as JSON.
+    Request(String),
+    /// Diagnostics of the document with the URI.
+    Diagnostics(String),
+}
+
+impl Key {
+    pub fn request(id: &Value) -> Self {
+        Self::Request(id.to_string())
+    }
+}
+
+/// Flag telling a job running on another thread that its result is not needed anymore. Jobs
+/// check it between their steps and stop early once it's set.
+#[derive(Clone, Default)]
+pub struct Cancellation(Arc<AtomicBool>);
+
+impl Cancellation {
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Runs the jobs of the language server on their own threads, and writes the messages they
+/// return along with the messages of the server itself. Messages of the jobs cancelled while
+/// they're running are replaced with the messages given when the jobs were started.
+#[derive(Clone)]
+pub struct Workers {
+    shared: Arc<Shared>,
+}
+
+struct Shared {
+    running: Mutex<HashMap<Key, Worker>>,
+    metrics: Mutex<Metrics>,
+    output: Mutex<Output>,
+}
+
+struct Worker {
+    method: String,
+    start: Instant,
+    cancellation: Cancellation,
+}
+
+struct Output {
+    writer: Box<dyn Write + Send>,
+    /// First error of writing a message of a job, reported by the next message of the server.
+    error: Option<io::Error>,
+}
+
+impl Workers {
+    pub fn new(writer: impl Write + Send + 'static) -> Self {
+        Self {
+            shared: Arc::new(Shared {
+                running: Mutex::new(HashMap::new()),
+                metrics: Mutex::new(Metrics::default()),
+                output: Mutex::new(Output {
+                    writer: Box::new(writer),
+                    error: None,
+                }),
+            }),
+        }
+    }
+
+    /// Writes a message of the server, or reports the first error of writing the messages of the
+    /// jobs.
+    pub fn write(&self, message: &Value) -> io::Result<()> {
+        let mut output = self.shared.output.lock().unwrap();
+        if let Some(error) = output.error.take() {
+            return Err(error);
+        }
+        write_message(&mut output.writer, message)
+    }
+
+    /// Runs the job on a new thread, cancelling the job with the same key if it's still running.
+    /// The message returned by the job is written unless the job has been cancelled, in which
+    /// case the given message is written instead, if any.
+    pub fn spawn<F>(&self, key: Key, method: &str, cancelled_message: Option<Value>, job: F)
+    where
+        F: FnOnce(&Cancellation) -> Option<Value> + Send + 'static,
+    {
+        let cancellation = Cancellation::default();
+        let worker = Worker {
+            method: method.to_owned(),
+            start: Instant::now(),
+            cancellation: cancellation.clone(),
+        };
+        if let Some(previous) = self
+            .shared
+            .running
+            .lock()
+            .unwrap()
+            .insert(key.clone(), worker)
+        {
+            previous.cancellation.cancel();
+        }
+
+        let shared = Arc::clone(&self.shared);
+        let method = method.to_owned();
+        thread::spawn(move || {
+            let start = Instant::now();
+            let (message, usage) = allocator::measure(|| job(&cancellation));
+            let duration = start.elapsed();
+
+            let mut running = shared.running.lock().unwrap();
+            if matches!(running.get(&key), Some(worker) if Arc::ptr_eq(&worker.cancellation.0, &cancellation.0))
+            {
+                running.remove(&key);
+            }
+            drop(running);
+            // The output stays locked from checking the cancellation until the message is
+            // written, so that a job started after this one was cancelled writes after it.
+            let mut output = shared.output.lock().unwrap();
+            let (message, outcome) = if cancellation.is_cancelled() {
+                let outcome = match key {
+                    Key::Request(_) => Outcome::Cancelled,
+                    Key::Diagnostics(_) => Outcome::Superseded,
+                };
+                (cancelled_message, outcome)
+            } else {
+                (message, Outcome::Handled)
+            };
+            if let Some(message) = message {
+                if let Err(error) = write_message(&mut output.writer, &message) {
+                    output.error.get_or_insert(error);
+                }
+            }
+            drop(output);
+            shared
+                .metrics
+                .lock()
+                .unwrap()
+                .record(&method, outcome, duration, usage);
+        });
+    }
+
+    /// Cancels the running job, returning whether there has been such job.
+    pub fn cancel(&self, key: &Key) -> bool {
+        match self.shared.running.lock().unwrap().remove(key) {
+            Some(worker) => {
+                worker.cancellation.cancel();
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn record(&self, method: &str, outcome: Outcome, duration: Duration, usage: Usage) {
+        self.shared
+            .metrics
+            .lock()
+            .unwrap()
+            .record(method, outcome, duration, usage);
+    }
+
+    /// Lists the running jobs, along with the time they've been running for, and the metrics of
+    /// the finished ones.
+    pub fn status(&self) -> (Value, Value) {
+        let running = self.shared.running.lock().unwrap();
+        let mut jobs: Vec<(&Key, &Worker)> = running.iter().collect();
+        jobs.sort_by_key(|(_, worker)| worker.start);
+        let jobs = jobs
+            .into_iter()
+            .map(|(key, worker)| {
+                let (kind, value) = match key {
+                    Key::Request(id) => ("id", json::parse(id).unwrap_or(Value::Null)),
+                    Key::Diagnostics(uri) => ("uri", Value::String(uri.clone())),
+                };
+                Value::Object(vec![
+                    (kind.to_owned(), value),
+                    ("method".to_owned(), Value::String(worker.method.clone())),
+                    (
+                        "milliseconds".to_owned(),
+                        milliseconds(worker.start.elapsed()),
+                    ),
+                ])
+            })
+            .collect();
+
+        (
+            Value::Array(jobs),
+            self.shared.metrics.lock().unwrap().to_value(),
+        )
+    }
+}
+
+/// How the handling of a message has ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Handled,
+    /// The request has been cancelled by the client.
+    Cancelled,
+    /// The change of the document has been overridden by a later change before its diagnostics
+    /// were published.
+    Superseded,
+}
+
+/// Timings and memory of the messages handled by the language server, by their methods, along
+/// with the numbers of the cancelled and superseded ones.
+#[derive(Default)]
+pub struct Metrics {
+    methods: BTreeMap<String, MethodMetrics>,
+}
+
+#[derive(Default)]
+struct MethodMetrics {
+    handled: usize,
+    cancelled: usize,
+    superseded: usize,
+    total: Duration,
+    longest: Duration,
+    allocated_bytes: usize,
+    peak_bytes: usize,
+}
+
+impl Metrics {
+    pub fn record(&mut self, method: &str, outcome: Outcome, duration: Duration, usage: Usage) {
+        let metrics = self.methods.entry(method.to_owned()).or_default();
+        match outcome {
+            Outcome::Handled => metrics.handled += 1,
+            Outcome::Cancelled => metrics.cancelled += 1,
+            Outcome::Superseded => metrics.superseded += 1,
+        }
+        metrics.total += duration;
+        metrics.longest = metrics.longest.max(duration);
+        metrics.allocated_bytes += usage.allocated;
+        metrics.peak_bytes = metrics.peak_bytes.max(usage.peak);
+    }
+
+    /// Returns the metrics as a JSON object with a member for every method, in alphabetical
+    /// order.
+    pub fn to_value(&self) -> Value {
+        let number = |number: usize| Value::Number(number as f64);
+
+        Value::Object(
+            self.methods
+                .iter()
+                .map(|(method, metrics)| {
+                    (
+                        method.clone(),
+                        Value::Object(vec![
+                            ("handled".to_owned(), number(metrics.handled)),
+                            ("cancelled".to_owned(), number(metrics.cancelled)),
+                            ("superseded".to_owned(), number(metrics.superseded)),
+                            ("totalMilliseconds".to_owned(), milliseconds(metrics.total)),
+                            (
+                                "longestMilliseconds".to_owned(),
+                                milliseconds(metrics.longest),
+                            ),
+                            ("allocatedBytes".to_owned(), number(metrics.allocated_bytes)),
+                            ("peakBytes".to_owned(), number(metrics.peak_bytes)),
+                        ]),
+                    )
+                })
+                .collect(),
+        )
+    }
+}
+
+fn milliseconds(duration: Duration) -> Value {
+    Value::Number(duration.as_secs_f64() * 1000.0)
+}
+
+pub fn method(message: &Value) -> Option<&str> {
+    message.get("method").and_then(Value::as_str)
+}
+
+fn uri(message: &Value) -> Option<&str> {
+    message
+        .get("params")
+        .and_then(|params| params.get("textDocument"))
+        .and_then(|text_document| text_document.get("uri"))
+        .and_then(Value::as_str)
+}
@@ -0,0 +1,38 @@
+use ruxnasm::{FileSystem, SourceProvider};
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Provider of the files in the file system, which looks for the included files not found
+/// relative to the working directory in the directories of the search path, in order.
+pub struct SearchPath<'a> {
+    directories: &'a [PathBuf],
+}
+
+impl<'a> SearchPath<'a> {
+    pub fn new(directories: &'a [PathBuf]) -> Self {
+        Self { directories }
+    }
+
+    /// Returns the path under which the file can be found, or the path itself if it can't be
+    /// found anywhere, so that it's reported as missing under the name written in the include.
+    fn resolve(&self, path: &Path) -> PathBuf {
+        if path.is_absolute() || path.is_file() {
+            return path.to_path_buf();
+        }
+        self.directories
+            .iter()
+            .map(|directory| directory.join(path))
+            .find(|path| path.is_file())
+            .unwrap_or_else(|| path.to_path_buf())
+    }
+}
+
+impl SourceProvider for SearchPath<'_> {
+    fn load(&self, path: &Path) -> Result<Vec<u8>, io::Error> {
+        FileSystem.load(&self.resolve(path))
+    }
+
+    fn canonicalize(&self, path: &Path) -> PathBuf {
+        FileSystem.canonicalize(&self.resolve(path))
+    }
+}
@@ -0,0 +1,121 @@
+use crate::json::Value;
+use ruxnasm::{Chunk, Source};
+use std::ops::Range;
+
+/// Version of the format of the source map, raised with every incompatible change.
+const VERSION: f64 = 1.0;
+
+/// Renders a JSON map from the addresses of the binary to the files and the spans of the
+/// statements the bytes have been emitted from, for the debuggers that show the source code
+/// while stepping through the binary. Bytes expanded from macros point into the definitions of
+/// the macros, and list the invocations they have been expanded through, starting with the
+/// outermost one.
+///
+/// The spans are byte offsets into the files, while the lines and the columns start at 1.
+pub fn render(chunks: &[Chunk], files: &[&Source]) -> Vec<u8> {
+    let (files, file_names) = index_files(files);
+
+    let mappings = chunks
+        .iter()
+        .map(|chunk| {
+            let mut members = vec![
+                ("address".to_owned(), Value::Number(chunk.address as f64)),
+                ("length".to_owned(), Value::Number(chunk.bytes.len() as f64)),
+            ];
+            members.extend(location(&files, &chunk.span));
+            let invocations = chunk
+                .invocations
+                .iter()
+                .map(|span| Value::Object(location(&files, span)))
+                .collect();
+            members.push(("invocations".to_owned(), Value::Array(invocations)));
+            Value::Object(members)
+        })
+        .collect();
+
+    let source_map = Value::Object(vec![
+        ("version".to_owned(), Value::Number(VERSION)),
+        ("files".to_owned(), Value::Array(file_names)),
+        ("mappings".to_owned(), Value::Array(mappings)),
+    ]);
+
+    format!("{}\n", source_map).into_bytes()
+}
+
+/// Sorts the files by their offsets, so that the files containing the spans can be found with
+/// [`locate`], and lists their names in that order.
+pub fn index_files<'a>(files: &[&'a Source]) -> (Vec<&'a Source>, Vec<Value>) {
+    let mut files: Vec<&Source> = files.to_vec();
+    files.sort_by_key(|file| file.offset);
+    let file_names = files
+        .iter()
+        .map(|file| Value::String(file.path.to_string_lossy().into_owned()))
+        .collect();
+    (files, file_names)
+}
+
+/// Where a span is in the files of the program.
+pub struct Location {
+    /// Index of the file containing the span.
+    pub file: usize,
+    /// The span relative to the start of the file.
+    pub span: Range<usize>,
+    pub line: usize,
+    pub column: usize,
+}
+
+/// Finds the file containing the span, in the files sorted with [`index_files`], along with the
+/// line and the column of the start of the span.
+pub fn locate(files: &[&Source], span: &Range<usize>) -> Location {
+    let file = files
+        .iter()
+        .rposition(|file| file.offset <= span.start)
+        .unwrap_or(0);
+    let (offset, contents): (usize, &[u8]) = match files.get(file) {
+        Some(file) => (file.offset, &file.contents),
+        None => (0, &[]),
+    };
+    let start = span.start - offset;
+    let preceding = &contents[..start.min(contents.len())];
+    let line = preceding.iter().filter(|byte| **byte == b'\n').count() + 1;
+    let column = match preceding.iter().rposition(|byte| *byte == b'\n') {
+        Some(position) => start - position,
+        None => start + 1,
+    };
+
+    Location {
+        file,
+        span: start..span.end - offset,
+        line,
+        column,
+    }
+}
+
+/// Describes where the span is, i.e. the index of the file containing it, the span relative to
+/// the start of the file, and the line and the column of its start.
+pub fn location(files: &[&Source], span: &Range<usize>) -> Vec<(String, Value)> {
+    let location = locate(files, span);
+
+    vec![
+        ("file".to_owned(), Value::Number(location.file as f64)),
+        (
+            "span".to_owned(),
+            Value::Array(vec![
+                Value::Number(location.span.start as f64),
+                Value::Number(location.span.end as f64),
+            ]),
+        ),
+        ("line".to_owned(), Value::Number(location.line as f64)),
+        ("column".to_owned(), Value::Number(location.column as f64)),
+    ]
+}
+
+/// Returns the source code at the span, e.g. the name of an invoked macro.
+pub fn text(files: &[&Source], span: &Range<usize>) -> String {
+    let location = locate(files, span);
+    files
+        .get(location.file)
+        .and_then(|file| file.contents.get(location.span))
+        .map(|text| String::from_utf8_lossy(text).into_owned())
+        .unwrap_or_default()
+}
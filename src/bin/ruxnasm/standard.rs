@@ -0,0 +1,36 @@
+/// Standard libraries shipped with the assembler, along with their versions. A released version
+/// of a library never changes, so that programs keep assembling to the same binaries; changes
+/// are released as new versions instead.
+const LIBRARIES: &[(&str, u32, &[u8])] = &[("lib", 1, include_bytes!("std/lib-1.tal"))];
+
+/// Finds a standard library by its name, either followed by a version, as in `lib@1`, or alone,
+/// referring to the latest version. Returns the name with the version and the contents.
+pub fn find(name: &str) -> Option<(String, &'static [u8])> {
+    let (name, version) = match name.find('@') {
+        Some(position) => (&name[..position], Some(name[position + 1..].parse().ok()?)),
+        None => (name, None),
+    };
+
+    LIBRARIES
+        .iter()
+        .filter(|(library_name, library_version, _)| {
+            *library_name == name && (version.is_none() || version == Some(*library_version))
+        })
+        .max_by_key(|(_, library_version, _)| *library_version)
+        .map(|(library_name, library_version, contents)| {
+            (format!("{}@{}", library_name, library_version), *contents)
+        })
+}
+
+/// Returns the names of all standard libraries with their latest versions.
+pub fn names() -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    for (name, _, _) in LIBRARIES {
+        if let Some((name, _)) = find(name) {
+            if !names.contains(&name) {
+                names.push(name);
+            }
+        }
+    }
+    names
+}
@@ -0,0 +1,65 @@
+/// Address at which the binaries are loaded.
+const START: u16 = 0x0100;
+
+pub enum Error {
+    ReassemblyFailed {
+        message: String,
+    },
+    BinariesDiffer {
+        address: u16,
+        original: Option<u8>,
+        reassembled: Option<u8>,
+        /// The disassembled instruction covering the address, if any.
+        instruction: Option<String>,
+    },
+}
+
+/// Disassembles the binary, assembles the disassembled instructions once again, and checks
+/// whether the result is the same binary, which it is unless the disassembler or the assembler
+/// get some opcodes wrong.
+pub fn round_trip(binary: &[u8]) -> Result<(), Error> {
+    let instructions = ruxnasm::disassemble(binary);
+    let mut source = format!("|{:04x}\n", START);
+    for instruction in &instructions {
+        source.push_str(&instruction.text);
+        source.push('\n');
+    }
+
+    let reassembled = match ruxnasm::assemble(source.as_bytes()) {
+        Ok((reassembled, _)) => reassembled,
+        Err((errors, _)) => {
+            return Err(Error::ReassemblyFailed {
+                message: errors.first().map(ToString::to_string).unwrap_or_default(),
+            })
+        }
+    };
+
+    let offset = binary
+        .iter()
+        .zip(&reassembled)
+        .position(|(original, reassembled)| original != reassembled)
+        .or_else(|| {
+            if binary.len() != reassembled.len() {
+                Some(binary.len().min(reassembled.len()))
+            } else {
+                None
+            }
+        });
+    match offset {
+        Some(offset) => {
+            let address = START + offset as u16;
+            let instruction = instructions
+                .iter()
+                .take_while(|instruction| instruction.address <= address)
+                .last()
+                .map(|instruction| instruction.text.clone());
+            Err(Error::BinariesDiffer {
+                address,
+                original: binary.get(offset).copied(),
+                reassembled: reassembled.get(offset).copied(),
+                instruction,
+            })
+        }
+        None => Ok(()),
+    }
+}
@@ -2,6 +2,7 @@ use std::path::{Path, PathBuf};
 use std::{
     fs,
     io::{self, Write},
+    process,
 };
 
 pub enum Error {
@@ -11,11 +12,11 @@ pub enum Error {
     },
 }
 
+/// Name of the link to the most recently written binary in the output directory.
+const LATEST_LINK_NAME: &str = "latest";
+
 pub fn write(path: &Path, binary: &[u8]) -> Result<(), Error> {
-    let mut file = fs::File::create(path).map_err(|io_error| Error::CouldNotWriteFile {
-        file_path: path.to_path_buf(),
-        io_error,
-    })?;
+    let mut file = create(path)?;
     file.write_all(binary)
         .map_err(|io_error| Error::CouldNotWriteFile {
             file_path: path.to_path_buf(),
@@ -23,3 +24,123 @@ pub fn write(path: &Path, binary: &[u8]) -> Result<(), Error> {
         })?;
     Ok(())
 }
+
+/// Creates the file, or truncates it if it already exists, to be written to later.
+pub fn create(path: &Path) -> Result<fs::File, Error> {
+    fs::File::create(path).map_err(|io_error| Error::CouldNotWriteFile {
+        file_path: path.to_path_buf(),
+        io_error,
+    })
+}
+
+/// A file written next to the file it's meant to replace, which is left untouched until the
+/// written file is kept. The written file is removed if it's dropped without being kept.
+pub struct Streamed {
+    path: PathBuf,
+    temporary_path: PathBuf,
+    /// The written file, buffered, until it's kept.
+    file: Option<io::BufWriter<fs::File>>,
+}
+
+impl Streamed {
+    /// Creates a temporary file in the directory of the file, e.g. to write the binary to while
+    /// it's being assembled. The file itself is only replaced once the written file is kept, e.g.
+    /// after the program has turned out to be valid.
+    pub fn create(path: &Path) -> Result<Self, Error> {
+        let mut temporary_name = std::ffi::OsString::from(".");
+        temporary_name.push(path.file_name().unwrap_or_default());
+        temporary_name.push(format!(".{}.tmp", process::id()));
+        let temporary_path = path.with_file_name(temporary_name);
+        let file = io::BufWriter::new(create(&temporary_path)?);
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            temporary_path,
+            file: Some(file),
+        })
+    }
+
+    /// Returns the written file.
+    pub fn file(&mut self) -> &mut dyn Write {
+        self.file
+            .as_mut()
+            .expect("the written file is taken only once it's kept")
+    }
+
+    /// Replaces the file with the written one.
+    pub fn keep(mut self) -> Result<(), Error> {
+        let path = self.path.clone();
+        let error = |io_error| Error::CouldNotWriteFile {
+            file_path: path.clone(),
+            io_error,
+        };
+        if let Some(mut file) = self.file.take() {
+            file.flush().map_err(error)?;
+        }
+        fs::rename(&self.temporary_path, &self.path).map_err(error)
+    }
+}
+
+impl Drop for Streamed {
+    fn drop(&mut self) {
+        // Once the file is kept, there is nothing left to remove.
+        drop(self.file.take());
+        let _ = fs::remove_file(&self.temporary_path);
+    }
+}
+
+/// Whether the file can be streamed to, i.e. it's a regular file or it doesn't exist yet. Other
+/// files, e.g. devices, can't be replaced.
+pub fn is_streamable(path: &Path) -> bool {
+    match fs::metadata(path) {
+        Ok(metadata) => metadata.is_file(),
+        Err(_) => true,
+    }
+}
+
+/// Writes the binary to the output directory as `NAME-HASH.EXTENSION`, where HASH is derived from
+/// the contents of the binary, so that older binaries are kept around. Points the `latest` link
+/// to the new binary. Returns the path of the written binary.
+pub fn write_to_directory(
+    directory: &Path,
+    name: &str,
+    extension: &str,
+    binary: &[u8],
+) -> Result<PathBuf, Error> {
+    let error = |file_path: &Path| {
+        let file_path = file_path.to_path_buf();
+        move |io_error| Error::CouldNotWriteFile {
+            file_path,
+            io_error,
+        }
+    };
+
+    fs::create_dir_all(directory).map_err(error(directory))?;
+    let file_name = format!("{}-{:08x}.{}", name, short_hash(binary), extension);
+    let file_path = directory.join(&file_name);
+    write(&file_path, binary)?;
+
+    let link_path = directory.join(LATEST_LINK_NAME);
+    match fs::remove_file(&link_path) {
+        Err(io_error) if io_error.kind() != io::ErrorKind::NotFound => {
+            return Err(error(&link_path)(io_error))
+        }
+        _ => (),
+    }
+    #[cfg(unix)]
+    std::os::unix::fs::symlink(&file_name, &link_path).map_err(error(&link_path))?;
+    #[cfg(not(unix))]
+    write(&link_path, binary)?;
+
+    Ok(file_path)
+}
+
+/// Computes the 32 most significant bits of the FNV-1a hash of the bytes.
+fn short_hash(bytes: &[u8]) -> u32 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in bytes {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    (hash >> 32) as u32
+}
@@ -0,0 +1,189 @@
+//! C interface of the assembler, for editors and emulators written in C. Requires the `capi`
+//! feature. The declarations are in `bindings/c/ruxnasm.h`.
+
+use crate::{Error, Warning};
+use std::ffi::CString;
+use std::ops::Range;
+use std::os::raw::c_char;
+use std::ptr;
+use std::string::ToString;
+
+/// Severity of a diagnostic.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RuxnasmSeverity {
+    Error = 0,
+    Warning = 1,
+}
+
+/// An error or a warning of the assembled program, as returned by [`ruxnasm_next_diagnostic`].
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct RuxnasmDiagnostic {
+    pub severity: RuxnasmSeverity,
+    /// Code of the diagnostic, e.g. `E0029`, as a null-terminated string.
+    pub code: *const c_char,
+    /// Name of the warning, e.g. `unused-label`, as a null-terminated string, or a null pointer
+    /// for errors.
+    pub name: *const c_char,
+    /// Message of the diagnostic, e.g. ``label `main` is never used``, as a null-terminated
+    /// string.
+    pub message: *const c_char,
+    /// Byte offset at which the span of the diagnostic starts.
+    pub start: usize,
+    /// Byte offset at which the span of the diagnostic ends.
+    pub end: usize,
+}
+
+/// An assembled program, owning the binary and the diagnostics.
+pub struct RuxnasmAssembly {
+    binary: Vec<u8>,
+    diagnostics: Vec<Diagnostic>,
+    next_diagnostic: usize,
+}
+
+/// A diagnostic owning its strings, which are borrowed by [`RuxnasmDiagnostic`].
+struct Diagnostic {
+    severity: RuxnasmSeverity,
+    code: CString,
+    name: Option<CString>,
+    message: CString,
+    span: Range<usize>,
+}
+
+impl From<&Error> for Diagnostic {
+    fn from(error: &Error) -> Self {
+        Self {
+            severity: RuxnasmSeverity::Error,
+            code: c_string(error.code()),
+            name: None,
+            message: c_message(error.to_string()),
+            span: error.span().clone(),
+        }
+    }
+}
+
+impl From<&Warning> for Diagnostic {
+    fn from(warning: &Warning) -> Self {
+        Self {
+            severity: RuxnasmSeverity::Warning,
+            code: c_string(warning.code()),
+            name: Some(c_string(warning.name())),
+            message: c_message(warning.to_string()),
+            span: warning.span().clone(),
+        }
+    }
+}
+
+/// Assembles the program of the given length, in bytes. The binary is written to `rom` and its
+/// length to `rom_length`, or a null pointer and zero if the program contains errors. The
+/// binary is owned by the returned assembly, and its diagnostics can be iterated over with
+/// [`ruxnasm_next_diagnostic`].
+///
+/// Returns a null pointer if `source` is null.
+///
+/// # Safety
+///
+/// `source` must point to at least `length` readable bytes, and `rom` and `rom_length` must be
+/// either null or valid for writes. The assembly must be freed with [`ruxnasm_assembly_free`].
+#[no_mangle]
+pub unsafe extern "C" fn ruxnasm_assemble(
+    source: *const c_char,
+    length: usize,
+    rom: *mut *const u8,
+    rom_length: *mut usize,
+) -> *mut RuxnasmAssembly {
+    if source.is_null() {
+        return ptr::null_mut();
+    }
+    let source = std::slice::from_raw_parts(source as *const u8, length);
+
+    let (binary, errors, warnings) = match crate::assemble(source) {
+        Ok((binary, warnings)) => (binary, Vec::new(), warnings),
+        Err((errors, warnings)) => (Vec::new(), errors, warnings),
+    };
+    let diagnostics = errors
+        .iter()
+        .map(Diagnostic::from)
+        .chain(warnings.iter().map(Diagnostic::from))
+        .collect();
+    let assembly = Box::new(RuxnasmAssembly {
+        binary,
+        diagnostics,
+        next_diagnostic: 0,
+    });
+
+    if !rom.is_null() {
+        *rom = if errors.is_empty() {
+            assembly.binary.as_ptr()
+        } else {
+            ptr::null()
+        };
+    }
+    if !rom_length.is_null() {
+        *rom_length = assembly.binary.len();
+    }
+
+    Box::into_raw(assembly)
+}
+
+/// Writes the next diagnostic of the assembly to `diagnostic`, the errors first. Returns false,
+/// leaving `diagnostic` untouched, if there are no more diagnostics. The strings of the
+/// diagnostic are owned by the assembly.
+///
+/// # Safety
+///
+/// `assembly` must have been returned by [`ruxnasm_assemble`] and not freed yet, and
+/// `diagnostic` must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn ruxnasm_next_diagnostic(
+    assembly: *mut RuxnasmAssembly,
+    diagnostic: *mut RuxnasmDiagnostic,
+) -> bool {
+    let assembly = match assembly.as_mut() {
+        Some(assembly) => assembly,
+        None => return false,
+    };
+    let next = match assembly.diagnostics.get(assembly.next_diagnostic) {
+        Some(next) => next,
+        None => return false,
+    };
+    assembly.next_diagnostic += 1;
+
+    if !diagnostic.is_null() {
+        *diagnostic = RuxnasmDiagnostic {
+            severity: next.severity,
+            code: next.code.as_ptr(),
+            name: next.name.as_ref().map_or(ptr::null(), |name| name.as_ptr()),
+            message: next.message.as_ptr(),
+            start: next.span.start,
+            end: next.span.end,
+        };
+    }
+    true
+}
+
+/// Frees the assembly along with its binary and diagnostics, including the strings of the
+/// diagnostics. Does nothing if `assembly` is null.
+///
+/// # Safety
+///
+/// `assembly` must have been returned by [`ruxnasm_assemble`] and not freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn ruxnasm_assembly_free(assembly: *mut RuxnasmAssembly) {
+    if !assembly.is_null() {
+        drop(Box::from_raw(assembly));
+    }
+}
+
+/// Codes and names of the diagnostics never contain null bytes.
+fn c_string(string: &str) -> CString {
+    CString::new(string).unwrap()
+}
+
+/// Messages quote the names from the source, which may contain null bytes. These are dropped,
+/// as they would end the string early.
+fn c_message(mut message: String) -> CString {
+    message.retain(|ch| ch != '\0');
+    CString::new(message).unwrap()
+}
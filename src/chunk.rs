@@ -0,0 +1,16 @@
+use alloc::vec::Vec;
+use core::ops::Range;
+
+/// A contiguous sequence of bytes emitted from a single statement of the program.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Chunk {
+    /// Address of the first byte of the chunk.
+    pub address: u16,
+    /// The emitted bytes.
+    pub bytes: Vec<u8>,
+    /// Span of the statement from which the bytes have been emitted.
+    pub span: Range<usize>,
+    /// Spans of the macro invocations the statement has been expanded through, starting with the
+    /// outermost invocation. Empty if the statement is written outside of macros.
+    pub invocations: Vec<Range<usize>>,
+}
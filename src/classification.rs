@@ -0,0 +1,110 @@
+use crate::{scanner::Scanner, tokenizer::Word, Error, Options, Token};
+use alloc::vec::Vec;
+use core::ops::Range;
+
+/// Kind of a token, as far as highlighting the source code is concerned.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum TokenClass {
+    /// A comment, including the parentheses.
+    Comment,
+    /// An instruction, e.g. `ADD2k`.
+    Instruction,
+    /// A label, sublabel, or constant definition, e.g. `@main` or `&loop`.
+    LabelDefinition,
+    /// An address of a label or a sublabel, e.g. `;main` or `,&loop`.
+    LabelReference,
+    /// A macro definition, e.g. `%MACRO`.
+    MacroDefinition,
+    /// A macro invocation, i.e. a word without a rune that is not an instruction.
+    MacroInvocation,
+    /// A hexadecimal number, a character, or a word, either literal or raw.
+    Literal,
+    /// An absolute or relative pad, e.g. `|0100` or `$2`.
+    Pad,
+    /// An include, a directive, or a query, e.g. `~file.tal`, `?ifdef`, or `$here`.
+    Directive,
+    /// A bracket or a brace.
+    Delimiter,
+    /// A word that is not a valid token, or a parenthesis without a match.
+    Invalid,
+}
+
+/// A token of the source code along with its class.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClassifiedToken {
+    /// Span of the whole token, including the rune.
+    pub span: Range<usize>,
+    pub class: TokenClass,
+}
+
+/// Splits the source code into tokens and classifies them, in the order of their spans.
+pub(crate) fn classify(source: &[u8]) -> Vec<ClassifiedToken> {
+    let options = Options::default();
+    let mut scanner = Scanner::new(
+        source,
+        0,
+        options.token_length,
+        options.opcode_table.instruction_set(),
+    );
+    let mut tokens = Vec::new();
+
+    while let Some(item) = scanner.next() {
+        let class = match item {
+            Ok((Word::Fine { token, .. }, _)) => class(&token.node),
+            Ok((Word::Faulty { .. }, _)) => TokenClass::Invalid,
+            // The unclosed comment is already among the comments.
+            Err(Error::NoMatchingClosingParenthesis { .. }) => continue,
+            Err(_) => TokenClass::Invalid,
+        };
+        tokens.push(ClassifiedToken {
+            span: scanner.word_span().into(),
+            class,
+        });
+    }
+    tokens.extend(scanner.comments.iter().map(|span| ClassifiedToken {
+        span: (*span).into(),
+        class: TokenClass::Comment,
+    }));
+    tokens.sort_by_key(|token| token.span.start);
+
+    tokens
+}
+
+fn class(token: &Token) -> TokenClass {
+    match token {
+        Token::OpeningBracket
+        | Token::ClosingBracket
+        | Token::OpeningBrace
+        | Token::ClosingBrace => TokenClass::Delimiter,
+        Token::Instruction(_) => TokenClass::Instruction,
+        Token::MacroDefine(_) => TokenClass::MacroDefinition,
+        Token::MacroInvoke(_) => TokenClass::MacroInvocation,
+        Token::PadAbsolute(_)
+        | Token::PadRelative(_)
+        | Token::PadAbsoluteExpression
+        | Token::PadRelativeExpression => TokenClass::Pad,
+        Token::LabelDefine(_) | Token::ConstantDefine(_) | Token::SublabelDefine(_) => {
+            TokenClass::LabelDefinition
+        }
+        Token::LiteralZeroPageAddress(_)
+        | Token::LiteralRelativeAddress(_)
+        | Token::LiteralAbsoluteAddress(_)
+        | Token::RawAddress(_) => TokenClass::LabelReference,
+        Token::LiteralHexByte(_)
+        | Token::LiteralHexShort(_)
+        | Token::RawHexByte(_)
+        | Token::RawHexShort(_)
+        | Token::RawChar(_)
+        | Token::RawWord(_) => TokenClass::Literal,
+        Token::Include(_)
+        | Token::IfDefined
+        | Token::IfNotDefined
+        | Token::Else
+        | Token::End
+        | Token::Repeat
+        | Token::ZeroPage
+        | Token::Library
+        | Token::Checksum
+        | Token::Query(_) => TokenClass::Directive,
+    }
+}
@@ -0,0 +1,61 @@
+use crate::Warning;
+use alloc::{borrow::ToOwned, string::String, vec::Vec};
+use core::ops::Range;
+
+const DIRECTIVE_PREFIX: &[u8] = b"ruxnasm:";
+
+/// Range of the source code in which the warnings with the given names are not reported.
+#[derive(Debug, Clone)]
+pub(crate) struct Suppression {
+    pub(crate) names: Vec<String>,
+    pub(crate) range: Range<usize>,
+}
+
+/// A directive written in a comment.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Directive {
+    /// `ruxnasm: allow NAME...`, suppressing the named warnings.
+    Allow(Vec<String>),
+    /// `ruxnasm: utf8`, turning on the UTF-8 mode for the rest of the file.
+    Utf8,
+}
+
+/// Parses the contents of a comment as a directive of the form `ruxnasm: allow NAME...` or
+/// `ruxnasm: utf8`.
+///
+/// Returns `None` if the comment is not meant to be a directive at all, and `Some(Err(()))` if
+/// it is, but it's malformed or it mentions an unknown warning.
+pub(crate) fn parse(comment: &[u8]) -> Option<Result<Directive, ()>> {
+    let comment = String::from_utf8_lossy(comment);
+    let comment = comment.trim();
+    if !comment.as_bytes().starts_with(DIRECTIVE_PREFIX) {
+        return None;
+    }
+
+    let mut words = comment[DIRECTIVE_PREFIX.len()..].split_whitespace();
+    match words.next() {
+        Some("allow") => (),
+        Some("utf8") if words.next().is_none() => return Some(Ok(Directive::Utf8)),
+        _ => return Some(Err(())),
+    }
+    let names: Vec<String> = words.map(str::to_owned).collect();
+    if names.is_empty()
+        || names
+            .iter()
+            .any(|name| !Warning::NAMES.contains(&name.as_str()))
+    {
+        return Some(Err(()));
+    }
+
+    Some(Ok(Directive::Allow(names)))
+}
+
+/// Removes the warnings that are suppressed by any of the directives.
+pub(crate) fn suppress(warnings: &mut Vec<Warning>, suppressions: &[Suppression]) {
+    warnings.retain(|warning| {
+        !suppressions.iter().any(|suppression| {
+            suppression.range.contains(&warning.span().start)
+                && suppression.names.iter().any(|name| name == warning.name())
+        })
+    });
+}
@@ -0,0 +1,56 @@
+use crate::instruction::{Instruction, LIT, LIT2};
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+
+/// Address at which the binaries are loaded.
+const START: u16 = 0x0100;
+
+/// A single instruction decoded from a binary.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DisassembledInstruction {
+    /// Address of the instruction.
+    pub address: u16,
+    /// Bytes of the instruction, i.e. the opcode followed by the operand of a literal.
+    pub bytes: Vec<u8>,
+    /// The instruction written in Uxntal, e.g. `ADD2k`, `LITk 2a`, or `LIT2 0100`.
+    pub text: String,
+}
+
+/// Decodes every byte of the binary loaded at address 0x0100 as an instruction, except for the
+/// bytes following the `LIT` and `LIT2` opcodes, which are their operands.
+pub(crate) fn disassemble(binary: &[u8]) -> Vec<DisassembledInstruction> {
+    let mut instructions = Vec::new();
+    let mut offset = 0;
+
+    while offset < binary.len() {
+        let opcode = binary[offset];
+        let operand_length = match opcode {
+            LIT => 1,
+            LIT2 => 2,
+            _ => 0,
+        };
+        // A literal cut off by the end of the binary is decoded as a bare instruction.
+        let operand = binary
+            .get(offset + 1..offset + 1 + operand_length)
+            .unwrap_or_default();
+        // `LIT` alone is an alias of `BRK`, so the literal byte is written in keep mode to be
+        // assembled back into the same opcode.
+        let text = match (opcode, operand) {
+            (LIT, [byte]) => format!("LITk {:02x}", byte),
+            (LIT2, [high, low]) => format!("LIT2 {:02x}{:02x}", high, low),
+            _ => Instruction::from_opcode(opcode).to_string(),
+        };
+        let length = 1 + operand.len();
+        instructions.push(DisassembledInstruction {
+            address: START.wrapping_add(offset as u16),
+            bytes: binary[offset..offset + length].to_vec(),
+            text,
+        });
+        offset += length;
+    }
+
+    instructions
+}
@@ -1,34 +1,80 @@
-use std::collections::HashSet;
+use alloc::{
+    borrow::ToOwned,
+    collections::{BTreeMap, BTreeSet},
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+use core::ops::Range;
 
 use crate::{
+    chunk::Chunk,
+    layout,
+    reference::{self, Reference},
     span::{Span, Spanned},
     token::{ScopedIdentifier, Statement},
+    trace::{self, TraceStep},
     walker::Definitions,
-    Error, Warning,
+    Error, InstructionKind, InstructionSet, Options, Recording, Suggestion, Warning,
 };
 
-const LIT: u8 = 0x80;
-const LIT2: u8 = 0x20;
+/// Receiver of the binary, piece by piece as it's emitted.
+pub(crate) type Output<'a> = &'a mut dyn FnMut(&[u8]);
 
 struct Binary {
-    data: [u8; 256 * 256 - 256],
+    /// Memory after the zeroth page, on the heap so that it isn't copied around and so that it
+    /// can become the binary without another copy.
+    data: Vec<u8>,
     pointer: u16,
-    length: u16,
+    /// The address right after the last written byte, which can be right past the last
+    /// address.
+    length: usize,
+    /// Whether the pointer has gone right past the last address, which it can't hold, in which
+    /// case it's left at the last address.
+    at_end: bool,
+    /// Whether a byte has been pushed, or the pointer has been moved, past the end of the
+    /// memory. Nothing is written past the end.
+    overflowed: bool,
+    /// Disjoint ranges of the written addresses, keyed by their starts, merged whenever they
+    /// touch each other.
+    written: BTreeMap<usize, usize>,
+    /// Addresses written by each of the statements, to find the statement that has written the
+    /// bytes that are overwritten.
+    writes: Vec<(Range<usize>, Span)>,
+    /// The address up to which the bytes have been passed to the output.
+    flushed: usize,
 }
 
 impl Binary {
     pub fn new() -> Self {
         Self {
-            data: [0; 256 * 256 - 256],
+            data: vec![0; 256 * 256 - 256],
             pointer: 256,
             length: 256,
+            at_end: false,
+            overflowed: false,
+            written: BTreeMap::new(),
+            writes: Vec::new(),
+            flushed: 256,
         }
     }
 
     pub fn push_byte(&mut self, byte: u8) {
-        self.data[self.pointer as usize - 256] = byte;
+        // Bytes in the zeroth page are only emitted if they are allowed, and they are never a
+        // part of the binary.
+        if self.pointer < 256 || self.at_end || self.overflowed {
+            self.increment_pointer(1);
+            return;
+        }
+        match self.data.get_mut(self.pointer as usize - 256) {
+            Some(slot) => *slot = byte,
+            None => {
+                self.overflowed = true;
+                return;
+            }
+        }
+        self.length = self.length.max(self.pointer as usize + 1);
         self.increment_pointer(1);
-        self.length = self.pointer;
     }
 
     pub fn push_short(&mut self, short: u16) {
@@ -38,50 +84,185 @@ impl Binary {
 
     pub fn set_pointer(&mut self, to: u16) {
         self.pointer = to;
+        self.at_end = false;
     }
 
     pub fn increment_pointer(&mut self, by: u16) {
-        self.pointer += by;
+        if self.at_end {
+            self.overflowed |= by > 0;
+            return;
+        }
+        match self.pointer.checked_add(by) {
+            Some(pointer) => self.pointer = pointer,
+            None if self.pointer as u32 + by as u32 == 256 * 256 => self.at_end = true,
+            None => self.overflowed = true,
+        }
+    }
+
+    /// Extends the binary with zeros up to the pointer, as if the pads have written them.
+    pub fn extend_to_pointer(&mut self) {
+        self.length = self.length.max(self.get_end());
+    }
+
+    /// Returns the size of the binary, which starts right after the zeroth page.
+    pub fn size(&self) -> usize {
+        self.length - 256
+    }
+
+    /// Whether anything has gone past the end of the memory so far.
+    pub fn has_overflowed(&self) -> bool {
+        self.overflowed
     }
 
     pub fn get_pointer(&self) -> u16 {
         self.pointer
     }
+
+    /// Returns the address the pointer is at, which is right past the last address once the
+    /// pointer has reached the end of the memory.
+    pub fn get_end(&self) -> usize {
+        if self.at_end {
+            256 * 256
+        } else {
+            self.pointer as usize
+        }
+    }
+
+    /// Marks the addresses `from..to` as written by the statement with the given span. If some of
+    /// them have already been written by another statement, returns the first such address
+    /// along with the span of that statement.
+    pub fn mark_written(&mut self, from: usize, to: usize, span: Span) -> Option<(usize, Span)> {
+        let overwritten = match self.written.range(..to).next_back() {
+            Some((_, end)) if *end > from => self
+                .writes
+                .iter()
+                .rev()
+                .filter(|(range, _)| range.start < to && range.end > from)
+                .map(|(range, other_span)| (range.start.max(from), *other_span))
+                .min_by_key(|(address, _)| *address),
+            _ => None,
+        };
+
+        let mut start = from;
+        let mut end = to;
+        while let Some((&other_start, &other_end)) = self.written.range(..=end).next_back() {
+            if other_end < start {
+                break;
+            }
+            self.written.remove(&other_start);
+            start = start.min(other_start);
+            end = end.max(other_end);
+        }
+        self.written.insert(start, end);
+        self.writes.push((from..to, span));
+
+        overwritten
+    }
+
+    /// Passes the bytes written since the last flush to the output, along with the zeros of the
+    /// pads before them. The flushed bytes must not be written again.
+    pub fn flush(&mut self, output: &mut dyn FnMut(&[u8])) {
+        if self.length > self.flushed {
+            output(&self.data[self.flushed - 256..self.length - 256]);
+            self.flushed = self.length;
+        }
+    }
+
+    pub fn get_bytes(&self, from: u16, to: usize) -> &[u8] {
+        if from < 256 || to < from as usize {
+            return &[];
+        }
+        &self.data[from as usize - 256..to - 256]
+    }
 }
 
 impl From<Binary> for Vec<u8> {
     fn from(binary: Binary) -> Self {
-        binary.data[0..binary.length as usize - 256].into()
+        let mut data = binary.data;
+        data.truncate(binary.length - 256);
+        data
     }
 }
 
 pub(crate) fn emit(
     statements: Vec<Spanned<Statement>>,
     definitions: Definitions,
+    expansions: &[(Range<usize>, Vec<Span>)],
+    options: &Options,
+    instruction_set: &dyn InstructionSet,
+    recording: &mut Recording,
 ) -> Result<(Vec<u8>, Vec<Warning>), (Vec<Error>, Vec<Warning>)> {
     let mut errors: Vec<Error> = Vec::new();
     let mut warnings: Vec<Warning> = Vec::new();
 
-    let mut unused_labels: HashSet<&ScopedIdentifier> = definitions.labels.keys().collect();
+    // Constants are not a part of the binary, so they are left out of the warnings about labels.
+    let mut unused_labels: BTreeSet<&ScopedIdentifier> = definitions
+        .labels
+        .keys()
+        .filter(|scoped_identifier| {
+            !definitions.constants.contains(scoped_identifier)
+                && !definitions.expression_labels.contains(scoped_identifier)
+        })
+        .collect();
 
+    let (lit, lit2) = instruction_set.literal_opcodes();
     let mut binary = Binary::new();
+    let mut far_references: Vec<(ScopedIdentifier, u16)> = Vec::new();
+
+    let mut statements = statements.into_iter().peekable();
+    let mut statement_index = 0;
+    // Whether bytes have been written to the reserved memory since the last absolute pad.
+    let mut reserved_memory_written = false;
+
+    while let Some(statement) = statements.next() {
+        let index = statement_index;
+        statement_index += 1;
+        let address = binary.get_pointer();
+        let error_count = errors.len();
+        let overflowed = binary.has_overflowed();
+        let size = binary.size();
+        let is_pad = matches!(
+            statement.node,
+            Statement::PadAbsolute(_) | Statement::PadRelative(_)
+        );
+        let span = statement.span;
+        let explanation = recording
+            .trace
+            .as_ref()
+            .map(|_| trace::explain(&statement.node, &definitions, address));
+
+        if let Some(scoped_identifier) = referenced_label(&statement.node) {
+            if let Some(references) = recording.references.as_mut() {
+                references.push(Reference {
+                    name: scoped_identifier.to_string(),
+                    rune: reference::rune(&statement.node),
+                    address,
+                    span: span.into(),
+                });
+            }
+            if definitions.unpadded_labels.contains(scoped_identifier) {
+                let (label_address, other_span) = definitions.labels[scoped_identifier];
+                warnings.push(Warning::UnpaddedLabelReferenced {
+                    name: scoped_identifier.to_string(),
+                    address: label_address,
+                    span: span.into(),
+                    other_span: other_span.into(),
+                });
+            }
+        }
 
-    for statement in statements {
         match statement {
             Spanned {
                 node: Statement::Instruction(instruction),
                 ..
             } => {
-                let opcode = instruction.instruction_kind as u8
-                    | ((instruction.short as u8) << 5)
-                    | ((instruction.r#return as u8) << 6)
-                    | ((instruction.keep as u8) << 7);
-                binary.push_byte(opcode);
+                binary.push_byte(instruction.opcode());
             }
             Spanned {
                 node: Statement::PadAbsolute(value),
                 ..
             } => {
+                reserved_memory_written = false;
                 binary.set_pointer(value as u16);
             }
             Spanned {
@@ -94,16 +275,37 @@ pub(crate) fn emit(
                 node: Statement::LiteralZeroPageAddress(scoped_identifier),
                 span,
             } => match find_address(&scoped_identifier, &definitions, &span) {
-                Ok((address, _)) => {
+                Ok((address, other_span)) => {
                     unused_labels.remove(&scoped_identifier);
                     if address <= 0xff {
-                        binary.push_byte(LIT);
+                        if let Some(warning) = port_width_mismatch(
+                            &scoped_identifier,
+                            &definitions,
+                            span,
+                            statements.peek(),
+                        ) {
+                            warnings.push(warning);
+                        }
+                        binary.push_byte(lit);
                         binary.push_byte((address & 0xff) as u8);
+                    } else if definitions.constants.contains(&scoped_identifier) {
+                        errors.push(Error::ConstantTooLarge {
+                            name: scoped_identifier.to_string(),
+                            value: address,
+                            span: span.into(),
+                            other_span: other_span.into(),
+                        });
+                        binary.increment_pointer(2);
                     } else {
                         errors.push(Error::AddressNotZeroPage {
                             address,
                             identifier: scoped_identifier.to_string(),
                             span: span.into(),
+                            suggestions: absolute_address_suggestions(
+                                span,
+                                false,
+                                statements.peek(),
+                            ),
                         });
                         binary.increment_pointer(2);
                     }
@@ -126,10 +328,32 @@ pub(crate) fn emit(
                             identifier: scoped_identifier.to_string(),
                             span: span.into(),
                             other_span: other_span.into(),
+                            regions: label_regions(
+                                &definitions,
+                                binary.get_pointer().saturating_add(3).min(address),
+                                binary.get_pointer().saturating_add(3).max(address),
+                            ),
+                            suggestions: absolute_address_suggestions(
+                                span,
+                                true,
+                                statements.peek(),
+                            ),
                         });
                         binary.increment_pointer(2);
                     } else {
-                        binary.push_byte(LIT);
+                        if let Some(margin) = options.relative_address_margin {
+                            let distance = offset.unsigned_abs();
+                            if distance + margin > 126 {
+                                warnings.push(Warning::AddressNearLimit {
+                                    distance,
+                                    margin,
+                                    identifier: scoped_identifier.to_string(),
+                                    span: span.into(),
+                                    other_span: other_span.into(),
+                                });
+                            }
+                        }
+                        binary.push_byte(lit);
                         binary.push_byte(offset as u8);
                     }
                 }
@@ -144,7 +368,12 @@ pub(crate) fn emit(
             } => match find_address(&scoped_identifier, &definitions, &span) {
                 Ok((address, _)) => {
                     unused_labels.remove(&scoped_identifier);
-                    binary.push_byte(LIT2);
+                    if recording.layout_hints.is_some()
+                        && is_far_jump(address, binary.get_pointer(), statements.peek())
+                    {
+                        far_references.push((scoped_identifier.clone(), binary.get_pointer()));
+                    }
+                    binary.push_byte(lit2);
                     binary.push_short(address);
                 }
                 Err(err) => {
@@ -169,14 +398,14 @@ pub(crate) fn emit(
                 node: Statement::LiteralHexByte(value),
                 ..
             } => {
-                binary.push_byte(LIT);
+                binary.push_byte(lit);
                 binary.push_byte(value);
             }
             Spanned {
                 node: Statement::LiteralHexShort(value),
                 ..
             } => {
-                binary.push_byte(LIT2);
+                binary.push_byte(lit2);
                 binary.push_short(value);
             }
             Spanned {
@@ -205,27 +434,234 @@ pub(crate) fn emit(
                     binary.push_byte(byte);
                 }
             }
+            Spanned {
+                node: Statement::Checksum(from, to),
+                span,
+            } => match (
+                find_address(&from, &definitions, &span),
+                find_address(&to, &definitions, &span),
+            ) {
+                (Ok((from_address, _)), Ok((to_address, _))) => {
+                    unused_labels.remove(&from);
+                    unused_labels.remove(&to);
+                    // Only the bytes emitted so far are known.
+                    if from_address < 0x100 || from_address > to_address || to_address > address {
+                        errors.push(Error::ChecksumRangeInvalid {
+                            from: from_address,
+                            to: to_address,
+                            address,
+                            span: span.into(),
+                        });
+                        binary.increment_pointer(2);
+                    } else {
+                        let checksum = binary
+                            .get_bytes(from_address, to_address as usize)
+                            .iter()
+                            .fold(0u16, |sum, byte| sum.wrapping_add(*byte as u16));
+                        binary.push_short(checksum);
+                    }
+                }
+                (from_result, to_result) => {
+                    errors.extend(from_result.err());
+                    errors.extend(to_result.err());
+                    binary.increment_pointer(2);
+                }
+            },
+        }
+
+        if !options.trim {
+            binary.extend_to_pointer();
+        }
+        // The walker reports the programs that are too long, but the statements can also come
+        // from elsewhere, so only the first statement that goes past the end is reported.
+        if binary.has_overflowed() && !overflowed {
+            errors.push(Error::ProgramTooLong { span: span.into() });
+        }
+        match options.size {
+            Some(maximum_size) if size <= maximum_size && binary.size() > maximum_size => {
+                errors.push(Error::BinaryTooLarge {
+                    size: maximum_size,
+                    span: span.into(),
+                });
+            }
+            _ => (),
+        }
+        let length = if is_pad || errors.len() > error_count {
+            0
+        } else {
+            binary.get_bytes(address, binary.get_end()).len()
+        };
+        if length > 0 {
+            let from = address as usize;
+            if let Some((overwritten_address, other_span)) =
+                binary.mark_written(from, from + length, span)
+            {
+                let overwritten_address = overwritten_address as u16;
+                // Bytes can only be overwritten on purpose after padding backwards.
+                if options.backward_padding {
+                    warnings.push(Warning::BytesOverwritten {
+                        address: overwritten_address,
+                        span: span.into(),
+                        other_span: other_span.into(),
+                    });
+                } else {
+                    errors.push(Error::BytesOverwritten {
+                        address: overwritten_address,
+                        span: span.into(),
+                        other_span: other_span.into(),
+                    });
+                }
+            }
+            if let Some(reserved_memory) = options.reserved_memory {
+                let start = reserved_memory.start as usize;
+                if from + length > start && !reserved_memory_written {
+                    reserved_memory_written = true;
+                    warnings.push(Warning::BytesInReservedMemory {
+                        address: from.max(start) as u16,
+                        limit: reserved_memory.start,
+                        profile: reserved_memory.profile.to_owned(),
+                        span: span.into(),
+                    });
+                }
+            }
+        }
+        let bytes = binary.get_bytes(address, address as usize + length);
+        if let (Some(trace), Some(explanation)) = (recording.trace.as_mut(), explanation) {
+            trace.push(TraceStep {
+                address,
+                bytes: bytes.to_vec(),
+                explanation,
+                span: span.into(),
+            });
+        }
+        if let Some(chunks) = recording.chunks.as_mut() {
+            if !bytes.is_empty() {
+                chunks.push(Chunk {
+                    address,
+                    bytes: bytes.to_vec(),
+                    span: span.into(),
+                    invocations: invocations(expansions, index),
+                });
+            }
+        }
+        // Bytes written before can only change after padding backwards, so the binary is
+        // flushed only at the end then.
+        if let Some(output) = recording.output.as_mut() {
+            if errors.is_empty() && !options.backward_padding {
+                binary.flush(*output);
+            }
         }
     }
 
-    for unused_label_name in unused_labels
+    // The labels are reported in the order of their definitions rather than of their names.
+    let mut unused_labels: Vec<&ScopedIdentifier> = unused_labels
         .into_iter()
         .filter(|scoped_identifier| !scoped_identifier.is_captital())
-    {
-        let (_, span) = definitions.labels[&unused_label_name];
-        warnings.push(Warning::LabelUnused {
-            name: unused_label_name.to_string(),
-            span: span.into(),
-        });
+        .collect();
+    unused_labels
+        .sort_by_key(|scoped_identifier| definitions.labels[*scoped_identifier].1.from.offset);
+
+    for unused_label_name in unused_labels {
+        let (address, span) = definitions.labels[&unused_label_name];
+        match unused_label_name {
+            ScopedIdentifier::Label(_) => warnings.push(Warning::LabelUnused {
+                name: unused_label_name.to_string(),
+                span: span.into(),
+            }),
+            ScopedIdentifier::Sublabel(label_name, sublabel_name) => {
+                warnings.push(Warning::SublabelUnused {
+                    label_name: String::from_utf8_lossy(label_name).into_owned(),
+                    name: String::from_utf8_lossy(sublabel_name).into_owned(),
+                    zero_page_address: if address <= 0xff {
+                        Some(address as u8)
+                    } else {
+                        None
+                    },
+                    span: span.into(),
+                })
+            }
+        }
+    }
+
+    warnings.extend(aliased_labels(&definitions));
+
+    if let Some((profile, hints)) = recording.layout_hints.as_mut() {
+        hints.extend(layout::hints(&far_references, &definitions, profile));
     }
 
     if errors.is_empty() {
-        Ok((binary.into(), warnings))
+        // The binary is padded with zeros up to the requested size.
+        let padding = options
+            .size
+            .map_or(0, |size| size.saturating_sub(binary.size()));
+        // The binary passed to the output is not kept.
+        if let Some(output) = recording.output.as_mut() {
+            binary.flush(*output);
+            output(&vec![0; padding]);
+            return Ok((Vec::new(), warnings));
+        }
+        let mut binary: Vec<u8> = binary.into();
+        binary.resize(binary.len() + padding, 0);
+        Ok((binary, warnings))
     } else {
         Err((errors, warnings))
     }
 }
 
+/// Returns the spans of the macro invocations the statement with the given index has been
+/// expanded through, starting with the outermost invocation.
+fn invocations(expansions: &[(Range<usize>, Vec<Span>)], index: usize) -> Vec<Range<usize>> {
+    // The expansions are recorded in the order of the statements.
+    let position = expansions.partition_point(|(range, _)| range.end <= index);
+    match expansions.get(position) {
+        Some((range, invocations)) if range.contains(&index) => {
+            invocations.iter().map(|span| (*span).into()).collect()
+        }
+        _ => Vec::new(),
+    }
+}
+
+fn referenced_label(statement: &Statement) -> Option<&ScopedIdentifier> {
+    match statement {
+        Statement::LiteralZeroPageAddress(scoped_identifier)
+        | Statement::LiteralRelativeAddress(scoped_identifier)
+        | Statement::LiteralAbsoluteAddress(scoped_identifier)
+        | Statement::RawAddress(scoped_identifier) => Some(scoped_identifier),
+        _ => None,
+    }
+}
+
+/// Checks whether the port referenced with a literal zero-page address, given that it's directly
+/// followed by a `DEO` instruction, has a declared width of a byte or a short which differs from
+/// the size of the written value.
+fn port_width_mismatch(
+    scoped_identifier: &ScopedIdentifier,
+    definitions: &Definitions,
+    span: Span,
+    next_statement: Option<&Spanned<Statement>>,
+) -> Option<Warning> {
+    let instruction = match next_statement {
+        Some(Spanned {
+            node: Statement::Instruction(instruction),
+            ..
+        }) if matches!(instruction.instruction_kind, InstructionKind::DeviceOut) => instruction,
+        _ => return None,
+    };
+    let width = *definitions.port_widths.get(scoped_identifier)?;
+    let written = if instruction.short { 2 } else { 1 };
+    if !(1..=2).contains(&width) || width == written {
+        return None;
+    }
+
+    Some(Warning::PortWidthMismatch {
+        name: scoped_identifier.to_string(),
+        width,
+        written,
+        span: span.into(),
+        other_span: definitions.labels[scoped_identifier].1.into(),
+    })
+}
+
 fn find_address(
     scoped_identifier: &ScopedIdentifier,
     definitions: &Definitions,
@@ -243,3 +679,155 @@ fn find_address(
         }
     }
 }
+
+/// Checks whether a literal absolute address at the pointer is too far from the address to be
+/// relative, and is directly followed by a jump that would take a relative address instead.
+fn is_far_jump(address: u16, pointer: u16, next_statement: Option<&Spanned<Statement>>) -> bool {
+    let offset = address as isize - pointer as isize - 3;
+    let is_jump = match next_statement {
+        Some(Spanned {
+            node: Statement::Instruction(instruction),
+            ..
+        }) => {
+            instruction.short
+                && !instruction.keep
+                && !instruction.r#return
+                && matches!(
+                    instruction.instruction_kind,
+                    InstructionKind::Jump
+                        | InstructionKind::JumpCondition
+                        | InstructionKind::JumpStash
+                )
+        }
+        _ => false,
+    };
+
+    !(-126..=126).contains(&offset) && is_jump
+}
+
+/// Suggests replacing a literal zero-page or relative address with a literal absolute address,
+/// given that it's directly followed by an instruction that consumes the address and that can be
+/// replaced with its absolute-addressing counterpart.
+fn absolute_address_suggestions(
+    address_span: Span,
+    relative: bool,
+    next_statement: Option<&Spanned<Statement>>,
+) -> Vec<Suggestion> {
+    let (instruction, instruction_span) = match next_statement {
+        Some(Spanned {
+            node: Statement::Instruction(instruction),
+            span,
+        }) => (instruction, span),
+        _ => return Vec::new(),
+    };
+    // The instruction must follow the address in the source code, otherwise it most likely
+    // comes from a macro and replacing it would affect the other invocations of the macro.
+    if instruction_span.from.offset < address_span.to.offset
+        || instruction.keep
+        || instruction.r#return
+    {
+        return Vec::new();
+    }
+
+    let replacement = match (instruction.instruction_kind, relative) {
+        (InstructionKind::LoadZeroPage, false) | (InstructionKind::LoadRelative, true) => "LDA",
+        (InstructionKind::StoreZeroPage, false) | (InstructionKind::StoreRelative, true) => "STA",
+        (InstructionKind::Jump, true) if !instruction.short => "JMP2",
+        (InstructionKind::JumpCondition, true) if !instruction.short => "JCN2",
+        (InstructionKind::JumpStash, true) if !instruction.short => "JSR2",
+        _ => return Vec::new(),
+    };
+
+    vec![
+        Suggestion {
+            span: address_span.from.offset..address_span.from.offset + 1,
+            replacement: ";".to_owned(),
+        },
+        Suggestion {
+            span: instruction_span.from.offset..instruction_span.from.offset + 3,
+            replacement: replacement.to_owned(),
+        },
+    ]
+}
+
+/// Finds the labels defined at the same address as a previously defined label.
+fn aliased_labels(definitions: &Definitions) -> Vec<Warning> {
+    let mut labels: Vec<(u16, Span, &ScopedIdentifier)> = definitions
+        .labels
+        .iter()
+        .filter(|(scoped_identifier, _)| {
+            matches!(scoped_identifier, ScopedIdentifier::Label(_))
+                && !definitions.constants.contains(scoped_identifier)
+        })
+        .map(|(scoped_identifier, (address, span))| (*address, *span, scoped_identifier))
+        .collect();
+    labels.sort_by_key(|(address, span, _)| (*address, span.from.offset));
+
+    let mut warnings: Vec<Warning> = Vec::new();
+    let mut first_label: Option<&(u16, Span, &ScopedIdentifier)> = None;
+
+    for label in &labels {
+        match first_label {
+            Some((address, other_span, other_scoped_identifier)) if *address == label.0 => {
+                warnings.push(Warning::LabelAliased {
+                    name: label.2.to_string(),
+                    other_name: other_scoped_identifier.to_string(),
+                    address: *address,
+                    span: label.1.into(),
+                    other_span: (*other_span).into(),
+                });
+            }
+            _ => first_label = Some(label),
+        }
+    }
+
+    warnings
+}
+
+/// Splits the address range `from..to` into the regions of the labels that own them. A region
+/// of a label starts at the address of its definition and lasts until the next label definition.
+fn label_regions(
+    definitions: &Definitions,
+    from: u16,
+    to: u16,
+) -> Vec<(String, usize, Range<usize>)> {
+    let mut labels: Vec<(u16, Span, &ScopedIdentifier)> = definitions
+        .labels
+        .iter()
+        .filter(|(scoped_identifier, _)| {
+            matches!(scoped_identifier, ScopedIdentifier::Label(_))
+                && !definitions.constants.contains(scoped_identifier)
+        })
+        .map(|(scoped_identifier, (address, span))| (*address, *span, scoped_identifier))
+        .collect();
+    labels.sort_by_key(|(address, span, _)| (*address, span.from.offset));
+
+    let mut regions: Vec<(String, usize, Range<usize>)> = Vec::new();
+    let mut owner = labels.iter().rev().find(|(address, _, _)| *address <= from);
+    let mut region_start = from;
+
+    for label in labels
+        .iter()
+        .filter(|(address, _, _)| *address > from && *address < to)
+    {
+        if let Some((_, span, scoped_identifier)) = owner {
+            regions.push((
+                scoped_identifier.to_string(),
+                (label.0 - region_start) as usize,
+                (*span).into(),
+            ));
+        }
+        owner = Some(label);
+        region_start = label.0;
+    }
+    if let Some((_, span, scoped_identifier)) = owner {
+        regions.push((
+            scoped_identifier.to_string(),
+            (to - region_start) as usize,
+            (*span).into(),
+        ));
+    }
+
+    regions.retain(|(_, bytes, _)| *bytes > 0);
+    regions
+}
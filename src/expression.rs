@@ -0,0 +1,67 @@
+use crate::{Error, Span, Spanned};
+use alloc::vec::Vec;
+
+/// An element of an expression written in reverse Polish notation.
+pub(crate) enum Item {
+    Value(u16),
+    Operator(Operator),
+}
+
+#[derive(Debug, Copy, Clone)]
+pub(crate) enum Operator {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+}
+
+impl Operator {
+    pub fn from_name(name: &[u8]) -> Option<Self> {
+        match name {
+            b"+" => Some(Self::Add),
+            b"-" => Some(Self::Subtract),
+            b"*" => Some(Self::Multiply),
+            b"/" => Some(Self::Divide),
+            _ => None,
+        }
+    }
+
+    fn apply(self, lhs: u16, rhs: u16, span: Span) -> Result<u16, Error> {
+        let result = match self {
+            Self::Add => lhs.checked_add(rhs),
+            Self::Subtract => lhs.checked_sub(rhs),
+            Self::Multiply => lhs.checked_mul(rhs),
+            Self::Divide if rhs == 0 => return Err(Error::DivisionByZero { span: span.into() }),
+            Self::Divide => lhs.checked_div(rhs),
+        };
+        result.ok_or(Error::ExpressionOverflow { span: span.into() })
+    }
+}
+
+/// Evaluates the expression, which must leave exactly one value on the stack. The span is the
+/// span of the whole expression.
+pub(crate) fn evaluate(items: &[Spanned<Item>], span: Span) -> Result<u16, Error> {
+    let mut stack: Vec<u16> = Vec::new();
+
+    for item in items {
+        match item.node {
+            Item::Value(value) => stack.push(value),
+            Item::Operator(operator) => match (stack.pop(), stack.pop()) {
+                (Some(rhs), Some(lhs)) => stack.push(operator.apply(lhs, rhs, item.span)?),
+                _ => {
+                    return Err(Error::OperandsExpected {
+                        span: item.span.into(),
+                    })
+                }
+            },
+        }
+    }
+
+    match stack.as_slice() {
+        [value] => Ok(*value),
+        _ => Err(Error::ExpressionMalformed {
+            values: stack.len(),
+            span: span.into(),
+        }),
+    }
+}
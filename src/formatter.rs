@@ -0,0 +1,201 @@
+use crate::Error;
+use alloc::{string::String, vec::Vec};
+
+const WHITESPACES: [u8; 6] = [b' ', b'\t', b'\n', 0x0b, 0x0c, b'\r'];
+const DELIMITERS: [u8; 6] = [b'(', b')', b'[', b']', b'{', b'}'];
+/// Width of a tab in the indentation of the formatted source code.
+const TAB_WIDTH: usize = 4;
+
+/// A line of the source code, with the tokens and comments kept exactly as they have been
+/// written. Comments and backtick strings spanning multiple lines count as a single token of
+/// the line they start in.
+#[derive(Default)]
+struct Line<'a> {
+    /// Width of the leading whitespace, in columns.
+    indentation: usize,
+    tokens: Vec<&'a [u8]>,
+}
+
+impl<'a> Line<'a> {
+    /// Whether the line starts with an absolute pad followed by a label, like the definitions
+    /// of devices, which are aligned in columns.
+    fn is_table_row(&self) -> bool {
+        self.indentation == 0
+            && self.tokens.len() >= 2
+            && self.tokens[0].starts_with(b"|")
+            && self.tokens[1].starts_with(b"@")
+    }
+}
+
+/// Formats the source code. The tokens and comments are left untouched, only the whitespace
+/// between them changes:
+///
+/// - tokens are separated by single spaces, with no whitespace at the ends of the lines,
+/// - lines are indented with tabs, rounding the original indentation up to whole tabs,
+/// - consecutive lines starting with an absolute pad and a label are aligned in columns,
+/// - runs of blank lines are collapsed into one, and the source ends with a single newline.
+pub(crate) fn format(source: &[u8]) -> Result<Vec<u8>, Error> {
+    let lines = split_lines(source)?;
+
+    let mut formatted: Vec<u8> = Vec::with_capacity(source.len());
+    let mut blank_line_pending = false;
+    let mut index = 0;
+    while index < lines.len() {
+        if lines[index].tokens.is_empty() {
+            blank_line_pending = true;
+            index += 1;
+            continue;
+        }
+        if blank_line_pending && !formatted.is_empty() {
+            formatted.push(b'\n');
+        }
+        blank_line_pending = false;
+
+        let rows = lines[index..]
+            .iter()
+            .take_while(|line| line.is_table_row())
+            .count();
+        if rows == 0 {
+            write_line(&mut formatted, &lines[index], &[]);
+            index += 1;
+            continue;
+        }
+
+        // The label column is only as wide as the labels followed by something else.
+        let table = &lines[index..index + rows];
+        let widths = [
+            table.iter().map(|line| width(line.tokens[0])).max(),
+            table
+                .iter()
+                .filter(|line| line.tokens.len() > 2)
+                .map(|line| width(line.tokens[1]))
+                .max(),
+        ];
+        let widths: Vec<usize> = widths.iter().map(|width| width.unwrap_or(0)).collect();
+        for line in table {
+            write_line(&mut formatted, line, &widths);
+        }
+        index += rows;
+    }
+
+    Ok(formatted)
+}
+
+fn write_line(formatted: &mut Vec<u8>, line: &Line, widths: &[usize]) {
+    let mut tabs = line.indentation / TAB_WIDTH;
+    if tabs * TAB_WIDTH < line.indentation {
+        tabs += 1;
+    }
+    formatted.resize(formatted.len() + tabs, b'\t');
+    for (column, token) in line.tokens.iter().enumerate() {
+        if column > 0 {
+            formatted.push(b' ');
+        }
+        formatted.extend_from_slice(token);
+        if let Some(column_width) = widths.get(column) {
+            if column + 1 < line.tokens.len() {
+                let padding = column_width.saturating_sub(width(token));
+                formatted.resize(formatted.len() + padding, b' ');
+            }
+        }
+    }
+    formatted.push(b'\n');
+}
+
+/// Number of characters of the token.
+fn width(token: &[u8]) -> usize {
+    String::from_utf8_lossy(token).chars().count()
+}
+
+/// Splits the source code into lines of tokens, the same way the scanner splits it into words.
+fn split_lines(source: &[u8]) -> Result<Vec<Line<'_>>, Error> {
+    let mut lines: Vec<Line> = Vec::new();
+    let mut line = Line::default();
+    let mut position = 0;
+
+    while position < source.len() {
+        let ch = source[position];
+        if ch == b'\n' {
+            lines.push(core::mem::take(&mut line));
+            position += 1;
+            continue;
+        }
+        if WHITESPACES.contains(&ch) {
+            if line.tokens.is_empty() {
+                line.indentation += match ch {
+                    b'\t' => TAB_WIDTH - line.indentation % TAB_WIDTH,
+                    b'\r' => 0,
+                    _ => 1,
+                };
+            }
+            position += 1;
+            continue;
+        }
+
+        let end = match ch {
+            b'(' => comment_end(source, position)?,
+            b')' => {
+                return Err(Error::NoMatchingOpeningParenthesis {
+                    span: position..position + 1,
+                })
+            }
+            b'`' => backtick_string_end(source, position)?,
+            b'"' | b'\'' => find_end(source, position, |ch| WHITESPACES.contains(&ch)),
+            _ => find_end(source, position, |ch| {
+                WHITESPACES.contains(&ch) || DELIMITERS.contains(&ch)
+            }),
+        };
+        line.tokens.push(&source[position..end]);
+        position = end;
+    }
+    lines.push(line);
+
+    Ok(lines)
+}
+
+/// Returns the position right after the token starting at `start`, which ends before the first
+/// byte satisfying `is_end`, not counting the first byte of the token.
+fn find_end(source: &[u8], start: usize, is_end: impl Fn(u8) -> bool) -> usize {
+    source[start + 1..]
+        .iter()
+        .position(|ch| is_end(*ch))
+        .map(|length| start + 1 + length)
+        .unwrap_or_else(|| source.len())
+}
+
+/// Returns the position right after the parenthesis closing the comment starting at `start`.
+fn comment_end(source: &[u8], start: usize) -> Result<usize, Error> {
+    let mut level: usize = 0;
+    for (position, ch) in source.iter().enumerate().skip(start) {
+        match ch {
+            b'(' => level += 1,
+            b')' => {
+                level -= 1;
+                if level == 0 {
+                    return Ok(position + 1);
+                }
+            }
+            _ => (),
+        }
+    }
+
+    Err(Error::NoMatchingClosingParenthesis {
+        span: start..start + 1,
+    })
+}
+
+/// Returns the position right after the backtick closing the string starting at `start`.
+fn backtick_string_end(source: &[u8], start: usize) -> Result<usize, Error> {
+    let mut escaped = false;
+    for (position, ch) in source.iter().enumerate().skip(start + 1) {
+        match ch {
+            b'`' if !escaped => return Ok(position + 1),
+            b'\\' => escaped = !escaped,
+            _ => escaped = false,
+        }
+    }
+
+    Err(Error::NoMatchingClosingBacktick {
+        span: start..start + 1,
+    })
+}
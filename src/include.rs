@@ -0,0 +1,279 @@
+#[cfg(not(feature = "std"))]
+use core::marker::PhantomData;
+#[cfg(feature = "std")]
+use std::{
+    collections::HashMap,
+    fs, io,
+    ops::Range,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    directive::Suppression, scanner::Scanner, tokenizer::Word, Error, InstructionSet, Options,
+    Span, Spanned, Stockpile, Token, UnzipCollect, Warning,
+};
+use alloc::{borrow::ToOwned, string::String, vec, vec::Vec};
+
+/// A file included in the program with the `~` rune.
+///
+/// The spans of the included files don't overlap with each other, nor with the spans of the
+/// program itself &mdash; every span of the file is shifted by its `offset`.
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Source {
+    /// Path of the file, as written in the include.
+    pub path: PathBuf,
+    /// Offset at which the spans of the file start.
+    pub offset: usize,
+    /// Contents of the file.
+    pub contents: Vec<u8>,
+}
+
+#[cfg(feature = "std")]
+impl Source {
+    /// Range of the spans covered by the file.
+    pub fn range(&self) -> Range<usize> {
+        self.offset..self.offset + self.contents.len()
+    }
+}
+
+/// Provider of the contents of the files included in the program, e.g. from the file system or
+/// from memory.
+#[cfg(feature = "std")]
+pub trait SourceProvider {
+    /// Loads the contents of the file with the given path, as written in the include.
+    fn load(&self, path: &Path) -> Result<Vec<u8>, io::Error>;
+
+    /// Returns the canonical form of the path, under which the file is recognized when it's
+    /// included again, e.g. in a circular include. By default, the path is left as it is.
+    fn canonicalize(&self, path: &Path) -> PathBuf {
+        path.to_path_buf()
+    }
+}
+
+/// Provider of the files in the file system, whose relative paths are resolved against the
+/// working directory.
+#[cfg(feature = "std")]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct FileSystem;
+
+#[cfg(feature = "std")]
+impl SourceProvider for FileSystem {
+    fn load(&self, path: &Path) -> Result<Vec<u8>, io::Error> {
+        fs::read(path)
+    }
+
+    fn canonicalize(&self, path: &Path) -> PathBuf {
+        fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+    }
+}
+
+/// Provider of the files held in memory, by their paths.
+#[cfg(feature = "std")]
+impl SourceProvider for HashMap<PathBuf, Vec<u8>> {
+    fn load(&self, path: &Path) -> Result<Vec<u8>, io::Error> {
+        self.get(path)
+            .cloned()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "file not found"))
+    }
+}
+
+/// Scans the program, replacing every include with the words of the included file. Without the
+/// standard library, files can't be read, so every include is reported as an error.
+pub(crate) struct Includer<'a> {
+    /// Offset at which the spans of the next included file start.
+    #[cfg(feature = "std")]
+    next_offset: usize,
+    /// Canonical paths of the files that are currently being included, along with the spans of
+    /// the includes.
+    #[cfg(feature = "std")]
+    chain: Vec<(PathBuf, String, Span)>,
+    /// Maximum length of the include chain.
+    #[cfg(feature = "std")]
+    depth_limit: usize,
+    /// Maximum number of characters of a token.
+    token_length: usize,
+    /// Instruction set whose mnemonics are recognized as instructions.
+    pub(crate) instruction_set: &'a dyn InstructionSet,
+    #[cfg(feature = "std")]
+    provider: &'a dyn SourceProvider,
+    #[cfg(not(feature = "std"))]
+    provider: PhantomData<&'a ()>,
+    #[cfg(feature = "std")]
+    pub(crate) sources: Vec<Source>,
+    pub(crate) suppressions: Vec<Suppression>,
+    pub(crate) warnings: Vec<Warning>,
+}
+
+impl<'a> Includer<'a> {
+    #[cfg(feature = "std")]
+    pub(crate) fn new(
+        source_length: usize,
+        options: &Options,
+        provider: &'a dyn SourceProvider,
+    ) -> Self {
+        Self {
+            next_offset: source_length + 1,
+            chain: Vec::new(),
+            depth_limit: options.limits.include_depth,
+            token_length: options.token_length,
+            instruction_set: options.opcode_table.instruction_set(),
+            provider,
+            sources: Vec::new(),
+            suppressions: Vec::new(),
+            warnings: Vec::new(),
+        }
+    }
+
+    /// Recognizes the instructions of the given instruction set instead of the opcode table of
+    /// the options.
+    #[cfg(feature = "std")]
+    pub(crate) fn with_instruction_set(self, instruction_set: &'a dyn InstructionSet) -> Self {
+        Self {
+            instruction_set,
+            ..self
+        }
+    }
+
+    /// Creates an includer reading the included files from the file system.
+    #[cfg(feature = "std")]
+    pub(crate) fn reading_files(source_length: usize, options: &Options) -> Includer<'static> {
+        Includer::new(source_length, options, &FileSystem)
+    }
+
+    /// Creates an includer reporting every include, as there is no file system to read the
+    /// included files from.
+    #[cfg(not(feature = "std"))]
+    pub(crate) fn reading_files(_source_length: usize, options: &Options) -> Self {
+        Self {
+            token_length: options.token_length,
+            instruction_set: options.opcode_table.instruction_set(),
+            provider: PhantomData,
+            suppressions: Vec::new(),
+            warnings: Vec::new(),
+        }
+    }
+
+    pub(crate) fn scan(&mut self, contents: &[u8], offset: usize) -> Result<Vec<Word>, Vec<Error>> {
+        let words = self.scan_file(contents, offset)?;
+
+        // The included files are expanded with an explicit stack rather than recursively, so
+        // deeply nested includes can't overflow the native stack.
+        let mut expanded_words = Vec::new();
+        let mut files = vec![words.into_iter()];
+        while let Some(file) = files.last_mut() {
+            match file.next() {
+                Some(Word::Fine {
+                    token:
+                        Spanned {
+                            node: Token::Include(path),
+                            span,
+                        },
+                    ..
+                }) => match self.include(&path, span) {
+                    Ok(words) => files.push(words.into_iter()),
+                    Err(words) => expanded_words.extend(words),
+                },
+                Some(word) => expanded_words.push(word),
+                None => {
+                    files.pop();
+                    #[cfg(feature = "std")]
+                    if !files.is_empty() {
+                        self.chain.pop();
+                    }
+                }
+            }
+        }
+
+        Ok(expanded_words)
+    }
+
+    fn scan_file(&mut self, contents: &[u8], offset: usize) -> Result<Vec<Word>, Vec<Error>> {
+        let mut scanner = Scanner::new(contents, offset, self.token_length, self.instruction_set);
+        let words = (&mut scanner).unzip_collect().stockpile(&mut self.warnings);
+        let (suppressions, directive_warnings) = scanner.finish();
+        self.suppressions.extend(suppressions);
+        self.warnings.extend(directive_warnings);
+        words
+    }
+
+    /// Reads and scans the included file. On success, returns the words of the file, which are
+    /// yet to be expanded, and pushes the file onto the include chain. Otherwise, returns the
+    /// words to be put in place of the include.
+    #[cfg(feature = "std")]
+    fn include(&mut self, path: &[u8], span: Span) -> Result<Vec<Word>, Vec<Word>> {
+        let path = String::from_utf8_lossy(path).into_owned();
+        let canonical_path = self.provider.canonicalize(Path::new(&path));
+
+        if let Some(position) = self
+            .chain
+            .iter()
+            .position(|(other_path, _, _)| *other_path == canonical_path)
+        {
+            let chain = self.chain[position..]
+                .iter()
+                .map(|(_, path, span)| (path.clone(), (*span).into()))
+                .collect();
+            return Err(faulty(Error::CircularInclude {
+                chain,
+                span: span.into(),
+            }));
+        }
+
+        if self.chain.len() >= self.depth_limit {
+            return Err(faulty(Error::DepthLimitExceeded {
+                construct: "include".to_owned(),
+                limit: self.depth_limit,
+                span: span.into(),
+            }));
+        }
+
+        let contents = match self.provider.load(Path::new(&path)) {
+            Ok(contents) => contents,
+            Err(io_error) => {
+                return Err(faulty(Error::IncludeNotReadable {
+                    path,
+                    message: io_error.to_string(),
+                    span: span.into(),
+                }))
+            }
+        };
+
+        let offset = self.next_offset;
+        self.next_offset += contents.len() + 1;
+        self.sources.push(Source {
+            path: PathBuf::from(&path),
+            offset,
+            contents: contents.clone(),
+        });
+
+        match self.scan_file(&contents, offset) {
+            Ok(words) => {
+                self.chain.push((canonical_path, path, span));
+                Ok(words)
+            }
+            Err(errors) => Err(vec![Word::Faulty {
+                errors,
+                warnings: Vec::new(),
+                placeholder: None,
+            }]),
+        }
+    }
+
+    #[cfg(not(feature = "std"))]
+    fn include(&mut self, path: &[u8], span: Span) -> Result<Vec<Word>, Vec<Word>> {
+        Err(faulty(Error::IncludeNotReadable {
+            path: String::from_utf8_lossy(path).into_owned(),
+            message: "files can't be included without the standard library".to_owned(),
+            span: span.into(),
+        }))
+    }
+}
+
+fn faulty(error: Error) -> Vec<Word> {
+    vec![Word::Faulty {
+        errors: vec![error],
+        warnings: Vec::new(),
+        placeholder: None,
+    }]
+}
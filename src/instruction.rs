@@ -1,13 +1,33 @@
-#[derive(Debug, Copy, Clone)]
-pub(crate) struct Instruction {
-    pub(crate) instruction_kind: InstructionKind,
-    pub(crate) keep: bool,
-    pub(crate) r#return: bool,
-    pub(crate) short: bool,
+use core::fmt;
+
+/// An Uxn instruction, i.e. an instruction kind along with its modes.
+///
+/// # Example
+///
+/// ```rust
+/// use ruxnasm::{Instruction, InstructionKind};
+///
+/// let instruction = Instruction::from_mnemonic("ADD2k").unwrap();
+///
+/// assert_eq!(instruction.instruction_kind, InstructionKind::Add);
+/// assert_eq!(instruction.opcode(), 0xb8);
+/// assert_eq!(Instruction::from_opcode(0xb8), instruction);
+/// assert_eq!(instruction.to_string(), "ADD2k");
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Instruction {
+    pub instruction_kind: InstructionKind,
+    /// The `k` mode, in which the operands are kept on the stack.
+    pub keep: bool,
+    /// The `r` mode, in which the instruction operates on the return stack.
+    pub r#return: bool,
+    /// The `2` mode, in which the instruction operates on shorts.
+    pub short: bool,
 }
 
-#[derive(Debug, Copy, Clone)]
-pub(crate) enum InstructionKind {
+/// Kind of an Uxn instruction, whose value is its opcode without any modes.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum InstructionKind {
     // Stack
     BreakOrLiteral = 0x00,
     Increment,
@@ -45,3 +65,238 @@ pub(crate) enum InstructionKind {
     ExclusiveOr,
     Shift = 0x1f,
 }
+
+/// Opcode of a literal byte, i.e. `BRK` in keep mode, which is followed by the byte.
+pub(crate) const LIT: u8 = 0x80;
+/// Opcode of a literal short, i.e. `BRK` in short mode, which is followed by the short.
+pub(crate) const LIT2: u8 = 0x20;
+
+/// Instruction kinds along with their mnemonics, in the order of their opcodes. Shared by the
+/// assembler and the disassembler.
+const INSTRUCTION_KINDS: [(InstructionKind, &str); 32] = [
+    (InstructionKind::BreakOrLiteral, "BRK"),
+    (InstructionKind::Increment, "INC"),
+    (InstructionKind::Pop, "POP"),
+    (InstructionKind::Duplicate, "DUP"),
+    (InstructionKind::NoOperation, "NIP"),
+    (InstructionKind::Swap, "SWP"),
+    (InstructionKind::Over, "OVR"),
+    (InstructionKind::Rotate, "ROT"),
+    (InstructionKind::Equal, "EQU"),
+    (InstructionKind::NotEqual, "NEQ"),
+    (InstructionKind::GreaterThan, "GTH"),
+    (InstructionKind::LesserThan, "LTH"),
+    (InstructionKind::Jump, "JMP"),
+    (InstructionKind::JumpCondition, "JCN"),
+    (InstructionKind::JumpStash, "JSR"),
+    (InstructionKind::Stash, "STH"),
+    (InstructionKind::LoadZeroPage, "LDZ"),
+    (InstructionKind::StoreZeroPage, "STZ"),
+    (InstructionKind::LoadRelative, "LDR"),
+    (InstructionKind::StoreRelative, "STR"),
+    (InstructionKind::LoadAbsolute, "LDA"),
+    (InstructionKind::StoreAbsolute, "STA"),
+    (InstructionKind::DeviceIn, "DEI"),
+    (InstructionKind::DeviceOut, "DEO"),
+    (InstructionKind::Add, "ADD"),
+    (InstructionKind::Subtract, "SUB"),
+    (InstructionKind::Multiply, "MUL"),
+    (InstructionKind::Divide, "DIV"),
+    (InstructionKind::And, "AND"),
+    (InstructionKind::Or, "ORA"),
+    (InstructionKind::ExclusiveOr, "EOR"),
+    (InstructionKind::Shift, "SFT"),
+];
+
+/// Mnemonics of the instructions of the legacy opcode table, in the order of their opcodes. The
+/// literals had an opcode of their own then, and the keep mode didn't exist yet.
+const LEGACY_MNEMONICS: [&str; 32] = [
+    "BRK", "LIT", "NOP", "POP", "DUP", "SWP", "OVR", "ROT", "EQU", "NEQ", "GTH", "LTH", "JMP",
+    "JNZ", "JSR", "STH", "PEK", "POK", "LDR", "STR", "LDA", "STA", "DEI", "DEO", "ADD", "SUB",
+    "MUL", "DIV", "AND", "ORA", "EOR", "SFT",
+];
+
+/// Numbering of the opcodes the instructions are assembled into.
+///
+/// # Example
+///
+/// ```rust
+/// let mut options = ruxnasm::Options::default();
+/// options.opcode_table = ruxnasm::OpcodeTable::Legacy;
+/// let assembly = ruxnasm::assemble_with_options(b"|0100 #01 NOP DUP2 JNZ", &options).unwrap();
+///
+/// assert_eq!(assembly.binary, [0x01, 0x01, 0x02, 0x24, 0x0d]);
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum OpcodeTable {
+    /// The current numbering, with `LIT` being `BRK` in keep mode.
+    Modern,
+    /// The numbering from before the opcodes were reordered, targeted by older sources, with
+    /// `LIT` at 0x01, `NOP` at 0x02, and `JNZ`, `PEK`, and `POK` in place of `JCN`, `LDZ`, and
+    /// `STZ`. Its instructions are given the instruction kinds with the same opcodes, so that
+    /// they are assembled into the same bytes, even though the kinds are named after the modern
+    /// instructions.
+    Legacy,
+}
+
+impl OpcodeTable {
+    /// Returns the table as an instruction set, which lives as long as the program.
+    pub(crate) fn instruction_set(self) -> &'static dyn InstructionSet {
+        match self {
+            Self::Modern => &Self::Modern,
+            Self::Legacy => &Self::Legacy,
+        }
+    }
+}
+
+impl InstructionSet for OpcodeTable {
+    fn opcode(&self, mnemonic: &[u8]) -> Option<u8> {
+        match self {
+            Self::Modern => InstructionKind::from_mnemonic(mnemonic)
+                .map(|instruction_kind| instruction_kind as u8),
+            Self::Legacy => LEGACY_MNEMONICS
+                .iter()
+                .position(|other_mnemonic| other_mnemonic.as_bytes() == mnemonic)
+                .map(|opcode| opcode as u8),
+        }
+    }
+
+    fn literal_opcodes(&self) -> (u8, u8) {
+        match self {
+            Self::Modern => (LIT, LIT2),
+            Self::Legacy => (0x01, 0x21),
+        }
+    }
+}
+
+/// Instruction set of a variant of Uxn, e.g. with extra or remapped instructions, for the
+/// experiments with the instruction set and the custom virtual machines. Consulted both when the
+/// instructions are recognized in the source code and when the literals are encoded.
+///
+/// Instructions are written just like the ones of Uxn, i.e. as 3-character mnemonics followed by
+/// any of the `2`, `k`, and `r` modes, which set the 0x20, 0x80, and 0x40 bits of the opcodes.
+///
+/// # Example
+///
+/// ```rust
+/// use ruxnasm::{InstructionSet, OpcodeTable};
+///
+/// /// Uxn with an extra `HLT` instruction in place of `BRK`.
+/// struct Halting;
+///
+/// impl InstructionSet for Halting {
+///     fn opcode(&self, mnemonic: &[u8]) -> Option<u8> {
+///         match mnemonic {
+///             b"HLT" => Some(0x00),
+///             b"BRK" => None,
+///             _ => OpcodeTable::Modern.opcode(mnemonic),
+///         }
+///     }
+/// }
+///
+/// let mut records = ruxnasm::Records::default();
+/// records.instruction_set = Some(&Halting);
+/// let assembly = ruxnasm::assemble_with_records(
+///     b"|0100 #01 HLT",
+///     &ruxnasm::Options::default(),
+///     &ruxnasm::FileSystem,
+///     &mut records,
+/// )
+/// .unwrap()
+/// .unwrap();
+///
+/// assert_eq!(assembly.binary, [0x80, 0x01, 0x00]);
+/// ```
+pub trait InstructionSet {
+    /// Returns the opcode of the instruction with the given mnemonic, without any modes, e.g.
+    /// `ADD`, or `None` if there is no such instruction.
+    fn opcode(&self, mnemonic: &[u8]) -> Option<u8>;
+
+    /// Returns the opcodes of a literal byte and a literal short. By default, these are the
+    /// opcodes of Uxn, i.e. `BRK` in keep mode and in short mode.
+    fn literal_opcodes(&self) -> (u8, u8) {
+        (LIT, LIT2)
+    }
+}
+
+impl InstructionKind {
+    /// Returns the instruction kind with the given mnemonic. `LIT` is an alias of `BRK`.
+    pub fn from_mnemonic(mnemonic: &[u8]) -> Option<Self> {
+        if mnemonic == b"LIT" {
+            return Some(Self::BreakOrLiteral);
+        }
+        INSTRUCTION_KINDS
+            .iter()
+            .find(|(_, other_mnemonic)| other_mnemonic.as_bytes() == mnemonic)
+            .map(|(instruction_kind, _)| *instruction_kind)
+    }
+
+    /// Returns the mnemonic of the instruction kind, e.g. `ADD`.
+    pub fn mnemonic(self) -> &'static str {
+        INSTRUCTION_KINDS[self as usize].1
+    }
+
+    /// Returns every instruction kind, in the order of their opcodes.
+    pub fn all() -> impl Iterator<Item = Self> {
+        INSTRUCTION_KINDS
+            .iter()
+            .map(|(instruction_kind, _)| *instruction_kind)
+    }
+}
+
+impl Instruction {
+    /// Returns the instruction written in Uxntal, i.e. the mnemonic of the instruction kind
+    /// followed by any of the `2`, `k`, and `r` modes in any order, e.g. `ADD2k`.
+    pub fn from_mnemonic(mnemonic: &str) -> Option<Self> {
+        let mut instruction = Self {
+            instruction_kind: InstructionKind::from_mnemonic(mnemonic.get(..3)?.as_bytes())?,
+            keep: false,
+            r#return: false,
+            short: false,
+        };
+        for mode in mnemonic[3..].chars() {
+            match mode {
+                '2' => instruction.short = true,
+                'k' => instruction.keep = true,
+                'r' => instruction.r#return = true,
+                _ => return None,
+            }
+        }
+
+        Some(instruction)
+    }
+
+    /// Decodes the instruction from its opcode.
+    pub fn from_opcode(opcode: u8) -> Self {
+        Self {
+            instruction_kind: INSTRUCTION_KINDS[(opcode & 0x1f) as usize].0,
+            keep: opcode & 0x80 != 0,
+            r#return: opcode & 0x40 != 0,
+            short: opcode & 0x20 != 0,
+        }
+    }
+
+    /// Encodes the instruction as its opcode.
+    pub fn opcode(&self) -> u8 {
+        self.instruction_kind as u8
+            | ((self.short as u8) << 5)
+            | ((self.r#return as u8) << 6)
+            | ((self.keep as u8) << 7)
+    }
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.instruction_kind.mnemonic())?;
+        if self.short {
+            write!(f, "2")?;
+        }
+        if self.keep {
+            write!(f, "k")?;
+        }
+        if self.r#return {
+            write!(f, "r")?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,68 @@
+use crate::{token::ScopedIdentifier, walker::Definitions};
+use alloc::{
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+use core::ops::Range;
+
+/// A routine that has been executed according to the profile and that is jumped to with literal
+/// absolute addresses from too far away to use literal relative addresses instead.
+///
+/// Moving the routine within 126 bytes of the jumps would allow replacing each `;routine JMP2`
+/// with `,routine JMP`, saving a byte per jump.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LayoutHint {
+    /// Name of the label, or the full `label/sublabel` path of the sublabel.
+    pub name: String,
+    /// Address of the routine.
+    pub address: u16,
+    /// Number of times the routine has been entered, i.e. the hit count of its address.
+    pub hits: u64,
+    /// Addresses of the literal absolute addresses of the routine which are too far from it.
+    pub references: Vec<u16>,
+    /// Span of the label or sublabel definition.
+    pub span: Range<usize>,
+}
+
+impl LayoutHint {
+    /// Returns the number of bytes saved by making all references relative.
+    pub fn savings(&self) -> usize {
+        self.references.len()
+    }
+}
+
+/// Hit counts of the addresses, e.g. from a run of an emulator.
+pub(crate) type Profile<'a> = dyn Fn(u16) -> u64 + 'a;
+
+/// Turns the far references into hints for the routines entered at least once according to the
+/// profile, the most often entered routines first.
+pub(crate) fn hints(
+    far_references: &[(ScopedIdentifier, u16)],
+    definitions: &Definitions,
+    profile: &Profile,
+) -> Vec<LayoutHint> {
+    let mut hints: Vec<LayoutHint> = Vec::new();
+
+    for (scoped_identifier, reference) in far_references {
+        let (address, span) = definitions.labels[scoped_identifier];
+        let hits = profile(address);
+        if hits == 0 {
+            continue;
+        }
+        let name = scoped_identifier.to_string();
+        match hints.iter_mut().find(|hint| hint.name == name) {
+            Some(hint) => hint.references.push(*reference),
+            None => hints.push(LayoutHint {
+                name,
+                address,
+                hits,
+                references: vec![*reference],
+                span: span.into(),
+            }),
+        }
+    }
+    hints.sort_by_key(|hint| (core::cmp::Reverse(hint.hits), hint.address));
+
+    hints
+}
@@ -1,18 +1,60 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
 mod anomalies;
+mod assembly;
+#[cfg(feature = "capi")]
+pub mod capi;
+mod chunk;
+mod classification;
+mod directive;
+mod disassembler;
 pub(crate) mod emitter;
+mod expression;
+mod formatter;
+mod include;
 mod instruction;
+mod layout;
+mod limits;
+mod lint;
+mod options;
+mod position;
+mod post_process;
+mod reference;
 pub(crate) mod scanner;
 mod span;
+mod stack_effect;
+mod suggestion;
+mod symbol;
 mod token;
 pub(crate) mod tokenizer;
+mod trace;
 mod traits;
 pub(crate) mod walker;
 
-pub use anomalies::{Error, Warning};
-pub(crate) use instruction::{Instruction, InstructionKind};
+use alloc::{borrow::ToOwned, string::String, vec, vec::Vec};
+pub use anomalies::{Error, Suggestion, Warning};
+pub use assembly::Assembly;
+#[cfg(feature = "std")]
+pub use assembly::Records;
+pub use chunk::Chunk;
+pub use classification::{ClassifiedToken, TokenClass};
+pub use disassembler::DisassembledInstruction;
+#[cfg(feature = "std")]
+pub use include::{FileSystem, Source, SourceProvider};
+pub use instruction::{Instruction, InstructionKind, InstructionSet, OpcodeTable};
+pub use layout::LayoutHint;
+pub use limits::Limits;
+pub use options::{Options, ReservedMemory};
+pub use position::Position;
+pub use post_process::PostProcessor;
+pub use reference::Reference;
 pub(crate) use span::{Location, Span, Spanned, Spanning};
-pub(crate) use token::{Identifier, Token};
+pub use symbol::Symbol;
+pub(crate) use token::{Identifier, Query, Token};
 use tokenizer::Word;
+pub use trace::TraceStep;
 pub(crate) use traits::{Stockpile, UnzipCollect};
 
 /// Assembles an Uxn binary from a string representing an Uxntal program.
@@ -33,74 +75,618 @@ pub(crate) use traits::{Stockpile, UnzipCollect};
 /// assert_eq!(binary, [0x80, 0x02, 0x80, 0x03, 0x18]);
 /// ```
 pub fn assemble(source: &[u8]) -> Result<(Vec<u8>, Vec<Warning>), (Vec<Error>, Vec<Warning>)> {
-    let mut warnings = Vec::new();
+    assemble_recorded(source, &Options::default(), Recording::default())
+}
 
-    let words = scanner::Scanner::new(source)
-        .unzip_collect()
-        .stockpile(&mut warnings)
-        .map_err(|errors| (errors, warnings.clone()))?;
+/// Assembles an Uxn binary with custom [`Options`], returning an [`Assembly`] with the binary,
+/// the addresses of the symbols, the names of the macros, and the warnings, instead of the
+/// binary alone.
+///
+/// With `std`, [`assemble_with_records`] additionally collects the [`Records`] of the assembly,
+/// loads the included files through a [`SourceProvider`], and can write the binary to a writer.
+///
+/// # Example
+///
+/// ```rust
+/// let mut options = ruxnasm::Options::default();
+/// options.unused_label_warnings = false;
+/// let assembly = ruxnasm::assemble_with_options(b"|0200 @main #02 ,main JMP", &options).unwrap();
+///
+/// assert_eq!(assembly.length(), 0x0105);
+/// assert_eq!(assembly.byte_count, 5);
+/// assert_eq!(assembly.first_address, Some(0x0200));
+/// assert_eq!(assembly.last_address, Some(0x0204));
+/// assert_eq!(assembly.symbols[0].name, "main");
+/// assert_eq!(assembly.symbols[0].address, 0x0200);
+/// assert!(assembly.warnings.is_empty());
+/// ```
+pub fn assemble_with_options(source: &[u8], options: &Options) -> Result<Assembly, AssemblyErrors> {
+    let mut symbols = Vec::new();
+    let mut macros = Vec::new();
+    let mut chunks = Vec::new();
+    let (binary, warnings) = assemble_recorded(
+        source,
+        options,
+        Recording {
+            symbols: Some(&mut symbols),
+            macros: Some(&mut macros),
+            chunks: Some(&mut chunks),
+            ..Recording::default()
+        },
+    )?;
 
-    let mut walker = walker::Walker::new();
-    let words: Vec<&Word> = words.iter().collect();
-    let mut stack: Vec<Vec<&Word>> = vec![words];
-    let mut chain: Vec<(Vec<u8>, Span)> = Vec::new();
+    Ok(Assembly::new(binary, symbols, macros, warnings, &chunks))
+}
 
-    while let Some(top) = stack.pop() {
-        match walker.walk(&top) {
-            Some((macro_words, macro_name, invoke_span, previous_words)) => {
-                stack.push(previous_words);
-                stack.push(macro_words);
-                if let Some(position) = chain.iter().position(|(n, _)| *n == macro_name) {
-                    let mut actual_chain = vec![(macro_name.clone(), invoke_span)];
-                    actual_chain.extend(chain.iter().skip(position + 1).cloned());
-                    return Err((
-                        vec![Error::RecursiveMacro {
-                            chain: actual_chain
-                                .into_iter()
-                                .map(|(macro_name, macro_span)| {
-                                    (
-                                        String::from_utf8_lossy(&macro_name).into_owned(),
-                                        macro_span.into(),
-                                    )
-                                })
-                                .collect(),
-                            span: chain[position].1.into(),
-                        }],
-                        warnings,
-                    ));
-                } else {
-                    chain.push((macro_name, invoke_span));
-                }
+/// Assembles an Uxn binary with the given [`Options`] just like [`assemble_with_options`] does,
+/// and collects the [`Records`] of the assembly along the way, so that everything known about
+/// the program comes from a single assembly.
+///
+/// The included files are loaded from the given [`SourceProvider`], e.g. [`FileSystem`] or a
+/// map of the files held in memory. The instructions are those of the
+/// [`Records::instruction_set`], if any, instead of the [`Options::opcode_table`]. The binary is
+/// passed through the [`Records::post_processor`], if any, and written to the
+/// [`Records::writer`], if any, instead of being kept in the [`Assembly`], in which case the
+/// first error of the writer or the post-processor is returned instead of the result of the
+/// assembly.
+///
+/// # Example
+///
+/// ```rust
+/// use std::collections::HashMap;
+/// use std::path::PathBuf;
+///
+/// let mut files: HashMap<PathBuf, Vec<u8>> = HashMap::new();
+/// files.insert(PathBuf::from("one.tal"), b"%ONE { #01 }".to_vec());
+/// let mut options = ruxnasm::Options::default();
+/// options.opcode_table = ruxnasm::OpcodeTable::Legacy;
+/// let mut records = ruxnasm::Records::default();
+/// records.trace = Some(Vec::new());
+/// let assembly = ruxnasm::assemble_with_records(
+///     b"~one.tal |0100 @main ONE NOP",
+///     &options,
+///     &files,
+///     &mut records,
+/// )
+/// .unwrap()
+/// .unwrap();
+///
+/// assert_eq!(assembly.binary, [0x01, 0x01, 0x02]);
+/// assert_eq!(assembly.symbols[0].name, "main");
+/// assert_eq!(records.sources[0].path, PathBuf::from("one.tal"));
+/// assert_eq!(records.chunks[1].bytes, [0x02]);
+/// assert_eq!(records.trace.unwrap()[2].bytes, [0x02]);
+/// ```
+#[cfg(feature = "std")]
+pub fn assemble_with_records(
+    source: &[u8],
+    options: &Options,
+    provider: &dyn SourceProvider,
+    records: &mut Records,
+) -> std::io::Result<Result<Assembly, AssemblyErrors>> {
+    let mut symbols = Vec::new();
+    let mut macros = Vec::new();
+    let Records {
+        sources,
+        chunks,
+        trace,
+        references,
+        layout_hints,
+        instruction_set,
+        writer,
+        post_processor,
+    } = records;
+    let profile = layout_hints.as_ref().map(|(profile, _)| *profile);
+    let profile = |address| {
+        profile
+            .and_then(|profile| profile.get(&address).copied())
+            .unwrap_or_default()
+    };
+    let mut io_error: Option<std::io::Error> = None;
+    let mut written = 0;
+    let (first_error, length) = (&mut io_error, &mut written);
+    // Post-processed binaries are written only once they have been processed.
+    let streamed_writer = match post_processor {
+        Some(_) => None,
+        None => writer.as_mut(),
+    };
+    let mut output = streamed_writer.map(|writer| {
+        move |bytes: &[u8]| {
+            if first_error.is_none() {
+                *first_error = writer.write_all(bytes).err();
+                *length += bytes.len();
             }
-            None => {
-                chain.pop();
+        }
+    });
+
+    let includer = include::Includer::new(source.len(), options, provider);
+    let includer = match instruction_set {
+        Some(instruction_set) => includer.with_instruction_set(*instruction_set),
+        None => includer,
+    };
+    let result = assemble_included(
+        source,
+        options,
+        includer,
+        Recording {
+            trace: trace.as_mut(),
+            chunks: Some(chunks),
+            symbols: Some(&mut symbols),
+            macros: Some(&mut macros),
+            references: references.as_mut(),
+            sources: Some(sources),
+            layout_hints: layout_hints
+                .as_mut()
+                .map(|(_, hints)| (&profile as &layout::Profile, hints)),
+            output: output
+                .as_mut()
+                .map(|output| output as &mut dyn FnMut(&[u8])),
+        },
+    );
+
+    let is_streamed = output.is_some();
+    if let Some(io_error) = io_error {
+        return Err(io_error);
+    }
+    let (binary, warnings) = match result {
+        Ok(assembled) => assembled,
+        Err(errors) => return Ok(Err(errors)),
+    };
+
+    let mut assembly = Assembly::new(binary, symbols, macros, warnings, chunks);
+    if is_streamed {
+        assembly.length = written;
+    } else if let (Some(post_processor), false) = (post_processor, options.library) {
+        // `std::io::Error::other` is newer than the pinned compiler.
+        #[allow(clippy::io_other_error)]
+        let binary = post_processor
+            .process(core::mem::take(&mut assembly.binary))
+            .map_err(|message| std::io::Error::new(std::io::ErrorKind::Other, message))?;
+        assembly.length = binary.len();
+        match writer {
+            Some(writer) => writer.write_all(&binary)?,
+            None => assembly.binary = binary,
+        }
+    }
+    Ok(Ok(assembly))
+}
+
+/// Decodes an Uxn binary back into instructions, using the same opcodes as the assembler. The
+/// binary is expected to be loaded at address 0x0100, and the operands of `LIT` and `LIT2` are
+/// decoded along with them.
+///
+/// # Example
+///
+/// ```rust
+/// let instructions = ruxnasm::disassemble(&[0x80, 0x2a, 0x20, 0x01, 0x00, 0xb8]);
+///
+/// assert_eq!(instructions[0].text, "LITk 2a");
+/// assert_eq!(instructions[1].address, 0x0102);
+/// assert_eq!(instructions[1].text, "LIT2 0100");
+/// assert_eq!(instructions[2].text, "ADD2k");
+/// ```
+pub fn disassemble(binary: &[u8]) -> Vec<DisassembledInstruction> {
+    disassembler::disassemble(binary)
+}
+
+/// Formats an Uxntal program in the canonical format, changing only the whitespace between the
+/// tokens and the comments: tokens are separated by single spaces, lines are indented with tabs,
+/// the definitions of devices are aligned in columns, and runs of blank lines are collapsed.
+///
+/// Returns an error if the program can't be split into tokens, e.g. because of an unclosed
+/// comment.
+///
+/// # Example
+///
+/// ```rust
+/// let formatted = ruxnasm::format(b"|10 @Console [ &pad $8 &char ]\n|20  @Screen   [ &pad $2 ]\n\n\n|0100   #01   ( one )  ").unwrap();
+///
+/// assert_eq!(
+///     formatted,
+///     b"|10 @Console [ &pad $8 &char ]\n|20 @Screen  [ &pad $2 ]\n\n|0100 #01 ( one )\n"
+/// );
+/// ```
+pub fn format(source: &[u8]) -> Result<Vec<u8>, Error> {
+    formatter::format(source)
+}
+
+/// Splits an Uxntal program into tokens and classifies them for highlighting, e.g. as
+/// instructions, label definitions, or comments. Every token and comment is listed in the order
+/// of their spans, including the invalid ones, so the program doesn't need to be valid.
+///
+/// The included files are not read &mdash; includes are classified as [`TokenClass::Directive`].
+///
+/// # Example
+///
+/// ```rust
+/// use ruxnasm::TokenClass;
+///
+/// let tokens = ruxnasm::classify(b"@main ( loop ) ,main JMP");
+/// let classes: Vec<TokenClass> = tokens.iter().map(|token| token.class).collect();
+///
+/// assert_eq!(
+///     classes,
+///     [
+///         TokenClass::LabelDefinition,
+///         TokenClass::Comment,
+///         TokenClass::LabelReference,
+///         TokenClass::Instruction
+///     ]
+/// );
+/// assert_eq!(tokens[1].span, 6..14);
+/// ```
+pub fn classify(source: &[u8]) -> Vec<ClassifiedToken> {
+    classification::classify(source)
+}
+
+/// Outputs of the assembly other than the binary, which are recorded only on demand.
+#[derive(Default)]
+pub(crate) struct Recording<'a> {
+    pub(crate) trace: Option<&'a mut Vec<TraceStep>>,
+    pub(crate) chunks: Option<&'a mut Vec<Chunk>>,
+    pub(crate) symbols: Option<&'a mut Vec<Symbol>>,
+    pub(crate) macros: Option<&'a mut Vec<String>>,
+    pub(crate) references: Option<&'a mut Vec<Reference>>,
+    #[cfg(feature = "std")]
+    pub(crate) sources: Option<&'a mut Vec<Source>>,
+    pub(crate) layout_hints: Option<(&'a layout::Profile<'a>, &'a mut Vec<LayoutHint>)>,
+    pub(crate) output: Option<emitter::Output<'a>>,
+}
+
+fn assemble_recorded(source: &[u8], options: &Options, recording: Recording) -> AssemblyResult {
+    let includer = include::Includer::reading_files(source.len(), options);
+    assemble_included(source, options, includer, recording)
+}
+
+fn assemble_included(
+    source: &[u8],
+    options: &Options,
+    mut includer: include::Includer,
+    mut recording: Recording,
+) -> AssemblyResult {
+    let words = includer.scan(source, 0);
+    let instruction_set = includer.instruction_set;
+    #[cfg(feature = "std")]
+    if let Some(recorded_sources) = recording.sources.as_mut() {
+        recorded_sources.append(&mut includer.sources);
+    }
+    let include::Includer {
+        suppressions,
+        warnings,
+        ..
+    } = includer;
+    // Libraries are only checked, so nothing of them is output.
+    if options.library {
+        recording.output = None;
+    }
+
+    let result = match words {
+        Ok(words) => assemble_words(&words, options, instruction_set, warnings, recording),
+        Err(errors) => Err((errors, warnings)),
+    };
+    // Unused labels and macros of libraries are usually meant to be used by the including
+    // programs.
+    let is_reported = |warning: &Warning| match warning {
+        Warning::LabelUnused { .. } => options.unused_label_warnings && !options.library,
+        Warning::MacroUnused { .. } => !options.library,
+        _ => true,
+    };
+
+    match result {
+        Ok((binary, mut warnings)) => {
+            directive::suppress(&mut warnings, &suppressions);
+            warnings.retain(is_reported);
+            let binary = if options.library { Vec::new() } else { binary };
+            Ok((binary, warnings))
+        }
+        Err((mut errors, mut warnings)) => {
+            if let Some(limit) = options.error_limit {
+                limit_errors(&mut errors, limit);
             }
+            directive::suppress(&mut warnings, &suppressions);
+            warnings.retain(is_reported);
+            Err((errors, warnings))
         }
     }
+}
+
+/// Replaces the errors beyond the limit with a single [`Error::TooManyErrors`].
+fn limit_errors(errors: &mut Vec<Error>, limit: usize) {
+    if errors.len() > limit {
+        let span = errors[limit].span().clone();
+        errors.truncate(limit);
+        errors.push(Error::TooManyErrors { limit, span });
+    }
+}
 
-    let (statements, definitions) = match walker.finalize() {
-        Ok((statements, definitions, new_warnings)) => {
-            warnings.extend(new_warnings);
-            (statements, definitions)
+/// Internal passes of the assembler and the types they operate on, for tools that need more than
+/// the binary, e.g. syntax highlighters or linters. Requires the `unstable` feature.
+///
+/// Unlike the rest of the crate, nothing in this module is covered by semantic versioning
+/// &mdash; it follows the internals of the assembler, which may change with any release.
+#[cfg(feature = "unstable")]
+pub mod unstable {
+    pub use crate::span::{Location, Span, Spanned};
+    pub use crate::token::{Identifier, Query, ScopedIdentifier, Statement, Token};
+    pub use crate::tokenizer::Word;
+    pub use crate::walker::Definitions;
+    use crate::{directive, include, AssemblyErrors, AssemblyResult, Options, Recording, Warning};
+    use alloc::vec::Vec;
+
+    type WalkResult = Result<(Vec<Spanned<Statement>>, Definitions, Vec<Warning>), AssemblyErrors>;
+
+    /// Splits the program into words and turns them into tokens, with the included files
+    /// expanded in place. This is the first pass of the assembler.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use ruxnasm::unstable::{Token, Word};
+    ///
+    /// let (words, _) = ruxnasm::unstable::scan(b"|0100 #02").unwrap();
+    ///
+    /// assert!(matches!(
+    ///     &words[0],
+    ///     Word::Fine { token, .. } if matches!(token.node, Token::PadAbsolute(0x0100))
+    /// ));
+    /// ```
+    pub fn scan(source: &[u8]) -> Result<(Vec<Word>, Vec<Warning>), AssemblyErrors> {
+        let mut includer = include::Includer::reading_files(source.len(), &Options::default());
+        let words = includer.scan(source, 0);
+        let include::Includer {
+            suppressions,
+            mut warnings,
+            ..
+        } = includer;
+        directive::suppress(&mut warnings, &suppressions);
+
+        match words {
+            Ok(words) => Ok((words, warnings)),
+            Err(errors) => Err((errors, warnings)),
         }
-        Err((errors, new_warnings)) => {
-            warnings.extend(new_warnings);
-            return Err((errors, warnings));
+    }
+
+    /// Walks the words returned by [`scan`], expanding the macros, evaluating the directives,
+    /// and resolving the addresses of the labels. Returns the statements emitting the binary,
+    /// along with the definitions of the program. This is the second pass of the assembler.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use ruxnasm::unstable::{ScopedIdentifier, Statement};
+    ///
+    /// let (words, _) = ruxnasm::unstable::scan(b"%TWO { #02 } |0100 @main TWO").unwrap();
+    /// let (statements, definitions, _) = ruxnasm::unstable::walk(&words).unwrap();
+    ///
+    /// assert!(matches!(statements[0].node, Statement::PadAbsolute(0x0100)));
+    /// assert!(matches!(statements[1].node, Statement::LiteralHexByte(0x02)));
+    /// let (address, _) = definitions.labels[&ScopedIdentifier::Label(b"main".to_vec())];
+    /// assert_eq!(address, 0x0100);
+    /// ```
+    pub fn walk(words: &[Word]) -> WalkResult {
+        match crate::walk_program(words, &Options::default(), Vec::new()) {
+            Ok(walked) if walked.errors.is_empty() => {
+                Ok((walked.statements, walked.definitions, walked.warnings))
+            }
+            Ok(walked) => Err((walked.errors, walked.warnings)),
+            Err(errors) => Err(errors),
         }
-    };
+    }
+
+    /// Emits the binary from the statements and the definitions returned by [`walk`], checking
+    /// the references to the labels. This is the last pass of the assembler.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let (words, _) = ruxnasm::unstable::scan(b"|0100 #02 #03 ADD").unwrap();
+    /// let (statements, definitions, _) = ruxnasm::unstable::walk(&words).unwrap();
+    /// let (binary, _) = ruxnasm::unstable::emit(statements, definitions).unwrap();
+    ///
+    /// assert_eq!(binary, [0x80, 0x02, 0x80, 0x03, 0x18]);
+    /// ```
+    pub fn emit(statements: Vec<Spanned<Statement>>, definitions: Definitions) -> AssemblyResult {
+        crate::emitter::emit(
+            statements,
+            definitions,
+            &[],
+            &Options::default(),
+            &crate::OpcodeTable::Modern,
+            &mut Recording::default(),
+        )
+    }
+}
+
+type AssemblyErrors = (Vec<Error>, Vec<Warning>);
+type AssemblyResult = Result<(Vec<u8>, Vec<Warning>), AssemblyErrors>;
+
+fn assemble_words(
+    words: &[Word],
+    options: &Options,
+    instruction_set: &dyn InstructionSet,
+    warnings: Vec<Warning>,
+    mut recording: Recording,
+) -> AssemblyResult {
+    let walker::Walked {
+        statements,
+        definitions,
+        mut warnings,
+        mut errors,
+        addresses_resolved,
+        expansions,
+    } = walk_program(words, options, warnings)?;
 
     // println!("statements: {:#?}", statements);
     // println!("labels: {:?}", definitions.labels.keys());
     // println!("sublabels: {:?}", definitions.sublabels.keys());
 
-    match emitter::emit(statements, definitions) {
-        Ok((binary, new_warnings)) => {
+    if let Some(symbols) = recording.symbols.as_mut() {
+        symbols.extend(symbol::collect(&definitions));
+    }
+    if let Some(macros) = recording.macros.as_mut() {
+        macros.extend(
+            definitions
+                .macros
+                .keys()
+                .map(|name| String::from_utf8_lossy(name).into_owned()),
+        );
+    }
+
+    if errors.is_empty() {
+        warnings.extend(lint::unreachable_code(&statements, &definitions));
+        warnings.extend(lint::unterminated_vectors(&statements, &definitions));
+        warnings.extend(lint::overlapping_variables(&definitions));
+        warnings.extend(lint::macros_named_like_labels(&definitions));
+        if options.stack_warnings {
+            warnings.extend(stack_effect::check(&statements, &definitions));
+        }
+    }
+
+    // The program is emitted even with the errors of the faulty words replaced with
+    // placeholders, so that the errors of the labels and the addresses are reported as well.
+    // Only the undefined labels are reported if undefined macros have been left out, since the
+    // addresses are off then. Such a binary is never output.
+    if !errors.is_empty() {
+        recording.output = None;
+    }
+    match emitter::emit(
+        statements,
+        definitions,
+        &expansions,
+        options,
+        instruction_set,
+        &mut recording,
+    ) {
+        Ok((binary, new_warnings)) if errors.is_empty() => {
             warnings.extend(new_warnings);
             Ok((binary, warnings))
         }
+        Ok((_, new_warnings)) => {
+            warnings.extend(new_warnings);
+            Err((errors, warnings))
+        }
+        Err((new_errors, new_warnings)) => {
+            warnings.extend(new_warnings);
+            errors.extend(new_errors.into_iter().filter(|error| {
+                addresses_resolved || matches!(error, Error::LabelUndefined { .. })
+            }));
+            Err((errors, warnings))
+        }
+    }
+}
+
+/// Walks the words of the program, returning the statements and the definitions along with the
+/// warnings reported so far, and the errors after which the statements can still be emitted.
+fn walk_program(
+    words: &[Word],
+    options: &Options,
+    mut warnings: Vec<Warning>,
+) -> Result<walker::Walked, AssemblyErrors> {
+    let mut walker = if options.library {
+        walker::Walker::library()
+    } else {
+        walker::Walker::new()
+    }
+    .allowing_zeroth_page_bytes(options.zeroth_page_bytes)
+    .allowing_backward_padding(options.backward_padding)
+    .limiting_repeat_length(options.limits.repeat_length)
+    .limiting_errors(options.error_limit);
+    if let Err(error) = walk_words(words, &mut walker, &options.limits) {
+        return Err((vec![error], warnings));
+    }
+
+    // Routines of library regions that turn out to be unreferenced are left out by walking the
+    // words once again. Libraries checked on their own keep all of their routines.
+    if !options.library {
+        let unreferenced_units = walker.unreferenced_units();
+        if !unreferenced_units.is_empty() {
+            walker = walker::Walker::pruning(unreferenced_units)
+                .allowing_zeroth_page_bytes(options.zeroth_page_bytes)
+                .allowing_backward_padding(options.backward_padding)
+                .limiting_repeat_length(options.limits.repeat_length)
+                .limiting_errors(options.error_limit);
+            if let Err(error) = walk_words(words, &mut walker, &options.limits) {
+                return Err((vec![error], warnings));
+            }
+        }
+    }
+
+    match walker.finalize() {
+        Ok(walked) => {
+            warnings.extend(walked.warnings);
+            Ok(walker::Walked { warnings, ..walked })
+        }
         Err((errors, new_warnings)) => {
             warnings.extend(new_warnings);
             Err((errors, warnings))
         }
     }
 }
+
+/// Walks the words, expanding the macros and repeats, and checking that the expansions are
+/// neither recursive nor too deep.
+fn walk_words<'words>(
+    words: &'words [Word],
+    walker: &mut walker::Walker<'words>,
+    limits: &Limits,
+) -> Result<(), Error> {
+    let words: Vec<&Word> = words.iter().collect();
+    let mut stack: Vec<Vec<&Word>> = vec![words];
+    let mut chain: Vec<(Vec<u8>, Span)> = Vec::new();
+
+    while let Some(top) = stack.pop() {
+        let error_count = walker.error_count();
+        let statement_count = walker.statement_count();
+        let result = walker.walk(&top);
+        // Errors and statements in the words of macros point at the definitions, so the
+        // invocations they have been expanded through are recorded along with them. Repeats are
+        // left out, as they are written in place.
+        let invocations: Vec<Span> = chain
+            .iter()
+            .filter(|(name, _)| name != walker::REPEAT_NAME)
+            .map(|(_, span)| *span)
+            .collect();
+        if walker.error_count() > error_count {
+            walker.attribute_errors(error_count, &invocations);
+        }
+        walker.attribute_statements(statement_count, &invocations);
+        match result {
+            Some((macro_words, macro_name, invoke_span, previous_words)) => {
+                stack.push(previous_words);
+                stack.push(macro_words);
+                if let Some(position) = chain
+                    .iter()
+                    .position(|(n, _)| *n == macro_name && n != walker::REPEAT_NAME)
+                {
+                    let mut actual_chain = vec![(macro_name.clone(), invoke_span)];
+                    actual_chain.extend(chain.iter().skip(position + 1).cloned());
+                    return Err(Error::RecursiveMacro {
+                        chain: actual_chain
+                            .into_iter()
+                            .map(|(macro_name, macro_span)| {
+                                (
+                                    String::from_utf8_lossy(&macro_name).into_owned(),
+                                    macro_span.into(),
+                                )
+                            })
+                            .collect(),
+                        span: chain[position].1.into(),
+                    });
+                } else if chain.len() >= limits.expansion_depth {
+                    return Err(Error::DepthLimitExceeded {
+                        construct: "macro expansion".to_owned(),
+                        limit: limits.expansion_depth,
+                        span: invoke_span.into(),
+                    });
+                } else {
+                    chain.push((macro_name, invoke_span));
+                }
+            }
+            None => {
+                chain.pop();
+            }
+        }
+    }
+
+    Ok(())
+}
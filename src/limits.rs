@@ -0,0 +1,27 @@
+/// Limits of the nesting and the expansions of the program, which guard the assembler against
+/// pathological inputs, e.g. generated by fuzzers. Exceeding a depth limit results in an
+/// [`Error::DepthLimitExceeded`](crate::Error::DepthLimitExceeded), and exceeding the length of
+/// a repeat in an [`Error::RepeatTooLong`](crate::Error::RepeatTooLong).
+///
+/// Just like the [`Options`](crate::Options), the limits are changed starting from the defaults,
+/// since new ones can be added in minor versions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Limits {
+    /// Maximum depth of macros expanded within the expansions of other macros.
+    pub expansion_depth: usize,
+    /// Maximum depth of files included within other included files.
+    pub include_depth: usize,
+    /// Maximum number of words a single `?repeat` directive expands into.
+    pub repeat_length: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            expansion_depth: 256,
+            include_depth: 64,
+            repeat_length: 0x10000,
+        }
+    }
+}
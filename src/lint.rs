@@ -0,0 +1,283 @@
+use crate::{
+    token::{ScopedIdentifier, Statement},
+    walker::Definitions,
+    Instruction, InstructionKind, Span, Spanned, Warning,
+};
+use alloc::{
+    collections::{BTreeMap, BTreeSet},
+    string::ToString,
+    vec::Vec,
+};
+
+/// Reports the bytes emitted right after the instructions which the execution never continues
+/// past, i.e. `BRK` and the unconditional jumps such as `JMP2r`, with no label defined in between.
+/// Such bytes can't be jumped to nor referenced, so they never get executed.
+pub(crate) fn unreachable_code(
+    statements: &[Spanned<Statement>],
+    definitions: &Definitions,
+) -> Vec<Warning> {
+    let labeled: BTreeSet<u16> = definitions
+        .labels
+        .iter()
+        .filter(|(scoped_identifier, _)| !definitions.constants.contains(scoped_identifier))
+        .map(|(_, (address, _))| *address)
+        .collect();
+
+    let mut warnings: Vec<Warning> = Vec::new();
+    let mut address: u16 = 0;
+    // The last statement, if the execution never continues past it.
+    let mut end: Option<&Spanned<Statement>> = None;
+    for statement in statements {
+        match &statement.node {
+            Statement::PadAbsolute(value) => {
+                address = *value;
+                end = None;
+            }
+            Statement::PadRelative(value) => {
+                address = address.wrapping_add(*value);
+                end = None;
+            }
+            node => {
+                if let Some(end) = end.take() {
+                    if !labeled.contains(&address) {
+                        if let Statement::Instruction(instruction) = &end.node {
+                            warnings.push(Warning::CodeUnreachable {
+                                instruction: instruction.to_string(),
+                                span: statement.span.into(),
+                                other_span: end.span.into(),
+                            });
+                        }
+                    }
+                }
+                if let Statement::Instruction(instruction) = node {
+                    if ends_execution(instruction) {
+                        end = Some(statement);
+                    }
+                }
+                address = address.wrapping_add(node.size());
+            }
+        }
+    }
+
+    warnings
+}
+
+/// Whether the execution never continues past the instruction, i.e. it's `BRK` or an
+/// unconditional jump.
+fn ends_execution(instruction: &Instruction) -> bool {
+    match instruction.instruction_kind {
+        InstructionKind::BreakOrLiteral => {
+            !instruction.keep && !instruction.r#return && !instruction.short
+        }
+        InstructionKind::Jump => true,
+        _ => false,
+    }
+}
+
+/// Reports the vectors of the devices, i.e. the labels assigned to them with
+/// `;label .Device/vector DEO2`, whose execution can fall through to the next label without
+/// reaching `BRK` or an unconditional jump. Bytes following such instructions are treated as data
+/// unless they start with code right after a sublabel, which may be jumped to.
+pub(crate) fn unterminated_vectors(
+    statements: &[Spanned<Statement>],
+    definitions: &Definitions,
+) -> Vec<Warning> {
+    let vectors: BTreeSet<&ScopedIdentifier> = statements
+        .windows(3)
+        .filter_map(
+            |window| match (&window[0].node, &window[1].node, &window[2].node) {
+                (
+                    Statement::LiteralAbsoluteAddress(vector),
+                    Statement::LiteralZeroPageAddress(ScopedIdentifier::Sublabel(_, port)),
+                    Statement::Instruction(instruction),
+                ) if port == b"vector"
+                    && instruction.instruction_kind == InstructionKind::DeviceOut
+                    && instruction.short
+                    && !instruction.r#return =>
+                {
+                    Some(vector)
+                }
+                _ => None,
+            },
+        )
+        .collect();
+    if vectors.is_empty() {
+        return Vec::new();
+    }
+
+    let mut labels: BTreeMap<u16, Vec<&ScopedIdentifier>> = BTreeMap::new();
+    for (scoped_identifier, (address, _)) in &definitions.labels {
+        if *address >= 0x0100 && !definitions.constants.contains(scoped_identifier) {
+            labels.entry(*address).or_default().push(scoped_identifier);
+        }
+    }
+    let mut address: u16 = 0;
+    // Addresses of the statements, i.e. of the address pointer right before them.
+    let addresses: Vec<u16> = statements
+        .iter()
+        .map(|statement| {
+            let statement_address = address;
+            address = match statement.node {
+                Statement::PadAbsolute(value) => value,
+                Statement::PadRelative(value) => address.wrapping_add(value),
+                ref node => address.wrapping_add(node.size()),
+            };
+            statement_address
+        })
+        .collect();
+
+    let mut warnings: Vec<Warning> = Vec::new();
+    for vector in vectors {
+        let (start, span) = match definitions.labels.get(vector) {
+            Some(definition) => definition,
+            None => continue,
+        };
+        let first = match statements
+            .iter()
+            .zip(&addresses)
+            .position(|(statement, address)| address == start && statement.node.size() > 0)
+        {
+            Some(first) => first,
+            None => continue,
+        };
+
+        let mut reachable = true;
+        let mut last: Option<&Spanned<Statement>> = None;
+        for (statement, address) in statements[first..].iter().zip(&addresses[first..]) {
+            match statement.node {
+                // The memory skipped over is filled with zeros, i.e. `BRK`.
+                Statement::PadAbsolute(value) if value != *address => break,
+                Statement::PadAbsolute(_) => continue,
+                Statement::PadRelative(value) => {
+                    if value > 0 {
+                        reachable = false;
+                    }
+                    continue;
+                }
+                _ => (),
+            }
+            if last.is_some() {
+                if let Some(defined) = labels.get(address) {
+                    if let Some(next) = defined.iter().find(|scoped_identifier| {
+                        matches!(scoped_identifier, ScopedIdentifier::Label(_))
+                    }) {
+                        if reachable {
+                            if let Some(last) = last {
+                                warnings.push(Warning::VectorUnterminated {
+                                    name: vector.to_string(),
+                                    next_name: next.to_string(),
+                                    span: last.span.into(),
+                                    other_span: (*span).into(),
+                                });
+                            }
+                        }
+                        break;
+                    }
+                    if is_code(&statement.node) {
+                        reachable = true;
+                    }
+                }
+            }
+            if let Statement::Instruction(instruction) = &statement.node {
+                if reachable && ends_execution(instruction) {
+                    reachable = false;
+                }
+            }
+            last = Some(statement);
+        }
+    }
+
+    warnings
+}
+
+/// Whether the statement is an instruction or a literal, rather than data.
+fn is_code(statement: &Statement) -> bool {
+    matches!(
+        statement,
+        Statement::Instruction(_)
+            | Statement::LiteralZeroPageAddress(_)
+            | Statement::LiteralRelativeAddress(_)
+            | Statement::LiteralAbsoluteAddress(_)
+            | Statement::LiteralHexByte(_)
+            | Statement::LiteralHexShort(_)
+    )
+}
+
+/// Reports the variables and the device ports of the zero page whose bytes overlap, given the
+/// sizes reserved for them with the relative pads right after them. The later of the two
+/// definitions is reported, as it's usually the one placed by mistake.
+pub(crate) fn overlapping_variables(definitions: &Definitions) -> Vec<Warning> {
+    let mut variables: Vec<(u16, u16, &ScopedIdentifier, Span)> = definitions
+        .labels
+        .iter()
+        .filter(|(scoped_identifier, (address, _))| {
+            *address < 0x0100 && !definitions.constants.contains(scoped_identifier)
+        })
+        .filter_map(|(scoped_identifier, (address, span))| {
+            let size = definitions
+                .variable_sizes
+                .get(scoped_identifier)
+                .or_else(|| definitions.port_widths.get(scoped_identifier))?;
+            if *size == 0 {
+                return None;
+            }
+            Some((
+                *address,
+                address.saturating_add(*size),
+                scoped_identifier,
+                *span,
+            ))
+        })
+        .collect();
+    variables.sort_by_key(|(start, _, _, span)| (*start, span.from.offset));
+
+    let mut warnings: Vec<Warning> = Vec::new();
+    for (index, (_, end, scoped_identifier, span)) in variables.iter().enumerate() {
+        for (other_start, _, other_scoped_identifier, other_span) in &variables[index + 1..] {
+            if other_start >= end {
+                break;
+            }
+            let (later, earlier) = if other_span.from.offset > span.from.offset {
+                (
+                    (other_scoped_identifier, other_span),
+                    (scoped_identifier, span),
+                )
+            } else {
+                (
+                    (scoped_identifier, span),
+                    (other_scoped_identifier, other_span),
+                )
+            };
+            warnings.push(Warning::VariablesOverlapping {
+                name: later.0.to_string(),
+                other_name: earlier.0.to_string(),
+                address: *other_start,
+                span: (*later.1).into(),
+                other_span: (*earlier.1).into(),
+            });
+        }
+    }
+
+    warnings
+}
+
+/// Reports the macros with the same names as labels, which the grammar tells apart by the runes
+/// of the references, but the readers might not.
+pub(crate) fn macros_named_like_labels(definitions: &Definitions) -> Vec<Warning> {
+    definitions
+        .labels
+        .iter()
+        .filter(|(scoped_identifier, _)| !definitions.constants.contains(scoped_identifier))
+        .filter_map(|(scoped_identifier, (_, span))| match scoped_identifier {
+            ScopedIdentifier::Label(name) => {
+                let macro_span = definitions.macros.get(name)?;
+                Some(Warning::MacroNamedLikeLabel {
+                    name: scoped_identifier.to_string(),
+                    span: (*span).into(),
+                    other_span: (*macro_span).into(),
+                })
+            }
+            ScopedIdentifier::Sublabel(_, _) => None,
+        })
+        .collect()
+}
@@ -0,0 +1,125 @@
+use crate::{Limits, OpcodeTable};
+
+/// Options of the assembly, for the tools that need the assembler to be more or less strict than
+/// it is by default.
+///
+/// The defaults match the assembly done by [`assemble`](crate::assemble). New options can be
+/// added in minor versions, so the options are changed starting from the defaults.
+///
+/// # Example
+///
+/// ```rust
+/// let mut options = ruxnasm::Options::default();
+/// options.error_limit = Some(20);
+/// options.limits.include_depth = 8;
+///
+/// assert!(ruxnasm::assemble_with_options(b"|0100 #01", &options).is_ok());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Options {
+    /// Limits of the nesting of the program.
+    pub limits: Limits,
+    /// Maximum number of characters of a token. The rest of a longer token is cut off with a
+    /// [`Warning::TokenTrimmed`](crate::Warning::TokenTrimmed).
+    pub token_length: usize,
+    /// Whether labels that are never used are reported with a
+    /// [`Warning::LabelUnused`](crate::Warning::LabelUnused).
+    pub unused_label_warnings: bool,
+    /// Whether bytes can be written to the zeroth page, which is never a part of the binary.
+    /// Such bytes are left out of the binary if they are allowed, and reported with an
+    /// [`Error::BytesInZerothPage`](crate::Error::BytesInZerothPage) otherwise.
+    pub zeroth_page_bytes: bool,
+    /// Maximum number of errors reported before the assembly stops, if any. The errors beyond
+    /// the limit are replaced with a single [`Error::TooManyErrors`](crate::Error::TooManyErrors).
+    pub error_limit: Option<usize>,
+    /// Whether absolute pads can move the address pointer back to the addresses that have
+    /// already been written, e.g. to patch a table. Such pads are reported with an
+    /// [`Error::PaddedBackwards`](crate::Error::PaddedBackwards) otherwise. The bytes written
+    /// again are reported with a [`Warning::BytesOverwritten`](crate::Warning::BytesOverwritten).
+    pub backward_padding: bool,
+    /// Size the binary is padded to with zeros, if any, e.g. for the loaders that require
+    /// images of an exact size. Longer binaries are reported with an
+    /// [`Error::BinaryTooLarge`](crate::Error::BinaryTooLarge).
+    pub size: Option<usize>,
+    /// Whether the pads at the end of the binary are left out of it. Otherwise, the binary goes
+    /// up to the highest address padded to, even if nothing is written there.
+    pub trim: bool,
+    /// Numbering of the opcodes the instructions are assembled into, which also decides the
+    /// mnemonics of the instructions.
+    pub opcode_table: OpcodeTable,
+    /// Whether the stack effects of the routines are checked, reporting the routines which
+    /// underflow their stacks with a [`Warning::StackUnderflow`](crate::Warning::StackUnderflow),
+    /// and the ones which leave unexpected heights of their stacks at `BRK` or `JMP2r` with a
+    /// [`Warning::StackHeightUnexpected`](crate::Warning::StackHeightUnexpected). The effects of
+    /// the instructions are those of Uxn, regardless of the opcode table.
+    pub stack_warnings: bool,
+    /// Number of bytes, if any, within which the relative addresses that are close to their
+    /// limit of 126 bytes are reported with a
+    /// [`Warning::AddressNearLimit`](crate::Warning::AddressNearLimit), as they will be too far
+    /// once a few more bytes get added between them and their labels.
+    pub relative_address_margin: Option<usize>,
+    /// Whether the program is checked as a library, i.e. a file meant only to be included in
+    /// other programs, without assembling a binary. The library doesn't need to start with a
+    /// `|0100` pad &mdash; it is checked as if it was included right after one. Unused labels
+    /// and macros are not reported, since they are usually meant to be used by the including
+    /// programs, and the addresses of the symbols defined before any absolute pad are
+    /// [`Symbol::relative`](crate::Symbol::relative) to the start of the library.
+    pub library: bool,
+    /// Memory reserved by a compatibility profile of the Uxn runtimes, if any. The first
+    /// statement writing bytes into it after every absolute pad is reported with a
+    /// [`Warning::BytesInReservedMemory`](crate::Warning::BytesInReservedMemory).
+    pub reserved_memory: Option<ReservedMemory>,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            limits: Limits::default(),
+            token_length: 64,
+            unused_label_warnings: true,
+            zeroth_page_bytes: false,
+            error_limit: None,
+            backward_padding: false,
+            size: None,
+            trim: true,
+            opcode_table: OpcodeTable::Modern,
+            stack_warnings: false,
+            relative_address_margin: None,
+            library: false,
+            reserved_memory: Some(ReservedMemory::UXN_LEGACY),
+        }
+    }
+}
+
+/// Memory at the top of the address space reserved by a compatibility profile of the Uxn
+/// runtimes, e.g. for the stacks and the devices, which the programs shouldn't write to.
+///
+/// # Example
+///
+/// ```rust
+/// let mut options = ruxnasm::Options::default();
+/// options.reserved_memory = Some(ruxnasm::ReservedMemory {
+///     profile: "tiny",
+///     start: 0x8000,
+/// });
+/// let assembly = ruxnasm::assemble_with_options(b"|7ffe #0102", &options).unwrap();
+///
+/// assert_eq!(assembly.warnings[0].code(), "W0009");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReservedMemory {
+    /// Name of the compatibility profile, reported along with the bytes written to the memory.
+    pub profile: &'static str,
+    /// Address at which the reserved memory starts. It goes on up to the end of the memory.
+    pub start: u16,
+}
+
+impl ReservedMemory {
+    /// The top page of the memory, occupied by the stacks and the devices in the early Uxn
+    /// runtimes.
+    pub const UXN_LEGACY: Self = Self {
+        profile: "uxn-legacy",
+        start: 0xff00,
+    };
+}
@@ -0,0 +1,55 @@
+/// Line and column of a place in the source code, both counted from 1, as shown to the user
+/// along with the spans of the errors and the warnings.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Position {
+    /// Finds the position of the byte offset in the source code, e.g. of the start of a span.
+    ///
+    /// Lines end with a `\n`, so `\r\n` line endings count as a single line break. Columns
+    /// count characters rather than bytes, so a multi-byte UTF-8 character takes up a single
+    /// column, and an offset in the middle of a character points at the whole character.
+    /// Offsets past the end of the source code point right after its last character.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use ruxnasm::Position;
+    ///
+    /// let source = "|0100\r\n( żółw ) ;missing\r\n".as_bytes();
+    /// let (errors, _) = ruxnasm::assemble(source).unwrap_err();
+    ///
+    /// assert_eq!(
+    ///     Position::new(source, errors[0].span().start),
+    ///     Position { line: 2, column: 10 }
+    /// );
+    /// ```
+    pub fn new(source: &[u8], offset: usize) -> Self {
+        let offset = offset.min(source.len());
+        let before = &source[..offset];
+        let line_start = before
+            .iter()
+            .rposition(|byte| *byte == b'\n')
+            .map_or(0, |position| position + 1);
+        // The first byte of every character is the only one that isn't a continuation byte.
+        let characters = source[line_start..]
+            .iter()
+            .take(offset - line_start + 1)
+            .filter(|byte| **byte & 0xc0 != 0x80)
+            .count();
+        let column = if offset < source.len() {
+            characters
+        } else {
+            characters + 1
+        };
+
+        Self {
+            line: before.iter().filter(|byte| **byte == b'\n').count() + 1,
+            column,
+        }
+    }
+}
@@ -0,0 +1,42 @@
+use alloc::{string::String, vec::Vec};
+/// A step applied to an assembled binary before it gets written, such as compression, signing,
+/// or packing.
+///
+/// Every closure taking the binary and returning the processed binary, or a description of the
+/// reason why the binary could not be processed, is a post-processor. Post-processors are
+/// applied by [`assemble_with_records`](crate::assemble_with_records) through the
+/// [`Records`](crate::Records).
+///
+/// # Example
+///
+/// ```rust
+/// let mut pad = |mut binary: Vec<u8>| -> Result<Vec<u8>, String> {
+///     binary.resize(8, 0x00);
+///     Ok(binary)
+/// };
+/// let mut records = ruxnasm::Records::default();
+/// records.post_processor = Some(&mut pad);
+/// let assembly = ruxnasm::assemble_with_records(
+///     b"|0100 #02 #03 ADD",
+///     &Default::default(),
+///     &ruxnasm::FileSystem,
+///     &mut records,
+/// )
+/// .unwrap()
+/// .unwrap();
+///
+/// assert_eq!(assembly.binary, [0x80, 0x02, 0x80, 0x03, 0x18, 0x00, 0x00, 0x00]);
+/// ```
+pub trait PostProcessor {
+    /// Processes the binary, or returns a description of the reason why it could not be done.
+    fn process(&mut self, binary: Vec<u8>) -> Result<Vec<u8>, String>;
+}
+
+impl<F> PostProcessor for F
+where
+    F: FnMut(Vec<u8>) -> Result<Vec<u8>, String>,
+{
+    fn process(&mut self, binary: Vec<u8>) -> Result<Vec<u8>, String> {
+        self(binary)
+    }
+}
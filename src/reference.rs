@@ -0,0 +1,28 @@
+use crate::token::Statement;
+use alloc::string::String;
+use core::ops::Range;
+
+/// A reference to a label or a sublabel made in the program.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Reference {
+    /// Name of the referenced label, or the full `label/sublabel` path of the sublabel.
+    pub name: String,
+    /// Rune the label is referenced with, i.e. `.`, `,`, `;`, or `:`.
+    pub rune: char,
+    /// Address at which the reference is written.
+    pub address: u16,
+    /// Span of the reference, along with its rune. References in the macros point into the
+    /// definitions of the macros.
+    pub span: Range<usize>,
+}
+
+/// Returns the rune of the statement referencing a label.
+pub(crate) fn rune(statement: &Statement) -> char {
+    match statement {
+        Statement::LiteralZeroPageAddress(_) => '.',
+        Statement::LiteralRelativeAddress(_) => ',',
+        Statement::LiteralAbsoluteAddress(_) => ';',
+        _ => ':',
+    }
+}
@@ -1,10 +1,15 @@
-use std::{
+use alloc::{borrow::ToOwned, string::String, vec, vec::Vec};
+use core::{
     iter::{Copied, Peekable},
     slice::Iter,
 };
 
-pub use crate::anomalies::{Error, Warning};
-use crate::{tokenizer::Word, Location, Span, Spanned, Spanning};
+pub use crate::anomalies::{Error, Suggestion, Warning};
+use crate::{
+    directive::{self, Directive, Suppression},
+    tokenizer::Word,
+    InstructionSet, Location, Span, Spanned, Spanning,
+};
 
 const WHITESPACES: [u8; 6] = [b' ', b'\t', b'\n', 0x0b, 0x0c, b'\r'];
 const DELIMITERS: [u8; 6] = [b'(', b')', b'[', b']', b'{', b'}'];
@@ -23,13 +28,84 @@ fn is_whitespace(ch: u8) -> bool {
 pub(crate) struct Scanner<'a> {
     chars: Peekable<Copied<Iter<'a, u8>>>,
     location: Location,
+    /// Names of the warnings allowed by the directives preceding the next word.
+    pending_names: Vec<String>,
+    /// Names of the warnings allowed within the scope of the most recent label, along with the
+    /// location of the label definition.
+    label_scope: Option<(Vec<String>, Location)>,
+    suppressions: Vec<Suppression>,
+    directive_warnings: Vec<Warning>,
+    /// Whether the UTF-8 mode has been turned on, in which characters encoded with multiple
+    /// bytes are treated as single characters.
+    utf8: bool,
+    /// Spans of the comments scanned so far, including an unclosed one, which spans until the
+    /// end of the source.
+    pub(crate) comments: Vec<Span>,
+    /// Location at which the most recently scanned word starts.
+    word_start: Location,
+    /// Maximum number of characters of a token, after which the rest of it is cut off.
+    token_length: usize,
+    /// Instruction set whose mnemonics are recognized as instructions.
+    instruction_set: &'a dyn InstructionSet,
 }
 
 impl<'a> Scanner<'a> {
-    pub fn new(input_file_contents: &'a [u8]) -> Self {
+    /// Creates a scanner of the given source, whose spans start at the given offset.
+    pub fn new(
+        input_file_contents: &'a [u8],
+        offset: usize,
+        token_length: usize,
+        instruction_set: &'a dyn InstructionSet,
+    ) -> Self {
         Self {
             chars: input_file_contents.into_iter().copied().peekable(),
-            location: Location { offset: 0 },
+            location: Location { offset },
+            pending_names: Vec::new(),
+            label_scope: None,
+            suppressions: Vec::new(),
+            directive_warnings: Vec::new(),
+            utf8: false,
+            comments: Vec::new(),
+            word_start: Location { offset },
+            token_length,
+            instruction_set,
+        }
+    }
+
+    /// Returns the span of the most recently scanned word, or of the parenthesis without a
+    /// match.
+    pub(crate) fn word_span(&self) -> Span {
+        Span {
+            from: self.word_start,
+            to: self.location,
+        }
+    }
+
+    /// Consumes the scanner, returning the ranges in which warnings have been suppressed by
+    /// the directives, along with the warnings reported about the directives themselves.
+    pub fn finish(mut self) -> (Vec<Suppression>, Vec<Warning>) {
+        self.close_label_scope(self.location);
+        (self.suppressions, self.directive_warnings)
+    }
+
+    fn close_label_scope(&mut self, end: Location) {
+        if let Some((names, start)) = self.label_scope.take() {
+            self.suppressions.push(Suppression {
+                names,
+                range: start.offset..end.offset,
+            });
+        }
+    }
+
+    fn scan_directive(&mut self, comment: &[u8], span: Span) {
+        match directive::parse(comment) {
+            Some(Ok(Directive::Allow(names))) => self.pending_names.extend(names),
+            Some(Ok(Directive::Utf8)) => self.utf8 = true,
+            Some(Err(())) => self.directive_warnings.push(Warning::DirectiveInvalid {
+                directive: String::from_utf8_lossy(comment).trim().to_owned(),
+                span: span.into(),
+            }),
+            None => (),
         }
     }
 }
@@ -48,12 +124,14 @@ impl<'a> Iterator for Scanner<'a> {
                     let comment_start_location = self.location;
                     self.location += 1;
                     let mut comment_level: usize = 1;
+                    let mut comment: Vec<u8> = Vec::new();
 
                     'comment: loop {
                         match self.chars.next() {
                             Some(b'(') => {
                                 self.location += 1;
                                 comment_level += 1;
+                                comment.push(b'(');
                             }
                             Some(b')') => {
                                 self.location += 1;
@@ -61,69 +139,151 @@ impl<'a> Iterator for Scanner<'a> {
                                 if comment_level == 0 {
                                     break 'comment;
                                 }
+                                comment.push(b')');
                             }
-                            Some(_) => {
+                            Some(ch) => {
                                 self.location += 1;
+                                comment.push(ch);
                             }
                             None => {
+                                self.comments.push(Span {
+                                    from: comment_start_location,
+                                    to: self.location,
+                                });
                                 return Some(Err(Error::NoMatchingClosingParenthesis {
                                     span: Span::new(comment_start_location).into(),
-                                }))
+                                }));
                             }
                         }
                     }
+
+                    let span = Span {
+                        from: comment_start_location,
+                        to: self.location,
+                    };
+                    self.comments.push(span);
+                    self.scan_directive(&comment, span);
                 }
                 Some(b')') => {
+                    self.word_start = self.location;
+                    self.location += 1;
                     return Some(Err(Error::NoMatchingOpeningParenthesis {
-                        span: Span::new(self.location).into(),
-                    }))
+                        span: self.word_span().into(),
+                    }));
                 }
                 Some(ch) => break 'whitespace ch,
                 None => return None,
             }
         };
 
+        let word_start = self.location;
+        self.word_start = word_start;
+        if ch == b'@' {
+            self.close_label_scope(word_start);
+        }
+
         let mut symbols: Vec<Spanned<u8>> = Vec::new();
         symbols.push((ch).spanning(Span::new(self.location)));
         self.location += 1;
         let mut ignored_start: Option<Location> = None;
+        let mut trimmed: Vec<u8> = Vec::new();
 
         // TODO: Refactor the string scanning
-        if ch == b'"' || ch == b'\'' {
+        if ch == b'`' {
+            // Strings can contain whitespace and delimiters, and are never trimmed.
+            let mut escaped = false;
+            loop {
+                match self.chars.next() {
+                    Some(ch) => {
+                        symbols.push(ch.spanning(Span::new(self.location)));
+                        self.location += 1;
+                        match ch {
+                            b'`' if !escaped => break,
+                            b'\\' => escaped = !escaped,
+                            _ => escaped = false,
+                        }
+                    }
+                    None => {
+                        return Some(Err(Error::NoMatchingClosingBacktick {
+                            span: Span::new(word_start).into(),
+                        }))
+                    }
+                }
+            }
+        } else if ch == b'"' || ch == b'\'' {
             while self.chars.peek().is_some() && !is_whitespace(*self.chars.peek().unwrap()) {
                 let ch = self.chars.next().unwrap();
-                if symbols.len() < 64 {
+                if symbols.len() < self.token_length {
                     symbols.push(ch.spanning(Span::new(self.location)));
                 } else {
                     if ignored_start.is_none() {
                         ignored_start = Some(self.location);
                     }
+                    trimmed.push(ch);
                 }
                 self.location += 1;
             }
         } else {
             while !is_delimiter(self.chars.peek()) {
                 let ch = self.chars.next().unwrap();
-                if symbols.len() < 64 {
+                if symbols.len() < self.token_length {
                     symbols.push(ch.spanning(Span::new(self.location)));
                 } else {
                     if ignored_start.is_none() {
                         ignored_start = Some(self.location);
                     }
+                    trimmed.push(ch);
                 }
                 self.location += 1;
             }
         }
 
-        let word = Word::new(&symbols);
+        // In the UTF-8 mode, a character cut off in the middle is left out entirely.
+        if self.utf8 && ignored_start.is_some() {
+            let bytes: Vec<u8> = symbols.iter().map(|symbol| symbol.node).collect();
+            if let Err(error) = core::str::from_utf8(&bytes) {
+                if error.error_len().is_none() {
+                    ignored_start = Some(symbols[error.valid_up_to()].span.from);
+                    trimmed.splice(0..0, bytes[error.valid_up_to()..].iter().copied());
+                    symbols.truncate(error.valid_up_to());
+                }
+            }
+        }
+
+        let word = Word::new(&symbols, self.utf8, self.instruction_set);
+        let token: Vec<u8> = symbols.iter().map(|symbol| symbol.node).collect();
+
+        if !self.pending_names.is_empty() {
+            let names = core::mem::take(&mut self.pending_names);
+            if ch == b'@' {
+                self.label_scope = Some((names, word_start));
+            } else {
+                self.suppressions.push(Suppression {
+                    names,
+                    range: word_start.offset..self.location.offset,
+                });
+            }
+        }
 
         if let Some(ignored_location) = ignored_start {
+            // The rest of a raw word can become a raw word of its own.
+            let suggestions = if ch == b'"' {
+                vec![Suggestion {
+                    span: ignored_location.offset..ignored_location.offset,
+                    replacement: " \"".to_owned(),
+                }]
+            } else {
+                Vec::new()
+            };
             let warning = Warning::TokenTrimmed {
+                token: String::from_utf8_lossy(&token).into_owned(),
+                trimmed: String::from_utf8_lossy(&trimmed).into_owned(),
                 span: Span {
                     from: ignored_location,
                     to: self.location,
                 }
                 .into(),
+                suggestions,
             };
             Some(Ok((word, Some(warning))))
         } else {
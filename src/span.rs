@@ -2,14 +2,16 @@ use crate::{
     instruction::Instruction,
     token::{Identifier, Statement, Token},
 };
-use std::{
+use alloc::{string::String, vec::Vec};
+use core::{
     fmt,
     ops::{Add, AddAssign, Range},
 };
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
-pub(crate) struct Location {
-    pub(crate) offset: usize,
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Location {
+    pub offset: usize,
 }
 
 impl Add<usize> for Location {
@@ -29,9 +31,10 @@ impl AddAssign<usize> for Location {
 }
 
 #[derive(Copy, Clone, PartialEq, Eq, Hash)]
-pub(crate) struct Span {
-    pub(crate) from: Location,
-    pub(crate) to: Location,
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Span {
+    pub from: Location,
+    pub to: Location,
 }
 
 impl Span {
@@ -66,9 +69,9 @@ impl fmt::Debug for Span {
 }
 
 #[derive(PartialEq, Eq, Clone)]
-pub(crate) struct Spanned<T> {
-    pub(crate) node: T,
-    pub(crate) span: Span,
+pub struct Spanned<T> {
+    pub node: T,
+    pub span: Span,
 }
 
 impl<T> fmt::Debug for Spanned<T>
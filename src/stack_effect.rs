@@ -0,0 +1,573 @@
+use crate::{
+    token::{ScopedIdentifier, Statement},
+    walker::Definitions,
+    Instruction, InstructionKind, Spanned, Warning,
+};
+use alloc::{
+    collections::{BTreeMap, BTreeSet},
+    string::ToString,
+    vec::Vec,
+};
+
+/// How a routine is entered, which decides what is expected of its stacks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Entry {
+    /// A vector, i.e. the reset vector or a vector of a device, which is entered with empty
+    /// stacks and expected to leave them empty at `BRK`.
+    Vector,
+    /// A subroutine called with `JSR`, which is entered with its arguments on the working stack
+    /// and its return address on the return stack, and expected to leave only the return address
+    /// there at `JMP2r`.
+    Subroutine,
+}
+
+/// Heights of the stacks in bytes, relative to the entry of the routine.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Heights {
+    working: i32,
+    r#return: i32,
+}
+
+/// Effect of a subroutine on the working stack of its callers.
+#[derive(Debug, Clone, Copy)]
+struct Summary {
+    /// Change of the height of the working stack once the subroutine returns.
+    working: i32,
+    /// The lowest height of the working stack within the subroutine, i.e. the negated number of
+    /// bytes it takes from its caller.
+    lowest: i32,
+}
+
+struct Checker<'a> {
+    statements: &'a [Spanned<Statement>],
+    definitions: &'a Definitions,
+    /// Indices of the first statements emitted at each address.
+    starts: BTreeMap<u16, usize>,
+    /// Labels and sublabels defined right before the statements.
+    labels: BTreeMap<usize, Vec<&'a ScopedIdentifier>>,
+    /// Summaries of the routines analyzed so far, or `None` for the routines whose effect isn't
+    /// known.
+    analyzed: BTreeMap<(usize, Entry), Option<Summary>>,
+    /// Routines being analyzed, the calls to which are recursive.
+    analyzing: BTreeSet<usize>,
+    warnings: Vec<Warning>,
+}
+
+/// Checks the stack effects of the routines, reporting the vectors which underflow their stacks
+/// or don't leave them empty at `BRK`, and the subroutines which don't leave their return
+/// addresses on top of the return stack at `JMP2r`.
+///
+/// The check is conservative. Routines are followed through straight-line code, the jumps to
+/// their own sublabels, and the calls to the subroutines whose effects are known, and are given
+/// up on at anything else, e.g. computed jumps, inline data, or paths joining with different
+/// heights of the stacks. Only the routines entered with the reset vector, assigned to the
+/// vectors of the devices with `;routine .Device/vector DEO2`, or called with `;routine JSR2` or
+/// `,routine JSR` are checked. The effects of the instructions are those of Uxn, regardless of
+/// the opcode table.
+pub(crate) fn check(statements: &[Spanned<Statement>], definitions: &Definitions) -> Vec<Warning> {
+    let mut checker = Checker::new(statements, definitions);
+    for (index, entry) in checker.roots() {
+        checker.analyze(index, entry);
+    }
+
+    checker.warnings
+}
+
+impl<'a> Checker<'a> {
+    fn new(statements: &'a [Spanned<Statement>], definitions: &'a Definitions) -> Self {
+        let mut starts: BTreeMap<u16, usize> = BTreeMap::new();
+        let mut address: u16 = 0;
+        for (index, statement) in statements.iter().enumerate() {
+            match statement.node {
+                Statement::PadAbsolute(value) => address = value,
+                Statement::PadRelative(value) => address = address.wrapping_add(value),
+                ref node => {
+                    starts.entry(address).or_insert(index);
+                    address = address.wrapping_add(node.size());
+                }
+            }
+        }
+
+        let mut labels: BTreeMap<usize, Vec<&ScopedIdentifier>> = BTreeMap::new();
+        for (scoped_identifier, (address, _)) in &definitions.labels {
+            if *address < 0x0100 || definitions.constants.contains(scoped_identifier) {
+                continue;
+            }
+            if let Some(index) = starts.get(address) {
+                labels.entry(*index).or_default().push(scoped_identifier);
+            }
+        }
+
+        Self {
+            statements,
+            definitions,
+            starts,
+            labels,
+            analyzed: BTreeMap::new(),
+            analyzing: BTreeSet::new(),
+            warnings: Vec::new(),
+        }
+    }
+
+    /// Finds the routines entered from outside of the code, i.e. the reset vector, the vectors
+    /// of the devices, and the subroutines.
+    fn roots(&self) -> BTreeSet<(usize, Entry)> {
+        let mut roots = BTreeSet::new();
+        // The program is entered at the reset vector whether or not a label is defined there.
+        if let Some(index) = self.starts.get(&0x0100) {
+            roots.insert((*index, Entry::Vector));
+        }
+
+        for (index, statement) in self.statements.iter().enumerate() {
+            let next = self
+                .statements
+                .get(index + 1)
+                .map(|statement| &statement.node);
+            let entry = match (&statement.node, next) {
+                (
+                    Statement::LiteralAbsoluteAddress(scoped_identifier),
+                    Some(Statement::Instruction(instruction)),
+                ) if is_plain(instruction, InstructionKind::JumpStash, true) => {
+                    Some((scoped_identifier, Entry::Subroutine))
+                }
+                (
+                    Statement::LiteralRelativeAddress(scoped_identifier),
+                    Some(Statement::Instruction(instruction)),
+                ) if is_plain(instruction, InstructionKind::JumpStash, false) => {
+                    Some((scoped_identifier, Entry::Subroutine))
+                }
+                (
+                    Statement::LiteralAbsoluteAddress(scoped_identifier),
+                    Some(Statement::LiteralZeroPageAddress(_)),
+                ) => match self
+                    .statements
+                    .get(index + 2)
+                    .map(|statement| &statement.node)
+                {
+                    Some(Statement::Instruction(instruction))
+                        if is_plain(instruction, InstructionKind::DeviceOut, true) =>
+                    {
+                        Some((scoped_identifier, Entry::Vector))
+                    }
+                    _ => None,
+                },
+                _ => None,
+            };
+            if let Some((scoped_identifier, entry)) = entry {
+                if let Some(index) = self.index_of(scoped_identifier) {
+                    roots.insert((index, entry));
+                }
+            }
+        }
+
+        roots
+    }
+
+    /// Returns the index of the statement the label or sublabel is defined before.
+    fn index_of(&self, scoped_identifier: &ScopedIdentifier) -> Option<usize> {
+        let (address, _) = self.definitions.labels.get(scoped_identifier)?;
+        let index = *self.starts.get(address)?;
+        match self.labels.get(&index) {
+            Some(labels) if labels.contains(&scoped_identifier) => Some(index),
+            _ => None,
+        }
+    }
+
+    /// Whether a label, rather than only sublabels, is defined before the statement, i.e. a new
+    /// routine starts there.
+    fn starts_routine(&self, index: usize) -> bool {
+        match self.labels.get(&index) {
+            Some(labels) => labels
+                .iter()
+                .any(|scoped_identifier| matches!(scoped_identifier, ScopedIdentifier::Label(_))),
+            None => false,
+        }
+    }
+
+    /// Analyzes the routine starting at the statement once, returning its summary if it's a
+    /// subroutine with a known effect.
+    fn analyze(&mut self, start: usize, entry: Entry) -> Option<Summary> {
+        if let Some(summary) = self.analyzed.get(&(start, entry)) {
+            return *summary;
+        }
+        if !self.analyzing.insert(start) {
+            return None;
+        }
+        let (summary, warnings) = self.simulate(start, entry);
+        self.analyzing.remove(&start);
+        self.analyzed.insert((start, entry), summary);
+        self.warnings.extend(warnings);
+        summary
+    }
+
+    /// Follows the routine starting at the statement, returning its summary along with the
+    /// warnings about it. No warnings are returned if the paths through the routine can't be
+    /// followed consistently.
+    fn simulate(&mut self, start: usize, entry: Entry) -> (Option<Summary>, Vec<Warning>) {
+        let statements = self.statements;
+        let (name, label_span) = match self.labels.get(&start).and_then(|labels| labels.first()) {
+            Some(scoped_identifier) => (
+                scoped_identifier.to_string(),
+                self.definitions.labels[*scoped_identifier].1,
+            ),
+            // The reset vector is referred to by its pad if no label is defined at it.
+            None if self.starts.get(&0x0100) == Some(&start) => {
+                let pad_span = statements[..start]
+                    .iter()
+                    .rev()
+                    .find(|statement| matches!(statement.node, Statement::PadAbsolute(_)))
+                    .unwrap_or(&statements[start])
+                    .span;
+                ("|0100".to_string(), pad_span)
+            }
+            None => return (None, Vec::new()),
+        };
+        let end = (start + 1..statements.len())
+            .find(|index| self.starts_routine(*index))
+            .unwrap_or(statements.len());
+        // Vectors start with empty stacks, while the depths of the stacks of the callers of the
+        // subroutines aren't known.
+        let floor = match entry {
+            Entry::Vector => 0,
+            Entry::Subroutine => i32::MIN,
+        };
+
+        let mut warnings: Vec<Warning> = Vec::new();
+        // Heights of the stacks before the current statement, or `None` if the execution can't
+        // fall through to it.
+        let mut heights = Some(Heights {
+            working: 0,
+            r#return: 0,
+        });
+        let mut lowest = 0;
+        // Heights of the working stack at the returns of the subroutine.
+        let mut returns: Vec<i32> = Vec::new();
+        // Whether some of the paths through the routine couldn't be followed to their ends.
+        let mut incomplete = false;
+        // Heights of the stacks at the sublabels jumped to from before them, and at the labels
+        // passed so far, for the jumps back to them.
+        let mut jumps: BTreeMap<usize, Heights> = BTreeMap::new();
+        let mut visited: BTreeMap<usize, Heights> = BTreeMap::new();
+
+        let mut index = start;
+        while index < end {
+            if let Some(jumped) = jumps.remove(&index) {
+                match heights {
+                    Some(current) if current != jumped => return (None, Vec::new()),
+                    _ => heights = Some(jumped),
+                }
+            }
+            let mut current = match heights {
+                Some(current) => current,
+                // The code after an unconditional jump is only reachable from the jumps to it.
+                None => match jumps.keys().next() {
+                    Some(next) => {
+                        index = *next;
+                        continue;
+                    }
+                    None => break,
+                },
+            };
+            if self.labels.contains_key(&index) {
+                visited.insert(index, current);
+            }
+
+            let statement = &statements[index];
+            index += 1;
+            match &statement.node {
+                Statement::LiteralHexByte(_)
+                | Statement::LiteralZeroPageAddress(_)
+                | Statement::LiteralRelativeAddress(_) => current.working += 1,
+                Statement::LiteralHexShort(_) | Statement::LiteralAbsoluteAddress(_) => {
+                    current.working += 2
+                }
+                Statement::Instruction(instruction) => {
+                    let mnemonic = instruction.to_string();
+                    let underflow = |r#return: bool| Warning::StackUnderflow {
+                        name: name.clone(),
+                        instruction: mnemonic.clone(),
+                        r#return,
+                        span: statement.span.into(),
+                        other_span: label_span.into(),
+                    };
+                    let (source, other) = if instruction.r#return {
+                        (&mut current.r#return, &mut current.working)
+                    } else {
+                        (&mut current.working, &mut current.r#return)
+                    };
+
+                    if let Some((taken, put, stashed)) = effect(instruction) {
+                        if *source - taken < floor {
+                            warnings.push(underflow(instruction.r#return));
+                            incomplete = true;
+                            break;
+                        }
+                        if !instruction.r#return {
+                            lowest = lowest.min(*source - taken);
+                        }
+                        if !instruction.keep {
+                            *source -= taken;
+                        }
+                        *source += put;
+                        *other += stashed;
+                        heights = Some(current);
+                        continue;
+                    }
+
+                    match instruction.instruction_kind {
+                        InstructionKind::BreakOrLiteral
+                            if !instruction.keep && !instruction.short && !instruction.r#return =>
+                        {
+                            if entry == Entry::Vector {
+                                for (bytes, r#return) in
+                                    [(current.working, false), (current.r#return, true)]
+                                        .iter()
+                                        .copied()
+                                {
+                                    if bytes != 0 {
+                                        warnings.push(Warning::StackHeightUnexpected {
+                                            name: name.clone(),
+                                            instruction: mnemonic.clone(),
+                                            r#return,
+                                            bytes,
+                                            span: statement.span.into(),
+                                            other_span: label_span.into(),
+                                        });
+                                    }
+                                }
+                            }
+                            heights = None;
+                            continue;
+                        }
+                        // `LIT` and `LIT2` followed by their operands.
+                        InstructionKind::BreakOrLiteral
+                            if !instruction.r#return && instruction.keep != instruction.short =>
+                        {
+                            let length = if instruction.short { 2 } else { 1 };
+                            match operand_end(statements, index, length) {
+                                Some(operand_end) if operand_end <= end => {
+                                    current.working += length as i32;
+                                    index = operand_end;
+                                }
+                                _ => {
+                                    incomplete = true;
+                                    break;
+                                }
+                            }
+                        }
+                        InstructionKind::Jump
+                        | InstructionKind::JumpCondition
+                        | InstructionKind::JumpStash
+                            if !instruction.keep =>
+                        {
+                            let address_length = if instruction.short { 2 } else { 1 };
+                            let taken = match instruction.instruction_kind {
+                                InstructionKind::JumpCondition => address_length + 1,
+                                _ => address_length,
+                            };
+                            if *source - taken < floor {
+                                warnings.push(underflow(instruction.r#return));
+                                incomplete = true;
+                                break;
+                            }
+                            // `JMP2r`, the return of a subroutine.
+                            if instruction.r#return
+                                && instruction.short
+                                && instruction.instruction_kind == InstructionKind::Jump
+                            {
+                                if entry == Entry::Subroutine {
+                                    if current.r#return != 0 {
+                                        warnings.push(Warning::StackHeightUnexpected {
+                                            name: name.clone(),
+                                            instruction: mnemonic,
+                                            r#return: true,
+                                            bytes: current.r#return,
+                                            span: statement.span.into(),
+                                            other_span: label_span.into(),
+                                        });
+                                    }
+                                    returns.push(current.working);
+                                } else {
+                                    incomplete = true;
+                                }
+                                heights = None;
+                                continue;
+                            }
+                            if instruction.r#return {
+                                incomplete = true;
+                                break;
+                            }
+                            current.working -= taken;
+                            lowest = lowest.min(current.working);
+
+                            let target = self.jump_target(index - 1, instruction);
+                            match instruction.instruction_kind {
+                                InstructionKind::JumpStash => {
+                                    let summary = match target {
+                                        Some(target) => self.analyze(target, Entry::Subroutine),
+                                        None => None,
+                                    };
+                                    match summary {
+                                        Some(summary) => {
+                                            if current.working + summary.lowest < floor {
+                                                warnings.push(underflow(false));
+                                                incomplete = true;
+                                                break;
+                                            }
+                                            lowest = lowest.min(current.working + summary.lowest);
+                                            current.working += summary.working;
+                                        }
+                                        None => {
+                                            incomplete = true;
+                                            break;
+                                        }
+                                    }
+                                }
+                                kind => {
+                                    match target {
+                                        Some(target) if (start..end).contains(&target) => {
+                                            let known = if target < index {
+                                                visited.get(&target).copied()
+                                            } else {
+                                                jumps.get(&target).copied()
+                                            };
+                                            match known {
+                                                Some(known) if known != current => {
+                                                    return (None, Vec::new())
+                                                }
+                                                Some(_) => (),
+                                                None if target < index => incomplete = true,
+                                                None => {
+                                                    jumps.insert(target, current);
+                                                }
+                                            }
+                                        }
+                                        // Jumps to other routines and to the computed
+                                        // addresses.
+                                        _ => incomplete = true,
+                                    }
+                                    if kind == InstructionKind::Jump {
+                                        heights = None;
+                                        continue;
+                                    }
+                                }
+                            }
+                        }
+                        _ => {
+                            incomplete = true;
+                            break;
+                        }
+                    }
+                }
+                // Inline data, pads, or anything else that isn't known to be code.
+                _ => {
+                    incomplete = true;
+                    break;
+                }
+            }
+            heights = Some(current);
+        }
+        // Routines falling through to the next ones.
+        if index >= end && heights.is_some() {
+            incomplete = true;
+        }
+
+        let summary = match (entry, returns.first()) {
+            (Entry::Subroutine, Some(working))
+                if !incomplete && returns.iter().all(|other| other == working) =>
+            {
+                Some(Summary {
+                    working: *working,
+                    lowest,
+                })
+            }
+            _ => None,
+        };
+
+        (summary, warnings)
+    }
+
+    /// Returns the index of the statement the jump at the given index jumps to, if its address
+    /// is the literal right before it.
+    fn jump_target(&self, index: usize, instruction: &Instruction) -> Option<usize> {
+        let previous = &self.statements.get(index.checked_sub(1)?)?.node;
+        match previous {
+            Statement::LiteralAbsoluteAddress(scoped_identifier) if instruction.short => {
+                self.index_of(scoped_identifier)
+            }
+            Statement::LiteralRelativeAddress(scoped_identifier) if !instruction.short => {
+                self.index_of(scoped_identifier)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Whether the instruction is of the given kind, in the short mode or not, and in neither the
+/// keep nor the return mode.
+fn is_plain(instruction: &Instruction, instruction_kind: InstructionKind, short: bool) -> bool {
+    instruction.instruction_kind == instruction_kind
+        && instruction.short == short
+        && !instruction.keep
+        && !instruction.r#return
+}
+
+/// Returns the numbers of bytes the instruction takes from the stack it operates on, puts on
+/// that stack, and puts on the other stack, unless it breaks, pushes a literal, or jumps.
+fn effect(instruction: &Instruction) -> Option<(i32, i32, i32)> {
+    let size = if instruction.short { 2 } else { 1 };
+
+    Some(match instruction.instruction_kind {
+        InstructionKind::Increment => (size, size, 0),
+        InstructionKind::Pop => (size, 0, 0),
+        InstructionKind::Duplicate => (size, 2 * size, 0),
+        InstructionKind::NoOperation => (2 * size, size, 0),
+        InstructionKind::Swap => (2 * size, 2 * size, 0),
+        InstructionKind::Over => (2 * size, 3 * size, 0),
+        InstructionKind::Rotate => (3 * size, 3 * size, 0),
+        InstructionKind::Equal
+        | InstructionKind::NotEqual
+        | InstructionKind::GreaterThan
+        | InstructionKind::LesserThan => (2 * size, 1, 0),
+        InstructionKind::Stash => (size, 0, size),
+        InstructionKind::LoadZeroPage
+        | InstructionKind::LoadRelative
+        | InstructionKind::DeviceIn => (1, size, 0),
+        InstructionKind::StoreZeroPage
+        | InstructionKind::StoreRelative
+        | InstructionKind::DeviceOut => (size + 1, 0, 0),
+        InstructionKind::LoadAbsolute => (2, size, 0),
+        InstructionKind::StoreAbsolute => (size + 2, 0, 0),
+        InstructionKind::Add
+        | InstructionKind::Subtract
+        | InstructionKind::Multiply
+        | InstructionKind::Divide
+        | InstructionKind::And
+        | InstructionKind::Or
+        | InstructionKind::ExclusiveOr => (2 * size, size, 0),
+        InstructionKind::Shift => (size + 1, size, 0),
+        InstructionKind::BreakOrLiteral
+        | InstructionKind::Jump
+        | InstructionKind::JumpCondition
+        | InstructionKind::JumpStash => return None,
+    })
+}
+
+/// Returns the index of the statement after the raw bytes of the given length starting at the
+/// given index, i.e. the operand of a literal, unless the bytes don't add up to the length.
+fn operand_end(statements: &[Spanned<Statement>], mut index: usize, length: u16) -> Option<usize> {
+    let mut remaining = length;
+    while remaining > 0 {
+        let size = match &statements.get(index)?.node {
+            node @ Statement::RawHexByte(_)
+            | node @ Statement::RawHexShort(_)
+            | node @ Statement::RawChar(_)
+            | node @ Statement::RawAddress(_) => node.size(),
+            _ => return None,
+        };
+        remaining = remaining.checked_sub(size)?;
+        index += 1;
+    }
+
+    Some(index)
+}
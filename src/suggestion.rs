@@ -0,0 +1,45 @@
+use alloc::vec;
+/// Computes the edit distance between two byte strings, counting insertions, deletions,
+/// substitutions, and transpositions of two adjacent bytes as a single edit each.
+fn edit_distance(a: &[u8], b: &[u8]) -> usize {
+    let mut distances = vec![vec![0; b.len() + 1]; a.len() + 1];
+
+    for (i, row) in distances.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, distance) in distances[0].iter_mut().enumerate() {
+        *distance = j;
+    }
+
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let substitution_cost = if a[i - 1] == b[j - 1] { 0 } else { 1 };
+            let mut distance = (distances[i - 1][j - 1] + substitution_cost)
+                .min(distances[i - 1][j] + 1)
+                .min(distances[i][j - 1] + 1);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                distance = distance.min(distances[i - 2][j - 2] + 1);
+            }
+            distances[i][j] = distance;
+        }
+    }
+
+    distances[a.len()][b.len()]
+}
+
+/// Finds the candidate most similar to `name`, as long as it is similar enough to be
+/// considered a typo. Ties are broken by picking the lexicographically smallest candidate,
+/// so that the result doesn't depend on the order of `candidates`.
+pub(crate) fn find_similar<'a>(
+    name: &[u8],
+    candidates: impl Iterator<Item = &'a [u8]>,
+) -> Option<&'a [u8]> {
+    let max_distance = (name.len() / 3).max(1);
+
+    candidates
+        .filter(|candidate| *candidate != name)
+        .map(|candidate| (edit_distance(name, candidate), candidate))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .min()
+        .map(|(_, candidate)| candidate)
+}
@@ -0,0 +1,62 @@
+use crate::{
+    token::ScopedIdentifier,
+    walker::{Definitions, LIBRARY_START},
+};
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::ops::Range;
+
+/// A label or a sublabel defined in the program, along with its resolved address.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Symbol {
+    /// Name of the label, or the full `label/sublabel` path of the sublabel.
+    pub name: String,
+    /// The resolved address, or the offset from the start of the library if the symbol is
+    /// relative.
+    pub address: u16,
+    /// Whether the symbol is defined in a library before any absolute pad, in which case its
+    /// address depends on where the library gets included.
+    pub relative: bool,
+    /// Span of the label or sublabel definition.
+    pub span: Range<usize>,
+    /// Number of bytes reserved for the symbol with the relative pads right after it, if it's
+    /// defined in the zero page, e.g. the size of a variable or the width of a device port.
+    pub size: Option<u16>,
+}
+
+/// Lists every label and sublabel in the order of their addresses, and then in the order of
+/// their definitions. Constants are left out, as their values are not addresses.
+pub(crate) fn collect(definitions: &Definitions) -> Vec<Symbol> {
+    let mut symbols: Vec<(usize, Symbol)> = definitions
+        .labels
+        .iter()
+        .filter(|(scoped_identifier, _)| !definitions.constants.contains(scoped_identifier))
+        .map(|(scoped_identifier, (address, span))| {
+            let relative = definitions.relative_labels.contains(scoped_identifier);
+            (
+                span.from.offset,
+                Symbol {
+                    name: ScopedIdentifier::to_string(scoped_identifier),
+                    address: if relative {
+                        address - LIBRARY_START
+                    } else {
+                        *address
+                    },
+                    relative,
+                    span: (*span).into(),
+                    size: definitions
+                        .variable_sizes
+                        .get(scoped_identifier)
+                        .or_else(|| definitions.port_widths.get(scoped_identifier))
+                        .copied(),
+                },
+            )
+        })
+        .collect();
+    symbols.sort_by_key(|(offset, symbol)| (!symbol.relative, symbol.address, *offset));
+
+    symbols.into_iter().map(|(_, symbol)| symbol).collect()
+}
@@ -1,7 +1,14 @@
 use super::Instruction;
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
 
+/// A statement of the program, with the macros expanded and the sublabels resolved, which emits
+/// the bytes of the binary.
 #[derive(Debug, Clone)]
-pub(crate) enum Statement {
+pub enum Statement {
     Instruction(Instruction),
     PadAbsolute(u16),
     PadRelative(u16),
@@ -15,23 +22,52 @@ pub(crate) enum Statement {
     RawHexShort(u16),
     RawChar(u8),
     RawWord(Vec<u8>),
+    /// Sum of the bytes from the first label up to the second one.
+    Checksum(ScopedIdentifier, ScopedIdentifier),
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
-pub(crate) enum ScopedIdentifier {
+impl Statement {
+    /// Returns the number of bytes the statement emits.
+    pub(crate) fn size(&self) -> u16 {
+        match self {
+            Self::Instruction(_) | Self::RawHexByte(_) | Self::RawChar(_) => 1,
+            Self::LiteralZeroPageAddress(_)
+            | Self::LiteralRelativeAddress(_)
+            | Self::LiteralHexByte(_)
+            | Self::RawAddress(_)
+            | Self::RawHexShort(_)
+            | Self::Checksum(_, _) => 2,
+            Self::LiteralAbsoluteAddress(_) | Self::LiteralHexShort(_) => 3,
+            Self::RawWord(word) => word.len() as u16,
+            Self::PadAbsolute(_) | Self::PadRelative(_) => 0,
+        }
+    }
+}
+
+/// A label, or a sublabel along with the label in which scope it is defined.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ScopedIdentifier {
     Label(Vec<u8>),
     Sublabel(Vec<u8>, Vec<u8>),
 }
 
 impl ScopedIdentifier {
+    /// Sublabels are considered capital if either their own name or the name of the label
+    /// in which scope they are defined starts with a capital letter.
     pub fn is_captital(&self) -> bool {
         match self {
-            Self::Label(name) => name.iter().next().unwrap().is_ascii_uppercase(),
-            Self::Sublabel(name, _) => name.iter().next().unwrap().is_ascii_uppercase(),
+            Self::Label(name) => starts_with_uppercase(name),
+            Self::Sublabel(name, sublabel_name) => {
+                starts_with_uppercase(name) || starts_with_uppercase(sublabel_name)
+            }
         }
     }
 }
 
+fn starts_with_uppercase(name: &[u8]) -> bool {
+    matches!(name.first(), Some(ch) if ch.is_ascii_uppercase())
+}
+
 impl ToString for ScopedIdentifier {
     fn to_string(&self) -> String {
         match self {
@@ -48,7 +84,7 @@ impl ToString for ScopedIdentifier {
 }
 
 #[derive(Debug, Clone)]
-pub(crate) enum Token {
+pub enum Token {
     OpeningBracket,
     ClosingBracket,
     OpeningBrace,
@@ -58,7 +94,14 @@ pub(crate) enum Token {
     MacroInvoke(Vec<u8>),
     PadAbsolute(u16),
     PadRelative(u16),
+    /// An absolute pad rune with no hexadecimal number after it, which is expected to be
+    /// followed by an expression in brackets.
+    PadAbsoluteExpression,
+    /// A relative pad rune with no hexadecimal number after it, which is expected to be
+    /// followed by an expression in brackets.
+    PadRelativeExpression,
     LabelDefine(Vec<u8>),
+    ConstantDefine(Vec<u8>),
     SublabelDefine(Vec<u8>),
     LiteralZeroPageAddress(Identifier),
     LiteralRelativeAddress(Identifier),
@@ -70,10 +113,40 @@ pub(crate) enum Token {
     RawHexShort(u16),
     RawChar(u8),
     RawWord(Vec<u8>),
+    Include(Vec<u8>),
+    IfDefined,
+    IfNotDefined,
+    Else,
+    End,
+    Repeat,
+    ZeroPage,
+    Library,
+    Checksum,
+    Query(Query),
+}
+
+impl Token {
+    /// Whether the token is a conditional assembly directive or a directive closed like one,
+    /// which is never skipped.
+    pub fn is_conditional(&self) -> bool {
+        matches!(
+            self,
+            Self::IfDefined | Self::IfNotDefined | Self::Else | Self::End | Self::Library
+        )
+    }
+}
+
+/// A read-only value known only during the assembly, which can be used in expressions.
+#[derive(Debug, Clone)]
+pub enum Query {
+    /// `$here`, the address pointer.
+    Here,
+    /// `$defined:NAME`, which is 1 if the macro `NAME` has been defined so far and 0 otherwise.
+    Defined(Vec<u8>),
 }
 
 #[derive(Debug, Clone)]
-pub(crate) enum Identifier {
+pub enum Identifier {
     Label(Vec<u8>),
     Sublabel(Vec<u8>),
     Path(Vec<u8>, Vec<u8>),
@@ -1,13 +1,14 @@
-use super::{Identifier, Token};
-use super::{Span, Spanned, Spanning};
+use super::{Identifier, Query, Token};
+use super::{Location, Span, Spanned, Spanning};
 use crate::anomalies::{Error, Warning};
-use crate::{Instruction, InstructionKind};
-use std::fmt;
+use crate::{Instruction, InstructionSet, OpcodeTable};
+use alloc::{borrow::ToOwned, format, string::String, vec, vec::Vec};
+use core::fmt;
 
 mod hex_number;
 
 #[derive(Clone)]
-pub(crate) enum Word {
+pub enum Word {
     Fine {
         token: Spanned<Token>,
         warnings: Vec<Warning>,
@@ -15,29 +16,66 @@ pub(crate) enum Word {
     Faulty {
         errors: Vec<Error>,
         warnings: Vec<Warning>,
+        /// Token taking up as many bytes as the word was meant to, with which the assembly can
+        /// go on in order to find the errors of the later passes as well.
+        placeholder: Option<Spanned<Token>>,
     },
 }
 
 impl Word {
-    pub(crate) fn new(symbols: &[Spanned<u8>]) -> Self {
+    /// Turns the symbols into a word. In the UTF-8 mode, a raw character rune can be followed by
+    /// a character encoded with multiple bytes. Instructions are recognized by their mnemonics in
+    /// the instruction set.
+    pub(crate) fn new(
+        symbols: &[Spanned<u8>],
+        utf8: bool,
+        instruction_set: &dyn InstructionSet,
+    ) -> Self {
         debug_assert!({
             const WHITESPACES: [u8; 6] = [b' ', b'\t', b'\n', 0x0b, 0x0c, b'\r'];
 
             let chars: Vec<u8> = symbols.iter().map(|Spanned { node: ch, .. }| *ch).collect();
-            WHITESPACES.iter().all(|ch| !chars.contains(ch))
+            chars[0] == b'`' || WHITESPACES.iter().all(|ch| !chars.contains(ch))
         });
 
-        match tokenize(symbols) {
+        match tokenize(symbols, utf8, instruction_set) {
             Ok((token, warnings)) => Self::Fine { token, warnings },
             Err(error) => Self::Faulty {
                 errors: vec![error],
                 warnings: Vec::new(),
+                placeholder: placeholder(symbols),
             },
         }
     }
 }
 
-fn tokenize(word: &[Spanned<u8>]) -> Result<(Spanned<Token>, Vec<Warning>), Error> {
+/// Finds the token standing in for a faulty number or character. Its size is guessed from the
+/// number of digits, and its value is always zero.
+fn placeholder(word: &[Spanned<u8>]) -> Option<Spanned<Token>> {
+    // Numbers of characters before the digits, and of the digits of the longest byte.
+    let (prefix_length, byte_digits) = match word.first()?.node {
+        b'\'' => return Some(Token::RawChar(0).spanning(to_span(word)?)),
+        b'#' if word.len() > 2 && word[1].node == b'+' => (2, 3),
+        b'#' if word.len() > 5 && word[1].node == b'b' && matches!(word[2].node, b'0' | b'1') => {
+            (2, 8)
+        }
+        b'#' => (1, 2),
+        _ => return None,
+    };
+    let token = if word.len() - prefix_length <= byte_digits {
+        Token::LiteralHexByte(0)
+    } else {
+        Token::LiteralHexShort(0)
+    };
+
+    Some(token.spanning(to_span(word)?))
+}
+
+fn tokenize(
+    word: &[Spanned<u8>],
+    utf8: bool,
+    instruction_set: &dyn InstructionSet,
+) -> Result<(Spanned<Token>, Vec<Warning>), Error> {
     match word.first().cloned().unwrap() {
         Spanned { node: b'[', span } => {
             return Ok((Token::OpeningBracket.spanning(span), Vec::new()))
@@ -51,7 +89,7 @@ fn tokenize(word: &[Spanned<u8>]) -> Result<(Spanned<Token>, Vec<Warning>), Erro
         Spanned { node: b'}', span } => {
             return Ok((Token::ClosingBrace.spanning(span), Vec::new()))
         }
-        Spanned { node: b'%', span } => match parse_macro(span, &word[1..]) {
+        Spanned { node: b'%', span } => match parse_macro(span, &word[1..], instruction_set) {
             Ok(name) => {
                 return Ok((
                     Token::MacroDefine(name).spanning(to_span(word).unwrap()),
@@ -60,6 +98,10 @@ fn tokenize(word: &[Spanned<u8>]) -> Result<(Spanned<Token>, Vec<Warning>), Erro
             }
             Err(err) => Err(err),
         },
+        Spanned { node: b'|', .. } if word.len() == 1 => Ok((
+            Token::PadAbsoluteExpression.spanning(to_span(word).unwrap()),
+            Vec::new(),
+        )),
         Spanned { node: b'|', span } => hex_number::parse_hex_number_unconstrained(&word[1..])
             .map_err(|err| match err {
                 hex_number::Error2::DigitExpected => Error::HexNumberExpected { span: span.into() },
@@ -76,6 +118,14 @@ fn tokenize(word: &[Spanned<u8>]) -> Result<(Spanned<Token>, Vec<Warning>), Erro
             })
             .map(|value| Token::PadAbsolute(value))
             .map(|token| (token.spanning(to_span(word).unwrap()), Vec::new())),
+        Spanned { node: b'$', .. } if word.len() == 1 => Ok((
+            Token::PadRelativeExpression.spanning(to_span(word).unwrap()),
+            Vec::new(),
+        )),
+        Spanned { node: b'$', .. } if parse_query(&word[1..]).is_some() => Ok((
+            Token::Query(parse_query(&word[1..]).unwrap()).spanning(to_span(word).unwrap()),
+            Vec::new(),
+        )),
         Spanned { node: b'$', span } => hex_number::parse_hex_number_unconstrained(&word[1..])
             .map_err(|err| match err {
                 hex_number::Error2::DigitExpected => Error::HexNumberExpected { span: span.into() },
@@ -107,7 +157,7 @@ fn tokenize(word: &[Spanned<u8>]) -> Result<(Spanned<Token>, Vec<Warning>), Erro
                         Ok((
                             Token::LabelDefine(to_string(&word[1..]))
                                 .spanning(to_span(word).unwrap()),
-                            Vec::new(),
+                            instruction_shadowed(word, false, instruction_set),
                         ))
                     }
                 } else {
@@ -119,6 +169,33 @@ fn tokenize(word: &[Spanned<u8>]) -> Result<(Spanned<Token>, Vec<Warning>), Erro
                 Err(Error::LabelExpected { span: span.into() })
             }
         }
+        Spanned { node: b'=', span } => {
+            if !word[1..].is_empty() {
+                if word[1].node != b'&' {
+                    if let Some(position) = word[1..]
+                        .iter()
+                        .map(|Spanned { node: ch, .. }| *ch)
+                        .position(|c| c == b'/')
+                    {
+                        Err(Error::SlashInLabelOrSublabel {
+                            span: word[1 + position].span.into(),
+                        })
+                    } else {
+                        Ok((
+                            Token::ConstantDefine(to_string(&word[1..]))
+                                .spanning(to_span(word).unwrap()),
+                            Vec::new(),
+                        ))
+                    }
+                } else {
+                    Err(Error::AmpersandAtTheStartOfLabel {
+                        span: word[1].span.into(),
+                    })
+                }
+            } else {
+                Err(Error::ConstantNameExpected { span: span.into() })
+            }
+        }
         Spanned { node: b'&', span } => {
             if !word[1..].is_empty() {
                 if let Some(position) = word[1..]
@@ -133,13 +210,24 @@ fn tokenize(word: &[Spanned<u8>]) -> Result<(Spanned<Token>, Vec<Warning>), Erro
                     Ok((
                         Token::SublabelDefine(to_string(&word[1..]))
                             .spanning(to_span(word).unwrap()),
-                        Vec::new(),
+                        instruction_shadowed(word, true, instruction_set),
                     ))
                 }
             } else {
                 Err(Error::LabelExpected { span: span.into() })
             }
         }
+        Spanned { node: b'#', .. } if word.len() > 2 && word[1].node == b'+' => {
+            parse_decimal_number(&word[2..])
+                .map(|token| (token.spanning(to_span(word).unwrap()), Vec::new()))
+        }
+        // Binary numbers are longer than any hexadecimal number starting with `b`.
+        Spanned { node: b'#', .. }
+            if word.len() > 5 && word[1].node == b'b' && matches!(word[2].node, b'0' | b'1') =>
+        {
+            parse_binary_number(&word[2..])
+                .map(|token| (token.spanning(to_span(word).unwrap()), Vec::new()))
+        }
         Spanned { node: b'#', span } => match hex_number::parse_hex_number(&word[1..]) {
             Ok(hex_number::HexNumber::Byte(value)) => Ok(Token::LiteralHexByte(value)),
             Ok(hex_number::HexNumber::Short(value)) => Ok(Token::LiteralHexShort(value)),
@@ -210,6 +298,10 @@ fn tokenize(word: &[Spanned<u8>]) -> Result<(Spanned<Token>, Vec<Warning>), Erro
                     Token::RawChar(bytes[0]).spanning(Span::combine(&span, &word[1].span)),
                     Vec::new(),
                 )),
+                _ if utf8 && is_single_character(&bytes) => Ok((
+                    Token::RawWord(bytes).spanning(to_span(word).unwrap()),
+                    Vec::new(),
+                )),
                 _ => {
                     let span = to_span(&word[1..]).unwrap();
                     Err(Error::MoreThanOneByteFound {
@@ -219,11 +311,37 @@ fn tokenize(word: &[Spanned<u8>]) -> Result<(Spanned<Token>, Vec<Warning>), Erro
                 }
             }
         }
-        Spanned { node: b'"', .. } => {
-            return Ok((
-                Token::RawWord(to_string(&word[1..])).spanning(to_span(word).unwrap()),
-                Vec::new(),
-            ));
+        Spanned { node: b'"', .. } => Ok((
+            Token::RawWord(unescape(&word[1..])?).spanning(to_span(word).unwrap()),
+            Vec::new(),
+        )),
+        // The scanner makes sure that the string ends with a closing backtick.
+        Spanned { node: b'`', .. } => Ok((
+            Token::RawWord(unescape(&word[1..word.len() - 1])?).spanning(to_span(word).unwrap()),
+            Vec::new(),
+        )),
+        Spanned { node: b'~', .. } => Ok((
+            Token::Include(to_string(&word[1..])).spanning(to_span(word).unwrap()),
+            Vec::new(),
+        )),
+        Spanned { node: b'?', .. } => {
+            let token = match to_string(&word[1..]).as_slice() {
+                b"ifdef" => Token::IfDefined,
+                b"ifndef" => Token::IfNotDefined,
+                b"else" => Token::Else,
+                b"end" => Token::End,
+                b"repeat" => Token::Repeat,
+                b"zeropage" => Token::ZeroPage,
+                b"library" => Token::Library,
+                b"checksum" => Token::Checksum,
+                _ => {
+                    return Err(Error::ConditionalDirectiveUnknown {
+                        directive: String::from_utf8_lossy(&to_string(word)).into_owned(),
+                        span: to_span(word).unwrap().into(),
+                    })
+                }
+            };
+            Ok((token.spanning(to_span(word).unwrap()), Vec::new()))
         }
         _ => {
             if let Ok(hex_number) = hex_number::parse_hex_number(word) {
@@ -236,7 +354,7 @@ fn tokenize(word: &[Spanned<u8>]) -> Result<(Spanned<Token>, Vec<Warning>), Erro
                     Vec::new(),
                 ));
             };
-            if let Some((instruction, new_warnings)) = parse_instruction(word) {
+            if let Some((instruction, new_warnings)) = parse_instruction(word, instruction_set) {
                 return Ok((
                     Token::Instruction(instruction).spanning(to_span(word).unwrap()),
                     new_warnings,
@@ -258,7 +376,24 @@ fn to_span(symbols: &[Spanned<u8>]) -> Option<Span> {
     Some(Span::combine(&symbols.first()?.span, &symbols.last()?.span))
 }
 
-fn parse_macro(rune_span: Span, symbols: &[Spanned<u8>]) -> Result<Vec<u8>, Error> {
+/// Parses the name of a query written after a relative pad rune. Names of queries contain
+/// characters which aren't hexadecimal digits, so they can't be mistaken for pads.
+fn parse_query(symbols: &[Spanned<u8>]) -> Option<Query> {
+    let name = to_string(symbols);
+    match name.as_slice() {
+        b"here" => Some(Query::Here),
+        _ => match name.strip_prefix(b"defined:") {
+            Some(macro_name) if !macro_name.is_empty() => Some(Query::Defined(macro_name.to_vec())),
+            _ => None,
+        },
+    }
+}
+
+fn parse_macro(
+    rune_span: Span,
+    symbols: &[Spanned<u8>],
+    instruction_set: &dyn InstructionSet,
+) -> Result<Vec<u8>, Error> {
     if symbols.is_empty() {
         return Err(Error::MacroNameExpected {
             span: rune_span.into(),
@@ -271,7 +406,7 @@ fn parse_macro(rune_span: Span, symbols: &[Spanned<u8>]) -> Result<Vec<u8>, Erro
             number: String::from_utf8_lossy(&to_string(symbols)).into_owned(),
         });
     }
-    if let Some(_) = parse_instruction(symbols) {
+    if let Some(_) = parse_instruction(symbols, instruction_set) {
         return Err(Error::MacroCannotBeAnInstruction {
             span: to_span(symbols).unwrap().into(),
             instruction: String::from_utf8_lossy(&to_string(symbols)).into_owned(),
@@ -281,6 +416,110 @@ fn parse_macro(rune_span: Span, symbols: &[Spanned<u8>]) -> Result<Vec<u8>, Erro
     Ok(to_string(symbols))
 }
 
+/// Parses the decimal number after a literal decimal prefix `#+`. The number is a literal byte
+/// if it fits in a byte and has at most 3 digits, and a literal short otherwise, so that e.g.
+/// `#+0010` is a short.
+fn parse_decimal_number(symbols: &[Spanned<u8>]) -> Result<Token, Error> {
+    let number = || String::from_utf8_lossy(&to_string(symbols)).into_owned();
+    let mut value: u32 = 0;
+
+    for symbol in symbols {
+        if !symbol.node.is_ascii_digit() {
+            return Err(Error::DecimalDigitInvalid {
+                digit: symbol.node as char,
+                number: number(),
+                span: symbol.span.into(),
+            });
+        }
+        value = value * 10 + (symbol.node - b'0') as u32;
+        if value > 0xffff {
+            return Err(Error::DecimalNumberTooLarge {
+                number: number(),
+                span: to_span(symbols).unwrap().into(),
+            });
+        }
+    }
+
+    if value <= 0xff && symbols.len() <= 3 {
+        Ok(Token::LiteralHexByte(value as u8))
+    } else {
+        Ok(Token::LiteralHexShort(value as u16))
+    }
+}
+
+/// Parses the binary number after a literal binary prefix `#b`, which is a literal byte if it
+/// has 8 digits and a literal short if it has 16.
+fn parse_binary_number(symbols: &[Spanned<u8>]) -> Result<Token, Error> {
+    let number = || String::from_utf8_lossy(&to_string(symbols)).into_owned();
+    let mut value: u32 = 0;
+
+    for symbol in symbols {
+        if !matches!(symbol.node, b'0' | b'1') {
+            return Err(Error::BinaryDigitInvalid {
+                digit: symbol.node as char,
+                number: number(),
+                span: symbol.span.into(),
+            });
+        }
+        value = (value << 1) | (symbol.node - b'0') as u32;
+    }
+
+    match symbols.len() {
+        8 => Ok(Token::LiteralHexByte(value as u8)),
+        16 => Ok(Token::LiteralHexShort(value as u16)),
+        length => Err(Error::BinaryNumberLengthInvalid {
+            length,
+            number: number(),
+            span: to_span(symbols).unwrap().into(),
+        }),
+    }
+}
+
+/// Whether the bytes are a single character encoded in UTF-8.
+fn is_single_character(bytes: &[u8]) -> bool {
+    match core::str::from_utf8(bytes) {
+        Ok(string) => string.chars().count() == 1,
+        Err(_) => false,
+    }
+}
+
+/// Replaces the escape sequences in the characters of a raw word or a string with the bytes they
+/// stand for.
+fn unescape(symbols: &[Spanned<u8>]) -> Result<Vec<u8>, Error> {
+    let mut bytes: Vec<u8> = Vec::new();
+    let mut symbols = symbols.iter();
+
+    while let Some(symbol) = symbols.next() {
+        if symbol.node != b'\\' {
+            bytes.push(symbol.node);
+            continue;
+        }
+        bytes.push(match symbols.next() {
+            Some(Spanned { node: b'0', .. }) => 0x00,
+            Some(Spanned { node: b't', .. }) => b'\t',
+            Some(Spanned { node: b'n', .. }) => b'\n',
+            Some(Spanned { node: b'r', .. }) => b'\r',
+            Some(Spanned { node: b's', .. }) => b' ',
+            Some(Spanned { node: b'\\', .. }) => b'\\',
+            Some(Spanned { node: b'`', .. }) => b'`',
+            Some(escaped) => {
+                return Err(Error::EscapeSequenceInvalid {
+                    sequence: format!("\\{}", escaped.node as char),
+                    span: Span::combine(&symbol.span, &escaped.span).into(),
+                })
+            }
+            None => {
+                return Err(Error::EscapeSequenceInvalid {
+                    sequence: "\\".to_owned(),
+                    span: symbol.span.into(),
+                })
+            }
+        });
+    }
+
+    Ok(bytes)
+}
+
 fn parse_identifier(rune_span: Span, symbols: &[Spanned<u8>]) -> Result<Identifier, Error> {
     if symbols.is_empty() {
         return Err(Error::IdentifierExpected {
@@ -346,46 +585,15 @@ fn parse_identifier(rune_span: Span, symbols: &[Spanned<u8>]) -> Result<Identifi
 }
 
 /// `symbols` must not be empty.
-fn parse_instruction(symbols: &[Spanned<u8>]) -> Option<(Instruction, Vec<Warning>)> {
+fn parse_instruction(
+    symbols: &[Spanned<u8>],
+    instruction_set: &dyn InstructionSet,
+) -> Option<(Instruction, Vec<Warning>)> {
     if symbols.len() < 3 {
         return None;
     }
 
-    let instruction_kind = match to_string(&symbols[..3]).as_slice() {
-        b"BRK" | b"LIT" => Some(InstructionKind::BreakOrLiteral),
-        b"INC" => Some(InstructionKind::Increment),
-        b"NIP" => Some(InstructionKind::NoOperation),
-        b"POP" => Some(InstructionKind::Pop),
-        b"DUP" => Some(InstructionKind::Duplicate),
-        b"SWP" => Some(InstructionKind::Swap),
-        b"OVR" => Some(InstructionKind::Over),
-        b"ROT" => Some(InstructionKind::Rotate),
-        b"EQU" => Some(InstructionKind::Equal),
-        b"NEQ" => Some(InstructionKind::NotEqual),
-        b"GTH" => Some(InstructionKind::GreaterThan),
-        b"LTH" => Some(InstructionKind::LesserThan),
-        b"JMP" => Some(InstructionKind::Jump),
-        b"JCN" => Some(InstructionKind::JumpCondition),
-        b"JSR" => Some(InstructionKind::JumpStash),
-        b"STH" => Some(InstructionKind::Stash),
-        b"LDZ" => Some(InstructionKind::LoadZeroPage),
-        b"STZ" => Some(InstructionKind::StoreZeroPage),
-        b"LDR" => Some(InstructionKind::LoadRelative),
-        b"STR" => Some(InstructionKind::StoreRelative),
-        b"LDA" => Some(InstructionKind::LoadAbsolute),
-        b"STA" => Some(InstructionKind::StoreAbsolute),
-        b"DEI" => Some(InstructionKind::DeviceIn),
-        b"DEO" => Some(InstructionKind::DeviceOut),
-        b"ADD" => Some(InstructionKind::Add),
-        b"SUB" => Some(InstructionKind::Subtract),
-        b"MUL" => Some(InstructionKind::Multiply),
-        b"DIV" => Some(InstructionKind::Divide),
-        b"AND" => Some(InstructionKind::And),
-        b"ORA" => Some(InstructionKind::Or),
-        b"EOR" => Some(InstructionKind::ExclusiveOr),
-        b"SFT" => Some(InstructionKind::Shift),
-        _ => None,
-    }?;
+    let opcode = instruction_set.opcode(&to_string(&symbols[..3]))?;
 
     let mut keep: Option<Span> = None;
     let mut r#return: Option<Span> = None;
@@ -436,15 +644,52 @@ fn parse_instruction(symbols: &[Spanned<u8>]) -> Option<(Instruction, Vec<Warnin
         }
     }
 
-    return Some((
-        Instruction {
-            instruction_kind,
-            keep: keep.is_some(),
-            r#return: r#return.is_some(),
-            short: short.is_some(),
-        },
-        warnings,
-    ));
+    // The modes are added to the opcode, which may already have some of them set in a custom
+    // instruction set.
+    let mut instruction = Instruction::from_opcode(opcode);
+    instruction.keep |= keep.is_some();
+    instruction.r#return |= r#return.is_some();
+    instruction.short |= short.is_some();
+
+    return Some((instruction, warnings));
+}
+
+/// Warns about the definition of a label or a sublabel, given with its rune, whose name is also
+/// an instruction, which the name assembles as whenever it's written without a rune.
+fn instruction_shadowed(
+    word: &[Spanned<u8>],
+    sublabel: bool,
+    instruction_set: &dyn InstructionSet,
+) -> Vec<Warning> {
+    match parse_instruction(&word[1..], instruction_set) {
+        Some(_) => vec![Warning::InstructionShadowed {
+            name: String::from_utf8_lossy(&to_string(&word[1..])).into_owned(),
+            sublabel,
+            span: to_span(word).unwrap().into(),
+        }],
+        None => Vec::new(),
+    }
+}
+
+/// Returns `name` with its letter case corrected, in case it's an instruction written in the
+/// wrong case, e.g. `dup2` or `Dup2`.
+pub(crate) fn fix_instruction_case(name: &[u8]) -> Option<Vec<u8>> {
+    if name.len() < 3 {
+        return None;
+    }
+
+    let fixed: Vec<u8> = name[..3]
+        .iter()
+        .map(u8::to_ascii_uppercase)
+        .chain(name[3..].iter().map(u8::to_ascii_lowercase))
+        .collect();
+    let symbols: Vec<Spanned<u8>> = fixed
+        .iter()
+        .enumerate()
+        .map(|(offset, ch)| ch.spanning(Span::new(Location { offset })))
+        .collect();
+
+    parse_instruction(&symbols, &OpcodeTable::Modern).map(|_| fixed)
 }
 
 impl fmt::Debug for Word {
@@ -458,12 +703,19 @@ impl fmt::Debug for Word {
                 }
                 debug_struct.finish()
             }
-            Word::Faulty { errors, warnings } => {
+            Word::Faulty {
+                errors,
+                warnings,
+                placeholder,
+            } => {
                 let mut debug_struct = f.debug_struct("Faulty");
                 debug_struct.field("errors", errors);
                 if !warnings.is_empty() {
                     debug_struct.field("warnings", warnings);
                 }
+                if let Some(placeholder) = placeholder {
+                    debug_struct.field("placeholder", placeholder);
+                }
                 debug_struct.finish()
             }
         }
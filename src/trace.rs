@@ -0,0 +1,98 @@
+use crate::{
+    token::{ScopedIdentifier, Statement},
+    walker::Definitions,
+};
+use alloc::{
+    borrow::ToOwned,
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::ops::Range;
+
+/// A single step of the emission of the binary, describing how one statement of the program
+/// has been turned into bytes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceStep {
+    /// Value of the address pointer before the statement has been emitted.
+    pub address: u16,
+    /// Bytes written by the statement. Empty for pads and for statements containing errors.
+    pub bytes: Vec<u8>,
+    /// Human-readable explanation of where the bytes come from.
+    pub explanation: String,
+    /// Span of the statement.
+    pub span: Range<usize>,
+}
+
+/// Explains how the statement is emitted at the given address.
+pub(crate) fn explain(statement: &Statement, definitions: &Definitions, address: u16) -> String {
+    let label_address = |scoped_identifier: &ScopedIdentifier| {
+        definitions
+            .labels
+            .get(scoped_identifier)
+            .map(|(address, _)| *address)
+            .unwrap_or_default()
+    };
+
+    match statement {
+        Statement::Instruction(instruction) => {
+            let mut modes = Vec::new();
+            if instruction.short {
+                modes.push("short mode (0x20)");
+            }
+            if instruction.r#return {
+                modes.push("return mode (0x40)");
+            }
+            if instruction.keep {
+                modes.push("keep mode (0x80)");
+            }
+            if modes.is_empty() {
+                format!("opcode {:#04x}", instruction.instruction_kind as u8)
+            } else {
+                format!(
+                    "opcode {:#04x} combined with {}",
+                    instruction.instruction_kind as u8,
+                    modes.join(", ")
+                )
+            }
+        }
+        Statement::PadAbsolute(value) => format!("absolute pad to address {:#06x}", value),
+        Statement::PadRelative(1) => "relative pad by 1 byte".to_owned(),
+        Statement::PadRelative(value) => format!("relative pad by {} bytes", value),
+        Statement::LiteralZeroPageAddress(scoped_identifier) => format!(
+            "LIT followed by the zero-page address {:#04x} of label `{}`",
+            label_address(scoped_identifier) & 0xff,
+            scoped_identifier.to_string()
+        ),
+        Statement::LiteralRelativeAddress(scoped_identifier) => format!(
+            "LIT followed by the offset {} from the end of the literal to label `{}`",
+            label_address(scoped_identifier) as isize - address as isize - 3,
+            scoped_identifier.to_string()
+        ),
+        Statement::LiteralAbsoluteAddress(scoped_identifier) => format!(
+            "LIT2 followed by the absolute address {:#06x} of label `{}`",
+            label_address(scoped_identifier),
+            scoped_identifier.to_string()
+        ),
+        Statement::RawAddress(scoped_identifier) => format!(
+            "absolute address {:#06x} of label `{}`",
+            label_address(scoped_identifier),
+            scoped_identifier.to_string()
+        ),
+        Statement::LiteralHexByte(value) => format!("LIT followed by the byte {:#04x}", value),
+        Statement::LiteralHexShort(value) => format!("LIT2 followed by the short {:#06x}", value),
+        Statement::RawHexByte(value) => format!("raw byte {:#04x}", value),
+        Statement::RawHexShort(value) => format!("raw short {:#06x}", value),
+        Statement::RawChar(value) => format!("ASCII code of the character `{}`", *value as char),
+        Statement::RawWord(word) => {
+            format!("ASCII codes of the {} characters of the word", word.len())
+        }
+        Statement::Checksum(from, to) => format!(
+            "sum of the bytes from label `{}` at {:#06x} up to label `{}` at {:#06x}",
+            from.to_string(),
+            label_address(from),
+            to.to_string(),
+            label_address(to)
+        ),
+    }
+}
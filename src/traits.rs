@@ -1,4 +1,5 @@
 use crate::{Error, Warning};
+use alloc::vec::Vec;
 
 pub trait UnzipCollect<S> {
     fn unzip_collect(self) -> Result<(Vec<S>, Vec<Warning>), (Vec<Error>, Vec<Warning>)>;
@@ -1,16 +1,90 @@
+use crate::expression::{self, Item, Operator};
+use crate::instruction::InstructionKind;
 use crate::span::Spanning;
-use crate::token::Identifier;
+use crate::suggestion::find_similar;
 use crate::token::ScopedIdentifier;
 use crate::token::Statement;
+use crate::token::{Identifier, Query};
+use crate::tokenizer::fix_instruction_case;
 use crate::{tokenizer::Word, Span, Spanned, Token};
-use crate::{Error, Warning};
-use std::collections::HashMap;
-use std::collections::HashSet;
-use std::iter::Peekable;
-use std::slice::Iter;
-
-pub(crate) struct Definitions {
-    pub labels: HashMap<ScopedIdentifier, (u16, Span)>,
+use crate::{Error, Limits, Warning};
+use alloc::{
+    borrow::ToOwned,
+    boxed::Box,
+    collections::{BTreeMap, BTreeSet},
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::iter::Peekable;
+use core::ops::Range;
+use core::slice::Iter;
+
+/// Address at which libraries are placed when they are checked on their own.
+pub(crate) const LIBRARY_START: u16 = 0x0100;
+
+/// Name under which the expansions of repeat directives are nested along with the expansions of
+/// macros. Repeats can be nested in each other, so they are never considered recursive.
+pub(crate) const REPEAT_NAME: &[u8] = b"?repeat";
+
+/// Everything defined in the program, as resolved by walking it.
+#[derive(Debug, Clone)]
+pub struct Definitions {
+    /// Addresses of the labels and sublabels, and values of the constants, along with the spans
+    /// of their definitions.
+    pub labels: BTreeMap<ScopedIdentifier, (u16, Span)>,
+    /// Labels and sublabels defined before any pad.
+    pub unpadded_labels: BTreeSet<ScopedIdentifier>,
+    /// Labels and sublabels of a library defined before any absolute pad, whose addresses are
+    /// relative to the start of the library.
+    pub relative_labels: BTreeSet<ScopedIdentifier>,
+    /// Labels defined with the constant rune, whose addresses are their values.
+    pub constants: BTreeSet<ScopedIdentifier>,
+    /// Labels and constants referenced in the expressions of pads.
+    pub expression_labels: BTreeSet<ScopedIdentifier>,
+    /// Widths of the device ports, i.e. the labels and sublabels in the zero page followed only
+    /// by relative pads, which are the widths.
+    pub port_widths: BTreeMap<ScopedIdentifier, u16>,
+    /// Sizes of the variables allocated in `?zeropage` sections, i.e. the sums of the relative
+    /// pads right after them.
+    pub variable_sizes: BTreeMap<ScopedIdentifier, u16>,
+    /// Macros defined in the program, along with the spans of their definitions.
+    pub macros: BTreeMap<Vec<u8>, Span>,
+}
+
+/// A walked program, whose statements can be emitted despite its errors, if it has any.
+pub struct Walked {
+    pub statements: Vec<Spanned<Statement>>,
+    pub definitions: Definitions,
+    pub warnings: Vec<Warning>,
+    /// Errors of the faulty words replaced with placeholders, and of the undefined macros.
+    pub errors: Vec<Error>,
+    /// Whether the statements are at the addresses they are meant to be at, which they aren't
+    /// if the invocations of undefined macros have been left out.
+    pub addresses_resolved: bool,
+    /// Ranges of the statements expanded from macros, along with the spans of the invocations
+    /// they have been expanded through, starting with the outermost invocation.
+    pub expansions: Vec<(Range<usize>, Vec<Span>)>,
+}
+
+/// A region of the program opened with `?ifdef`, `?ifndef`, or `?library` and not yet closed
+/// with `?end`.
+struct Conditional {
+    span: Span,
+    else_span: Option<Span>,
+    /// Whether the condition of the directive holds.
+    holds: bool,
+    /// Whether the region enclosing the conditional is assembled.
+    enclosing_taken: bool,
+    /// Whether the region is a library region opened with `?library`, which always holds.
+    library: bool,
+}
+
+impl Conditional {
+    /// Whether the words up to the next `?else` or `?end` are assembled.
+    fn taken(&self) -> bool {
+        self.enclosing_taken && self.holds == self.else_span.is_none()
+    }
 }
 
 pub(crate) struct Walker<'words> {
@@ -21,12 +95,55 @@ pub(crate) struct Walker<'words> {
     length: u16,
     opened_brackets: Vec<Span>,
     opened_braces: Vec<Span>,
+    conditionals: Vec<Conditional>,
     scope: Option<Vec<u8>>,
-    macro_definitions: HashMap<Vec<u8>, (Vec<&'words Word>, Span)>,
-    unused_macros: HashSet<Vec<u8>>,
-    label_definitions: HashMap<ScopedIdentifier, (u16, Span)>,
+    macro_definitions: BTreeMap<Vec<u8>, (Vec<&'words Word>, Span)>,
+    unused_macros: BTreeSet<Vec<u8>>,
+    label_definitions: BTreeMap<ScopedIdentifier, (u16, Span)>,
+    padded: bool,
+    unpadded_labels: BTreeSet<ScopedIdentifier>,
+    /// Whether the walked words are a library, and no absolute pad has been encountered yet.
+    relative: bool,
+    relative_labels: BTreeSet<ScopedIdentifier>,
+    constants: BTreeSet<ScopedIdentifier>,
+    expression_labels: BTreeSet<ScopedIdentifier>,
+    /// Address of the next variable allocated in a `?zeropage` section.
+    zero_page_pointer: u16,
+    /// Label or sublabel in the zero page followed so far only by relative pads.
+    port: Option<ScopedIdentifier>,
+    port_widths: BTreeMap<ScopedIdentifier, u16>,
+    variable_sizes: BTreeMap<ScopedIdentifier, u16>,
+    /// Label of the routine of a library region which the walked words belong to.
+    unit: Option<Vec<u8>>,
+    /// Number of statements before the start of the current routine of a library region.
+    unit_start: usize,
+    /// Labels, sublabels, and constants defined in the routines of library regions, along with
+    /// the labels of the routines.
+    unit_labels: BTreeMap<ScopedIdentifier, Vec<u8>>,
+    /// References along with the routines of library regions they are made in, if any.
+    unit_references: Vec<(Option<Vec<u8>>, ScopedIdentifier)>,
+    /// Routines of library regions left out of the program.
+    pruned_units: BTreeSet<Vec<u8>>,
+    /// Whether the words are currently skipped because they belong to a pruned routine.
+    pruning: bool,
+    /// Whether bytes can be written to the zeroth page, in which case they are not reported.
+    zeroth_page_bytes: bool,
     zeroth_page_spans: Vec<Span>,
+    /// Whether the address pointer has gone right past the last address, which it can't hold,
+    /// in which case it's left at the last address and nothing more can be written.
+    at_end: bool,
+    /// Whether absolute pads can go back to the addresses that have already been written.
+    backward_padding: bool,
     overflow_spans: Vec<Span>,
+    /// Maximum number of words a single repeat expands into.
+    repeat_length: usize,
+    /// Number of errors after which the rest of the words is left unwalked, if any.
+    error_limit: Option<usize>,
+    /// Number of errors of the faulty words which have been replaced with placeholders.
+    recoverable_error_count: usize,
+    /// Number of invocations of undefined macros, which have been left out.
+    undefined_macro_count: usize,
+    expansions: Vec<(Range<usize>, Vec<Span>)>,
 }
 
 impl<'words> Walker<'words> {
@@ -39,36 +156,192 @@ impl<'words> Walker<'words> {
             length: 0,
             opened_brackets: Vec::new(),
             opened_braces: Vec::new(),
+            conditionals: Vec::new(),
             scope: None,
-            macro_definitions: HashMap::new(),
-            unused_macros: HashSet::new(),
-            label_definitions: HashMap::new(),
+            macro_definitions: BTreeMap::new(),
+            unused_macros: BTreeSet::new(),
+            label_definitions: BTreeMap::new(),
+            padded: false,
+            unpadded_labels: BTreeSet::new(),
+            relative: false,
+            relative_labels: BTreeSet::new(),
+            constants: BTreeSet::new(),
+            expression_labels: BTreeSet::new(),
+            zero_page_pointer: 0,
+            port: None,
+            port_widths: BTreeMap::new(),
+            variable_sizes: BTreeMap::new(),
+            unit: None,
+            unit_start: 0,
+            unit_labels: BTreeMap::new(),
+            unit_references: Vec::new(),
+            pruned_units: BTreeSet::new(),
+            pruning: false,
+            zeroth_page_bytes: false,
             zeroth_page_spans: Vec::new(),
+            at_end: false,
+            backward_padding: false,
             overflow_spans: Vec::new(),
+            repeat_length: Limits::default().repeat_length,
+            error_limit: None,
+            recoverable_error_count: 0,
+            undefined_macro_count: 0,
+            expansions: Vec::new(),
+        }
+    }
+
+    /// Creates a walker of a library, i.e. a file meant to be included in other programs. The
+    /// library is placed at the start of the program, as if it was preceded by a `|0100` pad.
+    pub fn library() -> Self {
+        Self {
+            pointer: LIBRARY_START,
+            padded: true,
+            relative: true,
+            ..Self::new()
+        }
+    }
+
+    /// Creates a walker of a program whose routines of library regions with the given labels are
+    /// left out, as if they have never been written.
+    pub fn pruning(units: BTreeSet<Vec<u8>>) -> Self {
+        Self {
+            pruned_units: units,
+            ..Self::new()
+        }
+    }
+
+    /// Allows writing bytes to the zeroth page, which are then left out of the binary instead
+    /// of being reported.
+    pub fn allowing_zeroth_page_bytes(self, zeroth_page_bytes: bool) -> Self {
+        Self {
+            zeroth_page_bytes,
+            ..self
+        }
+    }
+
+    /// Allows absolute pads to addresses before the address pointer, even after some bytes have
+    /// been written, instead of reporting them.
+    pub fn allowing_backward_padding(self, backward_padding: bool) -> Self {
+        Self {
+            backward_padding,
+            ..self
+        }
+    }
+
+    /// Limits the number of words a single repeat expands into.
+    pub fn limiting_repeat_length(self, repeat_length: usize) -> Self {
+        Self {
+            repeat_length,
+            ..self
+        }
+    }
+
+    /// Stops walking the words once more errors than the limit have been reported, since the
+    /// ones beyond it are left out anyway.
+    pub fn limiting_errors(self, error_limit: Option<usize>) -> Self {
+        Self {
+            error_limit,
+            ..self
+        }
+    }
+
+    /// Number of errors reported so far.
+    pub fn error_count(&self) -> usize {
+        self.errors.len()
+    }
+
+    /// Wraps the errors reported since the given number of errors in the macro invocations
+    /// they have been reported from, starting with the outermost invocation.
+    pub fn attribute_errors(&mut self, since: usize, invocations: &[Span]) {
+        let errors = self.errors.split_off(since);
+        self.errors.extend(errors.into_iter().map(|error| {
+            invocations
+                .iter()
+                .rev()
+                .fold(error, |error, span| Error::MacroError {
+                    original_error: Box::new(error),
+                    span: (*span).into(),
+                })
+        }));
+    }
+
+    /// Number of statements walked so far.
+    pub fn statement_count(&self) -> usize {
+        self.statements.len()
+    }
+
+    /// Records the macro invocations the statements walked since the given number of statements
+    /// have been expanded through, starting with the outermost invocation.
+    pub fn attribute_statements(&mut self, since: usize, invocations: &[Span]) {
+        if !invocations.is_empty() && self.statements.len() > since {
+            self.expansions
+                .push((since..self.statements.len(), invocations.to_vec()));
+        }
+    }
+
+    /// Returns the labels of the routines of library regions which are not referenced from the
+    /// rest of the program, neither directly nor through other routines. Nothing is returned
+    /// if the program contains errors, so that all of them get reported.
+    pub fn unreferenced_units(&self) -> BTreeSet<Vec<u8>> {
+        let mut units: BTreeSet<Vec<u8>> = BTreeSet::new();
+        if !self.errors.is_empty() {
+            return units;
+        }
+        units.extend(self.unit_labels.values().cloned());
+
+        let mut queue: Vec<&Vec<u8>> = Vec::new();
+        for (from, to) in &self.unit_references {
+            if from.is_none() {
+                if let Some(unit) = self.unit_labels.get(to) {
+                    queue.push(unit);
+                }
+            }
+        }
+        while let Some(unit) = queue.pop() {
+            if !units.remove(unit) {
+                continue;
+            }
+            for (from, to) in &self.unit_references {
+                if from.as_ref() == Some(unit) {
+                    if let Some(unit) = self.unit_labels.get(to) {
+                        queue.push(unit);
+                    }
+                }
+            }
         }
+
+        units
     }
 
     pub fn push_bytes(&mut self, bytes: u16, span: Span) {
-        if self.pointer < 256 {
+        self.port = None;
+        if self.pointer < 256 && !self.zeroth_page_bytes {
             self.zeroth_page_spans.push(span);
         }
         self.increment_pointer(bytes, span);
-        self.length = self.pointer;
+        self.length = self.length.max(self.pointer);
     }
 
     pub fn set_pointer(&mut self, to: u16) -> Result<(), u16> {
-        if self.length > 0 && to < self.pointer {
+        if self.length > 0 && to < self.pointer && !self.backward_padding {
             return Err(self.pointer);
         }
         self.pointer = to;
+        self.at_end = false;
         Ok(())
     }
 
     pub fn increment_pointer(&mut self, by: u16, span: Span) {
+        if self.at_end && by > 0 {
+            self.overflow_spans.push(span);
+            return;
+        }
         match self.pointer.checked_add(by) {
             Some(result) => {
                 self.pointer = result;
             }
+            // The program can take up the memory up to and including the last address.
+            None if self.pointer as u32 + by as u32 == 0x10000 => self.at_end = true,
             None => self.overflow_spans.push(span),
         }
     }
@@ -80,7 +353,19 @@ impl<'words> Walker<'words> {
         let mut words = words.iter().peekable();
 
         loop {
+            if self.exceeds_error_limit() {
+                return None;
+            }
             match words.next() {
+                Some(Word::Fine { token, .. })
+                    if self.skipping() && !token.node.is_conditional() => {}
+                // Macro definitions of pruned routines are kept, since they don't take any space.
+                Some(Word::Fine { token, .. })
+                    if self.pruning
+                        && !token.node.is_conditional()
+                        && !matches!(token.node, Token::LabelDefine(_) | Token::MacroDefine(_)) => {
+                }
+                Some(Word::Faulty { .. }) if self.skipping() || self.pruning => {}
                 Some(Word::Fine {
                     token,
                     warnings: new_warnings,
@@ -150,38 +435,60 @@ impl<'words> Walker<'words> {
                                     words.copied().collect(),
                                 ));
                             }
-                            None => self.errors.push(Error::MacroUndefined {
-                                name: String::from_utf8_lossy(&name).into_owned(),
-                                span: (*span).into(),
-                            }),
+                            None => {
+                                self.errors.push(Error::MacroUndefined {
+                                    name: String::from_utf8_lossy(&name).into_owned(),
+                                    span: (*span).into(),
+                                    instruction: fix_instruction_case(name).map(|instruction| {
+                                        String::from_utf8_lossy(&instruction).into_owned()
+                                    }),
+                                    similar_macro: find_similar(
+                                        name,
+                                        self.macro_definitions.keys().map(Vec::as_slice),
+                                    )
+                                    .map(|similar_macro| {
+                                        String::from_utf8_lossy(similar_macro).into_owned()
+                                    }),
+                                });
+                                self.undefined_macro_count += 1;
+                            }
                         },
                         Spanned {
                             node: Token::PadAbsolute(value),
                             span,
+                        } => self.pad_absolute(*value, *span),
+                        Spanned {
+                            node: Token::PadRelative(value),
+                            span,
+                        } => self.pad_relative(*value, *span),
+                        Spanned {
+                            node: Token::PadAbsoluteExpression,
+                            span,
                         } => {
-                            self.statements
-                                .push(Statement::PadAbsolute(*value).spanning(*span));
-                            match self.set_pointer(*value as u16) {
-                                Ok(()) => (),
-                                Err(previous_address) => self.errors.push(Error::PaddedBackwards {
-                                    previous_pointer: previous_address as usize,
-                                    desired_pointer: *value as usize,
-                                    span: (*span).into(),
-                                }),
-                            }
+                            words = self.walk_pad_expression(true, *span, words);
                         }
                         Spanned {
-                            node: Token::PadRelative(value),
+                            node: Token::PadRelativeExpression,
                             span,
                         } => {
-                            self.statements
-                                .push(Statement::PadRelative(*value).spanning(*span));
-                            self.increment_pointer(*value as u16, *span);
+                            words = self.walk_pad_expression(false, *span, words);
                         }
                         Spanned {
                             node: Token::LabelDefine(name),
                             span,
                         } => {
+                            if !self.enter_unit(name) {
+                                continue;
+                            }
+                            self.record_unit_label(&ScopedIdentifier::Label(name.clone()));
+                            if !self.padded {
+                                self.unpadded_labels
+                                    .insert(ScopedIdentifier::Label(name.clone()));
+                            }
+                            if self.relative {
+                                self.relative_labels
+                                    .insert(ScopedIdentifier::Label(name.clone()));
+                            }
                             if let Some((_, other_span)) = self.label_definitions.insert(
                                 ScopedIdentifier::Label(name.clone()),
                                 (self.pointer, *span),
@@ -192,13 +499,36 @@ impl<'words> Walker<'words> {
                                     other_span: other_span.into(),
                                 });
                             }
+                            self.open_port(ScopedIdentifier::Label(name.clone()));
                             self.scope = Some(name.clone());
                         }
+                        Spanned {
+                            node: Token::ConstantDefine(name),
+                            span,
+                        } => {
+                            words = self.walk_constant_definition(name, *span, words);
+                        }
                         Spanned {
                             node: Token::SublabelDefine(name),
                             span,
-                        } => match &self.scope {
+                        } => match self.scope.clone() {
                             Some(scope_name) => {
+                                self.record_unit_label(&ScopedIdentifier::Sublabel(
+                                    scope_name.clone(),
+                                    name.clone(),
+                                ));
+                                if !self.padded {
+                                    self.unpadded_labels.insert(ScopedIdentifier::Sublabel(
+                                        scope_name.to_owned(),
+                                        name.clone(),
+                                    ));
+                                }
+                                if self.relative {
+                                    self.relative_labels.insert(ScopedIdentifier::Sublabel(
+                                        scope_name.to_owned(),
+                                        name.clone(),
+                                    ));
+                                }
                                 if let Some((_, other_span)) = self.label_definitions.insert(
                                     ScopedIdentifier::Sublabel(scope_name.to_owned(), name.clone()),
                                     (self.pointer, *span),
@@ -209,6 +539,10 @@ impl<'words> Walker<'words> {
                                         other_span: other_span.into(),
                                     });
                                 }
+                                self.open_port(ScopedIdentifier::Sublabel(
+                                    scope_name,
+                                    name.clone(),
+                                ));
                             }
                             None => self.errors.push(Error::SublabelDefinedWithoutScope {
                                 name: String::from_utf8_lossy(&name).into_owned(),
@@ -220,6 +554,7 @@ impl<'words> Walker<'words> {
                             span,
                         } => match scope_identifier(identifier, &self.scope, span) {
                             Ok(scoped_identifier) => {
+                                self.record_unit_reference(&scoped_identifier);
                                 self.statements.push(
                                     Statement::LiteralZeroPageAddress(scoped_identifier)
                                         .spanning(*span),
@@ -233,6 +568,7 @@ impl<'words> Walker<'words> {
                             span,
                         } => match scope_identifier(identifier, &self.scope, span) {
                             Ok(scoped_identifier) => {
+                                self.record_unit_reference(&scoped_identifier);
                                 self.statements.push(
                                     Statement::LiteralRelativeAddress(scoped_identifier)
                                         .spanning(*span),
@@ -246,6 +582,7 @@ impl<'words> Walker<'words> {
                             span,
                         } => match scope_identifier(identifier, &self.scope, &span) {
                             Ok(scoped_identifier) => {
+                                self.record_unit_reference(&scoped_identifier);
                                 self.statements.push(
                                     Statement::LiteralAbsoluteAddress(scoped_identifier)
                                         .spanning(*span),
@@ -259,6 +596,7 @@ impl<'words> Walker<'words> {
                             span,
                         } => match scope_identifier(identifier, &self.scope, span) {
                             Ok(scoped_identifier) => {
+                                self.record_unit_reference(&scoped_identifier);
                                 self.statements
                                     .push(Statement::RawAddress(scoped_identifier).spanning(*span));
                                 self.push_bytes(2, *span);
@@ -313,14 +651,117 @@ impl<'words> Walker<'words> {
                             self.statements
                                 .push(Statement::RawWord(word.clone()).spanning(*span));
                         }
+                        Spanned {
+                            node: Token::IfDefined,
+                            span,
+                        } => {
+                            words = self.walk_conditional(false, *span, words);
+                        }
+                        Spanned {
+                            node: Token::IfNotDefined,
+                            span,
+                        } => {
+                            words = self.walk_conditional(true, *span, words);
+                        }
+                        Spanned {
+                            node: Token::Else,
+                            span,
+                        } => match self.conditionals.last_mut() {
+                            Some(Conditional {
+                                else_span: Some(other_span),
+                                ..
+                            }) => self.errors.push(Error::ElseDefinedMoreThanOnce {
+                                span: (*span).into(),
+                                other_span: (*other_span).into(),
+                            }),
+                            Some(conditional) if !conditional.library => {
+                                conditional.else_span = Some(*span)
+                            }
+                            _ => self.errors.push(Error::NoMatchingConditional {
+                                directive: "?else".to_owned(),
+                                span: (*span).into(),
+                            }),
+                        },
+                        Spanned {
+                            node: Token::End,
+                            span,
+                        } => match self.conditionals.pop() {
+                            Some(Conditional { library: true, .. }) => {
+                                self.unit = None;
+                                self.pruning = false;
+                            }
+                            Some(_) => (),
+                            None => self.errors.push(Error::NoMatchingConditional {
+                                directive: "?end".to_owned(),
+                                span: (*span).into(),
+                            }),
+                        },
+                        Spanned {
+                            node: Token::Library,
+                            span,
+                        } => {
+                            self.conditionals.push(Conditional {
+                                span: *span,
+                                else_span: None,
+                                holds: true,
+                                enclosing_taken: !self.skipping(),
+                                library: true,
+                            });
+                            self.unit_start = self.statements.len();
+                        }
+                        Spanned {
+                            node: Token::Repeat,
+                            span,
+                        } => {
+                            let (items, rest) = self.walk_repeat(*span, words);
+                            words = rest;
+                            if !items.is_empty() {
+                                return Some((
+                                    items,
+                                    REPEAT_NAME.to_vec(),
+                                    *span,
+                                    words.copied().collect(),
+                                ));
+                            }
+                        }
+                        Spanned {
+                            node: Token::ZeroPage,
+                            span,
+                        } => {
+                            words = self.walk_zero_page(*span, words);
+                        }
+                        Spanned {
+                            node: Token::Checksum,
+                            span,
+                        } => {
+                            words = self.walk_checksum(*span, words);
+                        }
+                        Spanned {
+                            node: Token::Query(_),
+                            span,
+                        } => {
+                            self.errors.push(Error::QueryOutsideExpression {
+                                span: (*span).into(),
+                            });
+                        }
+                        // Includes are replaced with the words of the included files before walking.
+                        Spanned {
+                            node: Token::Include(_),
+                            ..
+                        } => (),
                     }
                 }
                 Some(Word::Faulty {
                     errors: new_errors,
                     warnings: new_warnings,
+                    placeholder,
                 }) => {
                     self.errors.extend(new_errors.iter().cloned());
                     self.warnings.extend(new_warnings.iter().cloned());
+                    if let Some(placeholder) = placeholder {
+                        self.recoverable_error_count += new_errors.len();
+                        self.walk_placeholder(placeholder);
+                    }
                 }
                 None => break,
             }
@@ -329,10 +770,27 @@ impl<'words> Walker<'words> {
         return None;
     }
 
-    pub fn finalize(
-        mut self,
-    ) -> Result<(Vec<Spanned<Statement>>, Definitions, Vec<Warning>), (Vec<Error>, Vec<Warning>)>
-    {
+    /// Walks the token standing in for a faulty word, which is always a number or a character.
+    fn walk_placeholder(&mut self, placeholder: &Spanned<Token>) {
+        let (statement, length) = match placeholder.node {
+            Token::LiteralHexByte(value) => (Statement::LiteralHexByte(value), 2),
+            Token::LiteralHexShort(value) => (Statement::LiteralHexShort(value), 3),
+            Token::RawChar(value) => (Statement::RawChar(value), 1),
+            _ => return,
+        };
+        self.statements.push(statement.spanning(placeholder.span));
+        self.push_bytes(length, placeholder.span);
+    }
+
+    fn exceeds_error_limit(&self) -> bool {
+        matches!(self.error_limit, Some(limit) if self.errors.len() > limit)
+    }
+
+    /// Reports the constructs left unclosed, and returns the statements and the definitions of
+    /// the program. They are returned along with the errors if the only errors are the ones of
+    /// the faulty words replaced with placeholders or of the undefined macros, so that the
+    /// statements can still be emitted to find the undefined labels as well.
+    pub fn finalize(mut self) -> Result<Walked, (Vec<Error>, Vec<Warning>)> {
         for opened_bracket in self.opened_brackets {
             self.errors.push(Error::NoMatchingClosingBracket {
                 span: opened_bracket.into(),
@@ -345,6 +803,12 @@ impl<'words> Walker<'words> {
             })
         }
 
+        for conditional in self.conditionals {
+            self.errors.push(Error::NoMatchingEnd {
+                span: conditional.span.into(),
+            })
+        }
+
         if !self.zeroth_page_spans.is_empty() {
             let mut entire_span = self.zeroth_page_spans[0];
             for span in self.zeroth_page_spans.into_iter().skip(1) {
@@ -365,7 +829,16 @@ impl<'words> Walker<'words> {
             });
         }
 
-        for unused_macro_name in self.unused_macros {
+        // Walking stops at the error limit, so the statements are missing the rest of the words.
+        let recoverable = self.errors.len()
+            == self.recoverable_error_count + self.undefined_macro_count
+            && !matches!(self.error_limit, Some(limit) if self.errors.len() > limit);
+
+        // The macros are reported in the order of their definitions rather than of their names.
+        let macro_definitions = &self.macro_definitions;
+        let mut unused_macros: Vec<Vec<u8>> = self.unused_macros.into_iter().collect();
+        unused_macros.sort_by_key(|name| macro_definitions[name].1.from.offset);
+        for unused_macro_name in unused_macros {
             let (_, span) = self.macro_definitions[&unused_macro_name];
             self.warnings.push(Warning::MacroUnused {
                 name: String::from_utf8_lossy(&unused_macro_name).into_owned(),
@@ -373,25 +846,634 @@ impl<'words> Walker<'words> {
             });
         }
 
-        if self.errors.is_empty() {
-            Ok((
-                self.statements,
-                Definitions {
+        // Words of macros and repeats are walked at each expansion, so the anomalies in them
+        // are reported only once.
+        let mut reported: BTreeSet<String> = BTreeSet::new();
+        self.errors
+            .retain(|error| reported.insert(format!("{:?}", error)));
+        self.warnings
+            .retain(|warning| reported.insert(format!("{:?}", warning)));
+
+        if recoverable {
+            Ok(Walked {
+                statements: self.statements,
+                definitions: Definitions {
                     labels: self.label_definitions,
+                    unpadded_labels: self.unpadded_labels,
+                    relative_labels: self.relative_labels,
+                    constants: self.constants,
+                    expression_labels: self.expression_labels,
+                    port_widths: self.port_widths,
+                    variable_sizes: self.variable_sizes,
+                    macros: self
+                        .macro_definitions
+                        .into_iter()
+                        .map(|(name, (_, span))| (name, span))
+                        .collect(),
                 },
-                self.warnings,
-            ))
+                warnings: self.warnings,
+                errors: self.errors,
+                addresses_resolved: self.undefined_macro_count == 0,
+                expansions: self.expansions,
+            })
         } else {
             Err((self.errors, self.warnings))
         }
     }
 
+    fn pad_absolute(&mut self, value: u16, span: Span) {
+        self.statements
+            .push(Statement::PadAbsolute(value).spanning(span));
+        self.padded = true;
+        self.relative = false;
+        self.port = None;
+        match self.set_pointer(value) {
+            Ok(()) => (),
+            Err(previous_address) => self.errors.push(Error::PaddedBackwards {
+                previous_pointer: previous_address as usize,
+                desired_pointer: value as usize,
+                span: span.into(),
+            }),
+        }
+    }
+
+    fn pad_relative(&mut self, value: u16, span: Span) {
+        self.statements
+            .push(Statement::PadRelative(value).spanning(span));
+        self.padded = true;
+        if let Some(port) = &self.port {
+            let width = self.port_widths.entry(port.clone()).or_default();
+            *width = width.saturating_add(value);
+        }
+        self.increment_pointer(value, span);
+    }
+
+    /// Starts measuring the width of the label or sublabel as a device port, given that it's
+    /// defined in the zero page. The width is the sum of the relative pads right after it.
+    fn open_port(&mut self, scoped_identifier: ScopedIdentifier) {
+        self.port = if self.pointer <= 0xff {
+            Some(scoped_identifier)
+        } else {
+            None
+        };
+    }
+
+    /// Evaluates the expression in brackets right after a pad rune, and pads by its value. The
+    /// labels and constants in the expression must be defined before the pad.
+    fn walk_pad_expression<'a>(
+        &mut self,
+        absolute: bool,
+        span: Span,
+        mut words: Peekable<Iter<'a, &'words Word>>,
+    ) -> Peekable<Iter<'a, &'words Word>> {
+        if !starts_with_opening_bracket(&mut words) {
+            self.errors
+                .push(Error::HexNumberExpected { span: span.into() });
+            return words;
+        }
+        let (value, words) = self.walk_expression(words);
+
+        if let Some(Spanned {
+            node: value,
+            span: expression_span,
+        }) = value
+        {
+            let span = Span::combine(&span, &expression_span);
+            if absolute {
+                self.pad_absolute(value, span);
+            } else {
+                self.pad_relative(value, span);
+            }
+        }
+
+        words
+    }
+
+    /// Evaluates the expression in brackets which the words start with. The labels and constants
+    /// in the expression must be defined before it. Returns no value if the expression contains
+    /// errors, which are reported.
+    fn walk_expression<'a>(
+        &mut self,
+        mut words: Peekable<Iter<'a, &'words Word>>,
+    ) -> (Option<Spanned<u16>>, Peekable<Iter<'a, &'words Word>>) {
+        let opening_bracket_span = match words.peek() {
+            Some(Word::Fine {
+                token:
+                    Spanned {
+                        node: Token::OpeningBracket,
+                        span: opening_bracket_span,
+                    },
+                warnings: new_warnings,
+            }) => {
+                self.warnings.extend(new_warnings.iter().cloned());
+                *opening_bracket_span
+            }
+            _ => return (None, words),
+        };
+        words.next();
+
+        let error_count = self.errors.len();
+        let mut items: Vec<Spanned<Item>> = Vec::new();
+        let closing_bracket_span = loop {
+            match words.next() {
+                Some(Word::Fine {
+                    token:
+                        Spanned {
+                            node: Token::ClosingBracket,
+                            span: closing_bracket_span,
+                        },
+                    warnings: new_warnings,
+                }) => {
+                    self.warnings.extend(new_warnings.iter().cloned());
+                    break *closing_bracket_span;
+                }
+                Some(Word::Fine {
+                    token,
+                    warnings: new_warnings,
+                }) => {
+                    self.warnings.extend(new_warnings.iter().cloned());
+                    let item = match &token.node {
+                        Token::RawHexByte(value) => Item::Value(*value as u16),
+                        Token::RawHexShort(value) => Item::Value(*value),
+                        Token::Query(query) => Item::Value(self.query_value(query)),
+                        Token::MacroInvoke(name) => match Operator::from_name(name) {
+                            Some(operator) => Item::Operator(operator),
+                            None => match self.expression_value(name, token.span) {
+                                Ok(value) => Item::Value(value),
+                                Err(error) => {
+                                    self.errors.push(error);
+                                    continue;
+                                }
+                            },
+                        },
+                        _ => {
+                            self.errors.push(Error::ExpressionTokenInvalid {
+                                span: token.span.into(),
+                            });
+                            continue;
+                        }
+                    };
+                    items.push(Spanned {
+                        node: item,
+                        span: token.span,
+                    });
+                }
+                Some(Word::Faulty {
+                    errors: new_errors,
+                    warnings: new_warnings,
+                    ..
+                }) => {
+                    self.errors.extend(new_errors.iter().cloned());
+                    self.warnings.extend(new_warnings.iter().cloned());
+                }
+                None => {
+                    self.opened_brackets.push(opening_bracket_span);
+                    return (None, words);
+                }
+            }
+        };
+        if self.errors.len() > error_count {
+            return (None, words);
+        }
+
+        let span = Span::combine(&opening_bracket_span, &closing_bracket_span);
+        match expression::evaluate(&items, span) {
+            Ok(value) => (Some(Spanned { node: value, span }), words),
+            Err(error) => {
+                self.errors.push(error);
+                (None, words)
+            }
+        }
+    }
+
+    /// Returns the address of the label, sublabel, or constant referenced in an expression.
+    fn expression_value(&mut self, name: &[u8], span: Span) -> Result<u16, Error> {
+        let scoped_identifier = path_identifier(name);
+        match self.label_definitions.get(&scoped_identifier) {
+            Some((address, _)) => {
+                let address = *address;
+                self.record_unit_reference(&scoped_identifier);
+                self.expression_labels.insert(scoped_identifier);
+                Ok(address)
+            }
+            None => Err(Error::ExpressionLabelUndefined {
+                name: String::from_utf8_lossy(name).into_owned(),
+                span: span.into(),
+            }),
+        }
+    }
+
+    /// Returns the value of the query at the point of the expression.
+    fn query_value(&mut self, query: &Query) -> u16 {
+        match query {
+            Query::Here => self.pointer,
+            Query::Defined(name) => {
+                self.unused_macros.remove(name);
+                self.macro_definitions.contains_key(name) as u16
+            }
+        }
+    }
+
+    /// Defines a constant with the value of the expression in brackets or the hexadecimal number
+    /// right after the definition. Constants share the namespace of labels, so they can be
+    /// referenced with the same runes.
+    fn walk_constant_definition<'a>(
+        &mut self,
+        name: &[u8],
+        span: Span,
+        mut words: Peekable<Iter<'a, &'words Word>>,
+    ) -> Peekable<Iter<'a, &'words Word>> {
+        if !starts_with_opening_bracket(&mut words) {
+            return self.walk_constant_value(name, span, words);
+        }
+        let (value, words) = self.walk_expression(words);
+        // The errors in the expression have already been reported.
+        if let Some(value) = value {
+            self.define_constant(name, value.node, span);
+        }
+
+        words
+    }
+
+    /// Defines a constant with the value of the raw hexadecimal number right after the
+    /// definition.
+    fn walk_constant_value<'a>(
+        &mut self,
+        name: &[u8],
+        span: Span,
+        mut words: Peekable<Iter<'a, &'words Word>>,
+    ) -> Peekable<Iter<'a, &'words Word>> {
+        let value = match words.peek() {
+            Some(Word::Fine {
+                token:
+                    Spanned {
+                        node: Token::RawHexByte(value),
+                        ..
+                    },
+                warnings: new_warnings,
+            }) => {
+                self.warnings.extend(new_warnings.iter().cloned());
+                *value as u16
+            }
+            Some(Word::Fine {
+                token:
+                    Spanned {
+                        node: Token::RawHexShort(value),
+                        ..
+                    },
+                warnings: new_warnings,
+            }) => {
+                self.warnings.extend(new_warnings.iter().cloned());
+                *value
+            }
+            _ => {
+                self.errors
+                    .push(Error::ConstantValueExpected { span: span.into() });
+                return words;
+            }
+        };
+        words.next();
+        self.define_constant(name, value, span);
+
+        words
+    }
+
+    fn define_constant(&mut self, name: &[u8], value: u16, span: Span) {
+        let scoped_identifier = ScopedIdentifier::Label(name.to_owned());
+        if let Some((_, other_span)) = self
+            .label_definitions
+            .insert(scoped_identifier.clone(), (value, span))
+        {
+            self.errors.push(Error::LabelDefinedMoreThanOnce {
+                name: String::from_utf8_lossy(name).into_owned(),
+                span: span.into(),
+                other_span: other_span.into(),
+            });
+        }
+        self.record_unit_label(&scoped_identifier);
+        self.constants.insert(scoped_identifier);
+    }
+
+    /// Whether the words are currently skipped because of a conditional.
+    fn skipping(&self) -> bool {
+        matches!(self.conditionals.last(), Some(conditional) if !conditional.taken())
+    }
+
+    /// Starts a new routine at a label defined in a library region. The previous routine, or
+    /// the code before the first one, is considered to reference the new routine unless it ends
+    /// with an unconditional jump or a break. Returns whether the label is assembled, i.e. it
+    /// doesn't start a pruned routine.
+    fn enter_unit(&mut self, name: &[u8]) -> bool {
+        if !self
+            .conditionals
+            .iter()
+            .any(|conditional| conditional.library)
+        {
+            return true;
+        }
+
+        let falls_through = match self.statements[self.unit_start..].last() {
+            Some(statement) => !ends_routine(statement),
+            None => self.unit.is_some(),
+        };
+        if falls_through {
+            self.unit_references
+                .push((self.unit.clone(), ScopedIdentifier::Label(name.to_owned())));
+        }
+        self.unit = Some(name.to_owned());
+        self.unit_start = self.statements.len();
+        self.pruning = self.pruned_units.contains(name);
+
+        !self.pruning
+    }
+
+    fn record_unit_label(&mut self, scoped_identifier: &ScopedIdentifier) {
+        if let Some(unit) = &self.unit {
+            self.unit_labels
+                .insert(scoped_identifier.clone(), unit.clone());
+        }
+    }
+
+    fn record_unit_reference(&mut self, scoped_identifier: &ScopedIdentifier) {
+        self.unit_references
+            .push((self.unit.clone(), scoped_identifier.clone()));
+    }
+
+    /// Opens a conditional, checking whether the macro named right after the directive has been
+    /// defined so far.
+    fn walk_conditional<'a>(
+        &mut self,
+        negated: bool,
+        span: Span,
+        mut words: Peekable<Iter<'a, &'words Word>>,
+    ) -> Peekable<Iter<'a, &'words Word>> {
+        let enclosing_taken = !self.skipping();
+
+        let defined = match words.peek() {
+            Some(Word::Fine {
+                token:
+                    Spanned {
+                        node: Token::MacroInvoke(name),
+                        ..
+                    },
+                warnings: new_warnings,
+            }) => {
+                let defined = self.macro_definitions.contains_key(name);
+                if enclosing_taken {
+                    self.warnings.extend(new_warnings.iter().cloned());
+                    self.unused_macros.remove(name);
+                }
+                words.next();
+                defined
+            }
+            _ => {
+                if enclosing_taken {
+                    self.errors
+                        .push(Error::ConditionalNameExpected { span: span.into() });
+                }
+                false
+            }
+        };
+
+        self.conditionals.push(Conditional {
+            span,
+            else_span: None,
+            holds: defined != negated,
+            enclosing_taken,
+            library: false,
+        });
+
+        words
+    }
+
     fn walk_macro_definition<'a>(
         &mut self,
         name: &Vec<u8>,
         span: Span,
+        words: Peekable<Iter<'a, &'words Word>>,
+    ) -> Peekable<Iter<'a, &'words Word>> {
+        let (items, words) = self.walk_body(words);
+
+        if let Some((_, other_span)) = self.macro_definitions.insert(name.clone(), (items, span)) {
+            self.errors.push(Error::MacroDefinedMoreThanOnce {
+                name: String::from_utf8_lossy(&name).into_owned(),
+                span: span.into(),
+                other_span: other_span.into(),
+            });
+        }
+        self.unused_macros.insert(name.to_owned());
+
+        words
+    }
+
+    /// Allocates the variables of a `?zeropage` section at the next free addresses of the zero
+    /// page, regardless of the address pointer. The variables are labels and sublabels, each
+    /// followed by a relative pad with its size.
+    fn walk_zero_page<'a>(
+        &mut self,
+        span: Span,
+        words: Peekable<Iter<'a, &'words Word>>,
+    ) -> Peekable<Iter<'a, &'words Word>> {
+        let (items, words) = self.walk_body(words);
+        let scope = self.scope.clone();
+        let mut variable: Option<ScopedIdentifier> = None;
+
+        for word in items {
+            match word {
+                Word::Fine {
+                    token,
+                    warnings: new_warnings,
+                } => {
+                    self.warnings.extend(new_warnings.iter().cloned());
+                    match &token.node {
+                        Token::LabelDefine(name) => {
+                            let scoped_identifier = ScopedIdentifier::Label(name.clone());
+                            self.define_zero_page_variable(scoped_identifier.clone(), token.span);
+                            variable = Some(scoped_identifier);
+                            self.scope = Some(name.clone());
+                        }
+                        Token::SublabelDefine(name) => match &self.scope {
+                            Some(scope_name) => {
+                                let scoped_identifier =
+                                    ScopedIdentifier::Sublabel(scope_name.to_owned(), name.clone());
+                                self.define_zero_page_variable(
+                                    scoped_identifier.clone(),
+                                    token.span,
+                                );
+                                variable = Some(scoped_identifier);
+                            }
+                            None => self.errors.push(Error::SublabelDefinedWithoutScope {
+                                name: String::from_utf8_lossy(name).into_owned(),
+                                span: token.span.into(),
+                            }),
+                        },
+                        Token::PadRelative(size) => {
+                            if let Some(variable) = &variable {
+                                let variable_size =
+                                    self.variable_sizes.entry(variable.clone()).or_default();
+                                *variable_size = variable_size.saturating_add(*size);
+                            }
+                            let end = self.zero_page_pointer as usize + *size as usize;
+                            if end > 0x100 {
+                                self.errors.push(Error::ZeroPageExhausted {
+                                    size: end,
+                                    span: token.span.into(),
+                                    other_span: span.into(),
+                                });
+                            }
+                            self.zero_page_pointer = end.min(0x100) as u16;
+                        }
+                        _ => self.errors.push(Error::ZeroPageTokenInvalid {
+                            span: token.span.into(),
+                        }),
+                    }
+                }
+                Word::Faulty {
+                    errors: new_errors,
+                    warnings: new_warnings,
+                    ..
+                } => {
+                    self.errors.extend(new_errors.iter().cloned());
+                    self.warnings.extend(new_warnings.iter().cloned());
+                }
+            }
+        }
+
+        // The variables don't affect the sublabels defined after the section.
+        self.scope = scope;
+        words
+    }
+
+    fn define_zero_page_variable(&mut self, scoped_identifier: ScopedIdentifier, span: Span) {
+        self.record_unit_label(&scoped_identifier);
+        if let Some((_, other_span)) = self
+            .label_definitions
+            .insert(scoped_identifier.clone(), (self.zero_page_pointer, span))
+        {
+            self.errors.push(Error::LabelDefinedMoreThanOnce {
+                name: scoped_identifier.to_string(),
+                span: span.into(),
+                other_span: other_span.into(),
+            });
+        }
+    }
+
+    /// Emits the sum of the bytes between the two labels or sublabels named right after a
+    /// checksum directive. The labels can be defined anywhere in the program.
+    fn walk_checksum<'a>(
+        &mut self,
+        span: Span,
         mut words: Peekable<Iter<'a, &'words Word>>,
     ) -> Peekable<Iter<'a, &'words Word>> {
+        let mut names: Vec<(ScopedIdentifier, Span)> = Vec::new();
+        while names.len() < 2 {
+            match words.peek() {
+                Some(Word::Fine {
+                    token:
+                        Spanned {
+                            node: Token::MacroInvoke(name),
+                            span,
+                        },
+                    warnings: new_warnings,
+                }) => {
+                    self.warnings.extend(new_warnings.iter().cloned());
+                    names.push((path_identifier(name), *span));
+                    words.next();
+                }
+                _ => {
+                    self.errors
+                        .push(Error::ChecksumLabelExpected { span: span.into() });
+                    return words;
+                }
+            }
+        }
+
+        let (to, to_span) = names.pop().unwrap();
+        let (from, _) = names.pop().unwrap();
+        self.record_unit_reference(&from);
+        self.record_unit_reference(&to);
+        let span = Span::combine(&span, &to_span);
+        self.statements
+            .push(Statement::Checksum(from, to).spanning(span));
+        self.push_bytes(2, span);
+
+        words
+    }
+
+    /// Collects the words of the body of a repeat directive, and returns them repeated as many
+    /// times as the hexadecimal number right after the directive says.
+    fn walk_repeat<'a>(
+        &mut self,
+        span: Span,
+        mut words: Peekable<Iter<'a, &'words Word>>,
+    ) -> (Vec<&'words Word>, Peekable<Iter<'a, &'words Word>>) {
+        let count = match words.peek() {
+            Some(Word::Fine {
+                token:
+                    Spanned {
+                        node: Token::RawHexByte(value),
+                        ..
+                    },
+                warnings: new_warnings,
+            }) => {
+                self.warnings.extend(new_warnings.iter().cloned());
+                Some(*value as usize)
+            }
+            Some(Word::Fine {
+                token:
+                    Spanned {
+                        node: Token::RawHexShort(value),
+                        ..
+                    },
+                warnings: new_warnings,
+            }) => {
+                self.warnings.extend(new_warnings.iter().cloned());
+                Some(*value as usize)
+            }
+            _ => {
+                self.errors
+                    .push(Error::RepeatCountExpected { span: span.into() });
+                None
+            }
+        };
+        // Anything in place of the count is skipped, and the body is consumed even without the
+        // count, so that its braces are not reported.
+        if !matches!(
+            words.peek(),
+            Some(Word::Fine {
+                token: Spanned {
+                    node: Token::OpeningBrace,
+                    ..
+                },
+                ..
+            })
+        ) {
+            words.next();
+        }
+        let (items, words) = self.walk_body(words);
+        // The repeats nested in the body are expanded later on, so their lengths are counted
+        // right away for the limit to cover the whole expansion.
+        let length = count.map(|count| expanded_length(&items).saturating_mul(count));
+        match count {
+            Some(_) if length > Some(self.repeat_length) => {
+                self.errors.push(Error::RepeatTooLong {
+                    length: length.unwrap_or_default(),
+                    limit: self.repeat_length,
+                    span: span.into(),
+                });
+                (Vec::new(), words)
+            }
+            Some(count) => (items.repeat(count), words),
+            None => (Vec::new(), words),
+        }
+    }
+
+    /// Collects the words between the opening brace right after a definition or a directive
+    /// and its matching closing brace. Without an opening brace, the body is empty.
+    fn walk_body<'a>(
+        &mut self,
+        mut words: Peekable<Iter<'a, &'words Word>>,
+    ) -> (Vec<&'words Word>, Peekable<Iter<'a, &'words Word>>) {
         let mut items: Vec<&'words Word> = Vec::new();
 
         match words.peek() {
@@ -448,17 +1530,94 @@ impl<'words> Walker<'words> {
             _ => (),
         }
 
-        if let Some((_, other_span)) = self.macro_definitions.insert(name.clone(), (items, span)) {
-            self.errors.push(Error::MacroDefinedMoreThanOnce {
-                name: String::from_utf8_lossy(&name).into_owned(),
-                span: span.into(),
-                other_span: other_span.into(),
-            });
+        (items, words)
+    }
+}
+
+/// Whether the statement is an unconditional jump or a break, after which the execution never
+/// falls through to the next routine.
+fn ends_routine(statement: &Spanned<Statement>) -> bool {
+    match &statement.node {
+        Statement::Instruction(instruction) => {
+            !instruction.keep
+                && match instruction.instruction_kind {
+                    InstructionKind::Jump => true,
+                    InstructionKind::BreakOrLiteral => !instruction.short && !instruction.r#return,
+                    _ => false,
+                }
         }
-        self.unused_macros.insert(name.to_owned());
+        _ => false,
+    }
+}
 
-        words
+/// Counts the words the body of a repeat expands into, with the bodies of the repeats nested in it
+/// counted as many times as they are repeated.
+fn expanded_length(words: &[&Word]) -> usize {
+    fn token(word: &Word) -> Option<&Token> {
+        match word {
+            Word::Fine { token, .. } => Some(&token.node),
+            Word::Faulty { .. } => None,
+        }
     }
+
+    let mut length: usize = 0;
+    let mut multiplier: usize = 1;
+    // The multipliers to restore at the closing braces of the bodies of the nested repeats, and
+    // nothing for the other braces.
+    let mut braces: Vec<Option<usize>> = Vec::new();
+    let mut words = words.iter().peekable();
+    while let Some(word) = words.next() {
+        match token(word) {
+            Some(Token::Repeat) => {
+                let count = match words.peek().and_then(|word| token(word)) {
+                    Some(Token::RawHexByte(value)) => *value as usize,
+                    Some(Token::RawHexShort(value)) => *value as usize,
+                    _ => continue,
+                };
+                words.next();
+                if let Some(Token::OpeningBrace) = words.peek().and_then(|word| token(word)) {
+                    words.next();
+                    braces.push(Some(multiplier));
+                    multiplier = multiplier.saturating_mul(count);
+                }
+            }
+            Some(Token::OpeningBrace) => {
+                braces.push(None);
+                length = length.saturating_add(multiplier);
+            }
+            Some(Token::ClosingBrace) => match braces.pop() {
+                Some(Some(previous_multiplier)) => multiplier = previous_multiplier,
+                _ => length = length.saturating_add(multiplier),
+            },
+            _ => length = length.saturating_add(multiplier),
+        }
+    }
+
+    length
+}
+
+/// Turns a name with an optional slash, as written in expressions and checksums, into a label or
+/// a sublabel.
+fn path_identifier(name: &[u8]) -> ScopedIdentifier {
+    match name.iter().position(|ch| *ch == b'/') {
+        Some(position) => {
+            ScopedIdentifier::Sublabel(name[..position].to_vec(), name[position + 1..].to_vec())
+        }
+        None => ScopedIdentifier::Label(name.to_vec()),
+    }
+}
+
+fn starts_with_opening_bracket(words: &mut Peekable<Iter<&Word>>) -> bool {
+    matches!(
+        words.peek(),
+        Some(Word::Fine {
+            token: Spanned {
+                node: Token::OpeningBracket,
+                ..
+            },
+            ..
+        })
+    )
 }
 
 fn scope_identifier(
@@ -0,0 +1,126 @@
+//! The C interface, called the way a C program calls it, with raw pointers. Requires the `capi`
+//! feature.
+
+#![cfg(feature = "capi")]
+
+use ruxnasm::capi::{
+    ruxnasm_assemble, ruxnasm_assembly_free, ruxnasm_next_diagnostic, RuxnasmDiagnostic,
+    RuxnasmSeverity,
+};
+use std::ffi::CStr;
+use std::os::raw::c_char;
+use std::{ptr, slice};
+
+struct Diagnostic {
+    severity: RuxnasmSeverity,
+    code: String,
+    name: Option<String>,
+    message: String,
+    start: usize,
+    end: usize,
+}
+
+unsafe fn string(pointer: *const c_char) -> String {
+    CStr::from_ptr(pointer).to_str().unwrap().to_owned()
+}
+
+/// Assembles the program, returning the binary, or nothing if there is none, and the
+/// diagnostics.
+fn assemble(source: &[u8]) -> (Option<Vec<u8>>, Vec<Diagnostic>) {
+    unsafe {
+        let mut rom: *const u8 = ptr::null();
+        let mut rom_length: usize = 0;
+        let assembly = ruxnasm_assemble(
+            source.as_ptr() as *const c_char,
+            source.len(),
+            &mut rom,
+            &mut rom_length,
+        );
+        assert!(!assembly.is_null());
+
+        let binary = if rom.is_null() {
+            None
+        } else {
+            Some(slice::from_raw_parts(rom, rom_length).to_vec())
+        };
+        let mut diagnostics = Vec::new();
+        let mut diagnostic = RuxnasmDiagnostic {
+            severity: RuxnasmSeverity::Error,
+            code: ptr::null(),
+            name: ptr::null(),
+            message: ptr::null(),
+            start: 0,
+            end: 0,
+        };
+        while ruxnasm_next_diagnostic(assembly, &mut diagnostic) {
+            diagnostics.push(Diagnostic {
+                severity: diagnostic.severity,
+                code: string(diagnostic.code),
+                name: if diagnostic.name.is_null() {
+                    None
+                } else {
+                    Some(string(diagnostic.name))
+                },
+                message: string(diagnostic.message),
+                start: diagnostic.start,
+                end: diagnostic.end,
+            });
+        }
+        ruxnasm_assembly_free(assembly);
+
+        (binary, diagnostics)
+    }
+}
+
+#[test]
+fn binary_is_returned_along_with_the_warnings() {
+    let (binary, diagnostics) = assemble(b"|0100 @main #02 #03 ADD");
+
+    assert_eq!(binary.unwrap(), [0x80, 0x02, 0x80, 0x03, 0x18]);
+    match &diagnostics[..] {
+        [warning] => {
+            assert_eq!(warning.severity, RuxnasmSeverity::Warning);
+            assert_eq!(warning.code, "W0004");
+            assert_eq!(warning.name.as_deref(), Some("unused-label"));
+            assert_eq!(warning.message, "label `main` is never used");
+            assert_eq!((warning.start, warning.end), (6, 11));
+        }
+        _ => panic!("unexpected number of diagnostics: {}", diagnostics.len()),
+    }
+}
+
+#[test]
+fn errors_come_first_and_leave_no_binary() {
+    let (binary, diagnostics) = assemble(b"|0100 @main ;missing JSR2");
+
+    assert!(binary.is_none());
+    let severities: Vec<_> = diagnostics
+        .iter()
+        .map(|diagnostic| diagnostic.severity)
+        .collect();
+    assert_eq!(
+        severities,
+        [RuxnasmSeverity::Error, RuxnasmSeverity::Warning]
+    );
+    assert_eq!(diagnostics[0].code, "E0029");
+    assert_eq!(diagnostics[0].name, None);
+}
+
+#[test]
+fn null_pointers_are_accepted() {
+    unsafe {
+        assert!(ruxnasm_assemble(ptr::null(), 0, ptr::null_mut(), ptr::null_mut()).is_null());
+        assert!(!ruxnasm_next_diagnostic(ptr::null_mut(), ptr::null_mut()));
+        ruxnasm_assembly_free(ptr::null_mut());
+
+        let source = b"|0100 BRK";
+        let assembly = ruxnasm_assemble(
+            source.as_ptr() as *const c_char,
+            source.len(),
+            ptr::null_mut(),
+            ptr::null_mut(),
+        );
+        assert!(!assembly.is_null());
+        ruxnasm_assembly_free(assembly);
+    }
+}
@@ -0,0 +1,89 @@
+//! The same inputs must result in byte-identical binaries and reports on every platform, no
+//! matter the order of the hash maps used by the assembler or the path separators.
+
+use std::path::PathBuf;
+use std::process::Command;
+use std::{env, fs, process};
+
+/// Number of times the same input is assembled, each time with different hash map seeds.
+const RUNS: usize = 8;
+
+struct Output {
+    binary: Vec<u8>,
+    stdout: Vec<u8>,
+    stderr: Vec<u8>,
+}
+
+fn input_file_path() -> PathBuf {
+    // Joined with the separator of the platform, which must not show up in the reports.
+    ["tests", "determinism", "program.tal"].iter().collect()
+}
+
+fn assemble(run: usize, arguments: &[&str]) -> Output {
+    let output_file_path =
+        env::temp_dir().join(format!("ruxnasm-determinism-{}-{}.rom", process::id(), run));
+    let output = Command::new(env!("CARGO_BIN_EXE_ruxnasm"))
+        .args(arguments)
+        .arg(input_file_path())
+        .arg(&output_file_path)
+        .output()
+        .unwrap();
+    let binary = fs::read(&output_file_path).unwrap();
+    let _ = fs::remove_file(&output_file_path);
+
+    Output {
+        binary,
+        stdout: output.stdout,
+        stderr: output.stderr,
+    }
+}
+
+/// Removes the colors, which differ between the platforms to stay readable in their terminals.
+fn strip_colors(report: &[u8]) -> String {
+    let report = String::from_utf8_lossy(report);
+    let mut stripped = String::with_capacity(report.len());
+    let mut chars = report.chars();
+    while let Some(ch) = chars.next() {
+        if ch == '\u{1b}' {
+            chars.by_ref().find(|ch| *ch == 'm');
+        } else {
+            stripped.push(ch);
+        }
+    }
+    stripped
+}
+
+#[test]
+fn binary_and_report_are_the_expected_ones() {
+    let output = assemble(0, &[]);
+
+    assert_eq!(output.binary, include_bytes!("determinism/expected.rom"));
+    assert_eq!(
+        strip_colors(&output.stderr),
+        include_str!("determinism/expected.txt")
+    );
+}
+
+#[test]
+fn every_run_has_the_same_output() {
+    for arguments in &[&[][..], &["--message-format", "sarif"][..]] {
+        let first = assemble(0, arguments);
+        for run in 1..RUNS {
+            let output = assemble(run, arguments);
+            assert_eq!(output.binary, first.binary);
+            assert_eq!(output.stdout, first.stdout);
+            assert_eq!(output.stderr, first.stderr);
+        }
+    }
+}
+
+#[test]
+fn every_assembly_has_the_same_warnings() {
+    let source = fs::read(input_file_path()).unwrap();
+    let (_, first_warnings) = ruxnasm::assemble(&source).unwrap();
+
+    for _ in 1..RUNS {
+        let (_, warnings) = ruxnasm::assemble(&source).unwrap();
+        assert_eq!(warnings, first_warnings);
+    }
+}
@@ -0,0 +1,60 @@
+//! Programs formatted with `ruxnasm::format`, which must assemble into the same binaries as before
+//! formatting, and stay the same when formatted again.
+
+use std::fs;
+use std::path::PathBuf;
+
+fn assert_formats_without_changing(name: &str) {
+    let file_path: PathBuf = ["tests", "language", &format!("{}.tal", name)]
+        .iter()
+        .collect();
+    let source = fs::read(&file_path).unwrap();
+    let options = ruxnasm::Options::default();
+
+    let formatted = ruxnasm::format(&source).unwrap();
+    let binary = ruxnasm::assemble_with_options(&source, &options)
+        .unwrap()
+        .binary;
+    let formatted_binary = ruxnasm::assemble_with_options(&formatted, &options)
+        .unwrap()
+        .binary;
+
+    assert_eq!(formatted_binary, binary, "binary of {}", name);
+    assert_eq!(
+        ruxnasm::format(&formatted).unwrap(),
+        formatted,
+        "{} formatted twice",
+        name
+    );
+}
+
+#[test]
+fn formatting_keeps_the_binaries() {
+    for name in &[
+        "checksum",
+        "conditionals",
+        "constants",
+        "repeat",
+        "zeropage",
+    ] {
+        assert_formats_without_changing(name);
+    }
+}
+
+#[test]
+fn comments_are_kept_as_they_are() {
+    let formatted = ruxnasm::format(b"|0100\n  ( two   spaces )   #01\n").unwrap();
+
+    assert_eq!(formatted, b"|0100\n\t( two   spaces ) #01\n");
+}
+
+#[test]
+fn unclosed_comments_are_not_formatted() {
+    let error = ruxnasm::format(b"|0100 ( BRK").unwrap_err();
+
+    assert!(
+        matches!(error, ruxnasm::Error::NoMatchingClosingParenthesis { .. }),
+        "{:?}",
+        error
+    );
+}
@@ -0,0 +1,130 @@
+//! Programs using the extensions of the language, assembled by the command line tool and compared
+//! byte by byte with the expected binaries. Each program is assembled twice: once with the binary
+//! streamed to the output file during the assembly, and once with the binary written after it.
+
+use std::path::PathBuf;
+use std::process::{Command, Output};
+use std::{env, fs, process};
+
+fn input_file_path(name: &str) -> PathBuf {
+    ["tests", "language", &format!("{}.tal", name)]
+        .iter()
+        .collect()
+}
+
+fn output_file_path(name: &str, suffix: &str) -> PathBuf {
+    env::temp_dir().join(format!(
+        "ruxnasm-language-{}-{}-{}.rom",
+        name,
+        suffix,
+        process::id()
+    ))
+}
+
+fn run(name: &str, output_file_path: &PathBuf, arguments: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_ruxnasm"))
+        .args(arguments)
+        .arg(input_file_path(name))
+        .arg(output_file_path)
+        .output()
+        .unwrap()
+}
+
+/// Assembles the program both ways and checks that the binaries are the expected one.
+fn assert_assembles_into(name: &str, expected: &[u8]) {
+    let expected_file_path: PathBuf = ["tests", "language", &format!("{}.rom", name)]
+        .iter()
+        .collect();
+    let expected_file_path = expected_file_path.to_string_lossy().into_owned();
+    let streams = [
+        ("streamed", &[][..]),
+        // Binaries compared with a reference are written only after the assembly.
+        ("written", &["--verify-against", &expected_file_path][..]),
+    ];
+
+    for (suffix, arguments) in &streams {
+        let output_file_path = output_file_path(name, suffix);
+        let output = run(name, &output_file_path, arguments);
+        let binary = fs::read(&output_file_path);
+        let _ = fs::remove_file(&output_file_path);
+
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        assert_eq!(binary.unwrap(), expected, "{} binary of {}", suffix, name);
+    }
+}
+
+#[test]
+fn unreferenced_library_routines_are_left_out() {
+    assert_assembles_into("library", include_bytes!("language/library.rom"));
+}
+
+#[test]
+fn only_holding_conditionals_are_assembled() {
+    assert_assembles_into("conditionals", include_bytes!("language/conditionals.rom"));
+}
+
+#[test]
+fn nested_repeats_are_expanded() {
+    assert_assembles_into("repeat", include_bytes!("language/repeat.rom"));
+}
+
+#[test]
+fn zero_page_sections_are_allocated_in_order() {
+    assert_assembles_into("zeropage", include_bytes!("language/zeropage.rom"));
+}
+
+#[test]
+fn checksum_sums_the_bytes_of_the_range() {
+    assert_assembles_into("checksum", include_bytes!("language/checksum.rom"));
+}
+
+#[test]
+fn constants_and_expressions_are_computed() {
+    assert_assembles_into("constants", include_bytes!("language/constants.rom"));
+}
+
+#[test]
+fn repeats_are_limited_through_their_nesting() {
+    let (errors, _) =
+        ruxnasm::assemble(b"|0100 ?repeat 10 { ?repeat 1000 { #01 POP } } BRK").unwrap_err();
+
+    match &errors[..] {
+        [ruxnasm::Error::RepeatTooLong { length, limit, .. }] => {
+            assert_eq!(*length, 0x10 * 0x1000 * 2);
+            assert_eq!(*limit, 0x10000);
+        }
+        _ => panic!("unexpected errors: {:?}", errors),
+    }
+}
+
+#[test]
+fn stack_heights_are_checked_only_when_asked_for() {
+    let output_file_path = output_file_path("stacks", "checked");
+    let unchecked = run("stacks", &output_file_path, &["--deny", "stack-height"]);
+    let checked = run(
+        "stacks",
+        &output_file_path,
+        &["--deny", "stack-height", "--check-stacks"],
+    );
+    let _ = fs::remove_file(&output_file_path);
+
+    assert!(unchecked.status.success());
+    assert!(!checked.status.success());
+    assert!(String::from_utf8_lossy(&checked.stderr).contains("W0013"));
+}
+
+#[test]
+fn failed_assembly_keeps_the_previous_binary() {
+    let output_file_path = output_file_path("invalid", "kept");
+    fs::write(&output_file_path, b"previous").unwrap();
+    let output = run("invalid", &output_file_path, &[]);
+    let binary = fs::read(&output_file_path);
+    let _ = fs::remove_file(&output_file_path);
+
+    assert!(!output.status.success());
+    assert_eq!(binary.unwrap(), b"previous");
+}
@@ -0,0 +1,218 @@
+//! Sessions with the language server, run by the command line tool with `ruxnasm lsp` and talked
+//! to over its standard input and output, the way an editor does.
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+
+const URI: &str = "file:///program.tal";
+
+struct Server {
+    process: Child,
+    input: ChildStdin,
+    output: BufReader<ChildStdout>,
+}
+
+impl Server {
+    fn start() -> Self {
+        let mut process = Command::new(env!("CARGO_BIN_EXE_ruxnasm"))
+            .arg("lsp")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let input = process.stdin.take().unwrap();
+        let output = BufReader::new(process.stdout.take().unwrap());
+
+        Self {
+            process,
+            input,
+            output,
+        }
+    }
+
+    /// Sends the messages at once, so that the server receives them together.
+    fn send(&mut self, messages: &[String]) {
+        let mut bytes = Vec::new();
+        for message in messages {
+            write!(
+                bytes,
+                "Content-Length: {}\r\n\r\n{}",
+                message.len(),
+                message
+            )
+            .unwrap();
+        }
+        self.input.write_all(&bytes).unwrap();
+        self.input.flush().unwrap();
+    }
+
+    fn receive(&mut self) -> String {
+        let mut length = None;
+        loop {
+            let mut header = String::new();
+            self.output.read_line(&mut header).unwrap();
+            let header = header.trim_end();
+            if header.is_empty() {
+                break;
+            }
+            if let Some(value) = header.strip_prefix("Content-Length:") {
+                length = Some(value.trim().parse().unwrap());
+            }
+        }
+        let mut content = vec![0; length.expect("missing Content-Length header")];
+        self.output.read_exact(&mut content).unwrap();
+        String::from_utf8(content).unwrap()
+    }
+
+    /// Receives the messages until the response to the request, skipping the notifications.
+    fn receive_response(&mut self, id: u32) -> String {
+        let pattern = format!("\"id\":{},", id);
+        loop {
+            let message = self.receive();
+            if message.contains(&pattern) {
+                return message;
+            }
+        }
+    }
+
+    fn receive_notification(&mut self, method: &str) -> String {
+        let pattern = format!("\"method\":\"{}\"", method);
+        loop {
+            let message = self.receive();
+            if message.contains(&pattern) {
+                return message;
+            }
+        }
+    }
+
+    /// Shuts the server down and waits for it to exit, returning whether it has exited
+    /// successfully.
+    fn shut_down(mut self, id: u32) -> bool {
+        self.send(&[request(id, "shutdown", "null")]);
+        assert_eq!(
+            self.receive_response(id),
+            format!("{{\"jsonrpc\":\"2.0\",\"id\":{},\"result\":null}}", id)
+        );
+        self.send(&[notification("exit", "null")]);
+        self.process.wait().unwrap().success()
+    }
+}
+
+fn request(id: u32, method: &str, params: &str) -> String {
+    format!(
+        "{{\"jsonrpc\":\"2.0\",\"id\":{},\"method\":\"{}\",\"params\":{}}}",
+        id, method, params
+    )
+}
+
+fn notification(method: &str, params: &str) -> String {
+    format!(
+        "{{\"jsonrpc\":\"2.0\",\"method\":\"{}\",\"params\":{}}}",
+        method, params
+    )
+}
+
+fn did_open(text: &str) -> String {
+    notification(
+        "textDocument/didOpen",
+        &format!(
+            "{{\"textDocument\":{{\"uri\":\"{}\",\"text\":\"{}\"}}}}",
+            URI,
+            text.replace('\n', "\\n")
+        ),
+    )
+}
+
+fn semantic_tokens(id: u32) -> String {
+    request(
+        id,
+        "textDocument/semanticTokens/full",
+        &format!("{{\"textDocument\":{{\"uri\":\"{}\"}}}}", URI),
+    )
+}
+
+/// Starts the server and opens the program in it, returning the diagnostics published for it.
+fn open(text: &str) -> (Server, String) {
+    let mut server = Server::start();
+    server.send(&[request(1, "initialize", "{}"), did_open(text)]);
+    let initialized = server.receive_response(1);
+    assert!(
+        initialized.contains("\"semanticTokensProvider\""),
+        "{}",
+        initialized
+    );
+    let diagnostics = server.receive_notification("textDocument/publishDiagnostics");
+
+    (server, diagnostics)
+}
+
+#[test]
+fn diagnostics_are_published_for_opened_documents() {
+    let (server, diagnostics) = open("|0100 ;missing JSR2 BRK\n");
+
+    assert!(diagnostics.contains(&format!("\"uri\":\"{}\"", URI)));
+    assert!(
+        diagnostics.contains("\"code\":\"E0029\""),
+        "{}",
+        diagnostics
+    );
+    assert!(diagnostics.contains(
+        "\"range\":{\"start\":{\"line\":0,\"character\":6},\"end\":{\"line\":0,\"character\":14}}"
+    ));
+    assert!(server.shut_down(2));
+}
+
+#[test]
+fn semantic_tokens_are_encoded_relative_to_each_other() {
+    let (mut server, diagnostics) = open("|0100 @Main\n#01 POP BRK\n");
+    assert!(
+        diagnostics.contains("\"diagnostics\":[]"),
+        "{}",
+        diagnostics
+    );
+
+    server.send(&[semantic_tokens(2)]);
+    let response = server.receive_response(2);
+
+    // `|0100` is an operator, `@Main` a declared function, `#01` a number, and the
+    // instructions keywords, each given as the line, the start, the length, the type, and the
+    // modifiers, relative to the token before it.
+    assert_eq!(
+        response,
+        "{\"jsonrpc\":\"2.0\",\"id\":2,\"result\":{\"data\":[\
+         0,0,5,5,0,\
+         0,6,5,2,1,\
+         1,0,3,4,0,\
+         0,4,3,1,0,\
+         0,4,3,1,0\
+         ]}}"
+    );
+    assert!(server.shut_down(3));
+}
+
+#[test]
+fn cancelled_requests_are_answered_with_an_error() {
+    let program: String = (0..4000)
+        .map(|index| format!("@l{} ( comment ) #00 ;l{} JSR2 POP\n", index, index))
+        .collect();
+    let (mut server, _) = open(&format!("|0100\n{}", program));
+
+    // The document is long enough for the request to be cancelled before it's handled, or
+    // while it's running.
+    server.send(&[
+        semantic_tokens(2),
+        notification("$/cancelRequest", "{\"id\":2}"),
+    ]);
+    let response = server.receive_response(2);
+
+    assert!(response.contains("\"code\":-32800"), "{}", response);
+    assert!(server.shut_down(3));
+}
+
+#[test]
+fn exiting_without_shutting_down_fails() {
+    let (mut server, _) = open("|0100 BRK\n");
+    server.send(&[notification("exit", "null")]);
+
+    assert!(!server.process.wait().unwrap().success());
+}